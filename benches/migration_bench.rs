@@ -0,0 +1,132 @@
+//! Benchmarks of the end-to-end migration path over a medium-sized wallet.
+//!
+//! Usage: cargo bench --bench migration_bench
+//!
+//! Parsing benchmarks report throughput in bytes of wallet data per second.
+//! The wallet is `benches/fixtures/medium-wallet.dat`:
+//! `tests/fixtures/plaintext-regtest-wallet.dat` with 1000 mined transparent
+//! transactions added, each spending the change output of the one before it,
+//! paying one of the wallet's keys and returning change to another. CI fails
+//! a pull request whose benchmarks regress by more than 20% against its base.
+
+use std::{hint::black_box, path::PathBuf};
+
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use zewif::BlockHeight;
+use zewif_zcashd::{
+    BDBDump, ZcashdDump, ZcashdParser, migrate_to_zewif, parse,
+    parser::prelude::{Parser, Result},
+    zcashd_wallet::orchard::OrchardNoteCommitmentTree,
+};
+
+/// The regtest fixture wallet with a chain of 1000 transparent transactions.
+fn fixture() -> BDBDump {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("benches/fixtures")
+        .join("medium-wallet.dat");
+    BDBDump::from_file(&path).expect("dump the fixture wallet")
+}
+
+fn bench_parse_dump(c: &mut Criterion) {
+    let bdb = fixture();
+    let dump = ZcashdDump::from_bdb_dump(&bdb, false).expect("collect records");
+    let bytes: usize = bdb
+        .data_records
+        .iter()
+        .map(|(key, value)| key.len() + value.len())
+        .sum();
+
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Bytes(bytes as u64));
+    group.bench_function("parse_dump", |b| {
+        b.iter(|| ZcashdParser::parse_dump(black_box(&dump), false).expect("parses"))
+    });
+    group.finish();
+}
+
+/// An `orchard_note_commitment_tree` record, as the wallet parser reads it.
+fn parse_orchard_tree(record: &[u8]) -> Result<OrchardNoteCommitmentTree> {
+    let mut p = Parser::new(&record);
+    // The tree is preceded by the client version that wrote it.
+    parse!(&mut p, bytes = 4, "client version")?;
+    let tree = parse!(
+        &mut p,
+        OrchardNoteCommitmentTree,
+        "orchard note commitment tree"
+    )?;
+    p.check_finished()?;
+    Ok(tree)
+}
+
+fn bench_orchard_tree(c: &mut Criterion) {
+    let bdb = fixture();
+    let dump = ZcashdDump::from_bdb_dump(&bdb, false).expect("collect records");
+    let value = dump
+        .value_for_keyname("orchard_note_commitment_tree")
+        .expect("the fixture has an Orchard note commitment tree");
+    let record: &[u8] = value.as_data().as_ref();
+
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Bytes(record.len() as u64));
+    group.bench_function("orchard_note_commitment_tree", |b| {
+        b.iter(|| parse_orchard_tree(black_box(record)).expect("parses"))
+    });
+    group.finish();
+}
+
+fn bench_migrate(c: &mut Criterion) {
+    let bdb = fixture();
+    let dump = ZcashdDump::from_bdb_dump(&bdb, false).expect("collect records");
+    let (wallet, _) = ZcashdParser::parse_dump(&dump, false).expect("parses");
+
+    c.bench_function("migrate_to_zewif", |b| {
+        b.iter(|| {
+            migrate_to_zewif(black_box(&wallet), BlockHeight::from_u32(2_000_000), None)
+                .expect("migrates")
+        })
+    });
+}
+
+/// 10,000 address lookups, cycling through the fixture's exported addresses,
+/// with and without the wallet's address index.
+fn bench_address_lookup(c: &mut Criterion) {
+    let bdb = fixture();
+    let dump = ZcashdDump::from_bdb_dump(&bdb, false).expect("collect records");
+    let (wallet, _) = ZcashdParser::parse_dump(&dump, false).expect("parses");
+    let zewif =
+        migrate_to_zewif(&wallet, BlockHeight::from_u32(2_000_000), None).expect("migrates");
+    let addresses: Vec<String> = zewif.wallets()[0]
+        .accounts()
+        .iter()
+        .flat_map(|account| account.addresses())
+        .map(|address| address.as_string())
+        .collect();
+    let lookups: Vec<&String> = addresses.iter().cycle().take(10_000).collect();
+
+    let mut group = c.benchmark_group("account_for_address");
+    group.sample_size(10);
+    group.bench_function("cached", |b| {
+        b.iter(|| {
+            for address in &lookups {
+                black_box(wallet.account_for_address(black_box(address)));
+            }
+        })
+    });
+    group.bench_function("uncached", |b| {
+        b.iter(|| {
+            for address in &lookups {
+                black_box(wallet.find_account_for_address(black_box(address)));
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_parse_dump,
+    bench_orchard_tree,
+    bench_migrate,
+    bench_address_lookup
+);
+criterion_main!(benches);
@@ -0,0 +1,51 @@
+//! Explain which account the migration attaches an address to, and why.
+//!
+//! Usage: cargo run --example explain_address -- /path/to/wallet.dat explain <address>
+//!        cargo run --example explain_address -- /path/to/wallet.dat transactions
+//!
+//! `explain` prints each routing rule consulted for the address, with the
+//! evidence it found, followed by the final account decision. `transactions`
+//! prints how many transactions the migration assigns to accounts, and how
+//! many each rule attributes. As with `read_wallet`, an
+//! encrypted wallet's passphrase is taken from `ZCASHD_WALLET_PASSPHRASE`, or
+//! its encrypted keys skipped with `ZCASHD_WALLET_SKIP_ENCRYPTED` (in which case
+//! addresses of the skipped keys are no longer recognized).
+
+use std::path::PathBuf;
+
+use zewif_zcashd::{
+    BDBDump, EncryptedKeyPolicy, SecretVec, ZcashdDump, ZcashdParser,
+    migrate::{explain_assignment, explain_transaction_attribution},
+};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (path, address) = match args.as_slice() {
+        [path, command, address] if command == "explain" => (path, Some(address)),
+        [path, command] if command == "transactions" => (path, None),
+        _ => {
+            return Err(
+                "usage: explain_address <wallet.dat> (explain <address> | transactions)".into(),
+            );
+        }
+    };
+
+    let policy = if let Some(pass) = std::env::var_os("ZCASHD_WALLET_PASSPHRASE") {
+        EncryptedKeyPolicy::Decrypt(SecretVec::new(pass.into_encoded_bytes()))
+    } else if std::env::var_os("ZCASHD_WALLET_SKIP_ENCRYPTED").is_some() {
+        EncryptedKeyPolicy::Skip
+    } else {
+        EncryptedKeyPolicy::Reject
+    };
+
+    let bdb = BDBDump::from_file(&PathBuf::from(path))?;
+    let dump = ZcashdDump::from_bdb_dump(&bdb, false)?;
+    let (wallet, _) = ZcashdParser::parse_dump_with_policy(&dump, false, policy)?;
+
+    match address {
+        Some(address) => println!("{}", explain_assignment(&wallet, address)?),
+        None => println!("{}", explain_transaction_attribution(&wallet)?),
+    }
+
+    Ok(())
+}
@@ -1,20 +1,28 @@
 //! Read a `zcashd` `wallet.dat`, migrate it to ZeWIF, and write the document.
 //!
-//! Usage: cargo run --example read_wallet -- /path/to/wallet.dat [out.zewif]
+//! Usage: cargo run --example read_wallet -- [--force] /path/to/wallet.dat [out.zewif]
 //!
 //! With no arguments, reads `$HOME/.zcash/wallet.dat` and writes `wallet.zewif`
 //! in the current directory.
 //!
+//! A wallet that looks like it is open in a running zcashd (Berkeley DB
+//! environment files beside it, or modified within the last
+//! `ZCASHD_WALLET_IDLE_SECS` seconds, ten minutes by default) is refused, as
+//! reading it would give torn records. Pass `--force` to read it anyway.
+//!
 //! For an encrypted wallet, supply the passphrase in the
 //! `ZCASHD_WALLET_PASSPHRASE` environment variable. To migrate only the
 //! plaintext records of an encrypted wallet whose passphrase is lost, set
 //! `ZCASHD_WALLET_SKIP_ENCRYPTED` instead.
 
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
 
 use zewif::BlockHeight;
 use zewif_zcashd::{
-    BDBDump, EncryptedKeyPolicy, SecretVec, ZcashdDump, ZcashdParser, migrate_to_zewif,
+    BDBDump, DEFAULT_RECENT_MODIFICATION_THRESHOLD, EncryptedKeyPolicy, SecretVec, ZcashdDump,
+    ZcashdParser, check_wallet_not_in_use,
+    migrate::{MigrationReport, next_steps},
+    migrate_to_zewif,
 };
 
 fn default_wallet_path() -> PathBuf {
@@ -25,7 +33,8 @@ fn default_wallet_path() -> PathBuf {
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut args = std::env::args().skip(1);
+    let mut args = std::env::args().skip(1).peekable();
+    let force = args.next_if(|arg| arg == "--force").is_some();
     let path: PathBuf = args
         .next()
         .map(PathBuf::from)
@@ -37,6 +46,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("Reading wallet: {}", path.display());
 
+    if !force {
+        let threshold = match std::env::var("ZCASHD_WALLET_IDLE_SECS") {
+            Ok(secs) => Duration::from_secs(secs.parse()?),
+            Err(_) => DEFAULT_RECENT_MODIFICATION_THRESHOLD,
+        };
+        check_wallet_not_in_use(&path, threshold)?;
+    }
+
     // Choose how to handle encrypted key material. The passphrase is taken from
     // the environment, so it is not captured in shell history or the process
     // argument list.
@@ -91,6 +108,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     );
 
+    println!("\n=== Next steps ===");
+    let steps = next_steps(&MigrationReport::from_wallet(&wallet, export_height));
+    if steps.is_empty() {
+        println!("none");
+    }
+    for step in steps {
+        println!("{step}");
+    }
+
     let bytes = zewif.to_bytes()?;
     std::fs::write(&out_path, &bytes)?;
     println!("\nWrote {} bytes to {}", bytes.len(), out_path.display());
@@ -29,6 +29,24 @@ pub enum BdbDumpError {
     #[error("invalid hex in db_dump output: {0}")]
     InvalidHex(#[source] zewif::Error),
 
+    /// A data line in printable `db_dump` output held a byte that `db_dump -p`
+    /// escapes, or a backslash not followed by a backslash or two hex digits.
+    #[error("invalid escape in printable db_dump output, on line {line}")]
+    InvalidPrintable { line: usize },
+
+    /// The dump's `format` header names a format `db_dump` does not write.
+    #[error(
+        "unsupported db_dump format \"{format}\"; dump the wallet with `db_dump <wallet.dat>`, without `-p`"
+    )]
+    UnsupportedFormat { format: String },
+
+    /// The dump has no `format` header, and every data line is valid in
+    /// both of `db_dump`'s formats, so its bytes cannot be recovered reliably.
+    #[error(
+        "cannot tell whether the dump is hexadecimal or printable: it has no format= header line; dump the wallet again with `db_dump <wallet.dat>`, without `-p`, and keep its header"
+    )]
+    AmbiguousFormat,
+
     /// A key line had no corresponding value line.
     #[error("found a key without a corresponding value")]
     UnmatchedKey,
@@ -36,11 +54,110 @@ pub enum BdbDumpError {
     /// The same key appeared more than once in the dump.
     #[error("non-uniqueness in keys detected")]
     NonUniqueKeys,
+
+    /// The dump has no `HEADER=END` marker, so its data section was never
+    /// reached.
+    #[error("dump has no HEADER=END marker after {header_lines} header lines")]
+    MissingHeaderEnd { header_lines: usize },
+
+    /// The dump has no `DATA=END` marker: it was cut short, and records may
+    /// be missing.
+    #[error("dump ends after {records} records, DATA=END marker not found")]
+    Truncated { records: usize },
+
+    /// The dump holds a different number of records than its header's
+    /// `nkeys` declares.
+    #[error("dump header declares {declared} records, but the dump holds {found}")]
+    RecordCountMismatch { declared: usize, found: usize },
+}
+
+/// How `db_dump` wrote the keys and values of a dump, as its `format` header
+/// line records. Either way, each key and each value is on a line of its own,
+/// after a single space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpFormat {
+    /// `format=bytevalue`, the default: each byte as two hex digits.
+    Bytevalue,
+    /// `format=print`, written by `db_dump -p`: printable ASCII bytes as
+    /// themselves, except a backslash as `\\`, and every other byte as a
+    /// backslash and two hex digits.
+    Print,
+}
+
+impl DumpFormat {
+    /// The format of a dump with the given header and data lines: the one its
+    /// `format` header names, or, when there is none, the one its data lines
+    /// can only have been written in.
+    fn detect(
+        header_records: &HashMap<String, String>,
+        data_lines: &[(usize, &str)],
+    ) -> Result<Self, BdbDumpError> {
+        match header_records.get("format").map(String::as_str) {
+            Some("bytevalue") => Ok(Self::Bytevalue),
+            Some("print") => Ok(Self::Print),
+            Some(format) => Err(BdbDumpError::UnsupportedFormat {
+                format: format.to_string(),
+            }),
+            // Hex lines are valid printable lines too, so only a line that
+            // is not hex tells the formats apart.
+            None if data_lines.iter().all(|(_, line)| is_hex(line.trim())) => {
+                if data_lines.is_empty() {
+                    Ok(Self::Bytevalue)
+                } else {
+                    Err(BdbDumpError::AmbiguousFormat)
+                }
+            }
+            None => Ok(Self::Print),
+        }
+    }
+
+    /// The bytes of a data line, leading space included, which is line
+    /// `line` of the dump.
+    fn decode(self, data_line: &str, line: usize) -> Result<Data, BdbDumpError> {
+        match self {
+            Self::Bytevalue => Data::from_hex(data_line.trim()).map_err(BdbDumpError::InvalidHex),
+            Self::Print => {
+                let printed = data_line.strip_prefix(' ').unwrap_or(data_line);
+                decode_printable(printed)
+                    .map(Data::from_vec)
+                    .ok_or(BdbDumpError::InvalidPrintable { line })
+            }
+        }
+    }
+}
+
+fn is_hex(s: &str) -> bool {
+    s.len().is_multiple_of(2) && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// The bytes of a key or value as `db_dump -p` prints it, or `None` if it is
+/// not in that form.
+fn decode_printable(printed: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(printed.len());
+    let mut rest = printed.bytes();
+    while let Some(b) = rest.next() {
+        match b {
+            b'\\' => match rest.next()? {
+                b'\\' => bytes.push(b'\\'),
+                high => {
+                    let high = (high as char).to_digit(16)?;
+                    let low = (rest.next()? as char).to_digit(16)?;
+                    bytes.push((high * 16 + low) as u8);
+                }
+            },
+            b' '..=b'~' => bytes.push(b),
+            _ => return None,
+        }
+    }
+    Some(bytes)
 }
 
 pub struct BDBDump {
     pub header_records: HashMap<String, String>,
     pub data_records: HashMap<Data, Data>,
+    /// The problems with an incomplete dump that were tolerated rather than
+    /// failed on; see [`Self::from_dump_output`].
+    pub problems: Vec<BdbDumpError>,
 }
 
 impl BDBDump {
@@ -64,8 +181,20 @@ impl BDBDump {
         Self::from_file_with_path(&db_dump_path, filepath)
     }
 
+    /// Dumps the BDB database at `filepath` as [`Self::from_file`] does, but
+    /// accepts a dump that is cut short; see [`Self::from_dump_output`].
+    pub fn from_file_allowing_truncation(filepath: &Path) -> Result<Self, BdbDumpError> {
+        let db_dump_path = Self::resolve_db_dump_path();
+        Self::from_dump_output(&Self::run_db_dump(&db_dump_path, filepath)?, true)
+    }
+
     /// Dumps the BDB database at `filepath` using the specified `db_dump_path` binary.
     pub fn from_file_with_path(db_dump_path: &Path, filepath: &Path) -> Result<Self, BdbDumpError> {
+        Self::from_dump_output(&Self::run_db_dump(db_dump_path, filepath)?, false)
+    }
+
+    /// The printable output of `db_dump` run against `filepath`.
+    fn run_db_dump(db_dump_path: &Path, filepath: &Path) -> Result<String, BdbDumpError> {
         // Execute the `db_dump` utility
         let output = Command::new(db_dump_path)
             .arg(filepath)
@@ -86,27 +215,41 @@ impl BDBDump {
             });
         }
 
-        // Convert the stdout to a string for parsing
-        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
 
+    /// Parses the printable output of `db_dump`: header lines up to
+    /// `HEADER=END`, then alternating key and value lines up to `DATA=END`.
+    ///
+    /// Both of `db_dump`'s formats are read (see [`DumpFormat`]), as the
+    /// `format` header line names. Output without that line is read in the
+    /// only format its data lines fit, and is an error if they fit both.
+    ///
+    /// Output that lacks either marker, ends with a key that has no value, or
+    /// holds a different number of records than a header `nkeys` line
+    /// declares is an error, since records may be missing. With
+    /// `allow_truncated`, the records that are present are kept instead, and
+    /// each such problem is recorded in [`Self::problems`], for recovering
+    /// what is left of a damaged dump.
+    pub fn from_dump_output(stdout: &str, allow_truncated: bool) -> Result<Self, BdbDumpError> {
         // Initialize HashMaps to hold header and data records
         let mut header_records: HashMap<String, String> = HashMap::new();
         let mut data_records: HashMap<Data, Data> = HashMap::new();
 
-        // Flag to indicate if we're past the header
+        // Flags to indicate if we're past the header, and past the data
         let mut in_data_section = false;
+        let mut data_ended = false;
 
-        // Temporary variable to hold the key
-        let mut current_key: Option<Data> = None;
-
-        let mut records_count = 0;
+        // The data lines, with their line numbers, decoded once the format
+        // is known
+        let mut data_lines: Vec<(usize, &str)> = Vec::new();
 
         // Iterate over each line of the db_dump output
-        for line in stdout.lines() {
+        for (index, line) in stdout.lines().enumerate() {
             let trimmed = line.trim();
 
             // Check for the end of the header section
-            if trimmed == "HEADER=END" {
+            if !in_data_section && trimmed == "HEADER=END" {
                 in_data_section = true;
                 continue;
             }
@@ -124,14 +267,22 @@ impl BDBDump {
             }
 
             if line.starts_with("DATA=END") {
+                data_ended = true;
                 break;
             }
 
-            // Each data entry line starts with a space; remove it
-            let hex_str = trimmed.trim_start_matches(' ');
+            data_lines.push((index + 1, line));
+        }
+
+        let format = DumpFormat::detect(&header_records, &data_lines)?;
 
-            // Decode the hexadecimal string
-            let bytes = Data::from_hex(hex_str).map_err(BdbDumpError::InvalidHex)?;
+        // Temporary variable to hold the key
+        let mut current_key: Option<Data> = None;
+
+        let mut records_count = 0;
+
+        for (line, data_line) in data_lines {
+            let bytes = format.decode(data_line, line)?;
 
             // Alternate between key and value
             if current_key.is_none() {
@@ -144,15 +295,176 @@ impl BDBDump {
             }
         }
 
+        if records_count != data_records.len() {
+            return Err(BdbDumpError::NonUniqueKeys);
+        }
+
+        // Check that nothing is missing
+        let mut problems = vec![];
+        if !in_data_section {
+            problems.push(BdbDumpError::MissingHeaderEnd {
+                header_lines: header_records.len(),
+            });
+        } else if !data_ended {
+            problems.push(BdbDumpError::Truncated {
+                records: records_count,
+            });
+        }
         // Check if there was an unmatched key without a corresponding value
         if current_key.is_some() {
-            return Err(BdbDumpError::UnmatchedKey);
+            problems.push(BdbDumpError::UnmatchedKey);
         }
-
-        if records_count != data_records.len() {
-            return Err(BdbDumpError::NonUniqueKeys);
+        if let Some(declared) = header_records.get("nkeys").and_then(|n| n.parse().ok())
+            && declared != records_count
+        {
+            problems.push(BdbDumpError::RecordCountMismatch {
+                declared,
+                found: records_count,
+            });
         }
+        if !allow_truncated && !problems.is_empty() {
+            return Err(problems.remove(0));
+        }
+
+        Ok(BDBDump {
+            header_records,
+            data_records,
+            problems,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRUNCATED_DUMP: &str = include_str!("../tests/fixtures/truncated-db-dump.txt");
+    /// The same records, dumped by `db_dump` and by `db_dump -p`.
+    const BYTEVALUE_DUMP: &str = include_str!("../tests/fixtures/db-dump-bytevalue.txt");
+    const PRINT_DUMP: &str = include_str!("../tests/fixtures/db-dump-print.txt");
+
+    /// The `db_dump` output of a wallet with a `version` and a `bestblock`
+    /// record, with `extra_header` lines before `HEADER=END`.
+    fn complete_dump(extra_header: &str) -> String {
+        format!(
+            "VERSION=3\nformat=bytevalue\ntype=btree\n{extra_header}HEADER=END\n \
+             0776657273696f6e\n f29a5e00\n 0962657374626c6f636b\n 0000000000\nDATA=END\n"
+        )
+    }
+
+    #[test]
+    fn complete_dump_is_read() {
+        let bdb = BDBDump::from_dump_output(&complete_dump(""), false).unwrap();
+        assert_eq!(bdb.header_records["type"], "btree");
+        assert_eq!(bdb.data_records.len(), 2);
+
+        let bdb = BDBDump::from_dump_output(&complete_dump("nkeys=2\n"), false).unwrap();
+        assert_eq!(bdb.data_records.len(), 2);
+    }
+
+    #[test]
+    fn truncated_dump_is_an_error() {
+        assert!(matches!(
+            BDBDump::from_dump_output(TRUNCATED_DUMP, false),
+            Err(BdbDumpError::Truncated { records: 3 })
+        ));
+        assert_eq!(
+            BdbDumpError::Truncated { records: 3 }.to_string(),
+            "dump ends after 3 records, DATA=END marker not found"
+        );
+
+        let header_only = "VERSION=3\nformat=bytevalue\ntype=btree\n";
+        assert!(matches!(
+            BDBDump::from_dump_output(header_only, false),
+            Err(BdbDumpError::MissingHeaderEnd { header_lines: 3 })
+        ));
+    }
+
+    #[test]
+    fn truncated_dump_keeps_its_complete_records_when_allowed() {
+        let bdb = BDBDump::from_dump_output(TRUNCATED_DUMP, true).unwrap();
+        // The fixture ends with a key whose value line was lost.
+        assert_eq!(bdb.data_records.len(), 3);
+        let version = Data::from_hex("0776657273696f6e").unwrap();
+        assert!(bdb.data_records.contains_key(&version));
+    }
+
+    #[test]
+    fn unmatched_key_is_an_error() {
+        let dump = complete_dump("").replace(" 0000000000\n", "");
+        assert!(matches!(
+            BDBDump::from_dump_output(&dump, false),
+            Err(BdbDumpError::UnmatchedKey)
+        ));
+    }
+
+    #[test]
+    fn record_count_must_match_the_declared_nkeys() {
+        assert!(matches!(
+            BDBDump::from_dump_output(&complete_dump("nkeys=3\n"), false),
+            Err(BdbDumpError::RecordCountMismatch {
+                declared: 3,
+                found: 2
+            })
+        ));
+        let bdb = BDBDump::from_dump_output(&complete_dump("nkeys=3\n"), true).unwrap();
+        assert_eq!(bdb.data_records.len(), 2);
+        assert!(matches!(
+            bdb.problems[..],
+            [BdbDumpError::RecordCountMismatch {
+                declared: 3,
+                found: 2
+            }]
+        ));
+    }
+
+    #[test]
+    fn both_db_dump_formats_give_the_same_records() {
+        let bytevalue = BDBDump::from_dump_output(BYTEVALUE_DUMP, false).unwrap();
+        let print = BDBDump::from_dump_output(PRINT_DUMP, false).unwrap();
+        assert_eq!(bytevalue.data_records.len(), 5);
+        assert_eq!(print.data_records, bytevalue.data_records);
+
+        // Leading spaces, backslashes, and escaped bytes are all kept.
+        let mut name_key = b"\x04name#".to_vec();
+        name_key.extend_from_slice(b"tmGys6dBuEGjch5LFnhdo5gpSa7jiNRWse3");
+        let name = &print.data_records[&Data::from_vec(name_key)];
+        assert_eq!(name.as_slice(), "\x10 savings \\ café".as_bytes());
+    }
+
+    #[test]
+    fn the_format_is_detected_without_a_format_header() {
+        let print = PRINT_DUMP.replace("format=print\n", "");
+        let bdb = BDBDump::from_dump_output(&print, false).unwrap();
+        assert_eq!(
+            bdb.data_records,
+            BDBDump::from_dump_output(BYTEVALUE_DUMP, false)
+                .unwrap()
+                .data_records
+        );
+
+        // Hex lines could have been printed by either format.
+        let bytevalue = BYTEVALUE_DUMP.replace("format=bytevalue\n", "");
+        let error = BDBDump::from_dump_output(&bytevalue, false)
+            .err()
+            .expect("hex lines without a format header are ambiguous");
+        assert!(matches!(error, BdbDumpError::AmbiguousFormat));
+        assert!(error.to_string().contains("without `-p`"));
+    }
+
+    #[test]
+    fn unknown_formats_and_bad_escapes_are_errors() {
+        let dump = complete_dump("").replace("format=bytevalue", "format=binary");
+        assert!(matches!(
+            BDBDump::from_dump_output(&dump, false),
+            Err(BdbDumpError::UnsupportedFormat { format }) if format == "binary"
+        ));
 
-        Ok(BDBDump { header_records, data_records })
+        // Line 7 is the first value, with an escape of one hex digit.
+        let dump = PRINT_DUMP.replace(" \\f2\\9a^\\00\n", " \\f\n");
+        assert!(matches!(
+            BDBDump::from_dump_output(&dump, false),
+            Err(BdbDumpError::InvalidPrintable { line: 7 })
+        ));
     }
 }
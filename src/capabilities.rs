@@ -0,0 +1,133 @@
+use crate::{
+    ZCASHD_KEYNAMES,
+    zcashd_wallet::{ClientVersion, orchard::OrchardNoteCommitmentTree},
+};
+
+/// The keynames whose records the parser recognizes but does not read; they
+/// are returned among the unparsed keys. These are accounting entries
+/// (`acc`, `acentry`), address data such as payment requests (`destdata`),
+/// the pre-mnemonic transparent HD chain (`hdchain`), and Sprout viewing keys
+/// (`vkey`). Every other keyname in [`ZCASHD_KEYNAMES`] is parsed.
+const UNPARSED_KEYNAMES: &[&str] = &["acc", "acentry", "destdata", "hdchain", "vkey"];
+
+/// How this crate handles the records of a zcashd keyname.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeynameSupport {
+    /// The records are parsed into the [`ZcashdWallet`](crate::ZcashdWallet).
+    Parsed,
+    /// The records are recognized but not read, and are returned among the
+    /// unparsed keys.
+    Unparsed,
+}
+
+/// How this crate handles `keyname`, or `None` if zcashd does not write it.
+pub fn keyname_support(keyname: &str) -> Option<KeynameSupport> {
+    if !ZCASHD_KEYNAMES.contains(&keyname) {
+        None
+    } else if UNPARSED_KEYNAMES.contains(&keyname) {
+        Some(KeynameSupport::Unparsed)
+    } else {
+        Some(KeynameSupport::Parsed)
+    }
+}
+
+/// What this build of the crate can read, for integrators to query at
+/// runtime; see [`capabilities`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrateCapabilities {
+    /// The crate's version.
+    pub version: &'static str,
+    /// Each keyname zcashd writes, in [`ZCASHD_KEYNAMES`] order, with how its
+    /// records are handled.
+    pub keynames: Vec<(&'static str, KeynameSupport)>,
+    /// The earliest zcashd release whose wallets are read; see
+    /// [`ClientVersion::OLDEST_SUPPORTED`]. No later release is known to be
+    /// unsupported.
+    pub min_client_version: ClientVersion,
+    /// Whether a wallet encrypted with a passphrase can be decrypted.
+    pub encrypted_wallets: bool,
+    /// Whether a wallet stored in SQLite, rather than Berkeley DB, can be
+    /// read.
+    pub sqlite_wallets: bool,
+    /// The serialization versions of the `orchard_note_commitment_tree`
+    /// record that are read.
+    pub orchard_tree_versions: Vec<u8>,
+    /// Whether the crate was built with `--cfg zcash_unstable="nu7"`, and so
+    /// reads NU7 transactions and activation heights.
+    pub zcash_unstable_nu7: bool,
+}
+
+impl CrateCapabilities {
+    /// How the records of `keyname` are handled, or `None` if zcashd does not
+    /// write it.
+    pub fn keyname_support(&self, keyname: &str) -> Option<KeynameSupport> {
+        self.keynames
+            .iter()
+            .find(|(known, _)| *known == keyname)
+            .map(|(_, support)| *support)
+    }
+}
+
+/// What this build of the crate can read. The keynames are those of
+/// [`ZCASHD_KEYNAMES`], each with its [`keyname_support`].
+pub fn capabilities() -> CrateCapabilities {
+    CrateCapabilities {
+        version: env!("CARGO_PKG_VERSION"),
+        keynames: ZCASHD_KEYNAMES
+            .iter()
+            .map(|&keyname| {
+                let support = keyname_support(keyname).expect("a zcashd keyname");
+                (keyname, support)
+            })
+            .collect(),
+        min_client_version: ClientVersion::OLDEST_SUPPORTED,
+        encrypted_wallets: true,
+        sqlite_wallets: false,
+        orchard_tree_versions: vec![OrchardNoteCommitmentTree::NOTE_STATE_V1],
+        zcash_unstable_nu7: cfg!(zcash_unstable = "nu7"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_zcashd_keyname_has_a_support_status() {
+        let capabilities = capabilities();
+        let keynames: Vec<&str> = capabilities.keynames.iter().map(|(k, _)| *k).collect();
+        assert_eq!(keynames, ZCASHD_KEYNAMES);
+        for keyname in UNPARSED_KEYNAMES {
+            assert!(ZCASHD_KEYNAMES.contains(keyname), "{keyname}");
+        }
+
+        assert_eq!(
+            capabilities.keyname_support("tx"),
+            Some(KeynameSupport::Parsed)
+        );
+        assert_eq!(
+            capabilities.keyname_support("orchard_note_commitment_tree"),
+            Some(KeynameSupport::Parsed)
+        );
+        assert_eq!(
+            capabilities.keyname_support("acentry"),
+            Some(KeynameSupport::Unparsed)
+        );
+        assert_eq!(capabilities.keyname_support("TX"), None);
+    }
+
+    #[test]
+    fn capabilities_reflect_the_build() {
+        let capabilities = capabilities();
+        assert_eq!(capabilities.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(capabilities.min_client_version.to_string(), "3.0.0");
+        assert!(capabilities.encrypted_wallets);
+        assert!(!capabilities.sqlite_wallets);
+        assert_eq!(capabilities.orchard_tree_versions, [1]);
+
+        #[cfg(zcash_unstable = "nu7")]
+        assert!(capabilities.zcash_unstable_nu7);
+        #[cfg(not(zcash_unstable = "nu7"))]
+        assert!(!capabilities.zcash_unstable_nu7);
+    }
+}
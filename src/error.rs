@@ -1,10 +1,13 @@
 use zewif::{TxId, sapling::SaplingIncomingViewingKey};
 
 use crate::{
-    BdbDumpError, DumpError,
+    BdbDumpError, DumpError, KeyExportError, WalletInUseError,
     migrate::MigrateError,
     parser::ParseError,
-    zcashd_wallet::{DecryptionError, sapling::SaplingZPaymentAddress, transparent::ScriptId},
+    zcashd_wallet::{
+        ClientVersion, DecryptionError, WalletBuildError, sapling::SaplingZPaymentAddress,
+        transparent::ScriptId,
+    },
 };
 
 /// The errors that can arise while reading a zcashd `wallet.dat` and
@@ -24,15 +27,62 @@ pub enum Error {
     #[error(transparent)]
     BdbDump(#[from] BdbDumpError),
 
+    /// The `wallet.dat` file looks like it is open in a running zcashd.
+    #[error(transparent)]
+    WalletInUse(#[from] WalletInUseError),
+
     /// A record expected to be present in the wallet database was missing
     /// or ambiguous.
     #[error(transparent)]
     Dump(#[from] DumpError),
 
+    /// The parsed records do not form a consistent wallet.
+    #[error(transparent)]
+    WalletBuild(#[from] WalletBuildError),
+
+    /// A `z_exportwallet` key export could not be read.
+    #[error(transparent)]
+    KeyExport(#[from] KeyExportError),
+
     /// The parsed wallet could not be migrated to a ZeWIF document.
     #[error(transparent)]
     Migrate(#[from] MigrateError),
 
+    /// A record this crate requires is missing from the wallet, which was last
+    /// written by a zcashd version too old to have written it.
+    #[error("wallet last written by zcashd {client_version} is too old to migrate: {source}")]
+    WalletTooOld {
+        client_version: ClientVersion,
+        source: DumpError,
+    },
+
+    /// The wallet was last written by a zcashd release older than
+    /// [`ClientVersion::OLDEST_SUPPORTED`].
+    #[error(
+        "wallet last written by zcashd {client_version} is older than the oldest supported release, {oldest}"
+    )]
+    UnsupportedClientVersion {
+        client_version: ClientVersion,
+        oldest: ClientVersion,
+    },
+
+    /// The wallet's `minversion` record requires a wallet format newer than
+    /// [`ClientVersion::NEWEST_SUPPORTED_FEATURE`].
+    #[error(
+        "wallet requires wallet format {}, newer than the newest supported, {}",
+        min_version.version(),
+        newest.version()
+    )]
+    UnsupportedWalletFormat {
+        min_version: ClientVersion,
+        newest: ClientVersion,
+    },
+
+    /// The wallet has no `networkinfo` record, and the addresses in its
+    /// address book do not identify a single network.
+    #[error("wallet has no networkinfo record and its addresses do not identify its network")]
+    UnknownNetwork,
+
     /// A key record set and its metadata record set differ in size.
     #[error("mismatched {keyname:?} and {metadata_keyname:?} records")]
     MismatchedKeyMetadata {
@@ -112,6 +162,15 @@ pub enum Error {
     #[error("duplicate cscript ScriptID: {script_id:?}")]
     DuplicateScriptId { script_id: ScriptId },
 
+    /// Two `unifiedaccount` or `unifiedfvk` records share a UFVK fingerprint.
+    /// Only reported as an error when parsing strictly; otherwise the first
+    /// record is kept with a warning.
+    #[error("duplicate {keyname} records for UFVK fingerprint {fingerprint}")]
+    DuplicateUfvkFingerprint {
+        keyname: &'static str,
+        fingerprint: String,
+    },
+
     /// Two `tx` records exist for one transaction ID.
     #[error("duplicate transaction: {txid:?}")]
     DuplicateTransaction { txid: TxId },
@@ -6,11 +6,17 @@ pub use error::Error;
 mod_use!(bdb_dump);
 mod_use!(zcashd_dump);
 mod_use!(zcashd_parser);
+mod_use!(zcashd_key_export);
+mod_use!(wallet_preflight);
+mod_use!(capabilities);
 
 pub mod migrate;
 pub mod parser;
 pub mod zcashd_wallet;
-pub use migrate::{RegtestActivations, migrate_to_zewif};
+pub use migrate::{
+    RegtestActivations, migrate_to_zewif, migrate_to_zewif_filtered, migrate_to_zewif_streaming,
+    migrate_to_zewif_with_mode, migrate_wallets_to_zewif,
+};
 pub use zcashd_wallet::ZcashdWallet;
 
 /// Re-exported so callers can build an [`EncryptedKeyPolicy::Decrypt`]
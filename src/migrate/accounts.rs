@@ -1,18 +1,29 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
+use orchard::keys::{FullViewingKey as OrchardFvk, SpendingKey as OrchardSpendingKey};
 use zcash_keys::keys::UnifiedFullViewingKey;
-use zcash_protocol::consensus;
+use zcash_protocol::consensus::{self, NetworkConstants};
 
 use zewif::{
     Account, AccountPurpose, AccountViewingKey, DerivationInfo, DerivedKeySource, KeyScope,
-    KeySource, NonHardenedChildIndex,
+    KeySource, NonHardenedChildIndex, sapling::SaplingIncomingViewingKey,
 };
 
 use crate::migrate::MigrateError;
 use crate::{
     ZcashdWallet,
-    migrate::secrets::{legacy_mnemonic_seed, mnemonic_seed_fingerprint},
-    zcashd_wallet::UfvkFingerprint,
+    migrate::{
+        AccountFingerprint, MigrationWarning, TransactionAttribution,
+        mnemonic::mnemonic_seed,
+        secrets::{
+            legacy_mnemonic_seed, mnemonic_seed_fingerprint_bytes,
+            mnemonic_seed_fingerprint_from_bytes,
+        },
+    },
+    zcashd_wallet::{
+        KeyMetadata, UfvkFingerprint, UnifiedAccountMetadata, encode_seed_fingerprint,
+        sapling_incoming_viewing_keys,
+    },
 };
 
 /// The ZIP-32 account index zcashd reserves for its legacy pool of
@@ -23,7 +34,8 @@ pub(crate) const ZCASHD_LEGACY_ACCOUNT: u32 = 0x7FFF_FFFF;
 /// metadata needed to attach addresses and received outputs to them.
 pub(crate) struct WalletAccounts {
     /// The accounts, in stable order: one per unified account (ascending
-    /// ZIP-32 account index), followed by the synthesized legacy account.
+    /// ZIP-32 account index), followed by the synthesized legacy account and
+    /// then one account per foreign seed (ascending fingerprint).
     pub accounts: Vec<Account>,
     /// Maps each unified account's zcashd UFVK fingerprint to its index in
     /// [`Self::accounts`], used to route unified addresses.
@@ -32,9 +44,42 @@ pub(crate) struct WalletAccounts {
     /// the parsed UFVK, used to route Orchard received outputs by matching
     /// incoming viewing keys.
     pub unified: Vec<(usize, UnifiedFullViewingKey)>,
+    /// Maps the Sapling incoming viewing keys derived from each unified
+    /// account's UFVK to its index in [`Self::accounts`], used to route
+    /// Sapling received outputs.
+    pub sapling_ivk_index: HashMap<SaplingIncomingViewingKey, usize>,
+    /// Maps each unified account's zcashd UFVK fingerprint to its ZIP-32
+    /// derivation path, using the coin type given by [`account_coin_type`].
+    pub derivation_paths: HashMap<UfvkFingerprint, String>,
     /// Index of the synthesized legacy account (transparent, legacy Sapling,
     /// and Sprout material).
     pub legacy_index: usize,
+    /// Maps the fingerprint of each foreign seed (see
+    /// [`foreign_seed_key_counts`]) to the index of the account holding the
+    /// keys derived from it.
+    pub foreign_seed_index: HashMap<[u8; 32], usize>,
+    /// The seed fingerprint and ZIP-32 account index of each account, in the
+    /// order of [`Self::accounts`].
+    pub fingerprints: Vec<AccountFingerprint>,
+    /// The rules that made each transaction relevant to an account, recorded
+    /// as received outputs are attached.
+    pub attribution: TransactionAttribution,
+    /// What building the accounts and attaching their addresses and outputs
+    /// found to warn of, in the order found.
+    pub warnings: Vec<MigrationWarning>,
+}
+
+impl WalletAccounts {
+    /// The index of the account holding legacy key material with the given
+    /// HD key metadata: the account of its foreign seed, if it has one, and
+    /// otherwise the legacy account.
+    pub fn legacy_index_for(&self, metadata: Option<&KeyMetadata>) -> usize {
+        metadata
+            .and_then(|m| m.seed_fp())
+            .and_then(|fp| self.foreign_seed_index.get(fp))
+            .copied()
+            .unwrap_or(self.legacy_index)
+    }
 }
 
 /// Build the accounts for a zcashd wallet.
@@ -47,6 +92,10 @@ pub(crate) struct WalletAccounts {
 /// account-0x7FFFFFFF legacy pool. Sapling and Sprout addresses within it
 /// carry their own protocol addresses; their spending keys live in the secret
 /// store.
+///
+/// Transparent and Sapling keys whose metadata names a seed other than the
+/// wallet's own are instead collected into a `foreign seed <fingerprint>`
+/// account per such seed, with a warning for each.
 pub(crate) fn build_accounts(
     wallet: &ZcashdWallet,
     params: &impl consensus::Parameters,
@@ -54,8 +103,17 @@ pub(crate) fn build_accounts(
     let mut accounts = Vec::new();
     let mut ufvk_index = HashMap::new();
     let mut unified = Vec::new();
+    let mut sapling_ivk_index = HashMap::new();
+    let mut derivation_paths = HashMap::new();
+    let mut fingerprints = Vec::new();
+    let mut warnings = Vec::new();
 
     let unified_accounts = wallet.unified_accounts();
+    // Each of these derives the mnemonic seed, a slow PBKDF2 derivation, so
+    // it is done once.
+    let mnemonic_fp_bytes = mnemonic_seed_fingerprint_bytes(wallet);
+    let mnemonic_fp = mnemonic_seed_fingerprint_from_bytes(wallet, mnemonic_fp_bytes);
+    let mut seed = None;
 
     // Deterministic order: ascending ZIP-32 account index.
     let mut metas: Vec<(&UfvkFingerprint, _)> = unified_accounts.account_metadata.iter().collect();
@@ -87,6 +145,31 @@ pub(crate) fn build_accounts(
         let idx = accounts.len();
         ufvk_index.insert(*ufvk_fp, idx);
         unified.push((idx, ufvk.clone()));
+        for ivk in sapling_incoming_viewing_keys(ufvk) {
+            sapling_ivk_index.insert(ivk, idx);
+        }
+        let coin_type = account_coin_type(meta, params, &mut warnings);
+        // zcashd stores no Orchard spending keys, only the seed they are
+        // derived from, which the account's key source names; check that it
+        // does derive the key of the account's Orchard viewing key.
+        if let Some(fvk) = ufvk.orchard()
+            && mnemonic_fp.as_ref() == Some(meta.seed_fingerprint())
+            && let Some(seed) =
+                seed.get_or_insert_with(|| wallet.bip39_mnemonic().and_then(mnemonic_seed))
+            && !derives_orchard_key(seed, coin_type, meta.zip32_account_id(), fvk)
+        {
+            warnings.push(MigrationWarning::OrchardKeyNotDerived {
+                zip32_account_id: meta.zip32_account_id(),
+            });
+        }
+        derivation_paths.insert(
+            *ufvk_fp,
+            account_derivation_path(coin_type, meta.zip32_account_id()),
+        );
+        fingerprints.push(AccountFingerprint::new(
+            Some(meta.seed_fingerprint().clone()),
+            meta.zip32_account_id(),
+        ));
         accounts.push(account);
     }
 
@@ -105,14 +188,14 @@ pub(crate) fn build_accounts(
     // seed-derived account. A wallet with neither a mnemonic nor a legacy seed
     // (a bare set of imported addresses) has no derivation root, so its legacy
     // account remains a bag of imported material.
-    let legacy_seed_fp = match mnemonic_seed_fingerprint(wallet) {
-        Some(fp) => Some(fp),
+    let legacy_seed_fp = match &mnemonic_fp {
+        Some(fp) => Some(fp.clone()),
         None => legacy_mnemonic_seed(wallet)?.map(|(_, fp)| fp),
     };
-    match legacy_seed_fp {
+    match &legacy_seed_fp {
         Some(seed_fp) => {
             legacy.set_key_source(KeySource::Derived(DerivedKeySource::new(
-                seed_fp,
+                seed_fp.clone(),
                 ZCASHD_LEGACY_ACCOUNT,
                 None,
             )));
@@ -121,18 +204,223 @@ pub(crate) fn build_accounts(
     }
     legacy.set_provenance("zcashd_legacy");
     legacy.set_purpose(AccountPurpose::Spending);
+    let beyond = keys_beyond_legacy_counters(wallet);
+    if beyond > 0 {
+        warnings.push(MigrationWarning::KeysBeyondCounters { count: beyond });
+    }
 
     let legacy_index = accounts.len();
+    fingerprints.push(AccountFingerprint::new(
+        legacy_seed_fp,
+        ZCASHD_LEGACY_ACCOUNT,
+    ));
     accounts.push(legacy);
 
+    // Keys derived from a seed the wallet no longer holds (e.g. keys left over
+    // from before the mnemonic was replaced on restore) cannot belong to the
+    // seed-derived legacy account, so each such seed gets an account of its
+    // own, holding its keys as imported material.
+    let mut foreign_seed_index = HashMap::new();
+    for (seed_fp, key_count) in foreign_seed_key_counts(wallet, mnemonic_fp_bytes)? {
+        warnings.push(MigrationWarning::ForeignSeedKeys {
+            seed_fingerprint: seed_fp,
+            key_count,
+        });
+        let mut account = Account::new(AccountViewingKey::TransparentAddressSet);
+        account.set_name(format!("foreign seed {}", hex::encode(seed_fp)));
+        account.set_key_source(KeySource::Imported);
+        account.set_provenance("zcashd_legacy");
+        account.set_purpose(AccountPurpose::Spending);
+        foreign_seed_index.insert(seed_fp, accounts.len());
+        fingerprints.push(AccountFingerprint::new(
+            Some(encode_seed_fingerprint(&seed_fp)),
+            ZCASHD_LEGACY_ACCOUNT,
+        ));
+        accounts.push(account);
+    }
+
     Ok(WalletAccounts {
         accounts,
         ufvk_index,
         unified,
+        sapling_ivk_index,
+        derivation_paths,
         legacy_index,
+        foreign_seed_index,
+        fingerprints,
+        attribution: TransactionAttribution::default(),
+        warnings,
     })
 }
 
+/// A chain of keys zcashd derives from the mnemonic seed in its legacy
+/// account, each with its own counter in the mnemonic HD chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LegacyKeyChain {
+    TransparentExternal,
+    TransparentInternal,
+    Sapling,
+}
+
+/// The legacy-account chain and index of a key from its HD keypath:
+/// `m/44'/coin'/0x7FFFFFFF'/change/index` for transparent keys and
+/// `m/32'/coin'/0x7FFFFFFF'/index'` for Sapling keys. `None` for any other
+/// path.
+fn legacy_key_position(keypath: &str) -> Option<(LegacyKeyChain, u32)> {
+    let parts: Vec<&str> = keypath.split('/').collect();
+    let legacy_account = format!("{ZCASHD_LEGACY_ACCOUNT}'");
+    match parts.as_slice() {
+        ["m", "44'", _, account, change, index] if *account == legacy_account => {
+            let chain = match *change {
+                "0" => LegacyKeyChain::TransparentExternal,
+                "1" => LegacyKeyChain::TransparentInternal,
+                _ => return None,
+            };
+            Some((chain, index.parse().ok()?))
+        }
+        ["m", "32'", _, account, index] if *account == legacy_account => {
+            let index = index.strip_suffix('\'')?.parse().ok()?;
+            Some((LegacyKeyChain::Sapling, index))
+        }
+        _ => None,
+    }
+}
+
+/// The number of mnemonic-derived legacy keys the wallet holds at or beyond
+/// the counters its mnemonic HD chain records, as a wallet restored from an
+/// older backup may. zcashd would derive those indices again for new
+/// addresses; the keys themselves are exported as recorded.
+fn keys_beyond_legacy_counters(wallet: &ZcashdWallet) -> usize {
+    let Some(chain) = wallet.mnemonic_hd_chain() else {
+        return 0;
+    };
+    wallet
+        .keys()
+        .keypairs()
+        .map(|keypair| keypair.metadata())
+        .chain(wallet.sapling_keys().keypairs().map(|key| key.metadata()))
+        .filter(|metadata| metadata.seed_fp() == Some(chain.seed_fp()))
+        .filter_map(|metadata| legacy_key_position(metadata.hd_keypath()?))
+        .filter(|(key_chain, index)| {
+            let counter = match key_chain {
+                LegacyKeyChain::TransparentExternal => chain.legacy_tkey_external_counter(),
+                LegacyKeyChain::TransparentInternal => chain.legacy_tkey_internal_counter(),
+                LegacyKeyChain::Sapling => chain.legacy_sapling_key_counter(),
+            };
+            *index >= counter
+        })
+        .count()
+}
+
+/// The raw ZIP-32 fingerprints of the seeds the wallet holds: its mnemonic
+/// seed, `mnemonic_fp` (see [`mnemonic_seed_fingerprint_bytes`]), and its
+/// pre-mnemonic legacy HD seed.
+fn wallet_seed_fingerprints(
+    wallet: &ZcashdWallet,
+    mnemonic_fp: Option<[u8; 32]>,
+) -> Result<HashSet<[u8; 32]>, MigrateError> {
+    let mut fingerprints = HashSet::new();
+    fingerprints.extend(mnemonic_fp);
+    if let Some(seed) = wallet.legacy_hd_seed() {
+        let fp = zip32::fingerprint::SeedFingerprint::from_seed(seed.as_slice())
+            .ok_or(MigrateError::InvalidLegacySeedLength)?;
+        fingerprints.insert(fp.to_bytes());
+    }
+    Ok(fingerprints)
+}
+
+/// The foreign seeds of a wallet, with the number of its transparent and
+/// Sapling keys derived from each.
+///
+/// A foreign seed is one that key metadata names as a key's derivation root
+/// but that is not among the wallet's own seeds (see
+/// [`wallet_seed_fingerprints`]). A wallet whose seeds are unavailable (e.g.
+/// encrypted and skipped) has no foreign seeds, since its keys cannot be told
+/// apart.
+fn foreign_seed_key_counts(
+    wallet: &ZcashdWallet,
+    mnemonic_fp: Option<[u8; 32]>,
+) -> Result<BTreeMap<[u8; 32], usize>, MigrateError> {
+    let key_seeds = wallet
+        .keys()
+        .keypairs()
+        .map(|keypair| keypair.metadata())
+        .chain(wallet.sapling_keys().keypairs().map(|key| key.metadata()))
+        .filter_map(|metadata| metadata.seed_fp());
+    Ok(count_foreign_seeds(
+        &wallet_seed_fingerprints(wallet, mnemonic_fp)?,
+        key_seeds,
+    ))
+}
+
+fn count_foreign_seeds<'a>(
+    wallet_seeds: &HashSet<[u8; 32]>,
+    key_seeds: impl IntoIterator<Item = &'a [u8; 32]>,
+) -> BTreeMap<[u8; 32], usize> {
+    let mut counts = BTreeMap::new();
+    if wallet_seeds.is_empty() {
+        return counts;
+    }
+    for seed_fp in key_seeds {
+        if !wallet_seeds.contains(seed_fp) {
+            *counts.entry(*seed_fp).or_default() += 1;
+        }
+    }
+    counts
+}
+
+/// The BIP-44 coin type a unified account's keys are derived with.
+///
+/// zcashd derives every account with its own network's coin type (133 on
+/// mainnet, 1 on testnet and regtest), whatever the account metadata records.
+/// The two can disagree for a wallet moved between networks or written by a
+/// buggy build; the network's coin type is then used, with a warning.
+pub(crate) fn account_coin_type(
+    meta: &UnifiedAccountMetadata,
+    params: &impl consensus::Parameters,
+    warnings: &mut Vec<MigrationWarning>,
+) -> u32 {
+    let coin_type = params.network_type().coin_type();
+    if meta.bip_44_coin_type() != coin_type {
+        warnings.push(MigrationWarning::CoinTypeMismatch {
+            zip32_account_id: meta.zip32_account_id(),
+            recorded: meta.bip_44_coin_type(),
+            used: coin_type,
+        });
+    }
+    coin_type
+}
+
+/// The Orchard spending key of unified account `zip32_account_id`, derived
+/// from `seed` at `m/32'/coin_type'/account'` as zcashd does. zcashd records
+/// no Orchard spending keys, so this is the only way to recover them.
+pub(crate) fn orchard_spending_key(
+    seed: &[u8],
+    coin_type: u32,
+    zip32_account_id: u32,
+) -> Option<OrchardSpendingKey> {
+    let account = zip32::AccountId::try_from(zip32_account_id).ok()?;
+    OrchardSpendingKey::from_zip32_seed(seed, coin_type, account).ok()
+}
+
+/// Whether `seed` derives the Orchard spending key whose full viewing key is
+/// `fvk` for unified account `zip32_account_id`.
+fn derives_orchard_key(
+    seed: &[u8],
+    coin_type: u32,
+    zip32_account_id: u32,
+    fvk: &OrchardFvk,
+) -> bool {
+    orchard_spending_key(seed, coin_type, zip32_account_id)
+        .is_some_and(|sk| OrchardFvk::from(&sk) == *fvk)
+}
+
+/// The ZIP-32 derivation path of a unified account,
+/// `m/32'/coin_type'/account'`.
+pub(crate) fn account_derivation_path(coin_type: u32, zip32_account_id: u32) -> String {
+    format!("m/32'/{coin_type}'/{zip32_account_id}'")
+}
+
 /// The key scope implied by a BIP-44/ZIP-32 change component
 /// (0 = external receiving, 1 = internal change, 2 = ephemeral).
 pub(crate) fn scope_for_change(change: u32) -> KeyScope {
@@ -160,6 +448,7 @@ pub(crate) fn derivation_info_from_keypath(keypath: &str) -> Option<DerivationIn
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::Parse;
 
     #[test]
     fn keypath_parses_canonical_bip44_path() {
@@ -182,12 +471,137 @@ mod tests {
         assert!(derivation_info_from_keypath("m/44'/133'/0'/0'/5").is_none());
     }
 
+    #[test]
+    fn legacy_key_positions_are_read_from_keypaths() {
+        assert_eq!(
+            legacy_key_position("m/44'/133'/2147483647'/0/4"),
+            Some((LegacyKeyChain::TransparentExternal, 4))
+        );
+        assert_eq!(
+            legacy_key_position("m/44'/1'/2147483647'/1/0"),
+            Some((LegacyKeyChain::TransparentInternal, 0))
+        );
+        assert_eq!(
+            legacy_key_position("m/32'/133'/2147483647'/9'"),
+            Some((LegacyKeyChain::Sapling, 9))
+        );
+        // Keys of other accounts, and legacy Sapling indices that are not
+        // hardened.
+        assert_eq!(legacy_key_position("m/44'/133'/0'/0/4"), None);
+        assert_eq!(legacy_key_position("m/32'/133'/0'/9'"), None);
+        assert_eq!(legacy_key_position("m/32'/133'/2147483647'/9"), None);
+        assert_eq!(legacy_key_position("m/44'/133'/2147483647'/2/4"), None);
+    }
+
     #[test]
     fn keypath_rejects_too_few_components() {
         assert!(derivation_info_from_keypath("").is_none());
         assert!(derivation_info_from_keypath("5").is_none());
     }
 
+    /// Account metadata for account 3 with the given coin type.
+    fn account_metadata(coin_type: u32) -> UnifiedAccountMetadata {
+        let mut bytes = vec![0x11; 32]; // seed fingerprint
+        bytes.extend_from_slice(&coin_type.to_le_bytes());
+        bytes.extend_from_slice(&3u32.to_le_bytes());
+        bytes.extend_from_slice(&[0x22; 32]); // UFVK fingerprint
+        UnifiedAccountMetadata::parse_buf(&bytes, false).unwrap()
+    }
+
+    #[test]
+    fn coin_type_matches_network() {
+        let meta = account_metadata(133);
+        assert_eq!(meta.bip_44_coin_type(), 133);
+        let mut warnings = vec![];
+        assert_eq!(
+            account_coin_type(&meta, &consensus::MainNetwork, &mut warnings),
+            133
+        );
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn mismatched_coin_type_prefers_wallet_network() {
+        let meta = account_metadata(1);
+        let mut warnings = vec![];
+        let coin_type = account_coin_type(&meta, &consensus::MainNetwork, &mut warnings);
+        assert_eq!(coin_type, 133);
+        assert_eq!(
+            warnings,
+            [MigrationWarning::CoinTypeMismatch {
+                zip32_account_id: 3,
+                recorded: 1,
+                used: 133
+            }]
+        );
+        assert_eq!(
+            account_derivation_path(coin_type, meta.zip32_account_id()),
+            "m/32'/133'/3'"
+        );
+
+        let meta = account_metadata(133);
+        assert_eq!(
+            account_coin_type(&meta, &consensus::TestNetwork, &mut vec![]),
+            1
+        );
+    }
+
+    #[test]
+    fn keys_from_another_seed_are_counted_per_seed() {
+        let wallet_seed = [0x01; 32];
+        let old_seed = [0x02; 32];
+        let other_seed = [0x03; 32];
+        let key_seeds = [wallet_seed, old_seed, wallet_seed, old_seed, other_seed];
+
+        let counts = count_foreign_seeds(&HashSet::from([wallet_seed]), &key_seeds);
+        assert_eq!(counts, BTreeMap::from([(old_seed, 2), (other_seed, 1)]));
+    }
+
+    #[test]
+    fn keys_from_the_mnemonic_and_legacy_seeds_are_not_foreign() {
+        let mnemonic_seed = [0x01; 32];
+        let legacy_seed = [0x02; 32];
+        let key_seeds = [mnemonic_seed, legacy_seed, legacy_seed];
+
+        let wallet_seeds = HashSet::from([mnemonic_seed, legacy_seed]);
+        assert!(count_foreign_seeds(&wallet_seeds, &key_seeds).is_empty());
+        // Without the legacy seed, its keys look like those of a foreign one.
+        assert_eq!(
+            count_foreign_seeds(&HashSet::from([mnemonic_seed]), &key_seeds),
+            BTreeMap::from([(legacy_seed, 2)])
+        );
+    }
+
+    #[test]
+    fn keys_are_not_foreign_without_wallet_seeds() {
+        let key_seeds = [[0x01; 32], [0x02; 32]];
+        assert!(count_foreign_seeds(&HashSet::new(), &key_seeds).is_empty());
+    }
+
+    #[test]
+    fn orchard_spending_keys_are_derived_from_the_seed() {
+        use zcash_keys::keys::UnifiedSpendingKey;
+
+        let seed = [0x5a; 32];
+        let usk =
+            UnifiedSpendingKey::from_seed(&consensus::MainNetwork, &seed, zip32::AccountId::ZERO)
+                .unwrap();
+        let ufvk = usk.to_unified_full_viewing_key();
+        assert!(ufvk.sapling().is_some());
+        let fvk = ufvk.orchard().expect("Orchard component");
+
+        let sk = orchard_spending_key(&seed, 133, 0).unwrap();
+        assert_eq!(sk.to_bytes(), usk.orchard().to_bytes());
+        assert!(derives_orchard_key(&seed, 133, 0, fvk));
+        assert!(!derives_orchard_key(&seed, 133, 1, fvk));
+        assert!(!derives_orchard_key(&seed, 1, 0, fvk));
+        assert!(!derives_orchard_key(&[0x5b; 32], 133, 0, fvk));
+
+        // The key round-trips through its 32-byte encoding.
+        let parsed = OrchardSpendingKey::parse_buf(sk.to_bytes(), false).unwrap();
+        assert_eq!(parsed.to_bytes(), sk.to_bytes());
+    }
+
     #[test]
     fn scope_maps_change_component() {
         assert_eq!(scope_for_change(0), KeyScope::External);
@@ -1,27 +1,33 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use minicbor::Encoder;
 
 use secp256k1::PublicKey;
-use zcash_address::{ToAddress, ZcashAddress};
-use zcash_keys::keys::{ReceiverRequirement, UnifiedAddressRequest};
+use zcash_address::{
+    ToAddress, ZcashAddress,
+    unified::{self, Container, Encoding},
+};
+use zcash_keys::keys::{ReceiverRequirement, UnifiedAddressRequest, UnifiedFullViewingKey};
 use zcash_protocol::consensus;
 use zcash_transparent::address::TransparentAddress;
 use zip32::DiversifierIndex;
 
 use zewif::{
-    Address, KeyScope, Network, ProtocolAddress, Script, UnifiedAddress,
+    Address, Data, KeyScope, Network, ProtocolAddress, Script, UnifiedAddress,
     transparent::TransparentSpendAuthority,
 };
 
-use crate::migrate::MigrateError;
+use crate::migrate::{MigrateError, MigrationWarning};
 use crate::{
     ZcashdWallet,
     migrate::{
-        WalletAccounts,
+        ExportMode, KEY_DERIVATION_EXTENSION_KEY, SAPLING_VIEWING_KEYS_EXTENSION_KEY,
+        UNIFIED_ADDRESS_COMPONENT_EXTENSION_KEY, WalletAccounts, ZCASHD_EXTENSION_VENDOR,
         accounts::{derivation_info_from_keypath, scope_for_change},
-        primitives::address_network_from_zewif,
+        secrets::sapling_hrps,
     },
     zcashd_wallet::{
-        ReceiverType,
+        KeyMetadata, ReceiverType, UnifiedAddressMetadata, address_network_from_zewif,
         sprout::SproutPaymentAddress,
         transparent::{KeyPair, WatchScriptKind},
     },
@@ -29,16 +35,41 @@ use crate::{
 
 /// Attach every address recoverable from the wallet to the appropriate
 /// account: unified addresses to their unified account, and all transparent,
-/// legacy Sapling, and Sprout addresses to the synthesized legacy account.
+/// legacy Sapling, and Sprout addresses to the synthesized legacy account,
+/// except those whose keys were derived from a foreign seed, which go to that
+/// seed's account.
+///
+/// Each account's addresses are added grouped by protocol (transparent,
+/// Sapling, Sprout, then unified) and sorted by their string encoding within
+/// each group, so that migrating the same wallet always yields the same
+/// document.
+///
+/// In [`ExportMode::ViewOnly`], transparent addresses carry their public key
+/// instead of a spend authority, and each account with legacy Sapling keys
+/// records their extended full viewing keys (see
+/// [`SAPLING_VIEWING_KEYS_EXTENSION_KEY`]).
+///
+/// A legacy Sapling address that is the Sapling receiver of a unified address
+/// is linked to it (see [`UNIFIED_ADDRESS_COMPONENT_EXTENSION_KEY`]), and each
+/// such link is reported as a warning.
 pub(crate) fn attach_addresses(
     wallet: &ZcashdWallet,
     accounts: &mut WalletAccounts,
     params: &impl consensus::Parameters,
+    mode: ExportMode,
 ) -> Result<(), MigrateError> {
-    attach_transparent_addresses(wallet, accounts)?;
-    attach_sapling_addresses(wallet, accounts)?;
+    let unified_addresses = unified_addresses(wallet, accounts, params)?;
+    let components = unified_address_components(&unified_addresses);
+
+    attach_transparent_addresses(wallet, accounts, mode)?;
+    attach_sapling_addresses(wallet, accounts, &components)?;
+    if mode == ExportMode::ViewOnly {
+        attach_sapling_viewing_keys(wallet, accounts);
+    }
     attach_sprout_addresses(wallet, accounts);
-    attach_unified_addresses(wallet, accounts, params)?;
+    for (_, address, account_index) in unified_addresses {
+        accounts.accounts[account_index].add_address(address);
+    }
     Ok(())
 }
 
@@ -51,11 +82,15 @@ struct TransparentInfo {
     scope: Option<KeyScope>,
     redeem_script: Option<Script>,
     pubkey: Option<zewif::transparent::TransparentPubKey>,
+    /// The account of the address's key, when the key database holds one;
+    /// every other address belongs to the legacy account.
+    account_index: Option<usize>,
 }
 
 fn attach_transparent_addresses(
     wallet: &ZcashdWallet,
     accounts: &mut WalletAccounts,
+    mode: ExportMode,
 ) -> Result<(), MigrateError> {
     let network = wallet.network();
     let mut entries: HashMap<String, TransparentInfo> = HashMap::new();
@@ -70,8 +105,24 @@ fn attach_transparent_addresses(
         let addr_str = p2pkh_address_string(&pk, network);
         let (authority, scope) = transparent_spend_info(keypair);
         let entry = entries.entry(addr_str).or_default();
-        entry.spend_authority.get_or_insert(authority);
+        match mode {
+            ExportMode::Full => {
+                entry.spend_authority.get_or_insert(authority);
+            }
+            // With no private key in the document, the public key is what
+            // lets an importer watch the address.
+            ExportMode::ViewOnly => {
+                if let Ok(pk) = zewif::transparent::TransparentPubKey::from_bytes(
+                    keypair.pubkey().as_slice().to_vec(),
+                ) {
+                    entry.pubkey.get_or_insert(pk);
+                }
+            }
+        }
         entry.scope.get_or_insert(scope);
+        entry
+            .account_index
+            .get_or_insert(accounts.legacy_index_for(Some(keypair.metadata())));
     }
 
     // Watch-only imports (`importaddress` / `importpubkey`). P2PK entries carry
@@ -89,15 +140,21 @@ fn attach_transparent_addresses(
                         Ok(pk) => {
                             entry.pubkey.get_or_insert(pk);
                         }
-                        Err(e) => eprintln!(
-                            "warning: watch-only P2PK public key dropped: {e}"
-                        ),
+                        Err(e) => {
+                            accounts
+                                .warnings
+                                .push(MigrationWarning::UnparsableWatchOnlyPublicKey {
+                                    error: e.to_string(),
+                                })
+                        }
                     }
                     entry.scope.get_or_insert(KeyScope::Foreign);
                 }
-                Err(_) => {
-                    eprintln!("warning: watch-only P2PK script with unparsable public key dropped",)
-                }
+                Err(e) => accounts
+                    .warnings
+                    .push(MigrationWarning::UnparsableWatchOnlyPublicKey {
+                        error: e.to_string(),
+                    }),
             },
             WatchScriptKind::P2PKH(_) | WatchScriptKind::P2SH(_) => {
                 if let Some(addr_str) = watch.to_address_string(network) {
@@ -108,10 +165,13 @@ fn attach_transparent_addresses(
                         .get_or_insert(KeyScope::Foreign);
                 }
             }
-            WatchScriptKind::Other(_) => eprintln!(
-                "warning: watch-only script with no standard t-address encoding ({:?}) dropped",
-                watch.kind(),
-            ),
+            WatchScriptKind::Other(_) => {
+                accounts
+                    .warnings
+                    .push(MigrationWarning::NonStandardWatchOnlyScript(
+                        watch.kind().clone(),
+                    ))
+            }
         }
     }
 
@@ -128,7 +188,6 @@ fn attach_transparent_addresses(
     let mut sorted: Vec<(String, TransparentInfo)> = entries.into_iter().collect();
     sorted.sort_by(|(a, _), (b, _)| a.cmp(b));
 
-    let legacy = &mut accounts.accounts[accounts.legacy_index];
     for (addr_str, info) in sorted {
         let mut t_addr = zewif::transparent::Address::new(addr_str);
         if let Some(authority) = info.spend_authority {
@@ -146,7 +205,8 @@ fn attach_transparent_addresses(
         }
         let mut address = Address::new(ProtocolAddress::Transparent(t_addr));
         address.set_scope(info.scope.unwrap_or(KeyScope::External));
-        legacy.add_address(address);
+        let account_index = info.account_index.unwrap_or(accounts.legacy_index);
+        accounts.accounts[account_index].add_address(address);
     }
 
     Ok(())
@@ -155,7 +215,7 @@ fn attach_transparent_addresses(
 /// The spend authority and key scope for a transparent keypair: HD-derived
 /// keys carry their derivation (change component determines the scope);
 /// independently generated keys are `Imported` and treated as foreign.
-fn transparent_spend_info(keypair: &KeyPair) -> (TransparentSpendAuthority, KeyScope) {
+pub(crate) fn transparent_spend_info(keypair: &KeyPair) -> (TransparentSpendAuthority, KeyScope) {
     if let Some(hd_path) = keypair.metadata().hd_keypath()
         && let Some(info) = derivation_info_from_keypath(hd_path)
     {
@@ -165,22 +225,33 @@ fn transparent_spend_info(keypair: &KeyPair) -> (TransparentSpendAuthority, KeyS
     (TransparentSpendAuthority::Imported, KeyScope::Foreign)
 }
 
-fn p2pkh_address_string(pk: &PublicKey, network: &Network) -> String {
+pub(crate) fn p2pkh_address_string(pk: &PublicKey, network: &Network) -> String {
     let TransparentAddress::PublicKeyHash(hash) = TransparentAddress::from_pubkey(pk) else {
         unreachable!("from_pubkey always returns PublicKeyHash");
     };
     ZcashAddress::from_transparent_p2pkh(address_network_from_zewif(network), hash).to_string()
 }
 
-fn attach_sapling_addresses(wallet: &ZcashdWallet, accounts: &mut WalletAccounts) -> Result<(), MigrateError> {
+fn attach_sapling_addresses(
+    wallet: &ZcashdWallet,
+    accounts: &mut WalletAccounts,
+    components: &BTreeMap<String, BTreeSet<String>>,
+) -> Result<(), MigrateError> {
     let network = wallet.network();
     let legacy_index = accounts.legacy_index;
     let mut emitted: HashSet<zewif::sapling::SaplingIncomingViewingKey> = HashSet::new();
 
-    // Collect (address string, protocol address, scope) and emit sorted by
-    // address, so the migrated wallet is reproducible across runs (the source
-    // maps have no stable iteration order).
-    let mut collected: Vec<(String, zewif::sapling::Address, KeyScope)> = Vec::new();
+    // Collect (address string, protocol address, scope, account index, key
+    // derivation) and emit sorted by address, so the migrated wallet is
+    // reproducible across runs (the source maps have no stable iteration
+    // order).
+    let mut collected: Vec<(
+        String,
+        zewif::sapling::Address,
+        KeyScope,
+        usize,
+        Option<Data>,
+    )> = Vec::new();
 
     // Spend-capable and view-only-with-default-address Sapling addresses have a
     // `sapzaddr` record.
@@ -190,7 +261,14 @@ fn attach_sapling_addresses(wallet: &ZcashdWallet, accounts: &mut WalletAccounts
         // part of the address encoding itself, not the ZIP 32 diversifier
         // index; legacy zcashd records no index, so none is set here.
         let sapling_addr = zewif::sapling::Address::new(addr_str.clone());
-        collected.push((addr_str, sapling_addr, KeyScope::External));
+        let metadata = wallet.sapling_keys().get(ivk).map(|key| key.metadata());
+        collected.push((
+            addr_str,
+            sapling_addr,
+            KeyScope::External,
+            accounts.legacy_index_for(metadata),
+            metadata.and_then(encode_key_derivation),
+        ));
         emitted.insert(*ivk);
     }
 
@@ -200,30 +278,113 @@ fn attach_sapling_addresses(wallet: &ZcashdWallet, accounts: &mut WalletAccounts
         if !emitted.insert(*ivk) {
             continue;
         }
-        let (_j, payment_address) = extfvk.to_diversifiable_full_viewing_key().default_address();
-        let addr_str = ZcashAddress::from_sapling(
-            address_network_from_zewif(network),
-            payment_address.to_bytes(),
-        )
-        .to_string();
+        let addr_str = sapling_default_address_string(extfvk, network);
         // Imported view-only key material not derived from account keys.
         collected.push((
             addr_str.clone(),
             zewif::sapling::Address::new(addr_str),
             KeyScope::Foreign,
+            legacy_index,
+            None,
         ));
     }
 
-    collected.sort_by(|(a, _, _), (b, _, _)| a.cmp(b));
-    for (_, sapling_addr, scope) in collected {
+    collected.sort_by(|(a, ..), (b, ..)| a.cmp(b));
+    for (addr_str, sapling_addr, scope, account_index, derivation) in collected {
         let mut address = Address::new(ProtocolAddress::Sapling(Box::new(sapling_addr)));
         address.set_scope(scope);
-        accounts.accounts[legacy_index].add_address(address);
+        if let Some(derivation) = derivation {
+            address.extensions_mut().add(
+                ZCASHD_EXTENSION_VENDOR,
+                KEY_DERIVATION_EXTENSION_KEY,
+                derivation,
+            );
+        }
+        if let Some(unified_addresses) = components.get(&addr_str) {
+            accounts
+                .warnings
+                .push(MigrationWarning::SaplingAddressLinked {
+                    address: addr_str.clone(),
+                    unified_addresses: unified_addresses.iter().cloned().collect(),
+                });
+            address.extensions_mut().add(
+                ZCASHD_EXTENSION_VENDOR,
+                UNIFIED_ADDRESS_COMPONENT_EXTENSION_KEY,
+                encode_unified_address_component(unified_addresses),
+            );
+        }
+        accounts.accounts[account_index].add_address(address);
     }
 
     Ok(())
 }
 
+/// The seed fingerprint and derivation path recorded in a key's metadata, if
+/// either is. See [`KEY_DERIVATION_EXTENSION_KEY`].
+fn encode_key_derivation(metadata: &KeyMetadata) -> Option<Data> {
+    let seed_fp = metadata.seed_fp();
+    let hd_keypath = metadata.hd_keypath();
+    let len = seed_fp.is_some() as u64 + hd_keypath.is_some() as u64;
+    if len == 0 {
+        return None;
+    }
+    let mut e = Encoder::new(Vec::new());
+    e.map(len).expect("writing to a Vec cannot fail");
+    // Keys in RFC 8949 deterministic order: shorter first.
+    if let Some(seed_fp) = seed_fp {
+        e.str("seed_fp")
+            .and_then(|e| e.bytes(seed_fp))
+            .expect("writing to a Vec cannot fail");
+    }
+    if let Some(hd_keypath) = hd_keypath {
+        e.str("hd_keypath")
+            .and_then(|e| e.str(hd_keypath))
+            .expect("writing to a Vec cannot fail");
+    }
+    Some(Data::from_vec(e.into_writer()))
+}
+
+/// Record on each account the extended full viewing keys of its legacy Sapling
+/// keys: those of its spending keys, which a full export carries only in the
+/// secret store, and, on the legacy account, the imported viewing keys. See
+/// [`SAPLING_VIEWING_KEYS_EXTENSION_KEY`].
+fn attach_sapling_viewing_keys(wallet: &ZcashdWallet, accounts: &mut WalletAccounts) {
+    let (_, extfvk_hrp) = sapling_hrps(wallet.network());
+    let mut viewing_keys: BTreeMap<usize, BTreeSet<String>> = BTreeMap::new();
+    for sapling_key in wallet.sapling_keys().keypairs() {
+        #[allow(deprecated)]
+        let extfvk = sapling_key.extsk().to_extended_full_viewing_key();
+        viewing_keys
+            .entry(accounts.legacy_index_for(Some(sapling_key.metadata())))
+            .or_default()
+            .insert(zcash_keys::encoding::encode_extended_full_viewing_key(
+                extfvk_hrp, &extfvk,
+            ));
+    }
+    for extfvk in wallet.sapling_extended_full_viewing_keys().values() {
+        viewing_keys
+            .entry(accounts.legacy_index)
+            .or_default()
+            .insert(zcash_keys::encoding::encode_extended_full_viewing_key(
+                extfvk_hrp, extfvk,
+            ));
+    }
+
+    for (account_index, keys) in viewing_keys {
+        let mut e = Encoder::new(Vec::new());
+        e.array(keys.len() as u64)
+            .expect("writing to a Vec cannot fail");
+        for key in &keys {
+            e.str(key).expect("writing to a Vec cannot fail");
+        }
+        accounts.accounts[account_index].extensions_mut().add(
+            ZCASHD_EXTENSION_VENDOR,
+            SAPLING_VIEWING_KEYS_EXTENSION_KEY,
+            Data::from_vec(e.into_writer()),
+        );
+    }
+}
+
 fn attach_sprout_addresses(wallet: &ZcashdWallet, accounts: &mut WalletAccounts) {
     let Some(sprout_keys) = wallet.sprout_keys() else {
         return;
@@ -245,13 +406,17 @@ fn attach_sprout_addresses(wallet: &ZcashdWallet, accounts: &mut WalletAccounts)
     }
 }
 
-fn attach_unified_addresses(
+/// The unified addresses of the wallet's `unifiedaddrmeta` records, as
+/// (address string, address, account index), sorted by address as for the
+/// other protocols.
+fn unified_addresses(
     wallet: &ZcashdWallet,
-    accounts: &mut WalletAccounts,
+    accounts: &WalletAccounts,
     params: &impl consensus::Parameters,
-) -> Result<(), MigrateError> {
+) -> Result<Vec<(String, Address, usize)>, MigrateError> {
     let unified_accounts = wallet.unified_accounts();
 
+    let mut collected: Vec<(String, Address, usize)> = Vec::new();
     for metadata in &unified_accounts.address_metadata {
         let ufvk = unified_accounts
             .full_viewing_keys
@@ -260,39 +425,148 @@ fn attach_unified_addresses(
                 fingerprint: metadata.key_id.to_hex(),
             })?;
 
-        let j = DiversifierIndex::from(metadata.diversifier_index);
-        let require = |present: bool| {
-            if present {
-                ReceiverRequirement::Require
-            } else {
-                ReceiverRequirement::Omit
-            }
-        };
-        let request = UnifiedAddressRequest::custom(
-            require(metadata.receiver_types.contains(&ReceiverType::Orchard)),
-            require(metadata.receiver_types.contains(&ReceiverType::Sapling)),
-            require(metadata.receiver_types.contains(&ReceiverType::P2PKH)),
-        )
-        .map_err(MigrateError::InvalidReceiverTypes)?;
-
-        let ua_str = ufvk.address(j, request)?.encode(params);
+        let ua_str = unified_address_string(metadata, ufvk, params)?;
 
-        let mut unified_address = UnifiedAddress::new(ua_str);
+        let mut unified_address = UnifiedAddress::new(ua_str.clone());
         unified_address
             .set_diversifier_index(zewif::DiversifierIndex::new(metadata.diversifier_index));
 
         let mut address = Address::new(ProtocolAddress::Unified(Box::new(unified_address)));
         address.set_scope(KeyScope::External);
 
-        match accounts.ufvk_index.get(&metadata.key_id) {
-            Some(&idx) => accounts.accounts[idx].add_address(address),
-            None => accounts.accounts[accounts.legacy_index].add_address(address),
+        let account_index = accounts
+            .ufvk_index
+            .get(&metadata.key_id)
+            .copied()
+            .unwrap_or(accounts.legacy_index);
+        collected.push((ua_str, address, account_index));
+    }
+
+    collected.sort_by(|(a, _, _), (b, _, _)| a.cmp(b));
+    Ok(collected)
+}
+
+/// The unified addresses containing each Sapling receiver, keyed by the
+/// receiver's encoding as a standalone Sapling address.
+fn unified_address_components(
+    unified_addresses: &[(String, Address, usize)],
+) -> BTreeMap<String, BTreeSet<String>> {
+    let mut components: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for (ua_str, ..) in unified_addresses {
+        if let Some(sapling_receiver) = sapling_receiver_string(ua_str) {
+            components
+                .entry(sapling_receiver)
+                .or_default()
+                .insert(ua_str.clone());
         }
     }
+    components
+}
+
+/// The Sapling receiver of an encoded unified address, encoded as a
+/// standalone Sapling address, if it has one.
+fn sapling_receiver_string(unified_address: &str) -> Option<String> {
+    let (network, address) = unified::Address::decode(unified_address).ok()?;
+    address
+        .items()
+        .into_iter()
+        .find_map(|receiver| match receiver {
+            unified::Receiver::Sapling(bytes) => {
+                Some(ZcashAddress::from_sapling(network, bytes).to_string())
+            }
+            _ => None,
+        })
+}
+
+/// The unified addresses a legacy Sapling address is the receiver of. See
+/// [`UNIFIED_ADDRESS_COMPONENT_EXTENSION_KEY`].
+fn encode_unified_address_component(unified_addresses: &BTreeSet<String>) -> Data {
+    let mut e = Encoder::new(Vec::new());
+    e.array(unified_addresses.len() as u64)
+        .expect("writing to a Vec cannot fail");
+    for ua_str in unified_addresses {
+        e.str(ua_str).expect("writing to a Vec cannot fail");
+    }
+    Data::from_vec(e.into_writer())
+}
 
+/// The canonical default address of a Sapling extended full viewing key, as
+/// recovered for view-only keys imported without a `sapzaddr` record.
+pub(crate) fn sapling_default_address_string(
+    extfvk: &::sapling::zip32::ExtendedFullViewingKey,
+    network: &Network,
+) -> String {
+    let (_j, payment_address) = extfvk.to_diversifiable_full_viewing_key().default_address();
+    ZcashAddress::from_sapling(
+        address_network_from_zewif(network),
+        payment_address.to_bytes(),
+    )
+    .to_string()
+}
+
+/// Derive and encode the unified address described by a `unifiedaddrmeta`
+/// record from its account's UFVK.
+pub(crate) fn unified_address_string(
+    metadata: &UnifiedAddressMetadata,
+    ufvk: &UnifiedFullViewingKey,
+    params: &impl consensus::Parameters,
+) -> Result<String, MigrateError> {
+    let j = DiversifierIndex::from(metadata.diversifier_index);
+    let request = unified_address_request(&metadata.receiver_types)?;
+    let encoded = ufvk.address(j, request)?.encode(params);
+    check_receiver_types(&encoded, &metadata.receiver_types)?;
+    Ok(encoded)
+}
+
+/// Check that a derived unified address has exactly the receiver types its
+/// metadata records, failing with [`MigrateError::ReceiverTypeMismatch`] if
+/// not.
+fn check_receiver_types(
+    address: &str,
+    recorded: &HashSet<ReceiverType>,
+) -> Result<(), MigrateError> {
+    let actual = ReceiverType::from_unified_address_str(address)?;
+    if actual != *recorded {
+        let sorted = |types: &HashSet<ReceiverType>| {
+            let mut types: Vec<ReceiverType> = types.iter().copied().collect();
+            types.sort_by_key(|t| *t as u8);
+            types
+        };
+        return Err(MigrateError::ReceiverTypeMismatch {
+            address: address.to_string(),
+            recorded: sorted(recorded),
+            actual: sorted(&actual),
+        });
+    }
     Ok(())
 }
 
+/// The request that reproduces a unified address with exactly the receivers
+/// recorded in its metadata.
+///
+/// Each receiver is required if recorded and omitted otherwise, so that
+/// derivation fails rather than silently producing a different address when
+/// the UFVK lacks a recorded receiver's component. In particular no Sapling
+/// receiver is requested for an Orchard-only address (as created with
+/// `z_getaddressforaccount <account> '["orchard"]'` after NU5).
+pub(crate) fn unified_address_request(
+    receiver_types: &HashSet<ReceiverType>,
+) -> Result<UnifiedAddressRequest, MigrateError> {
+    let require = |receiver_type: ReceiverType| {
+        if receiver_types.contains(&receiver_type) {
+            ReceiverRequirement::Require
+        } else {
+            ReceiverRequirement::Omit
+        }
+    };
+    UnifiedAddressRequest::custom(
+        require(ReceiverType::Orchard),
+        require(ReceiverType::Sapling),
+        require(ReceiverType::P2PKH),
+    )
+    .map_err(MigrateError::InvalidReceiverTypes)
+}
+
 /// Encode a Sprout payment address as its canonical `zc`-prefixed string.
 pub(crate) fn sprout_address_string(addr: &SproutPaymentAddress, network: &Network) -> String {
     let mut bytes = [0u8; 64];
@@ -300,3 +574,194 @@ pub(crate) fn sprout_address_string(addr: &SproutPaymentAddress, network: &Netwo
     bytes[32..].copy_from_slice(AsRef::<[u8; 32]>::as_ref(&addr.pk_enc()));
     ZcashAddress::from_sprout(address_network_from_zewif(network), bytes).to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use zcash_keys::{address::UnifiedAddress, keys::UnifiedSpendingKey};
+    use zcash_protocol::consensus::MainNetwork;
+
+    use super::*;
+    use crate::parser::Parse;
+
+    fn ufvk() -> UnifiedFullViewingKey {
+        UnifiedSpendingKey::from_seed(&MainNetwork, &[0x5a; 32], zip32::AccountId::ZERO)
+            .unwrap()
+            .to_unified_full_viewing_key()
+    }
+
+    /// Address metadata at the given diversifier index with the given
+    /// receivers.
+    fn address_metadata(j: [u8; 11], receiver_types: &[ReceiverType]) -> UnifiedAddressMetadata {
+        let mut bytes = vec![0x22; 32]; // UFVK fingerprint
+        bytes.extend_from_slice(&j);
+        bytes.push(receiver_types.len() as u8);
+        bytes.extend(receiver_types.iter().map(|t| *t as u8));
+        UnifiedAddressMetadata::parse_buf(&bytes, false).unwrap()
+    }
+
+    /// The mainnet unified address `encoded` decodes to.
+    fn decode_unified_address(encoded: &str) -> UnifiedAddress {
+        match zcash_keys::address::Address::decode(&MainNetwork, encoded) {
+            Some(zcash_keys::address::Address::Unified(ua)) => ua,
+            other => panic!("expected a unified address, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn orchard_only_address_has_only_an_orchard_receiver() {
+        let ufvk = ufvk();
+        // Every diversifier index is valid for Orchard.
+        let metadata = address_metadata([0; 11], &[ReceiverType::Orchard]);
+
+        let encoded = unified_address_string(&metadata, &ufvk, &MainNetwork).unwrap();
+        assert!(encoded.starts_with("u1"));
+
+        let ua = decode_unified_address(&encoded);
+        assert!(ua.has_orchard());
+        assert!(!ua.has_sapling());
+        assert!(!ua.has_transparent());
+        let expected = ufvk
+            .orchard()
+            .unwrap()
+            .address_at(0u32, orchard::keys::Scope::External);
+        assert_eq!(ua.orchard(), Some(&expected));
+    }
+
+    #[test]
+    fn address_has_exactly_the_recorded_receivers() {
+        let ufvk = ufvk();
+        // Not every diversifier index is valid for Sapling.
+        let (j, _) = ufvk.sapling().unwrap().default_address();
+        let metadata = address_metadata(
+            *j.as_bytes(),
+            &[
+                ReceiverType::Orchard,
+                ReceiverType::Sapling,
+                ReceiverType::P2PKH,
+            ],
+        );
+
+        let encoded = unified_address_string(&metadata, &ufvk, &MainNetwork).unwrap();
+        let ua = decode_unified_address(&encoded);
+        assert!(ua.has_orchard());
+        assert!(ua.has_sapling());
+        assert!(ua.has_transparent());
+    }
+
+    #[test]
+    fn receiver_types_missing_from_the_address_are_reported() {
+        let ufvk = ufvk();
+        let metadata = address_metadata([0; 11], &[ReceiverType::Orchard]);
+        let encoded = unified_address_string(&metadata, &ufvk, &MainNetwork).unwrap();
+
+        let recorded = HashSet::from([ReceiverType::Orchard, ReceiverType::Sapling]);
+        match check_receiver_types(&encoded, &recorded) {
+            Err(MigrateError::ReceiverTypeMismatch {
+                address,
+                recorded,
+                actual,
+            }) => {
+                assert_eq!(address, encoded);
+                assert_eq!(recorded, [ReceiverType::Sapling, ReceiverType::Orchard]);
+                assert_eq!(actual, [ReceiverType::Orchard]);
+            }
+            other => panic!("expected a receiver type mismatch, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn transparent_only_request_is_rejected() {
+        let receiver_types = HashSet::from([ReceiverType::P2PKH]);
+        assert!(matches!(
+            unified_address_request(&receiver_types),
+            Err(MigrateError::InvalidReceiverTypes(_))
+        ));
+    }
+
+    #[test]
+    fn sapling_receivers_are_linked_to_their_unified_addresses() {
+        let ufvk = ufvk();
+        let (j, sapling_receiver) = ufvk.sapling().unwrap().default_address();
+        let with_sapling = unified_address_string(
+            &address_metadata(
+                *j.as_bytes(),
+                &[ReceiverType::Orchard, ReceiverType::Sapling],
+            ),
+            &ufvk,
+            &MainNetwork,
+        )
+        .unwrap();
+        let orchard_only = unified_address_string(
+            &address_metadata([0; 11], &[ReceiverType::Orchard]),
+            &ufvk,
+            &MainNetwork,
+        )
+        .unwrap();
+        let unified_addresses: Vec<(String, Address, usize)> = [&with_sapling, &orchard_only]
+            .into_iter()
+            .map(|ua_str| {
+                let address = Address::new(ProtocolAddress::Unified(Box::new(
+                    zewif::UnifiedAddress::new(ua_str.clone()),
+                )));
+                (ua_str.clone(), address, 0)
+            })
+            .collect();
+
+        // The receiver as zcashd's `sapzaddr` record of it is exported.
+        let standalone = ZcashAddress::from_sapling(
+            zcash_protocol::consensus::NetworkType::Main,
+            sapling_receiver.to_bytes(),
+        )
+        .to_string();
+        let components = unified_address_components(&unified_addresses);
+        assert_eq!(
+            components,
+            BTreeMap::from([(standalone, BTreeSet::from([with_sapling.clone()]))])
+        );
+
+        let data = encode_unified_address_component(&BTreeSet::from([with_sapling.clone()]));
+        let mut d = minicbor::Decoder::new(data.as_slice());
+        assert_eq!(d.array().unwrap(), Some(1));
+        assert_eq!(d.str().unwrap(), with_sapling);
+    }
+
+    /// The seed fingerprint and keypath recorded for a key's address.
+    fn decode_key_derivation(data: &Data) -> (Option<[u8; 32]>, Option<String>) {
+        let mut d = minicbor::Decoder::new(data.as_slice());
+        let (mut seed_fp, mut hd_keypath) = (None, None);
+        for _ in 0..d.map().unwrap().unwrap() {
+            match d.str().unwrap() {
+                "seed_fp" => seed_fp = Some(d.bytes().unwrap().try_into().unwrap()),
+                "hd_keypath" => hd_keypath = Some(d.str().unwrap().to_string()),
+                key => panic!("unexpected key {key}"),
+            }
+        }
+        (seed_fp, hd_keypath)
+    }
+
+    #[test]
+    fn key_derivation_records_the_seed_of_each_key() {
+        let mnemonic_seed = [0x01; 32];
+        let legacy_seed = [0x02; 32];
+        for (seed_fp, hd_keypath) in [
+            (mnemonic_seed, "m/32'/133'/2147483647'/0'"),
+            (legacy_seed, "m/32'/133'/0'"),
+        ] {
+            let metadata = KeyMetadata::new(None, Some(hd_keypath.to_string()), Some(seed_fp));
+            let data = encode_key_derivation(&metadata).unwrap();
+            assert_eq!(
+                decode_key_derivation(&data),
+                (Some(seed_fp), Some(hd_keypath.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn key_derivation_holds_only_recorded_fields() {
+        let seed_only = KeyMetadata::new(None, None, Some([0x02; 32]));
+        let data = encode_key_derivation(&seed_only).unwrap();
+        assert_eq!(decode_key_derivation(&data), (Some([0x02; 32]), None));
+
+        assert!(encode_key_derivation(&KeyMetadata::new(None, None, None)).is_none());
+    }
+}
@@ -0,0 +1,577 @@
+use std::collections::{HashMap, HashSet};
+
+use orchard::keys::{IncomingViewingKey as OrchardIvk, Scope};
+use secp256k1::PublicKey;
+use zcash_transparent::address::TransparentAddress;
+use zewif::TxId;
+
+use crate::{
+    ZcashdWallet,
+    migrate::{
+        AccountFingerprint, MigrateError, TransactionAccounts, TransactionAttribution,
+        TransactionClassification, explain_transaction_attribution,
+    },
+    zcashd_wallet::{UfvkFingerprint, WalletTx, is_confirmed, sapling_incoming_viewing_keys},
+};
+
+/// A rough figure for an account's funds, for comparing a migration against
+/// what zcashd reported. See [`estimate_balances`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BalanceEstimate {
+    /// The value, in zatoshis, of the account's unspent transparent outputs:
+    /// P2PKH outputs of confirmed transactions paid to the account's keys
+    /// that no wallet transaction spends.
+    pub transparent_zats: u64,
+    /// Sapling notes received by the account in confirmed transactions that
+    /// no wallet transaction spends. A note with no recorded nullifier counts
+    /// as unspent.
+    pub unspent_sapling_notes: usize,
+    /// Orchard notes received by the account in confirmed transactions, from
+    /// zcashd's Orchard transaction metadata. zcashd does not record which
+    /// note an action spends, so spent notes are included.
+    pub orchard_notes: usize,
+    /// Confirmed transactions paying the account without spending its funds.
+    pub received_transactions: usize,
+    /// Confirmed transactions spending the account's funds, other than
+    /// internal transfers.
+    pub sent_transactions: usize,
+    /// Confirmed internal transfers (see
+    /// [`TransactionClassification::InternalTransfer`]) out of or into the
+    /// account, which are counted as neither sent nor received.
+    pub internal_transfers: usize,
+}
+
+/// The balance estimates of a wallet's unified accounts and of its funds
+/// outside them, computed together. See [`estimate_balances`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BalanceEstimates {
+    /// Each unified account's estimate, keyed by its UFVK fingerprint.
+    pub accounts: HashMap<UfvkFingerprint, BalanceEstimate>,
+    /// The estimate of the funds outside the unified accounts. See
+    /// [`estimate_legacy_balance`].
+    pub legacy: BalanceEstimate,
+}
+
+impl BalanceEstimates {
+    /// Estimate the balances of all the wallet's funds. This builds the
+    /// migration's accounts, and so derives the wallet's mnemonic seed, once;
+    /// prefer it to calling both [`estimate_balances`] and
+    /// [`estimate_legacy_balance`].
+    pub fn from_wallet(wallet: &ZcashdWallet) -> Result<Self, MigrateError> {
+        let attribution = explain_transaction_attribution(wallet)?;
+        let routes = BalanceRoutes::from_wallet(wallet, &attribution);
+        let mut estimates = estimate(wallet.transactions(), &routes);
+        let legacy = estimates.remove(&None).unwrap_or_default();
+        Ok(Self {
+            accounts: estimates
+                .into_iter()
+                .filter_map(|(owner, estimate)| owner.map(|fingerprint| (fingerprint, estimate)))
+                .collect(),
+            legacy,
+        })
+    }
+}
+
+/// Estimate the balance of each of the wallet's unified accounts from its
+/// own records, keyed by the account's UFVK fingerprint.
+///
+/// This is an estimate: it has no access to the chain, so it sees only the
+/// transactions the wallet recorded, and trusts their confirmation status as
+/// of the wallet's last sync. An output counts as spent if any wallet
+/// transaction spends it, confirmed or not, as zcashd does. Transparent keys
+/// belong to the unified account whose ZIP-32 account index their HD keypath
+/// names, and shielded notes to the account whose UFVK has their incoming
+/// viewing key. Everything else is left to [`estimate_legacy_balance`].
+///
+/// Transactions are counted as the migration classifies them (see
+/// [`explain_transaction_attribution`]): an internal transfer between two
+/// accounts counts once for each, and for neither as sent or received.
+///
+/// To estimate the legacy balance as well, use
+/// [`BalanceEstimates::from_wallet`], which builds the accounts only once.
+pub fn estimate_balances(
+    wallet: &ZcashdWallet,
+) -> Result<HashMap<UfvkFingerprint, BalanceEstimate>, MigrateError> {
+    Ok(BalanceEstimates::from_wallet(wallet)?.accounts)
+}
+
+/// Estimate the balance held outside the wallet's unified accounts: by
+/// legacy and imported keys, and by keys derived from foreign seeds. See
+/// [`estimate_balances`].
+pub fn estimate_legacy_balance(wallet: &ZcashdWallet) -> Result<BalanceEstimate, MigrateError> {
+    Ok(BalanceEstimates::from_wallet(wallet)?.legacy)
+}
+
+/// Which unified account, if any, owns each of the wallet's keys. `None`
+/// stands for funds outside the unified accounts.
+#[derive(Default)]
+struct BalanceRoutes {
+    accounts: Vec<UfvkFingerprint>,
+    /// Owned P2PKH key hashes.
+    transparent: HashMap<[u8; 20], Option<UfvkFingerprint>>,
+    /// Sapling incoming viewing keys of the unified accounts.
+    sapling: HashMap<[u8; 32], UfvkFingerprint>,
+    /// Orchard incoming viewing keys of the unified accounts.
+    orchard: Vec<(UfvkFingerprint, Vec<OrchardIvk>)>,
+    /// How each classified transaction moves each owner's funds.
+    flows: HashMap<TxId, Vec<(Option<UfvkFingerprint>, TransactionFlow)>>,
+}
+
+/// How a transaction moves an owner's funds, counted once per owner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TransactionFlow {
+    Received,
+    Sent,
+    Internal,
+}
+
+impl BalanceRoutes {
+    fn from_wallet(wallet: &ZcashdWallet, attribution: &TransactionAttribution) -> Self {
+        let unified = wallet.unified_accounts();
+        let by_account_id: HashMap<u32, UfvkFingerprint> = unified
+            .account_metadata
+            .values()
+            .map(|metadata| (metadata.zip32_account_id(), *metadata.ufvk_fingerprint()))
+            .collect();
+
+        let mut routes = BalanceRoutes {
+            accounts: unified.account_metadata.keys().copied().collect(),
+            ..Default::default()
+        };
+        for keypair in wallet.keys().keypairs() {
+            let Ok(pk) = PublicKey::from_slice(keypair.pubkey().as_slice()) else {
+                continue;
+            };
+            let TransparentAddress::PublicKeyHash(hash) = TransparentAddress::from_pubkey(&pk)
+            else {
+                continue;
+            };
+            let owner = keypair
+                .metadata()
+                .hd_keypath()
+                .map(String::as_str)
+                .and_then(bip44_account_id)
+                .and_then(|account_id| by_account_id.get(&account_id).copied());
+            routes.transparent.insert(hash, owner);
+        }
+        for (fingerprint, ufvk) in &unified.full_viewing_keys {
+            for ivk in sapling_incoming_viewing_keys(ufvk) {
+                routes.sapling.insert(*ivk.as_bytes(), *fingerprint);
+            }
+            if let Some(fvk) = ufvk.orchard() {
+                routes.orchard.push((
+                    *fingerprint,
+                    vec![fvk.to_ivk(Scope::External), fvk.to_ivk(Scope::Internal)],
+                ));
+            }
+        }
+
+        // The migration's accounts outside the unified accounts are all
+        // funds outside them.
+        let owner = |account: &AccountFingerprint| {
+            unified
+                .account_metadata
+                .values()
+                .find(|metadata| {
+                    account.seed_fingerprint() == Some(metadata.seed_fingerprint())
+                        && account.zip32_account_id() == metadata.zip32_account_id()
+                })
+                .map(|metadata| *metadata.ufvk_fingerprint())
+        };
+        for (txid, accounts) in attribution.accounts() {
+            let classification = &attribution.classifications()[txid];
+            routes
+                .flows
+                .insert(*txid, transaction_flows(classification, accounts, owner));
+        }
+        routes
+    }
+
+    fn orchard_owner(&self, ivk: &OrchardIvk) -> Option<UfvkFingerprint> {
+        self.orchard
+            .iter()
+            .find(|(_, ivks)| ivks.contains(ivk))
+            .map(|(fingerprint, _)| *fingerprint)
+    }
+}
+
+/// How a transaction classified as `classification` moves the funds of the
+/// owners of `accounts`. An owner spent from sends, unless the transaction is
+/// an internal transfer, and an owner only paid receives. When no spend was
+/// traced, the owners a sent transaction pays (its change) are taken to send.
+fn transaction_flows(
+    classification: &TransactionClassification,
+    accounts: &TransactionAccounts,
+    owner: impl Fn(&AccountFingerprint) -> Option<UfvkFingerprint>,
+) -> Vec<(Option<UfvkFingerprint>, TransactionFlow)> {
+    let flows: Vec<(&AccountFingerprint, TransactionFlow)> = match classification {
+        TransactionClassification::InternalTransfer { from, to } => vec![
+            (from, TransactionFlow::Internal),
+            (to, TransactionFlow::Internal),
+        ],
+        TransactionClassification::Sent if accounts.spent_from.is_empty() => accounts
+            .paid_to
+            .iter()
+            .map(|account| (account, TransactionFlow::Sent))
+            .collect(),
+        _ => accounts
+            .spent_from
+            .iter()
+            .map(|account| (account, TransactionFlow::Sent))
+            .chain(
+                accounts
+                    .paid_to
+                    .iter()
+                    .map(|account| (account, TransactionFlow::Received)),
+            )
+            .collect(),
+    };
+    // Each owner's first flow counts: spending outweighs receiving change.
+    let mut owner_flows: Vec<(Option<UfvkFingerprint>, TransactionFlow)> = Vec::new();
+    for (account, flow) in flows {
+        let owner = owner(account);
+        if !owner_flows.iter().any(|(seen, _)| *seen == owner) {
+            owner_flows.push((owner, flow));
+        }
+    }
+    owner_flows
+}
+
+/// The ZIP-32 account index of a BIP-44 keypath, `m/44'/coin'/account'/...`.
+fn bip44_account_id(keypath: &str) -> Option<u32> {
+    let mut parts = keypath.split('/');
+    if parts.next()? != "m" || parts.next()? != "44'" {
+        return None;
+    }
+    parts.next()?;
+    parts.next()?.strip_suffix('\'')?.parse().ok()
+}
+
+/// The balance estimate of each owner in `routes`, computed over the
+/// wallet's transaction graph.
+fn estimate(
+    transactions: &HashMap<TxId, WalletTx>,
+    routes: &BalanceRoutes,
+) -> HashMap<Option<UfvkFingerprint>, BalanceEstimate> {
+    let mut estimates: HashMap<Option<UfvkFingerprint>, BalanceEstimate> = std::iter::once(None)
+        .chain(routes.accounts.iter().copied().map(Some))
+        .map(|owner| (owner, BalanceEstimate::default()))
+        .collect();
+
+    let mut spent_outputs = HashSet::new();
+    let mut spent_nullifiers = HashSet::new();
+    for wtx in transactions.values() {
+        for input in wtx.vin() {
+            let prevout = input.prevout();
+            spent_outputs.insert((TxId::from_bytes(*prevout.hash()), prevout.n()));
+        }
+        if let Some(bundle) = wtx.sapling_bundle() {
+            spent_nullifiers.extend(bundle.shielded_spends().iter().map(|s| s.nullifier().0));
+        }
+    }
+
+    for (txid, wtx) in transactions.iter().filter(|(_, wtx)| is_confirmed(wtx)) {
+        for (n, output) in wtx.vout().iter().enumerate() {
+            let Some(TransparentAddress::PublicKeyHash(hash)) = output.recipient_address() else {
+                continue;
+            };
+            let Some(owner) = routes.transparent.get(&hash) else {
+                continue;
+            };
+            if !spent_outputs.contains(&(*txid, n as u32)) {
+                estimates.entry(*owner).or_default().transparent_zats += output.value().into_u64();
+            }
+        }
+
+        for note_data in wtx
+            .sapling_note_data()
+            .into_iter()
+            .flat_map(|notes| notes.values())
+        {
+            if note_data
+                .nullifier()
+                .is_some_and(|nullifier| spent_nullifiers.contains(nullifier))
+            {
+                continue;
+            }
+            let owner = routes
+                .sapling
+                .get(note_data.incoming_viewing_key().as_bytes())
+                .copied();
+            estimates.entry(owner).or_default().unspent_sapling_notes += 1;
+        }
+
+        if let Some(meta) = wtx.orchard_tx_meta() {
+            for ivk in meta.receiving_keys().values() {
+                let owner = ivk.key().and_then(|ivk| routes.orchard_owner(ivk));
+                estimates.entry(owner).or_default().orchard_notes += 1;
+            }
+        }
+
+        for (owner, flow) in routes.flows.get(txid).into_iter().flatten() {
+            let estimate = estimates.entry(*owner).or_default();
+            match flow {
+                TransactionFlow::Received => estimate.received_transactions += 1,
+                TransactionFlow::Sent => estimate.sent_transactions += 1,
+                TransactionFlow::Internal => estimate.internal_transfers += 1,
+            }
+        }
+    }
+    estimates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::prelude::*;
+
+    const LEGACY_KEY: [u8; 20] = [0x01; 20];
+    const ACCOUNT_KEY: [u8; 20] = [0x02; 20];
+    const ACCOUNT_IVK: [u8; 32] = [0x0a; 32];
+
+    fn account() -> UfvkFingerprint {
+        UfvkFingerprint::new([0xa1; 32])
+    }
+
+    fn idle_account() -> UfvkFingerprint {
+        UfvkFingerprint::new([0xa2; 32])
+    }
+
+    fn routes() -> BalanceRoutes {
+        BalanceRoutes {
+            accounts: vec![account(), idle_account()],
+            transparent: HashMap::from([(LEGACY_KEY, None), (ACCOUNT_KEY, Some(account()))]),
+            sapling: HashMap::from([(ACCOUNT_IVK, account())]),
+            orchard: vec![],
+            flows: HashMap::new(),
+        }
+    }
+
+    fn txid(n: u8) -> TxId {
+        TxId::from_bytes([n; 32])
+    }
+
+    /// A version 1 transaction spending `inputs` to P2PKH `outputs`.
+    fn transparent_transaction(inputs: &[(TxId, u32)], outputs: &[(u64, [u8; 20])]) -> Vec<u8> {
+        let mut bytes = 1u32.to_le_bytes().to_vec();
+        bytes.push(inputs.len() as u8);
+        for (txid, n) in inputs {
+            bytes.extend_from_slice(txid.as_bytes());
+            bytes.extend_from_slice(&n.to_le_bytes());
+            bytes.push(0); // empty script_sig
+            bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        }
+        bytes.push(outputs.len() as u8);
+        for (value, hash) in outputs {
+            bytes.extend_from_slice(&value.to_le_bytes());
+            bytes.extend_from_slice(&[25, 0x76, 0xa9, 0x14]);
+            bytes.extend_from_slice(hash);
+            bytes.extend_from_slice(&[0x88, 0xac]);
+        }
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes
+    }
+
+    /// A version 4 transaction with no inputs, outputs or shielded
+    /// components.
+    fn sapling_transaction() -> Vec<u8> {
+        let mut bytes = 0x8000_0004u32.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&0x892f_2085u32.to_le_bytes());
+        bytes.extend_from_slice(&[0, 0]); // vin, vout
+        bytes.extend_from_slice(&[0; 8]); // lock time, expiry height
+        bytes.extend_from_slice(&[0; 8]); // value balance
+        bytes.extend_from_slice(&[0, 0, 0]); // spends, outputs, joinsplits
+        bytes
+    }
+
+    /// A wallet transaction holding `transaction`, mined if `confirmed`, with
+    /// Sapling note data for `notes` (incoming viewing key and nullifier)
+    /// when the transaction is version 4.
+    fn wallet_tx(
+        transaction: &[u8],
+        confirmed: bool,
+        notes: &[([u8; 32], Option<[u8; 32]>)],
+    ) -> WalletTx {
+        let mut bytes = transaction.to_vec();
+        bytes.extend_from_slice(&[if confirmed { 0x42 } else { 0 }; 32]);
+        bytes.push(0); // merkle branch
+        bytes.extend_from_slice(&0i32.to_le_bytes());
+        bytes.extend_from_slice(&[0, 0, 0, 0]); // vtxPrev, mapValue, mapSproutNoteData, vOrderForm
+        bytes.extend_from_slice(&0i32.to_le_bytes());
+        bytes.extend_from_slice(&1_600_000_000i32.to_le_bytes());
+        bytes.extend_from_slice(&[1, 0]);
+        // Only an overwintered transaction, flagged in the header's top bit,
+        // carries Sapling note data.
+        if transaction[3] & 0x80 != 0 {
+            bytes.push(notes.len() as u8);
+            for (n, (ivk, nullifier)) in notes.iter().enumerate() {
+                bytes.extend_from_slice(&[0x77; 32]);
+                bytes.extend_from_slice(&(n as u32).to_le_bytes());
+                bytes.extend_from_slice(&5_060_150i32.to_le_bytes());
+                bytes.extend_from_slice(ivk);
+                match nullifier {
+                    Some(nullifier) => {
+                        bytes.push(1);
+                        bytes.extend_from_slice(nullifier);
+                    }
+                    None => bytes.push(0),
+                }
+                bytes.push(0); // witnesses
+                bytes.extend_from_slice(&(-1i32).to_le_bytes());
+            }
+        }
+        WalletTx::parse_buf(&bytes, false).unwrap()
+    }
+
+    #[test]
+    fn transparent_balance_tracks_unspent_outputs() {
+        let transactions = HashMap::from([
+            (
+                txid(1),
+                wallet_tx(
+                    &transparent_transaction(
+                        &[],
+                        &[
+                            (5_000, LEGACY_KEY),
+                            (7_000, ACCOUNT_KEY),
+                            (1_000, [0x03; 20]),
+                            (400, LEGACY_KEY),
+                        ],
+                    ),
+                    true,
+                    &[],
+                ),
+            ),
+            // Spends the first output, returning change.
+            (
+                txid(2),
+                wallet_tx(
+                    &transparent_transaction(&[(txid(1), 0)], &[(3_000, LEGACY_KEY)]),
+                    true,
+                    &[],
+                ),
+            ),
+            // Unconfirmed: its spend counts, its output does not.
+            (
+                txid(3),
+                wallet_tx(
+                    &transparent_transaction(&[(txid(2), 0)], &[(2_500, ACCOUNT_KEY)]),
+                    false,
+                    &[],
+                ),
+            ),
+        ]);
+
+        let estimates = estimate(&transactions, &routes());
+        assert_eq!(estimates.len(), 3);
+        assert_eq!(estimates[&None].transparent_zats, 400);
+        assert_eq!(estimates[&Some(account())].transparent_zats, 7_000);
+        assert_eq!(estimates[&Some(idle_account())], BalanceEstimate::default());
+    }
+
+    #[test]
+    fn sapling_notes_are_counted_by_owner() {
+        let transactions = HashMap::from([
+            (
+                txid(1),
+                wallet_tx(
+                    &sapling_transaction(),
+                    true,
+                    &[
+                        (ACCOUNT_IVK, None),
+                        ([0x0b; 32], Some([0x99; 32])),
+                        ([0x0b; 32], None),
+                    ],
+                ),
+            ),
+            (
+                txid(2),
+                wallet_tx(&sapling_transaction(), false, &[(ACCOUNT_IVK, None)]),
+            ),
+        ]);
+
+        let estimates = estimate(&transactions, &routes());
+        assert_eq!(
+            estimates[&Some(account())],
+            BalanceEstimate {
+                unspent_sapling_notes: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(estimates[&None].unspent_sapling_notes, 2);
+    }
+
+    #[test]
+    fn internal_transfers_are_counted_apart_from_sent_and_received() {
+        let legacy = AccountFingerprint::new(None, 0x7FFF_FFFF);
+        let unified = AccountFingerprint::new(
+            Some(crate::zcashd_wallet::encode_seed_fingerprint(&[0x11; 32])),
+            0,
+        );
+        let owner = |fingerprint: &AccountFingerprint| (*fingerprint == unified).then(account);
+        let internal = TransactionClassification::InternalTransfer {
+            from: legacy.clone(),
+            to: unified.clone(),
+        };
+        let accounts = TransactionAccounts {
+            spent_from: vec![legacy.clone()],
+            paid_to: vec![legacy.clone(), unified.clone()],
+        };
+        assert_eq!(
+            transaction_flows(&internal, &accounts, owner),
+            [
+                (None, TransactionFlow::Internal),
+                (Some(account()), TransactionFlow::Internal)
+            ]
+        );
+        assert_eq!(
+            transaction_flows(&TransactionClassification::Sent, &accounts, owner),
+            [
+                (None, TransactionFlow::Sent),
+                (Some(account()), TransactionFlow::Received)
+            ]
+        );
+        // With no spend traced, the change it returns marks the sender.
+        let untraced = TransactionAccounts {
+            spent_from: vec![],
+            paid_to: vec![unified.clone()],
+        };
+        assert_eq!(
+            transaction_flows(&TransactionClassification::Sent, &untraced, owner),
+            [(Some(account()), TransactionFlow::Sent)]
+        );
+
+        let transactions = HashMap::from([
+            (txid(1), wallet_tx(&sapling_transaction(), true, &[])),
+            (txid(2), wallet_tx(&sapling_transaction(), false, &[])),
+        ]);
+        let mut routes = routes();
+        for n in [1, 2] {
+            routes
+                .flows
+                .insert(txid(n), transaction_flows(&internal, &accounts, owner));
+        }
+        let estimates = estimate(&transactions, &routes);
+        for owner in [None, Some(account())] {
+            assert_eq!(
+                estimates[&owner],
+                BalanceEstimate {
+                    internal_transfers: 1,
+                    ..Default::default()
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn bip44_account_ids_are_read_from_keypaths() {
+        assert_eq!(bip44_account_id("m/44'/133'/3'/0/7"), Some(3));
+        assert_eq!(
+            bip44_account_id("m/44'/1'/2147483647'/1/0"),
+            Some(0x7fff_ffff)
+        );
+        assert_eq!(bip44_account_id("m/32'/133'/3'"), None);
+        assert_eq!(bip44_account_id("m/44'/133'/3/0/7"), None);
+        assert_eq!(bip44_account_id("m"), None);
+    }
+}
@@ -1,6 +1,9 @@
 use zewif::TxId;
 
-use crate::parser::ParseError;
+use crate::{
+    parser::ParseError,
+    zcashd_wallet::{ReceiverType, orchard::CheckpointPruneError},
+};
 
 /// Errors arising while migrating a parsed zcashd wallet to a ZeWIF
 /// document.
@@ -23,6 +26,17 @@ pub enum MigrateError {
     #[error("receiver types do not produce a valid unified address: {0}")]
     InvalidReceiverTypes(zcash_keys::keys::ReceiverRequirementError),
 
+    /// A unified address derived from its metadata does not have the
+    /// receiver types the metadata records.
+    #[error(
+        "unified address {address} has receivers {actual:?}, but its metadata records {recorded:?}"
+    )]
+    ReceiverTypeMismatch {
+        address: String,
+        recorded: Vec<ReceiverType>,
+        actual: Vec<ReceiverType>,
+    },
+
     /// A unified address could not be derived from its UFVK at the recorded
     /// diversifier index.
     #[error("deriving unified address: {0}")]
@@ -46,6 +60,21 @@ pub enum MigrateError {
     #[error("legacy HD seed has an invalid length for ZIP 32 fingerprinting")]
     InvalidLegacySeedLength,
 
+    /// The export height is below a block the wallet has already seen, which
+    /// would give its witnesses and transactions negative depths. See
+    /// [`MigrationFilter::allow_past_export_height`] for exporting a historical
+    /// snapshot.
+    ///
+    /// [`MigrationFilter::allow_past_export_height`]: super::MigrationFilter::allow_past_export_height
+    #[error(
+        "export height {export_height} is below height {minimum}, the newest block the wallet has seen; export at height {minimum} or above, or allow a past export height for a historical snapshot"
+    )]
+    ExportHeightInPast { export_height: u32, minimum: u32 },
+
+    /// Wallets merged into one document are on different networks.
+    #[error("wallets to be merged are on different networks")]
+    MixedNetworks,
+
     /// Converting a single wallet transaction failed.
     #[error("converting transaction {txid}: {source}")]
     TransactionConversion {
@@ -53,6 +82,19 @@ pub enum MigrateError {
         source: Box<MigrateError>,
     },
 
+    /// The caller's sink rejected a converted transaction during a streaming
+    /// migration.
+    #[error("handing off transaction {txid}: {source}")]
+    TransactionSink {
+        txid: TxId,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    /// The wallet's Orchard commitment tree could not be pruned to the
+    /// checkpoint its witnesses are anchored at.
+    #[error("pruning the Orchard commitment tree: {0}")]
+    OrchardTreePrune(#[source] CheckpointPruneError),
+
     /// Re-serializing a parsed transaction to its canonical bytes failed.
     #[error("re-serializing parsed transaction to raw bytes: {0}")]
     TransactionSerialization(#[source] std::io::Error),
@@ -0,0 +1,1026 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use secp256k1::PublicKey;
+use zewif::{SeedFingerprint, TxId, sapling::SaplingIncomingViewingKey};
+
+use crate::migrate::MigrateError;
+use crate::{
+    ZcashdWallet,
+    migrate::{
+        ExportMode,
+        addresses::attach_addresses,
+        addresses::{
+            p2pkh_address_string, sapling_default_address_string, sprout_address_string,
+            unified_address_string,
+        },
+        attach_received_outputs, attach_spends, build_accounts,
+    },
+    zcashd_wallet::{Address, ReceiverType, transparent::WatchScriptKind},
+};
+
+/// A rule consulted when routing an address to an account, in the order the
+/// migration applies them (see `docs/TransactionAssignment.md`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssignmentRule {
+    /// The P2PKH address of a keypair in the key database (`key`/`ckey`).
+    TransparentKey,
+    /// A watch-only script imported with `importaddress`/`importpubkey`.
+    WatchOnlyScript,
+    /// A P2SH redeem script (`cscript`).
+    RedeemScript,
+    /// A legacy Sapling address record (`sapzaddr`).
+    SaplingAddress,
+    /// The default address of a view-only Sapling key (`sapextfvk`) that has
+    /// no `sapzaddr` record.
+    SaplingViewingKey,
+    /// A Sprout spending key (`zkey`).
+    SproutKey,
+    /// A unified address rederived from its metadata (`unifiedaddrmeta`).
+    UnifiedAddressMetadata,
+    /// The unified account owning the matched UFVK fingerprint.
+    UnifiedAccount,
+}
+
+/// What a single rule found for the address being explained.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssignmentEvidence {
+    /// No record consulted by the rule produced the address.
+    NoMatch,
+    /// A key database entry, with its HD derivation when it has one.
+    TransparentKey {
+        hd_keypath: Option<String>,
+        seed_fingerprint: Option<[u8; 32]>,
+    },
+    /// A watch-only script.
+    WatchOnlyScript,
+    /// A P2SH redeem script.
+    RedeemScript,
+    /// A legacy Sapling address, with the HD derivation of its spending key
+    /// when the wallet holds one.
+    SaplingAddress {
+        has_spending_key: bool,
+        hd_keypath: Option<String>,
+        seed_fingerprint: Option<[u8; 32]>,
+    },
+    /// A Sprout spending key.
+    SproutKey,
+    /// Unified address metadata whose derived address matches.
+    UnifiedAddress {
+        ufvk_fingerprint: String,
+        diversifier_index: [u8; 11],
+        receiver_types: Vec<ReceiverType>,
+    },
+    /// The unified account holding the matched UFVK, with its ZIP-32
+    /// derivation path.
+    UnifiedAccount {
+        zip32_account_id: u32,
+        derivation_path: String,
+    },
+}
+
+/// One rule consulted while explaining an address's assignment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssignmentStep {
+    rule: AssignmentRule,
+    evidence: AssignmentEvidence,
+}
+
+impl AssignmentStep {
+    pub fn rule(&self) -> AssignmentRule {
+        self.rule
+    }
+
+    pub fn evidence(&self) -> &AssignmentEvidence {
+        &self.evidence
+    }
+
+    /// Whether the rule produced the address.
+    pub fn is_match(&self) -> bool {
+        self.evidence != AssignmentEvidence::NoMatch
+    }
+}
+
+/// The account an address is attached to by the migration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssignmentDecision {
+    /// The unified account with the given ZIP-32 account index.
+    UnifiedAccount { zip32_account_id: u32 },
+    /// The synthesized legacy account.
+    LegacyAccount,
+    /// The account collecting keys derived from a seed the wallet does not
+    /// hold, with that seed's fingerprint.
+    ForeignSeedAccount { seed_fingerprint: [u8; 32] },
+    /// No record in the wallet produces the address, so it is not exported.
+    Unassigned,
+}
+
+/// Why an address is (or is not) attached to a particular account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssignmentExplanation {
+    address: String,
+    steps: Vec<AssignmentStep>,
+    decision: AssignmentDecision,
+}
+
+impl AssignmentExplanation {
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Every rule consulted, in the order the migration applies them.
+    pub fn steps(&self) -> &[AssignmentStep] {
+        &self.steps
+    }
+
+    pub fn decision(&self) -> AssignmentDecision {
+        self.decision
+    }
+}
+
+impl std::fmt::Display for AssignmentExplanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "{}", self.address)?;
+        for step in &self.steps {
+            writeln!(f, "  {:?}: {:?}", step.rule, step.evidence)?;
+        }
+        write!(f, "  => {:?}", self.decision)
+    }
+}
+
+/// Explain which account the migration attaches `addr` to, and why.
+///
+/// Each routing rule of [`migrate_to_zewif`] is consulted for the one address,
+/// recording what it found. Rules that cannot apply to the address's encoding
+/// still appear, with [`AssignmentEvidence::NoMatch`]. The decision is the
+/// account the migration's address routing attaches the address to.
+///
+/// Fails with [`MigrateError::Parse`] if `addr` is not an address on the
+/// wallet's network.
+///
+/// [`migrate_to_zewif`]: crate::migrate_to_zewif
+pub fn explain_assignment(
+    wallet: &ZcashdWallet,
+    addr: &str,
+) -> Result<AssignmentExplanation, MigrateError> {
+    let network = wallet.network();
+    Address::from_address_string_on_network(addr, network)?;
+    let params = wallet.network_info().to_address_encoding_network();
+    let mut steps = Vec::new();
+
+    let mut transparent_key = None;
+    for keypair in wallet.keys().keypairs() {
+        let pk = PublicKey::from_slice(keypair.pubkey().as_slice())
+            .map_err(MigrateError::InvalidPublicKey)?;
+        if p2pkh_address_string(&pk, network) == addr {
+            let metadata = keypair.metadata();
+            transparent_key = Some(AssignmentEvidence::TransparentKey {
+                hd_keypath: metadata.hd_keypath().cloned(),
+                seed_fingerprint: metadata.seed_fp().copied(),
+            });
+            break;
+        }
+    }
+    push_step(&mut steps, AssignmentRule::TransparentKey, transparent_key);
+
+    let watched = wallet.watch_scripts().iter().any(|watch| {
+        let addr_str = match watch.kind() {
+            WatchScriptKind::P2PK(pubkey) => PublicKey::from_slice(pubkey.as_slice())
+                .ok()
+                .map(|pk| p2pkh_address_string(&pk, network)),
+            _ => watch.to_address_string(network),
+        };
+        addr_str.as_deref() == Some(addr)
+    });
+    push_step(
+        &mut steps,
+        AssignmentRule::WatchOnlyScript,
+        watched.then_some(AssignmentEvidence::WatchOnlyScript),
+    );
+
+    let redeemable = wallet
+        .cscripts()
+        .keys()
+        .any(|script_id| script_id.to_string(network) == addr);
+    push_step(
+        &mut steps,
+        AssignmentRule::RedeemScript,
+        redeemable.then_some(AssignmentEvidence::RedeemScript),
+    );
+
+    let sapling_evidence = |ivk: &SaplingIncomingViewingKey| {
+        let key = wallet.sapling_keys().get(ivk);
+        let metadata = key.map(|k| k.metadata());
+        AssignmentEvidence::SaplingAddress {
+            has_spending_key: key.is_some(),
+            hd_keypath: metadata.and_then(|m| m.hd_keypath().cloned()),
+            seed_fingerprint: metadata.and_then(|m| m.seed_fp().copied()),
+        }
+    };
+    let sapling_address = wallet
+        .sapling_z_addresses()
+        .iter()
+        .find(|(address, _)| address.to_string(network) == addr)
+        .map(|(_, ivk)| ivk);
+    push_step(
+        &mut steps,
+        AssignmentRule::SaplingAddress,
+        sapling_address.map(sapling_evidence),
+    );
+
+    let sapling_viewing_key = wallet
+        .sapling_extended_full_viewing_keys()
+        .iter()
+        .filter(|(ivk, _)| wallet.sapling_address_for_ivk(ivk).is_none())
+        .find(|(_, extfvk)| sapling_default_address_string(extfvk, network) == addr)
+        .map(|(ivk, _)| ivk);
+    push_step(
+        &mut steps,
+        AssignmentRule::SaplingViewingKey,
+        sapling_viewing_key.map(sapling_evidence),
+    );
+
+    let sprout_key = wallet.sprout_keys().is_some_and(|keys| {
+        keys.iter()
+            .any(|(address, _)| sprout_address_string(address, network) == addr)
+    });
+    push_step(
+        &mut steps,
+        AssignmentRule::SproutKey,
+        sprout_key.then_some(AssignmentEvidence::SproutKey),
+    );
+
+    let unified_accounts = wallet.unified_accounts();
+    let mut unified_match = None;
+    for metadata in &unified_accounts.address_metadata {
+        let Some(ufvk) = unified_accounts.full_viewing_keys.get(&metadata.key_id) else {
+            return Err(MigrateError::MissingAddressUfvk {
+                fingerprint: metadata.key_id.to_hex(),
+            });
+        };
+        if unified_address_string(metadata, ufvk, &params)? == addr {
+            unified_match = Some(metadata);
+            break;
+        }
+    }
+    push_step(
+        &mut steps,
+        AssignmentRule::UnifiedAddressMetadata,
+        unified_match.map(|metadata| {
+            let mut receiver_types: Vec<ReceiverType> =
+                metadata.receiver_types.iter().copied().collect();
+            receiver_types.sort_by_key(|t| *t as u8);
+            AssignmentEvidence::UnifiedAddress {
+                ufvk_fingerprint: metadata.key_id.to_hex(),
+                diversifier_index: metadata.diversifier_index,
+                receiver_types,
+            }
+        }),
+    );
+
+    // The decision is read off the migration's own routing: the account the
+    // address is attached to, if any.
+    let mut accounts = build_accounts(wallet, &params)?;
+    attach_addresses(wallet, &mut accounts, &params, ExportMode::Full)?;
+    let account_index = accounts.accounts.iter().position(|account| {
+        account
+            .addresses()
+            .iter()
+            .any(|address| address.as_string() == addr)
+    });
+    let unified_account = account_index.and_then(|index| {
+        accounts
+            .ufvk_index
+            .iter()
+            .find(|(_, i)| **i == index)
+            .map(|(ufvk_fp, _)| ufvk_fp)
+    });
+    if unified_match.is_some() {
+        push_step(
+            &mut steps,
+            AssignmentRule::UnifiedAccount,
+            unified_account.and_then(|ufvk_fp| {
+                let account = unified_accounts.account_metadata.get(ufvk_fp)?;
+                Some(AssignmentEvidence::UnifiedAccount {
+                    zip32_account_id: account.zip32_account_id(),
+                    derivation_path: accounts.derivation_paths[ufvk_fp].clone(),
+                })
+            }),
+        );
+    }
+    let decision = match (account_index, unified_account) {
+        (None, _) => AssignmentDecision::Unassigned,
+        (Some(_), Some(ufvk_fp)) => AssignmentDecision::UnifiedAccount {
+            zip32_account_id: unified_accounts.account_metadata[ufvk_fp].zip32_account_id(),
+        },
+        (Some(index), None) => accounts
+            .foreign_seed_index
+            .iter()
+            .find(|(_, i)| **i == index)
+            .map_or(AssignmentDecision::LegacyAccount, |(seed_fp, _)| {
+                AssignmentDecision::ForeignSeedAccount {
+                    seed_fingerprint: *seed_fp,
+                }
+            }),
+    };
+
+    Ok(AssignmentExplanation {
+        address: addr.to_string(),
+        steps,
+        decision,
+    })
+}
+
+/// A rule that makes a transaction relevant to an account, as applied when
+/// the migration attaches received outputs (see
+/// `docs/TransactionAssignment.md`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TransactionRule {
+    /// A Sapling note received by the wallet.
+    SaplingNote,
+    /// An Orchard action whose receiving key matches a unified account's
+    /// incoming viewing key.
+    OrchardViewingKey,
+    /// An Orchard action whose recorded receiving key matches no unified
+    /// account, but which one's incoming viewing key decrypts.
+    OrchardDecryption,
+    /// An Orchard action received by the wallet that no unified account's
+    /// viewing key matches, attributed to the legacy account.
+    OrchardFallback,
+    /// A Sprout note received by the wallet.
+    SproutNote,
+    /// A Sapling spend of a note the wallet received, making the transaction
+    /// relevant to the account that received the note.
+    SaplingSpend,
+    /// An Orchard spend of a note the wallet received, making the transaction
+    /// relevant to the account that received the note.
+    OrchardSpend,
+    /// A transparent input spending an output paid to one of the wallet's
+    /// keys, making the transaction relevant to the account holding the key.
+    TransparentSpend,
+    /// A transparent output paying one of the wallet's keys in a transaction
+    /// that spends the wallet's funds, making the transaction relevant to the
+    /// account holding the key.
+    TransparentPayment,
+}
+
+/// One of the wallet's accounts, identified as in its migrated key source: by
+/// the fingerprint of the seed its keys are derived from and its ZIP-32
+/// account index.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AccountFingerprint {
+    seed_fingerprint: Option<SeedFingerprint>,
+    zip32_account_id: u32,
+}
+
+impl AccountFingerprint {
+    pub(crate) fn new(seed_fingerprint: Option<SeedFingerprint>, zip32_account_id: u32) -> Self {
+        Self {
+            seed_fingerprint,
+            zip32_account_id,
+        }
+    }
+
+    /// The fingerprint of the account's seed; `None` for a legacy account of
+    /// imported keys, which has no seed.
+    pub fn seed_fingerprint(&self) -> Option<&SeedFingerprint> {
+        self.seed_fingerprint.as_ref()
+    }
+
+    /// The account's ZIP-32 account index: zcashd's legacy account index,
+    /// `0x7FFFFFFF`, for the legacy account and for the account of each
+    /// foreign seed.
+    pub fn zip32_account_id(&self) -> u32 {
+        self.zip32_account_id
+    }
+}
+
+/// How a transaction moves funds into, out of, or within the wallet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionClassification {
+    /// Pays the wallet's accounts without spending any of the wallet's funds.
+    Received,
+    /// Spends the wallet's funds, other than as an internal transfer.
+    Sent,
+    /// Spends only funds of one of the wallet's accounts and pays only one
+    /// other of its accounts, besides change returned to the first, and no
+    /// address outside the wallet.
+    InternalTransfer {
+        from: AccountFingerprint,
+        to: AccountFingerprint,
+    },
+}
+
+/// The accounts whose funds a transaction spends, and those it pays.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct TransactionAccounts {
+    pub spent_from: Vec<AccountFingerprint>,
+    pub paid_to: Vec<AccountFingerprint>,
+}
+
+/// The rules that made each transaction relevant to an account.
+///
+/// A transaction can be attributed by several rules (e.g. one receiving both
+/// Sapling and Orchard outputs), but is assigned only once; the per-rule
+/// counts therefore add up to more than [`Self::transaction_count`] by the
+/// overlap.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionAttribution {
+    rules: BTreeMap<TxId, BTreeSet<TransactionRule>>,
+    classifications: BTreeMap<TxId, TransactionClassification>,
+    accounts: BTreeMap<TxId, TransactionAccounts>,
+}
+
+impl TransactionAttribution {
+    pub(crate) fn record(&mut self, txid: TxId, rule: TransactionRule) {
+        self.rules.entry(txid).or_default().insert(rule);
+    }
+
+    pub(crate) fn classify(
+        &mut self,
+        txid: TxId,
+        classification: TransactionClassification,
+        accounts: TransactionAccounts,
+    ) {
+        self.classifications.insert(txid, classification);
+        self.accounts.insert(txid, accounts);
+    }
+
+    /// The accounts each classified transaction spends from and pays.
+    pub(crate) fn accounts(&self) -> &BTreeMap<TxId, TransactionAccounts> {
+        &self.accounts
+    }
+
+    /// The rules attributing each assigned transaction.
+    pub fn rules(&self) -> &BTreeMap<TxId, BTreeSet<TransactionRule>> {
+        &self.rules
+    }
+
+    /// The number of transactions assigned, each counted once.
+    pub fn transaction_count(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// The number of transactions each rule attributes, counting a
+    /// transaction under every rule that attributes it.
+    pub fn rule_counts(&self) -> BTreeMap<TransactionRule, usize> {
+        let mut counts = BTreeMap::new();
+        for rule in self.rules.values().flatten() {
+            *counts.entry(*rule).or_default() += 1;
+        }
+        counts
+    }
+
+    /// The number of transactions attributed by more than one rule.
+    pub fn overlap_count(&self) -> usize {
+        self.rules.values().filter(|rules| rules.len() > 1).count()
+    }
+
+    /// The classification of each transaction that pays or spends from the
+    /// wallet.
+    pub fn classifications(&self) -> &BTreeMap<TxId, TransactionClassification> {
+        &self.classifications
+    }
+
+    /// The transactions moving funds between two of the wallet's accounts,
+    /// with the accounts sending and receiving them.
+    pub fn internal_transfers(
+        &self,
+    ) -> impl Iterator<Item = (&TxId, &AccountFingerprint, &AccountFingerprint)> {
+        self.classifications
+            .iter()
+            .filter_map(|(txid, classification)| match classification {
+                TransactionClassification::InternalTransfer { from, to } => Some((txid, from, to)),
+                _ => None,
+            })
+    }
+
+    fn classification_count(&self, f: impl Fn(&TransactionClassification) -> bool) -> usize {
+        self.classifications.values().filter(|c| f(c)).count()
+    }
+}
+
+impl std::fmt::Display for TransactionAttribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "{} transactions assigned", self.transaction_count())?;
+        for (rule, count) in self.rule_counts() {
+            writeln!(f, "  {rule:?}: {count}")?;
+        }
+        writeln!(
+            f,
+            "  {} attributed by more than one rule",
+            self.overlap_count()
+        )?;
+        // Internal transfers are neither sends nor receipts of the wallet.
+        write!(
+            f,
+            "{} received, {} sent, {} internal transfers",
+            self.classification_count(|c| *c == TransactionClassification::Received),
+            self.classification_count(|c| *c == TransactionClassification::Sent),
+            self.internal_transfers().count()
+        )
+    }
+}
+
+/// Report which rules make each of the wallet's transactions relevant to an
+/// account in [`migrate_to_zewif`].
+///
+/// [`migrate_to_zewif`]: crate::migrate_to_zewif
+pub fn explain_transaction_attribution(
+    wallet: &ZcashdWallet,
+) -> Result<TransactionAttribution, MigrateError> {
+    let params = wallet.network_info().to_address_encoding_network();
+    let mut accounts = build_accounts(wallet, &params)?;
+    // Attribution does not depend on which anchor witnesses are built against.
+    attach_received_outputs(
+        wallet,
+        &mut accounts,
+        wallet.orchard_note_commitment_tree(),
+        None,
+    )?;
+    attach_spends(wallet, &mut accounts);
+    Ok(accounts.attribution)
+}
+
+fn push_step(
+    steps: &mut Vec<AssignmentStep>,
+    rule: AssignmentRule,
+    evidence: Option<AssignmentEvidence>,
+) {
+    steps.push(AssignmentStep {
+        rule,
+        evidence: evidence.unwrap_or(AssignmentEvidence::NoMatch),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use zcash_transparent::address::TransparentAddress;
+    use zewif::{BlockHeight, LegacySeed, Network, Script};
+
+    use super::*;
+    use crate::{
+        migrate_to_zewif,
+        parser::Parse,
+        zcashd_wallet::{
+            BlockLocator, ClientVersion, KeyMetadata, NetworkInfo, UfvkFingerprint,
+            UnifiedAccountMetadata, UnifiedAccounts, UnifiedAddressMetadata, ZcashdWalletBuilder,
+            sapling::{SaplingKey, SaplingKeys, SaplingZPaymentAddress},
+            sprout::{SproutKeys, SproutPaymentAddress, SproutSpendingKey},
+            transparent::{KeyPair, Keys, PubKey, ScriptId, WatchScript},
+            u252,
+        },
+    };
+
+    fn txid(byte: u8) -> TxId {
+        TxId::from_bytes([byte; 32])
+    }
+
+    const LEGACY_SEED: [u8; 32] = [0x33; 32];
+    const FOREIGN_SEED_FP: [u8; 32] = [0x77; 32];
+
+    fn public_key(scalar: [u8; 32]) -> PublicKey {
+        let secret_key = secp256k1::SecretKey::from_slice(&scalar).unwrap();
+        PublicKey::from_secret_key(&secp256k1::Secp256k1::new(), &secret_key)
+    }
+
+    fn keypair(scalar: [u8; 32], seed_fp: [u8; 32]) -> (PubKey, KeyPair) {
+        let pubkey = PubKey::from_slice(&public_key(scalar).serialize()).unwrap();
+        let metadata = KeyMetadata::new(
+            None,
+            Some("m/44'/1'/2147483647'/0/0".to_string()),
+            Some(seed_fp),
+        );
+        let keypair = KeyPair::from_decrypted_scalar(pubkey.clone(), &scalar, metadata);
+        (pubkey, keypair)
+    }
+
+    /// A regtest wallet with a legacy HD seed and a key derived from it, a key
+    /// derived from a foreign seed, and a watch-only P2PKH script.
+    fn wallet() -> ZcashdWallet {
+        wallet_builder().build().unwrap()
+    }
+
+    fn wallet_builder() -> ZcashdWalletBuilder {
+        let legacy_seed_fp = zip32::fingerprint::SeedFingerprint::from_seed(&LEGACY_SEED)
+            .unwrap()
+            .to_bytes();
+        let keys = Keys::new(HashMap::from([
+            keypair([0x01; 32], legacy_seed_fp),
+            keypair([0x02; 32], FOREIGN_SEED_FP),
+        ]));
+        let TransparentAddress::PublicKeyHash(hash) =
+            TransparentAddress::from_pubkey(&public_key([0x03; 32]))
+        else {
+            unreachable!("from_pubkey always returns PublicKeyHash");
+        };
+        let mut script = vec![0x76, 0xa9, 0x14];
+        script.extend_from_slice(&hash);
+        script.extend_from_slice(&[0x88, 0xac]);
+
+        ZcashdWalletBuilder::new()
+            .bestblock(BlockLocator::new(ClientVersion::from_integer(0), vec![]))
+            .network_info(NetworkInfo::new(Network::Regtest(Default::default())))
+            .legacy_hd_seed(LegacySeed::new(LEGACY_SEED))
+            .keys(keys)
+            .watch_scripts(vec![WatchScript::new(Script::from(zewif::Data::from_vec(
+                script,
+            )))])
+    }
+
+    /// `explain_assignment` reaches the same decision as the migration for
+    /// every address the migration exports, through the rule matching its
+    /// kind.
+    #[test]
+    fn explain_assignment_agrees_with_migration() {
+        let wallet = wallet();
+        let zewif = migrate_to_zewif(&wallet, BlockHeight::from_u32(1), None).unwrap();
+
+        let mut decisions = Vec::new();
+        for account in zewif.wallets().iter().flat_map(|w| w.accounts()) {
+            let expected = match account.name().strip_prefix("foreign seed ") {
+                Some(seed_hex) => {
+                    let mut seed_fingerprint = [0u8; 32];
+                    hex::decode_to_slice(seed_hex, &mut seed_fingerprint).unwrap();
+                    AssignmentDecision::ForeignSeedAccount { seed_fingerprint }
+                }
+                None => AssignmentDecision::LegacyAccount,
+            };
+            for address in account.addresses() {
+                let explanation = explain_assignment(&wallet, &address.as_string()).unwrap();
+                assert_eq!(explanation.decision(), expected, "{explanation}");
+                let matched: Vec<_> = explanation
+                    .steps()
+                    .iter()
+                    .filter(|s| s.is_match())
+                    .map(|s| s.rule())
+                    .collect();
+                assert_eq!(matched.len(), 1, "{explanation}");
+                decisions.push(explanation.decision());
+            }
+        }
+        decisions.sort_by_key(|decision| format!("{decision:?}"));
+        assert_eq!(
+            decisions,
+            [
+                AssignmentDecision::ForeignSeedAccount {
+                    seed_fingerprint: FOREIGN_SEED_FP
+                },
+                AssignmentDecision::LegacyAccount,
+                AssignmentDecision::LegacyAccount,
+            ]
+        );
+    }
+
+    #[test]
+    fn explain_assignment_reports_unknown_address_as_unassigned() {
+        // The P2PKH address of the all-zero key hash.
+        let explanation =
+            explain_assignment(&wallet(), "tm9iMLAuYMzJ6jtFLcA7rzUmfreGuKvr7Ma").unwrap();
+        assert_eq!(explanation.decision(), AssignmentDecision::Unassigned);
+        assert!(explanation.steps().iter().all(|s| !s.is_match()));
+    }
+
+    /// An address for another network than the wallet's is rejected rather
+    /// than reported as unassigned.
+    #[test]
+    fn explain_assignment_rejects_an_address_on_another_network() {
+        let result = explain_assignment(&wallet(), "t1Hsc1LR8yKnbbe3twRp88p6vFfC5t7DLbs");
+        assert!(matches!(result, Err(MigrateError::Parse(_))));
+    }
+
+    /// A Sapling spending key derived from `seed`, with its incoming viewing
+    /// key and default address.
+    fn sapling_key(
+        seed: u8,
+    ) -> (
+        sapling::zip32::ExtendedSpendingKey,
+        SaplingIncomingViewingKey,
+        SaplingZPaymentAddress,
+    ) {
+        let extsk = sapling::zip32::ExtendedSpendingKey::master(&[seed; 32]);
+        let dfvk = extsk.to_diversifiable_full_viewing_key();
+        let ivk = SaplingIncomingViewingKey::new(dfvk.to_ivk(zip32::Scope::External).to_repr());
+        let (_, address) = dfvk.default_address();
+        let address = SaplingZPaymentAddress::parse_buf(&address.to_bytes(), false).unwrap();
+        (extsk, ivk, address)
+    }
+
+    const SAPLING_KEYPATH: &str = "m/32'/1'/2147483647'/0'";
+    const UFVK_FINGERPRINT: [u8; 32] = [0x22; 32];
+
+    /// Unified account 0 of a seed the wallet does not hold, and its address
+    /// with Orchard and Sapling receivers at the Sapling default diversifier
+    /// index.
+    fn unified_accounts() -> UnifiedAccounts {
+        let params = zcash_protocol::consensus::Network::TestNetwork;
+        let ufvk = zcash_keys::keys::UnifiedSpendingKey::from_seed(
+            &params,
+            &[0x5a; 32],
+            zip32::AccountId::ZERO,
+        )
+        .unwrap()
+        .to_unified_full_viewing_key();
+        let (j, _) = ufvk.sapling().unwrap().default_address();
+
+        let mut account = vec![0x11; 32]; // seed fingerprint
+        account.extend_from_slice(&1u32.to_le_bytes()); // coin type
+        account.extend_from_slice(&0u32.to_le_bytes()); // ZIP-32 account
+        account.extend_from_slice(&UFVK_FINGERPRINT);
+        let mut address = UFVK_FINGERPRINT.to_vec();
+        address.extend_from_slice(j.as_bytes());
+        address.extend_from_slice(&[2, ReceiverType::Orchard as u8, ReceiverType::Sapling as u8]);
+
+        let fingerprint = UfvkFingerprint::new(UFVK_FINGERPRINT);
+        UnifiedAccounts::new(
+            vec![UnifiedAddressMetadata::parse_buf(&address, false).unwrap()],
+            BTreeMap::from([(fingerprint, ufvk)]),
+            BTreeMap::from([(
+                fingerprint,
+                UnifiedAccountMetadata::parse_buf(&account, false).unwrap(),
+            )]),
+        )
+    }
+
+    /// [`wallet`] on testnet, with shielded keys and a redeem script besides:
+    /// a Sapling key derived from the foreign seed (`sapling_key(1)`), a
+    /// view-only Sapling address (`sapling_key(2)`), a view-only Sapling key
+    /// with no address record (`sapling_key(3)`), a Sprout key, a P2SH redeem
+    /// script, and [`unified_accounts`].
+    fn shielded_wallet() -> ZcashdWallet {
+        let (spending, spending_ivk, spending_address) = sapling_key(1);
+        let (view_only, view_only_ivk, view_only_address) = sapling_key(2);
+        let (unrecorded, unrecorded_ivk, _) = sapling_key(3);
+        let metadata = KeyMetadata::new(
+            None,
+            Some(SAPLING_KEYPATH.to_string()),
+            Some(FOREIGN_SEED_FP),
+        );
+        #[allow(deprecated)]
+        let viewing_keys = HashMap::from([
+            (view_only_ivk, view_only.to_extended_full_viewing_key()),
+            (unrecorded_ivk, unrecorded.to_extended_full_viewing_key()),
+        ]);
+        let sprout_key = SproutSpendingKey::new(
+            u252::from_bytes([0x05; 32]).unwrap(),
+            KeyMetadata::new(None, None, None),
+        );
+
+        wallet_builder()
+            .network_info(NetworkInfo::new(Network::Testnet))
+            .sapling_keys(SaplingKeys::new(HashMap::from([(
+                spending_ivk,
+                SaplingKey::new(spending_ivk, spending, metadata).unwrap(),
+            )])))
+            .sapling_z_addresses(HashMap::from([
+                (spending_address, spending_ivk),
+                (view_only_address, view_only_ivk),
+            ]))
+            .sapling_extended_full_viewing_keys(viewing_keys)
+            .sprout_keys(SproutKeys::new(HashMap::from([(
+                sprout_address(),
+                sprout_key,
+            )])))
+            .cscripts(HashMap::from([(
+                redeem_script_id(),
+                Script::from(zewif::Data::from_vec(vec![0x51])),
+            )]))
+            .unified_accounts(unified_accounts())
+            .build()
+            .unwrap()
+    }
+
+    fn sprout_address() -> SproutPaymentAddress {
+        SproutPaymentAddress::parse_buf(&[0x44; 64], false).unwrap()
+    }
+
+    fn redeem_script_id() -> ScriptId {
+        ScriptId::parse_buf(&[0x66; 20], false).unwrap()
+    }
+
+    /// The rules that produced the explained address, with what they found.
+    fn matches(explanation: &AssignmentExplanation) -> Vec<(AssignmentRule, AssignmentEvidence)> {
+        explanation
+            .steps()
+            .iter()
+            .filter(|s| s.is_match())
+            .map(|s| (s.rule(), s.evidence().clone()))
+            .collect()
+    }
+
+    /// A Sapling address is routed through its incoming viewing key to the
+    /// spending key's seed: the foreign seed's account for a key derived from
+    /// it, and the legacy account for an address with no spending key.
+    #[test]
+    fn explain_assignment_follows_a_sapling_address_to_its_key() {
+        let wallet = shielded_wallet();
+        let network = wallet.network();
+
+        let explanation =
+            explain_assignment(&wallet, &sapling_key(1).2.to_string(network)).unwrap();
+        assert_eq!(
+            matches(&explanation),
+            [(
+                AssignmentRule::SaplingAddress,
+                AssignmentEvidence::SaplingAddress {
+                    has_spending_key: true,
+                    hd_keypath: Some(SAPLING_KEYPATH.to_string()),
+                    seed_fingerprint: Some(FOREIGN_SEED_FP),
+                }
+            )],
+            "{explanation}"
+        );
+        assert_eq!(
+            explanation.decision(),
+            AssignmentDecision::ForeignSeedAccount {
+                seed_fingerprint: FOREIGN_SEED_FP
+            }
+        );
+
+        // The view-only key also has an address record, so the viewing key
+        // rule does not apply.
+        let explanation =
+            explain_assignment(&wallet, &sapling_key(2).2.to_string(network)).unwrap();
+        assert_eq!(
+            matches(&explanation),
+            [(
+                AssignmentRule::SaplingAddress,
+                AssignmentEvidence::SaplingAddress {
+                    has_spending_key: false,
+                    hd_keypath: None,
+                    seed_fingerprint: None,
+                }
+            )],
+            "{explanation}"
+        );
+        assert_eq!(explanation.decision(), AssignmentDecision::LegacyAccount);
+    }
+
+    /// The default address of a view-only Sapling key with no address record
+    /// is attached to the legacy account.
+    #[test]
+    fn explain_assignment_derives_the_address_of_a_sapling_viewing_key() {
+        let wallet = shielded_wallet();
+        let explanation =
+            explain_assignment(&wallet, &sapling_key(3).2.to_string(wallet.network())).unwrap();
+        assert_eq!(
+            matches(&explanation),
+            [(
+                AssignmentRule::SaplingViewingKey,
+                AssignmentEvidence::SaplingAddress {
+                    has_spending_key: false,
+                    hd_keypath: None,
+                    seed_fingerprint: None,
+                }
+            )],
+            "{explanation}"
+        );
+        assert_eq!(explanation.decision(), AssignmentDecision::LegacyAccount);
+    }
+
+    #[test]
+    fn explain_assignment_attaches_sprout_keys_to_the_legacy_account() {
+        let wallet = shielded_wallet();
+        let address = sprout_address_string(&sprout_address(), wallet.network());
+        let explanation = explain_assignment(&wallet, &address).unwrap();
+        assert_eq!(
+            matches(&explanation),
+            [(AssignmentRule::SproutKey, AssignmentEvidence::SproutKey)],
+            "{explanation}"
+        );
+        assert_eq!(explanation.decision(), AssignmentDecision::LegacyAccount);
+    }
+
+    #[test]
+    fn explain_assignment_attaches_redeem_scripts_to_the_legacy_account() {
+        let wallet = shielded_wallet();
+        let address = redeem_script_id().to_string(wallet.network());
+        let explanation = explain_assignment(&wallet, &address).unwrap();
+        assert_eq!(
+            matches(&explanation),
+            [(
+                AssignmentRule::RedeemScript,
+                AssignmentEvidence::RedeemScript
+            )],
+            "{explanation}"
+        );
+        assert_eq!(explanation.decision(), AssignmentDecision::LegacyAccount);
+    }
+
+    /// A unified address is rederived from its metadata and attached to the
+    /// unified account holding its UFVK, even when the wallet does not hold
+    /// the account's seed.
+    #[test]
+    fn explain_assignment_finds_the_unified_account_of_a_unified_address() {
+        let wallet = shielded_wallet();
+        let accounts = unified_accounts();
+        let metadata = &accounts.address_metadata[0];
+        let address = unified_address_string(
+            metadata,
+            &accounts.full_viewing_keys[&metadata.key_id],
+            &wallet.network_info().to_address_encoding_network(),
+        )
+        .unwrap();
+
+        let explanation = explain_assignment(&wallet, &address).unwrap();
+        assert_eq!(
+            matches(&explanation),
+            [
+                (
+                    AssignmentRule::UnifiedAddressMetadata,
+                    AssignmentEvidence::UnifiedAddress {
+                        ufvk_fingerprint: hex::encode(UFVK_FINGERPRINT),
+                        diversifier_index: metadata.diversifier_index,
+                        receiver_types: vec![ReceiverType::Sapling, ReceiverType::Orchard],
+                    }
+                ),
+                (
+                    AssignmentRule::UnifiedAccount,
+                    AssignmentEvidence::UnifiedAccount {
+                        zip32_account_id: 0,
+                        derivation_path: "m/32'/1'/0'".to_string(),
+                    }
+                ),
+            ],
+            "{explanation}"
+        );
+        assert_eq!(
+            explanation.decision(),
+            AssignmentDecision::UnifiedAccount {
+                zip32_account_id: 0
+            }
+        );
+    }
+
+    #[test]
+    fn counts_reconcile_with_overlapping_rules() {
+        let mut attribution = TransactionAttribution::default();
+        attribution.record(txid(1), TransactionRule::SaplingNote);
+        attribution.record(txid(1), TransactionRule::OrchardViewingKey);
+        attribution.record(txid(1), TransactionRule::SaplingNote);
+        attribution.record(txid(2), TransactionRule::OrchardViewingKey);
+        attribution.record(txid(3), TransactionRule::SproutNote);
+
+        assert_eq!(attribution.transaction_count(), 3);
+        assert_eq!(
+            attribution.rule_counts(),
+            BTreeMap::from([
+                (TransactionRule::SaplingNote, 1),
+                (TransactionRule::OrchardViewingKey, 2),
+                (TransactionRule::SproutNote, 1),
+            ])
+        );
+        assert_eq!(attribution.overlap_count(), 1);
+
+        let rule_total: usize = attribution.rule_counts().values().sum();
+        let extra_rules: usize = attribution
+            .rules()
+            .values()
+            .map(|rules| rules.len() - 1)
+            .sum();
+        assert_eq!(rule_total - extra_rules, attribution.transaction_count());
+    }
+
+    #[test]
+    fn summary_counts_internal_transfers_apart() {
+        let legacy = AccountFingerprint::new(None, 0x7FFF_FFFF);
+        let unified = AccountFingerprint::new(
+            Some(crate::zcashd_wallet::encode_seed_fingerprint(&[0x11; 32])),
+            0,
+        );
+        let mut attribution = TransactionAttribution::default();
+        attribution.record(txid(1), TransactionRule::OrchardViewingKey);
+        attribution.classify(
+            txid(1),
+            TransactionClassification::Received,
+            TransactionAccounts::default(),
+        );
+        attribution.record(txid(2), TransactionRule::SaplingSpend);
+        attribution.classify(
+            txid(2),
+            TransactionClassification::Sent,
+            TransactionAccounts::default(),
+        );
+        attribution.record(txid(3), TransactionRule::SaplingSpend);
+        attribution.record(txid(3), TransactionRule::OrchardViewingKey);
+        attribution.classify(
+            txid(3),
+            TransactionClassification::InternalTransfer {
+                from: legacy.clone(),
+                to: unified.clone(),
+            },
+            TransactionAccounts::default(),
+        );
+
+        assert_eq!(
+            attribution.internal_transfers().collect::<Vec<_>>(),
+            [(&txid(3), &legacy, &unified)]
+        );
+        assert!(
+            attribution
+                .to_string()
+                .ends_with("1 received, 1 sent, 1 internal transfers")
+        );
+    }
+}
@@ -0,0 +1,550 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use zewif::{
+    Account, AccountViewingKey, Address, AddressBookEntry, KeySource, ProtocolAddress,
+    ReceivedOutputPool, SecretStore, SentOutput, Transaction, TxId,
+};
+
+use crate::zcashd_wallet::ReceiverType;
+
+use super::{accounts::ZCASHD_LEGACY_ACCOUNT, migrate_to_zewif::MigratedWallet};
+
+/// A value pool, as selected by [`MigrationFilter::protocols`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Protocol {
+    Transparent,
+    Sprout,
+    Sapling,
+    Orchard,
+}
+
+impl From<ReceiverType> for Protocol {
+    fn from(receiver_type: ReceiverType) -> Self {
+        match receiver_type {
+            ReceiverType::P2PKH | ReceiverType::P2SH => Protocol::Transparent,
+            ReceiverType::Sapling => Protocol::Sapling,
+            ReceiverType::Orchard => Protocol::Orchard,
+        }
+    }
+}
+
+/// Selects the part of a wallet that [`migrate_to_zewif_filtered`] exports,
+/// for a user who does not want to hand a whole wallet to another application.
+///
+/// The default filter selects everything, and the filtered migration then
+/// produces exactly the output of [`migrate_to_zewif`].
+///
+/// - Accounts are selected by ZIP-32 account index. zcashd's legacy account is
+///   index `0x7FFFFFFF`; the accounts of foreign seeds have no index and are
+///   exported only when `include_accounts` is `None`. An excluded account is
+///   dropped along with its viewing key and spending keys.
+/// - Addresses are selected by their encoding, and by `protocols`: a unified
+///   address is kept only if all of its receivers are. A selected account
+///   whose addresses are all filtered out is dropped.
+/// - `protocols` also drops received and sent outputs in other pools.
+///
+/// `allow_past_export_height` selects nothing; it relaxes a check that every
+/// migration otherwise makes.
+///
+/// Only transactions relevant to an exported account (a received or sent
+/// output, or a spend) are exported. A kept unified account still carries its
+/// whole unified full viewing key, which can derive the filtered-out
+/// addresses; exclude the account to withhold it.
+///
+/// [`migrate_to_zewif_filtered`]: super::migrate_to_zewif_filtered
+/// [`migrate_to_zewif`]: super::migrate_to_zewif
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MigrationFilter {
+    /// If set, only the accounts with these ZIP-32 account indices.
+    pub include_accounts: Option<BTreeSet<u32>>,
+    /// Accounts not to export, by ZIP-32 account index.
+    pub exclude_accounts: BTreeSet<u32>,
+    /// If set, only these addresses.
+    pub include_addresses: Option<BTreeSet<String>>,
+    /// Addresses not to export.
+    pub exclude_addresses: BTreeSet<String>,
+    /// If set, only addresses and outputs in these pools.
+    pub protocols: Option<BTreeSet<Protocol>>,
+    /// Leave the wallet's seeds (its mnemonic or legacy HD seed) out of the
+    /// secret store.
+    pub exclude_seed_material: bool,
+    /// Accept an export height below the newest block the wallet has seen,
+    /// for an intentional historical snapshot, rather than failing with
+    /// [`MigrateError::ExportHeightInPast`].
+    ///
+    /// [`MigrateError::ExportHeightInPast`]: super::MigrateError::ExportHeightInPast
+    pub allow_past_export_height: bool,
+}
+
+impl MigrationFilter {
+    /// Whether the filter selects the whole wallet.
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn includes_account(&self, zip32_account_id: Option<u32>) -> bool {
+        match zip32_account_id {
+            Some(id) => {
+                self.include_accounts
+                    .as_ref()
+                    .is_none_or(|ids| ids.contains(&id))
+                    && !self.exclude_accounts.contains(&id)
+            }
+            None => self.include_accounts.is_none(),
+        }
+    }
+
+    fn includes_protocol(&self, protocol: Option<Protocol>) -> bool {
+        match &self.protocols {
+            None => true,
+            Some(protocols) => protocol.is_some_and(|p| protocols.contains(&p)),
+        }
+    }
+
+    fn includes_address(&self, address: &Address) -> bool {
+        let encoded = address.as_string();
+        if self.exclude_addresses.contains(&encoded)
+            || self
+                .include_addresses
+                .as_ref()
+                .is_some_and(|addresses| !addresses.contains(&encoded))
+        {
+            return false;
+        }
+        if self.protocols.is_none() {
+            return true;
+        }
+        match address_protocols(address) {
+            Some(protocols) => protocols
+                .into_iter()
+                .all(|p| self.includes_protocol(Some(p))),
+            None => false,
+        }
+    }
+
+    /// The account with only the selected addresses and outputs, or `None`
+    /// if all of its addresses are filtered out.
+    fn filter_account(&self, account: &Account) -> Option<Account> {
+        let addresses: Vec<&Address> = account
+            .addresses()
+            .iter()
+            .filter(|address| self.includes_address(address))
+            .collect();
+        if addresses.is_empty() && !account.addresses().is_empty() {
+            return None;
+        }
+
+        let mut filtered = Account::new(account.viewing_key().clone());
+        filtered.set_name(account.name());
+        if let Some(key_source) = account.key_source() {
+            filtered.set_key_source(key_source.clone());
+        }
+        if let Some(height) = account.birthday_height() {
+            filtered.set_birthday_height(height);
+        }
+        if let Some(hash) = account.birthday_block() {
+            filtered.set_birthday_block(hash);
+        }
+        if let Some(chain_state) = account.birthday_chain_state() {
+            filtered.set_birthday_chain_state(chain_state.clone());
+        }
+        if let Some(height) = account.recover_until_height() {
+            filtered.set_recover_until_height(height);
+        }
+        if let Some(purpose) = account.purpose() {
+            filtered.set_purpose(purpose);
+        }
+        if let Some(provenance) = account.provenance() {
+            filtered.set_provenance(provenance);
+        }
+        for range in account.scanned_ranges() {
+            filtered.add_scanned_range(*range);
+        }
+        for address in addresses {
+            filtered.add_address(address.clone());
+        }
+        for (txid, outputs) in account.relevant_transactions() {
+            let kept: Vec<_> = outputs
+                .iter()
+                .filter(|output| self.includes_protocol(pool_protocol(output.pool())))
+                .cloned()
+                .collect();
+            // A transaction relevant only through filtered-out outputs is no
+            // longer relevant; one relevant through a spend alone still is.
+            if kept.is_empty() && !outputs.is_empty() {
+                continue;
+            }
+            filtered.add_relevant_transaction(*txid, kept);
+        }
+        for (txid, outputs) in account.sent_outputs() {
+            let kept: Vec<_> = outputs
+                .iter()
+                .filter(|output| self.includes_protocol(sent_output_protocol(output)))
+                .cloned()
+                .collect();
+            if !kept.is_empty() {
+                filtered.add_sent_outputs(*txid, kept);
+            }
+        }
+        for (vendor, key, value) in account.extensions().iter() {
+            filtered.extensions_mut().add(vendor, key, value.clone());
+        }
+        Some(filtered)
+    }
+
+    /// Apply the filter to a migrated wallet and its transactions.
+    pub(crate) fn apply(
+        &self,
+        wallet: MigratedWallet,
+        transactions: HashMap<TxId, Transaction>,
+    ) -> (MigratedWallet, HashMap<TxId, Transaction>) {
+        let mut accounts = Vec::new();
+        let mut legacy_index = None;
+        for (index, account) in wallet.accounts.iter().enumerate() {
+            let zip32_account_id = if index == wallet.legacy_index {
+                Some(ZCASHD_LEGACY_ACCOUNT)
+            } else {
+                match account.key_source() {
+                    Some(KeySource::Derived(source)) => Some(source.account_index()),
+                    _ => None,
+                }
+            };
+            if !self.includes_account(zip32_account_id) {
+                continue;
+            }
+            if let Some(filtered) = self.filter_account(account) {
+                if index == wallet.legacy_index {
+                    legacy_index = Some(accounts.len());
+                }
+                accounts.push(filtered);
+            }
+        }
+
+        let kept_addresses: HashSet<String> = accounts
+            .iter()
+            .flat_map(|account| account.addresses())
+            .map(Address::as_string)
+            .collect();
+        let removed_addresses: HashSet<String> = wallet
+            .accounts
+            .iter()
+            .flat_map(|account| account.addresses())
+            .map(Address::as_string)
+            .filter(|address| !kept_addresses.contains(address))
+            .collect();
+        let address_book: Vec<AddressBookEntry> = wallet
+            .address_book
+            .into_iter()
+            .filter(|entry| !removed_addresses.contains(entry.address()))
+            .collect();
+
+        let relevant: HashSet<TxId> = accounts
+            .iter()
+            .flat_map(|account| {
+                account
+                    .relevant_transactions()
+                    .keys()
+                    .chain(account.sent_outputs().keys())
+                    .copied()
+            })
+            .collect();
+        let transactions = transactions
+            .into_iter()
+            .filter(|(txid, _)| relevant.contains(txid))
+            .collect();
+
+        let secrets = wallet
+            .secrets
+            .and_then(|store| self.filter_secrets(&store, &accounts));
+
+        (
+            MigratedWallet {
+                legacy_index: legacy_index.unwrap_or(accounts.len()),
+                accounts,
+                address_book,
+                secrets,
+                warnings: wallet.warnings,
+            },
+            transactions,
+        )
+    }
+
+    /// The secret store with the seeds removed if requested, and only the
+    /// spending keys of exported accounts; `None` if nothing is left.
+    ///
+    /// Legacy transparent, Sapling and Sprout keys are kept if the legacy
+    /// account or a foreign-seed account is exported.
+    fn filter_secrets(&self, store: &SecretStore, accounts: &[Account]) -> Option<SecretStore> {
+        let keeps_legacy_keys = accounts
+            .iter()
+            .any(|account| !matches!(account.viewing_key(), AccountViewingKey::Ufvk(_)));
+        let mut filtered = SecretStore::new();
+        if !self.exclude_seed_material {
+            for entry in store.seeds() {
+                filtered.add_seed(entry.clone());
+            }
+        }
+        if keeps_legacy_keys {
+            for entry in store.transparent_keys() {
+                filtered.add_transparent_key(entry.clone());
+            }
+            for entry in store.sapling_keys() {
+                filtered.add_sapling_key(entry.clone());
+            }
+            for entry in store.sprout_keys() {
+                filtered.add_sprout_key(entry.clone());
+            }
+        }
+        for entry in store.unified_keys() {
+            let exported = accounts.iter().any(|account| {
+                matches!(account.viewing_key(), AccountViewingKey::Ufvk(fvk) if fvk == entry.fvk())
+            });
+            if exported {
+                filtered.add_unified_key(entry.clone());
+            }
+        }
+        for (vendor, key, value) in store.extensions().iter() {
+            filtered.extensions_mut().add(vendor, key, value.clone());
+        }
+
+        let is_empty = filtered.seeds().is_empty()
+            && filtered.transparent_keys().is_empty()
+            && filtered.sapling_keys().is_empty()
+            && filtered.sprout_keys().is_empty()
+            && filtered.unified_keys().is_empty();
+        (!is_empty).then_some(filtered)
+    }
+}
+
+/// The pools an address receives in, or `None` for an address whose
+/// receivers cannot be determined.
+fn address_protocols(address: &Address) -> Option<Vec<Protocol>> {
+    match address.address() {
+        ProtocolAddress::Transparent(_) => Some(vec![Protocol::Transparent]),
+        ProtocolAddress::Sprout(_) => Some(vec![Protocol::Sprout]),
+        ProtocolAddress::Sapling(_) => Some(vec![Protocol::Sapling]),
+        ProtocolAddress::Unified(_) => {
+            let receiver_types =
+                ReceiverType::from_unified_address_str(&address.as_string()).ok()?;
+            Some(receiver_types.into_iter().map(Protocol::from).collect())
+        }
+    }
+}
+
+fn pool_protocol(pool: &ReceivedOutputPool) -> Option<Protocol> {
+    match pool {
+        ReceivedOutputPool::Transparent(_) => Some(Protocol::Transparent),
+        ReceivedOutputPool::Sprout(_) => Some(Protocol::Sprout),
+        ReceivedOutputPool::Sapling(_) => Some(Protocol::Sapling),
+        ReceivedOutputPool::Orchard(_) => Some(Protocol::Orchard),
+        _ => None,
+    }
+}
+
+fn sent_output_protocol(output: &SentOutput) -> Option<Protocol> {
+    match output {
+        SentOutput::Transparent(_) => Some(Protocol::Transparent),
+        SentOutput::Sapling(_) => Some(Protocol::Sapling),
+        SentOutput::Orchard(_) => Some(Protocol::Orchard),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zewif::{
+        Bip39Mnemonic, DerivedKeySource, ReceivedOutput, SeedEntry, SeedMaterial, SproutOutputData,
+        TransparentOutputData, UnifiedFullViewingKey, sprout::SproutAddress, transparent,
+    };
+
+    use super::*;
+    use crate::zcashd_wallet::encode_seed_fingerprint;
+
+    fn txid(n: u8) -> TxId {
+        TxId::from_bytes([n; 32])
+    }
+
+    fn transparent_address(name: &str) -> Address {
+        Address::new(ProtocolAddress::Transparent(transparent::Address::new(
+            name,
+        )))
+    }
+
+    fn transparent_output() -> ReceivedOutput {
+        ReceivedOutput::new(
+            0,
+            ReceivedOutputPool::Transparent(TransparentOutputData::new(None, None)),
+        )
+    }
+
+    /// A unified account with the given ZIP-32 index, holding one transparent
+    /// address that received in one transaction.
+    fn unified_account(zip32_account_id: u32) -> Account {
+        let mut account = Account::new(AccountViewingKey::Ufvk(UnifiedFullViewingKey::new(
+            format!("uview{zip32_account_id}"),
+        )));
+        account.set_name(format!("Account #{zip32_account_id}"));
+        account.set_key_source(KeySource::Derived(DerivedKeySource::new(
+            encode_seed_fingerprint(&[0x11; 32]),
+            zip32_account_id,
+            None,
+        )));
+        account.add_address(transparent_address(&format!(
+            "t-account-{zip32_account_id}"
+        )));
+        account.add_relevant_transaction(txid(zip32_account_id as u8), vec![transparent_output()]);
+        account
+    }
+
+    /// The legacy account, holding a transparent and a Sprout address that
+    /// received in transactions 10 and 11 respectively.
+    fn legacy_account() -> Account {
+        let mut account = Account::new(AccountViewingKey::TransparentAddressSet);
+        account.set_name("Legacy");
+        account.add_address(transparent_address("t-legacy"));
+        account.add_address(Address::new(ProtocolAddress::Sprout(SproutAddress::new(
+            "zc-legacy",
+        ))));
+        account.add_relevant_transaction(txid(10), vec![transparent_output()]);
+        account.add_relevant_transaction(
+            txid(11),
+            vec![ReceivedOutput::new(
+                0,
+                ReceivedOutputPool::Sprout(SproutOutputData::new(None)),
+            )],
+        );
+        account
+    }
+
+    fn migrated_wallet() -> (MigratedWallet, HashMap<TxId, Transaction>) {
+        let accounts = vec![unified_account(0), unified_account(1), legacy_account()];
+        let address_book = ["t-account-0", "t-account-1", "zc-legacy", "t-elsewhere"]
+            .into_iter()
+            .map(AddressBookEntry::new)
+            .collect();
+        let mut store = SecretStore::new();
+        store.add_seed(SeedEntry::new(
+            encode_seed_fingerprint(&[0x11; 32]),
+            SeedMaterial::Bip39Mnemonic(Bip39Mnemonic::new("abandon", None)),
+        ));
+        let transactions = [0, 1, 10, 11]
+            .into_iter()
+            .map(|n| (txid(n), Transaction::new(txid(n))))
+            .collect();
+        (
+            MigratedWallet {
+                accounts,
+                legacy_index: 2,
+                address_book,
+                secrets: Some(store),
+                warnings: vec![],
+            },
+            transactions,
+        )
+    }
+
+    fn sorted_txids(transactions: &HashMap<TxId, Transaction>) -> Vec<TxId> {
+        let mut txids: Vec<_> = transactions.keys().copied().collect();
+        txids.sort();
+        txids
+    }
+
+    fn address_book(wallet: &MigratedWallet) -> Vec<&str> {
+        wallet
+            .address_book
+            .iter()
+            .map(|entry| entry.address())
+            .collect()
+    }
+
+    #[test]
+    fn default_filter_keeps_everything() {
+        let (wallet, transactions) = migrated_wallet();
+        let (filtered, filtered_transactions) =
+            MigrationFilter::default().apply(wallet, transactions.clone());
+        assert_eq!(filtered.accounts, migrated_wallet().0.accounts);
+        assert_eq!(filtered.legacy_index, 2);
+        assert_eq!(filtered.address_book.len(), 4);
+        assert_eq!(
+            sorted_txids(&filtered_transactions),
+            sorted_txids(&transactions)
+        );
+        assert!(filtered.secrets.is_some());
+    }
+
+    #[test]
+    fn accounts_filter_selects_accounts() {
+        let (wallet, transactions) = migrated_wallet();
+        let filter = MigrationFilter {
+            include_accounts: Some(BTreeSet::from([1, ZCASHD_LEGACY_ACCOUNT])),
+            ..MigrationFilter::default()
+        };
+        let (filtered, transactions) = filter.apply(wallet, transactions);
+
+        let names: Vec<_> = filtered.accounts.iter().map(Account::name).collect();
+        assert_eq!(names, ["Account #1", "Legacy"]);
+        assert_eq!(filtered.legacy_index, 1);
+        assert_eq!(sorted_txids(&transactions), [txid(1), txid(10), txid(11)]);
+        // Account #0's address leaves the address book; others' entries stay.
+        assert_eq!(
+            address_book(&filtered),
+            ["t-account-1", "zc-legacy", "t-elsewhere"]
+        );
+        assert!(filtered.secrets.is_some());
+
+        let excluded = MigrationFilter {
+            exclude_accounts: BTreeSet::from([ZCASHD_LEGACY_ACCOUNT]),
+            exclude_seed_material: true,
+            ..MigrationFilter::default()
+        };
+        let (wallet, transactions) = migrated_wallet();
+        let (filtered, transactions) = excluded.apply(wallet, transactions);
+        assert_eq!(filtered.accounts.len(), 2);
+        assert_eq!(filtered.legacy_index, 2);
+        assert_eq!(sorted_txids(&transactions), [txid(0), txid(1)]);
+        assert!(filtered.secrets.is_none());
+    }
+
+    #[test]
+    fn protocol_filter_selects_addresses_and_outputs() {
+        let (wallet, transactions) = migrated_wallet();
+        let filter = MigrationFilter {
+            protocols: Some(BTreeSet::from([Protocol::Transparent])),
+            ..MigrationFilter::default()
+        };
+        let (filtered, transactions) = filter.apply(wallet, transactions);
+
+        assert_eq!(filtered.accounts.len(), 3);
+        let legacy = &filtered.accounts[filtered.legacy_index];
+        let addresses: Vec<_> = legacy.addresses().iter().map(Address::as_string).collect();
+        assert_eq!(addresses, ["t-legacy"]);
+        // The transaction that received only Sprout output is dropped.
+        assert!(!legacy.relevant_transactions().contains_key(&txid(11)));
+        assert_eq!(sorted_txids(&transactions), [txid(0), txid(1), txid(10)]);
+        assert!(!address_book(&filtered).contains(&"zc-legacy"));
+
+        // An account whose addresses are all filtered out is dropped.
+        let sprout_only = MigrationFilter {
+            protocols: Some(BTreeSet::from([Protocol::Sprout])),
+            ..MigrationFilter::default()
+        };
+        let (wallet, transactions) = migrated_wallet();
+        let (filtered, transactions) = sprout_only.apply(wallet, transactions);
+        let names: Vec<_> = filtered.accounts.iter().map(Account::name).collect();
+        assert_eq!(names, ["Legacy"]);
+        assert_eq!(sorted_txids(&transactions), [txid(11)]);
+    }
+
+    #[test]
+    fn address_filter_selects_addresses() {
+        let (wallet, transactions) = migrated_wallet();
+        let filter = MigrationFilter {
+            exclude_addresses: BTreeSet::from(["zc-legacy".to_string()]),
+            ..MigrationFilter::default()
+        };
+        let (filtered, _) = filter.apply(wallet, transactions);
+        let legacy = &filtered.accounts[filtered.legacy_index];
+        assert_eq!(legacy.addresses().len(), 1);
+        assert!(!address_book(&filtered).contains(&"zc-legacy"));
+    }
+}
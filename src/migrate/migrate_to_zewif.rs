@@ -1,18 +1,27 @@
-use std::collections::BTreeMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet, btree_map::Entry};
 
-use zcash_protocol::consensus::BranchId;
+use zcash_protocol::consensus::{BlockHeight as ConsensusBlockHeight, BranchId};
 use zcash_protocol::local_consensus::LocalNetwork;
-use zewif::{BlockHash, BlockHeight, Network, RegtestParams, Secrets, Zewif, ZewifWallet};
+use zewif::{
+    Account, AddressBookEntry, BlockHash, BlockHeight, Network, RegtestParams, SecretStore,
+    Secrets, Transaction, TxId, Zewif, ZewifWallet,
+};
 
 use crate::migrate::MigrateError;
 use crate::ZcashdWallet;
+use crate::zcashd_wallet::orchard::{CheckpointPruneError, OrchardNoteCommitmentTree};
 
 use super::{
-    attach_received_outputs, attach_sent_outputs, build_accounts, build_address_book,
-    build_secret_store, convert_transactions,
+    attach_received_outputs, attach_sent_outputs, attach_spends, build_accounts,
+    build_address_book, build_secret_store, convert_transactions, for_each_converted_transaction,
+    MigrationFilter, MigrationWarning,
     accounts::WalletAccounts,
     addresses::attach_addresses,
-    transactions::collect_tx_heights,
+    transactions::{
+        attach_best_block_height, attach_order_pos_next, best_block_height, collect_tx_heights,
+        order_pos_next_warning,
+    },
 };
 
 /// How to determine a regtest network's network-upgrade activation schedule
@@ -31,6 +40,23 @@ pub enum RegtestActivations {
     Local(LocalNetwork),
 }
 
+/// How much of a wallet's key material a migration exports.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExportMode {
+    /// Everything, including the secret store of spending keys and seeds.
+    #[default]
+    Full,
+    /// A watch-only export: addresses, viewing keys and transaction history,
+    /// with no secret store. Transparent addresses carry their public keys
+    /// rather than a spend authority, and the extended full viewing keys of
+    /// legacy Sapling keys are recorded on their accounts (see
+    /// [`SAPLING_VIEWING_KEYS_EXTENSION_KEY`]). Sprout keys have no viewing
+    /// key in the document, so only their addresses are exported.
+    ///
+    /// [`SAPLING_VIEWING_KEYS_EXTENSION_KEY`]: super::SAPLING_VIEWING_KEYS_EXTENSION_KEY
+    ViewOnly,
+}
+
 /// Builds the ZeWIF regtest activation schedule — a map from consensus branch ID
 /// to activation height — from a set of local consensus parameters. Upgrades
 /// that the parameters leave unactivated are omitted.
@@ -89,43 +115,414 @@ pub fn migrate_to_zewif(
     export_height: BlockHeight,
     regtest_activations: Option<RegtestActivations>,
 ) -> Result<Zewif, MigrateError> {
-    let params = wallet.network_info().to_address_encoding_network();
+    migrate_wallets_to_zewif(&[wallet], export_height, regtest_activations).map(|(zewif, _)| zewif)
+}
+
+/// Migrate a parsed zcashd wallet into a ZeWIF document, exporting the key
+/// material that `mode` selects.
+///
+/// `export_height` and `regtest_activations` are as for [`migrate_to_zewif`].
+/// With [`ExportMode::Full`] the result is identical to [`migrate_to_zewif`]'s.
+pub fn migrate_to_zewif_with_mode(
+    wallet: &ZcashdWallet,
+    export_height: BlockHeight,
+    regtest_activations: Option<RegtestActivations>,
+    mode: ExportMode,
+) -> Result<Zewif, MigrateError> {
+    migrate_wallets(&[wallet], export_height, regtest_activations, mode).map(|(zewif, _)| zewif)
+}
+
+/// Migrate several parsed zcashd wallets (e.g. a hot and a cold wallet) into a
+/// single ZeWIF document.
+///
+/// Each wallet becomes its own [`ZewifWallet`], in input order, keeping its own
+/// accounts, addresses, and address book; their secret stores are combined,
+/// without duplicate entries. The wallets must all be on the same network. The
+/// export block hash is taken from the first wallet's best-block locator, and
+/// `export_height` and `regtest_activations` are as for [`migrate_to_zewif`].
+///
+/// A transaction recorded by more than one wallet appears once in the
+/// document, with any metadata one wallet lacks filled in from another. Where
+/// the wallets disagree on a value, the earliest wallet's is kept. Each
+/// wallet's received and sent outputs stay attached to its own accounts.
+///
+/// An account name used in more than one wallet is prefixed with its wallet's
+/// index in `wallets` (e.g. `wallet 1: Account #0`).
+///
+/// Returns the document along with a
+/// [`MigrationWarning::ConflictingTransaction`] for each transaction the
+/// wallets disagree on.
+pub fn migrate_wallets_to_zewif(
+    wallets: &[&ZcashdWallet],
+    export_height: BlockHeight,
+    regtest_activations: Option<RegtestActivations>,
+) -> Result<(Zewif, Vec<MigrationWarning>), MigrateError> {
+    migrate_wallets(
+        wallets,
+        export_height,
+        regtest_activations,
+        ExportMode::Full,
+    )
+}
+
+fn migrate_wallets(
+    wallets: &[&ZcashdWallet],
+    export_height: BlockHeight,
+    regtest_activations: Option<RegtestActivations>,
+    mode: ExportMode,
+) -> Result<(Zewif, Vec<MigrationWarning>), MigrateError> {
+    if wallets.windows(2).any(|w| w[0].network() != w[1].network()) {
+        return Err(MigrateError::MixedNetworks);
+    }
+
+    let export_hash = wallets
+        .first()
+        .map(|wallet| best_block_hash(wallet))
+        .unwrap_or_else(|| BlockHash::from_bytes([0u8; 32]));
+    let mut zewif = Zewif::new(export_height, export_hash);
+
+    for wallet in wallets {
+        check_export_height(export_height, wallet, false)?;
+    }
+    let migrated = wallets
+        .iter()
+        .map(|wallet| {
+            let transactions = convert_transactions(wallet)?;
+            Ok((transactions, migrate_wallet(wallet, export_height, mode)?))
+        })
+        .collect::<Result<Vec<_>, MigrateError>>()?;
+
+    // How many wallets use each account name.
+    let mut name_uses: HashMap<String, usize> = HashMap::new();
+    for (_, wallet) in &migrated {
+        let names: HashSet<&str> = wallet.accounts.iter().map(|a| a.name()).collect();
+        for name in names {
+            *name_uses.entry(name.to_string()).or_default() += 1;
+        }
+    }
+
+    let mut warnings = Vec::new();
+    let mut transactions: BTreeMap<TxId, Transaction> = BTreeMap::new();
+    let mut secrets: Option<SecretStore> = None;
+    for (index, (wallet, (wallet_transactions, migrated))) in
+        wallets.iter().zip(migrated).enumerate()
+    {
+        let mut accounts = migrated.accounts;
+        for account in &mut accounts {
+            if name_uses[account.name()] > 1 {
+                account.set_name(format!("wallet {index}: {}", account.name()));
+            }
+        }
+        zewif.add_wallet(assemble_wallet(
+            wallet,
+            accounts,
+            migrated.address_book,
+            regtest_activations.as_ref(),
+        ));
+
+        for (txid, tx) in wallet_transactions {
+            match transactions.entry(txid) {
+                Entry::Vacant(entry) => {
+                    entry.insert(tx);
+                }
+                Entry::Occupied(mut entry) => {
+                    let fields = merge_transaction(entry.get_mut(), &tx);
+                    if !fields.is_empty() {
+                        warnings.push(MigrationWarning::ConflictingTransaction {
+                            wallet_index: index,
+                            txid,
+                            fields,
+                        });
+                    }
+                }
+            }
+        }
+
+        if let Some(store) = migrated.secrets {
+            match &mut secrets {
+                Some(merged) => merge_secret_store(merged, store),
+                None => secrets = Some(store),
+            }
+        }
+    }
+    zewif.set_transactions(transactions);
+
+    // Sensitive material (omitted entirely for viewing-only wallets).
+    if let Some(store) = secrets {
+        zewif.set_secrets(Secrets::Plain(store));
+    }
+
+    Ok((zewif, warnings))
+}
+
+/// Migrate a parsed zcashd wallet into a ZeWIF document, handing each
+/// converted transaction to `sink` as soon as it is converted instead of
+/// collecting them in the document.
+///
+/// This bounds the memory a very large wallet's migration needs: the returned
+/// document holds the wallet's accounts, addresses, address book and secrets,
+/// but no transactions. Transactions are passed to `sink` in txid order, and
+/// each is identical to the one [`migrate_to_zewif`] would record.
+/// `export_height` and `regtest_activations` are as for [`migrate_to_zewif`].
+///
+/// Fails with [`MigrateError::TransactionSink`] as soon as `sink` returns an
+/// error.
+pub fn migrate_to_zewif_streaming<E>(
+    wallet: &ZcashdWallet,
+    export_height: BlockHeight,
+    regtest_activations: Option<RegtestActivations>,
+    mut sink: impl FnMut(TxId, Transaction) -> Result<(), E>,
+) -> Result<Zewif, MigrateError>
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    check_export_height(export_height, wallet, false)?;
+    for_each_converted_transaction(wallet, |txid, tx| {
+        sink(txid, tx).map_err(|source| MigrateError::TransactionSink {
+            txid,
+            source: source.into(),
+        })
+    })?;
+    let migrated = migrate_wallet(wallet, export_height, ExportMode::Full)?;
 
     let mut zewif = Zewif::new(export_height, best_block_hash(wallet));
+    zewif.add_wallet(assemble_wallet(
+        wallet,
+        migrated.accounts,
+        migrated.address_book,
+        regtest_activations.as_ref(),
+    ));
 
-    // Global transaction table (raw bytes + metadata).
-    let transactions = convert_transactions(wallet)?;
+    // Sensitive material (omitted entirely for viewing-only wallets).
+    if let Some(store) = migrated.secrets {
+        zewif.set_secrets(Secrets::Plain(store));
+    }
 
-    // Accounts, addresses, received and sent outputs.
-    let mut accounts = build_accounts(wallet, &params)?;
-    attach_addresses(wallet, &mut accounts, &params)?;
-    attach_received_outputs(wallet, &mut accounts)?;
-    attach_sent_outputs(wallet, &mut accounts)?;
-    set_account_birthdays(wallet, &mut accounts);
+    Ok(zewif)
+}
 
-    // Assemble the wallet.
-    let mut zewif_wallet = ZewifWallet::new(export_network(
-        wallet.network(),
+/// Migrate the part of a parsed zcashd wallet that `filter` selects into a
+/// ZeWIF document; see [`MigrationFilter`] for what is exported.
+///
+/// `export_height` and `regtest_activations` are as for [`migrate_to_zewif`].
+/// With the default filter the result is identical to [`migrate_to_zewif`]'s.
+pub fn migrate_to_zewif_filtered(
+    wallet: &ZcashdWallet,
+    export_height: BlockHeight,
+    regtest_activations: Option<RegtestActivations>,
+    filter: &MigrationFilter,
+) -> Result<Zewif, MigrateError> {
+    if filter.is_default() {
+        return migrate_to_zewif(wallet, export_height, regtest_activations);
+    }
+    check_export_height(export_height, wallet, filter.allow_past_export_height)?;
+    let (migrated, transactions) = filter.apply(
+        migrate_wallet(wallet, export_height, ExportMode::Full)?,
+        convert_transactions(wallet)?,
+    );
+
+    let mut zewif = Zewif::new(export_height, best_block_hash(wallet));
+    zewif.add_wallet(assemble_wallet(
+        wallet,
+        migrated.accounts,
+        migrated.address_book,
         regtest_activations.as_ref(),
     ));
-    for account in accounts.accounts {
+    zewif.set_transactions(transactions.into_iter().collect());
+
+    if let Some(store) = migrated.secrets {
+        zewif.set_secrets(Secrets::Plain(store));
+    }
+
+    Ok(zewif)
+}
+
+/// The exported wallet of `wallet`, holding `accounts` and `address_book` and
+/// the wallet-level metadata recorded alongside them.
+fn assemble_wallet(
+    wallet: &ZcashdWallet,
+    accounts: Vec<Account>,
+    address_book: Vec<AddressBookEntry>,
+    regtest_activations: Option<&RegtestActivations>,
+) -> ZewifWallet {
+    let mut zewif_wallet = ZewifWallet::new(export_network(wallet.network(), regtest_activations));
+    for account in accounts {
         zewif_wallet.add_account(account);
     }
-    for entry in build_address_book(wallet) {
+    for entry in address_book {
         zewif_wallet.add_address_book_entry(entry);
     }
-    zewif.add_wallet(zewif_wallet);
+    attach_order_pos_next(&mut zewif_wallet, wallet);
+    attach_best_block_height(&mut zewif_wallet, wallet);
+    zewif_wallet
+}
 
-    for (txid, tx) in transactions {
-        zewif.add_transaction(txid, tx);
-    }
+/// The parts of the ZeWIF document migrated from a single zcashd wallet,
+/// other than its transactions.
+pub(crate) struct MigratedWallet {
+    pub accounts: Vec<Account>,
+    /// The index in [`Self::accounts`] of the synthesized legacy account, or
+    /// the number of accounts if it was filtered out.
+    pub legacy_index: usize,
+    pub address_book: Vec<AddressBookEntry>,
+    pub secrets: Option<SecretStore>,
+    /// What migrating the wallet found to warn of, in the order found.
+    pub warnings: Vec<MigrationWarning>,
+}
+
+/// Migrate everything but the transactions of `wallet`, whose export height
+/// the caller has already checked with [`check_export_height`].
+fn migrate_wallet(
+    wallet: &ZcashdWallet,
+    export_height: BlockHeight,
+    mode: ExportMode,
+) -> Result<MigratedWallet, MigrateError> {
+    let params = wallet.network_info().to_address_encoding_network();
 
+    // Accounts, addresses, received and sent outputs.
+    let mut accounts = build_accounts(wallet, &params)?;
+    accounts.warnings.extend(no_funds_warning(wallet));
+    attach_addresses(wallet, &mut accounts, &params, mode)?;
+    let anchor_height = witness_anchor_height(wallet, export_height, &mut accounts.warnings);
+    let orchard_tree = stable_orchard_tree(wallet, anchor_height)?;
+    attach_received_outputs(wallet, &mut accounts, &orchard_tree, Some(anchor_height))?;
+    attach_spends(wallet, &mut accounts);
+    attach_sent_outputs(wallet, &mut accounts)?;
+    set_account_birthdays(wallet, &mut accounts);
+
+    let mut warnings = accounts.warnings;
     // Sensitive material (omitted entirely for a viewing-only wallet).
-    if let Some(store) = build_secret_store(wallet)? {
-        zewif.set_secrets(Secrets::Plain(store));
+    let secrets = match mode {
+        ExportMode::Full => build_secret_store(wallet, &mut warnings)?,
+        ExportMode::ViewOnly => None,
+    };
+    warnings.extend(order_pos_next_warning(wallet));
+    Ok(MigratedWallet {
+        accounts: accounts.accounts,
+        legacy_index: accounts.legacy_index,
+        address_book: build_address_book(wallet),
+        secrets,
+        warnings,
+    })
+}
+
+/// What migrating `wallet` at `export_height` warns of; see
+/// [`MigrationReport::warnings`](super::MigrationReport::warnings). A wallet
+/// that fails to migrate gives none.
+pub(crate) fn migration_warnings(
+    wallet: &ZcashdWallet,
+    export_height: BlockHeight,
+) -> Vec<MigrationWarning> {
+    migrate_wallet(wallet, export_height, ExportMode::Full)
+        .map(|migrated| migrated.warnings)
+        .unwrap_or_default()
+}
+
+/// Merge `other`, another wallet's record of the same transaction, into `tx`.
+///
+/// Metadata and extension entries `tx` lacks are taken from `other`, the
+/// earlier creation time is kept, and the transaction is trusted if either
+/// record trusts it. Returns the names of the fields on which the two records
+/// disagree; `tx` keeps its own values for those.
+fn merge_transaction(tx: &mut Transaction, other: &Transaction) -> Vec<&'static str> {
+    fn merge<T: PartialEq>(
+        conflicts: &mut Vec<&'static str>,
+        name: &'static str,
+        ours: Option<T>,
+        theirs: Option<T>,
+        set: impl FnOnce(T),
+    ) {
+        match (ours, theirs) {
+            (None, Some(theirs)) => set(theirs),
+            (Some(ours), Some(theirs)) if ours != theirs => conflicts.push(name),
+            _ => {}
+        }
     }
 
-    Ok(zewif)
+    let mut conflicts = Vec::new();
+    merge(
+        &mut conflicts,
+        "transaction data",
+        tx.tx_data().cloned(),
+        other.tx_data().cloned(),
+        |v| tx.set_tx_data(v),
+    );
+    merge(
+        &mut conflicts,
+        "target height",
+        tx.target_height(),
+        other.target_height(),
+        |v| tx.set_target_height(v),
+    );
+    merge(
+        &mut conflicts,
+        "mined height",
+        tx.mined_height(),
+        other.mined_height(),
+        |v| tx.set_mined_height(v),
+    );
+    merge(
+        &mut conflicts,
+        "block position",
+        tx.block_position().cloned(),
+        other.block_position().cloned(),
+        |v| tx.set_block_position(v),
+    );
+    merge(&mut conflicts, "fee", tx.fee(), other.fee(), |v| {
+        tx.set_fee(v)
+    });
+    merge(
+        &mut conflicts,
+        "expiry height",
+        tx.expiry_height(),
+        other.expiry_height(),
+        |v| tx.set_expiry_height(v),
+    );
+    if let Some(time) = other.created_time()
+        && tx.created_time().is_none_or(|ours| time < ours)
+    {
+        tx.set_created_time(time);
+    }
+    if other.is_trusted() {
+        tx.set_trusted(true);
+    }
+    for (vendor, key, value) in other.extensions().iter() {
+        match tx.extensions().get(vendor, key) {
+            None => tx.extensions_mut().add(vendor, key, value.clone()),
+            Some(ours) if ours != value => conflicts.push("extension data"),
+            Some(_) => {}
+        }
+    }
+    conflicts
+}
+
+/// Add the entries of `other` that `store` does not already hold.
+fn merge_secret_store(store: &mut SecretStore, other: SecretStore) {
+    for entry in other.seeds() {
+        if !store.seeds().contains(entry) {
+            store.add_seed(entry.clone());
+        }
+    }
+    for entry in other.transparent_keys() {
+        if !store.transparent_keys().contains(entry) {
+            store.add_transparent_key(entry.clone());
+        }
+    }
+    for entry in other.sapling_keys() {
+        if !store.sapling_keys().contains(entry) {
+            store.add_sapling_key(entry.clone());
+        }
+    }
+    for entry in other.sprout_keys() {
+        if !store.sprout_keys().contains(entry) {
+            store.add_sprout_key(entry.clone());
+        }
+    }
+    for entry in other.unified_keys() {
+        if !store.unified_keys().contains(entry) {
+            store.add_unified_key(entry.clone());
+        }
+    }
 }
 
 /// The export block hash: the tip of zcashd's best-block locator, or the zero
@@ -139,6 +536,146 @@ fn best_block_hash(wallet: &ZcashdWallet) -> BlockHash {
         .unwrap_or_else(|| BlockHash::from_bytes([0u8; 32]))
 }
 
+/// A warning if the wallet holds no unspent Sapling notes or transparent
+/// outputs, and received no Orchard or Sprout notes (whose spends it cannot
+/// track), so that migrating it would carry no funds: often a sign of the
+/// wrong wallet file or an unsynced copy.
+fn no_funds_warning(wallet: &ZcashdWallet) -> Option<MigrationWarning> {
+    let other_notes = wallet.transactions().values().any(|wtx| {
+        !wtx.map_sprout_note_data().is_empty()
+            || wtx
+                .orchard_tx_meta()
+                .is_some_and(|meta| !meta.receiving_keys().is_empty())
+    });
+    (!wallet.has_sapling_funds() && wallet.unspent_transparent_outputs().is_empty() && !other_notes)
+        .then_some(MigrationWarning::NoFundsDetected)
+}
+
+/// How many blocks below the export height Orchard and Sapling witnesses are
+/// anchored, so that the exported witnesses survive a reorg of the most recent
+/// blocks.
+pub(crate) const ANCHOR_DEPTH: u32 = 100;
+
+/// The lowest export height consistent with the blocks the wallet has seen:
+/// the height of its newest transaction of known height, or the Orchard
+/// tree's last checkpoint if that is higher. `None` if it knows of neither.
+fn minimum_export_height(wallet: &ZcashdWallet) -> Option<u32> {
+    let newest_transaction = collect_tx_heights(wallet).into_values().max();
+    let last_checkpoint = wallet
+        .orchard_note_commitment_tree()
+        .last_checkpoint()
+        .map(u32::from);
+    newest_transaction.max(last_checkpoint)
+}
+
+/// Fail with [`MigrateError::ExportHeightInPast`] if `export_height` is below
+/// the [`minimum_export_height`] of `wallet`, unless `allow_past` is set.
+///
+/// Every entry point calls this before converting anything, so that a
+/// streaming migration fails before handing off any transaction.
+fn check_export_height(
+    export_height: BlockHeight,
+    wallet: &ZcashdWallet,
+    allow_past: bool,
+) -> Result<(), MigrateError> {
+    check_minimum_export_height(
+        u32::from(export_height),
+        minimum_export_height(wallet),
+        allow_past,
+    )
+}
+
+/// Fail with [`MigrateError::ExportHeightInPast`] if `export_height` is below
+/// `minimum`, unless `allow_past` is set.
+fn check_minimum_export_height(
+    export_height: u32,
+    minimum: Option<u32>,
+    allow_past: bool,
+) -> Result<(), MigrateError> {
+    match minimum {
+        Some(minimum) if export_height < minimum && !allow_past => {
+            Err(MigrateError::ExportHeightInPast {
+                export_height,
+                minimum,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The height the exported witnesses of an export at `export_height` are
+/// anchored at; see [`clamp_anchor_height`].
+fn witness_anchor_height(
+    wallet: &ZcashdWallet,
+    export_height: BlockHeight,
+    warnings: &mut Vec<MigrationWarning>,
+) -> u32 {
+    clamp_anchor_height(
+        u32::from(export_height),
+        wallet
+            .orchard_note_commitment_tree()
+            .last_checkpoint()
+            .map(u32::from),
+        best_block_height(wallet),
+        |warning| warnings.push(warning),
+    )
+}
+
+/// The height witnesses are anchored at for an export at `export_height`:
+/// [`ANCHOR_DEPTH`] blocks below it, but no higher than the Orchard tree's
+/// last checkpoint, since the tree cannot witness notes as of a later block.
+///
+/// A last checkpoint more than [`ANCHOR_DEPTH`] blocks below the export height
+/// or the wallet's best block means the wallet's witness data lags the chain,
+/// so the exported witnesses are older than expected and the importing wallet
+/// must scan forward from them; that is reported through `warn`. A wallet
+/// with no checkpoint (no Orchard tree) is not clamped.
+fn clamp_anchor_height(
+    export_height: u32,
+    last_checkpoint: Option<u32>,
+    best_block_height: Option<u32>,
+    mut warn: impl FnMut(MigrationWarning),
+) -> u32 {
+    let anchor_height = export_height.saturating_sub(ANCHOR_DEPTH);
+    let Some(last_checkpoint) = last_checkpoint else {
+        return anchor_height;
+    };
+    let lag = export_height
+        .max(best_block_height.unwrap_or(0))
+        .saturating_sub(last_checkpoint);
+    if lag > ANCHOR_DEPTH {
+        warn(MigrationWarning::LaggingOrchardCheckpoint {
+            last_checkpoint,
+            export_height,
+            best_block_height,
+            anchor_height: anchor_height.min(last_checkpoint),
+        });
+    }
+    anchor_height.min(last_checkpoint)
+}
+
+/// The wallet's Orchard commitment tree, pruned to its last checkpoint at or
+/// below `anchor_height` (see [`witness_anchor_height`]).
+///
+/// If the tree has no such checkpoint, or a note was received after it (so the
+/// checkpoint cannot witness every note), the tree is exported unpruned and
+/// its witnesses are anchored at the latest checkpoint instead. Any other
+/// failure to prune is an error.
+fn stable_orchard_tree(
+    wallet: &ZcashdWallet,
+    anchor_height: u32,
+) -> Result<Cow<'_, OrchardNoteCommitmentTree>, MigrateError> {
+    let tree = wallet.orchard_note_commitment_tree();
+    match tree.pruned_to_checkpoint(ConsensusBlockHeight::from_u32(anchor_height)) {
+        Ok(pruned) => Ok(Cow::Owned(pruned)),
+        Err(
+            CheckpointPruneError::NoCheckpoint { .. }
+            | CheckpointPruneError::WitnessedPositionAfterCheckpoint { .. },
+        ) => Ok(Cow::Borrowed(tree)),
+        Err(err) => Err(MigrateError::OrchardTreePrune(err)),
+    }
+}
+
 /// Estimate each account's birthday height as the earliest mined height among
 /// its relevant transactions. Only transactions that touched the Orchard
 /// commitment tree have a recoverable height, so accounts with no such
@@ -160,10 +697,160 @@ fn set_account_birthdays(wallet: &ZcashdWallet, accounts: &mut WalletAccounts) {
 
 #[cfg(test)]
 mod tests {
-    use zcash_protocol::consensus::BlockHeight as ConsensusBlockHeight;
-
     use super::*;
 
+    /// Clamp the anchor height, collecting the warnings.
+    fn clamp(
+        export_height: u32,
+        last_checkpoint: Option<u32>,
+        best_block_height: Option<u32>,
+    ) -> (u32, Vec<String>) {
+        let mut warnings = vec![];
+        let anchor_height = clamp_anchor_height(
+            export_height,
+            last_checkpoint,
+            best_block_height,
+            |warning| warnings.push(warning.to_string()),
+        );
+        (anchor_height, warnings)
+    }
+
+    /// The best-block height of a locator whose tip is the block with hash
+    /// `[0x01; 32]`, resolved by a resolver knowing that block at `height`.
+    fn resolved_best_block(height: u32) -> Option<u32> {
+        use crate::zcashd_wallet::{BlockLocator, ClientVersion, u256};
+
+        let tip = u256::try_from(&[0x01; 32]).unwrap();
+        let locator = BlockLocator::new(ClientVersion::from_integer(0), vec![tip]);
+        locator.height(|hash| (*hash == BlockHash::from_bytes([0x01; 32])).then_some(height))
+    }
+
+    #[test]
+    fn consistent_checkpoint_does_not_clamp() {
+        let best_block = resolved_best_block(2_000);
+        assert_eq!(best_block, Some(2_000));
+        assert_eq!(clamp(2_000, Some(1_950), best_block), (1_900, vec![]));
+        assert_eq!(clamp(2_000, None, best_block), (1_900, vec![]));
+    }
+
+    #[test]
+    fn lagging_checkpoint_clamps_the_anchor() {
+        let (anchor_height, warnings) = clamp(2_000, Some(1_500), resolved_best_block(2_000));
+        assert_eq!(anchor_height, 1_500);
+        assert_eq!(warnings.len(), 1);
+        for height in ["1500", "2000"] {
+            assert!(warnings[0].contains(height), "{}", warnings[0]);
+        }
+
+        // A best block far above the checkpoint is reported even when the
+        // export height is not.
+        let (anchor_height, warnings) = clamp(1_550, Some(1_500), resolved_best_block(3_000));
+        assert_eq!(anchor_height, 1_450);
+        assert_eq!(warnings.len(), 1);
+        for height in ["1500", "1550", "3000"] {
+            assert!(warnings[0].contains(height), "{}", warnings[0]);
+        }
+    }
+
+    #[test]
+    fn export_height_at_or_above_the_newest_block_is_accepted() {
+        assert!(check_minimum_export_height(2_000, Some(2_000), false).is_ok());
+        assert!(check_minimum_export_height(2_001, Some(2_000), false).is_ok());
+        // A wallet that knows of no block accepts any height.
+        assert!(check_minimum_export_height(1, None, false).is_ok());
+    }
+
+    #[test]
+    fn export_height_below_the_newest_block_is_rejected() {
+        let err = check_minimum_export_height(1_999, Some(2_000), false).unwrap_err();
+        assert!(matches!(
+            err,
+            MigrateError::ExportHeightInPast {
+                export_height: 1_999,
+                minimum: 2_000
+            }
+        ));
+        let message = err.to_string();
+        assert!(message.contains("at height 2000 or above"), "{message}");
+    }
+
+    /// A wallet holding one transaction, whose Orchard tree was last
+    /// checkpointed at `height`.
+    fn wallet_checkpointed_at(height: u32) -> ZcashdWallet {
+        use crate::{
+            parser::Parse,
+            zcashd_wallet::{
+                BlockLocator, ClientVersion, NetworkInfo, WalletTx, ZcashdWalletBuilder,
+            },
+        };
+
+        // A version 1 transaction with no inputs and one output, in a
+        // `CWalletTx` with empty collections.
+        let mut bytes = 1u32.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&[0, 1]);
+        bytes.extend_from_slice(&1_000i64.to_le_bytes());
+        bytes.extend_from_slice(&[0, 0, 0, 0, 0]);
+        bytes.extend_from_slice(&[0x42; 32]);
+        bytes.push(0);
+        bytes.extend_from_slice(&0i32.to_le_bytes());
+        bytes.extend_from_slice(&[0, 0, 0, 0]);
+        bytes.extend_from_slice(&0i32.to_le_bytes());
+        bytes.extend_from_slice(&1_600_000_000i32.to_le_bytes());
+        bytes.extend_from_slice(&[1, 0]);
+        let wtx = WalletTx::parse_buf(&bytes, false).unwrap();
+
+        ZcashdWalletBuilder::new()
+            .bestblock(BlockLocator::new(ClientVersion::from_integer(0), vec![]))
+            .network_info(NetworkInfo::new(Network::Regtest(Default::default())))
+            .orchard_note_commitment_tree(OrchardNoteCommitmentTree::checkpointed_at(
+                ConsensusBlockHeight::from_u32(height),
+            ))
+            .transactions(HashMap::from([(
+                TxId::from_bytes(*wtx.transaction().txid().as_ref()),
+                wtx,
+            )]))
+            .build()
+            .unwrap()
+    }
+
+    /// Every entry point rejects a past export height before converting
+    /// anything; in particular, a streaming migration hands off no
+    /// transaction.
+    #[test]
+    fn entry_points_check_the_export_height_first() {
+        let wallet = wallet_checkpointed_at(2_000);
+        let past = BlockHeight::from_u32(1_999);
+        let is_in_past = |result: Result<Zewif, MigrateError>| {
+            matches!(result, Err(MigrateError::ExportHeightInPast { .. }))
+        };
+
+        let mut handed_off = 0;
+        assert!(is_in_past(migrate_to_zewif_streaming(
+            &wallet,
+            past,
+            None,
+            |_, _| {
+                handed_off += 1;
+                Ok::<_, std::convert::Infallible>(())
+            }
+        )));
+        assert_eq!(handed_off, 0);
+
+        assert!(is_in_past(migrate_to_zewif(&wallet, past, None)));
+        let filter = MigrationFilter {
+            exclude_seed_material: true,
+            ..MigrationFilter::default()
+        };
+        assert!(is_in_past(migrate_to_zewif_filtered(
+            &wallet, past, None, &filter
+        )));
+    }
+
+    #[test]
+    fn past_export_height_is_accepted_when_allowed() {
+        assert!(check_minimum_export_height(1_000, Some(2_000), true).is_ok());
+    }
+
     /// A regtest network activating every upgrade at a distinct height, so the
     /// branch-ID-to-height mapping can be checked unambiguously.
     fn distinct_local_network() -> LocalNetwork {
@@ -236,4 +923,63 @@ mod tests {
             Network::Mainnet
         );
     }
+
+    fn txid() -> TxId {
+        TxId::from_bytes([7u8; 32])
+    }
+
+    #[test]
+    fn merge_transaction_fills_missing_metadata() {
+        let mut tx = Transaction::new(txid());
+        tx.set_expiry_height(BlockHeight::from_u32(500));
+        tx.set_created_time(2_000);
+        let mut other = Transaction::new(txid());
+        other.set_mined_height(BlockHeight::from_u32(400));
+        other.set_created_time(1_000);
+        other.set_trusted(true);
+
+        assert!(merge_transaction(&mut tx, &other).is_empty());
+        assert_eq!(tx.mined_height(), Some(BlockHeight::from_u32(400)));
+        assert_eq!(tx.expiry_height(), Some(BlockHeight::from_u32(500)));
+        assert_eq!(tx.created_time(), Some(1_000));
+        assert!(tx.is_trusted());
+    }
+
+    #[test]
+    fn merge_transaction_keeps_its_own_conflicting_values() {
+        let mut tx = Transaction::new(txid());
+        tx.set_mined_height(BlockHeight::from_u32(400));
+        let mut other = Transaction::new(txid());
+        other.set_mined_height(BlockHeight::from_u32(401));
+        other.set_expiry_height(BlockHeight::from_u32(500));
+
+        assert_eq!(merge_transaction(&mut tx, &other), ["mined height"]);
+        assert_eq!(tx.mined_height(), Some(BlockHeight::from_u32(400)));
+        assert_eq!(tx.expiry_height(), Some(BlockHeight::from_u32(500)));
+    }
+
+    #[test]
+    fn merge_transaction_takes_missing_extension_entries() {
+        let mut tx = Transaction::new(txid());
+        tx.extensions_mut()
+            .add("zcashd", "a", zewif::Data::from_slice(&[0x01]));
+        let mut other = Transaction::new(txid());
+        other
+            .extensions_mut()
+            .add("zcashd", "a", zewif::Data::from_slice(&[0x02]));
+        other
+            .extensions_mut()
+            .add("zcashd", "b", zewif::Data::from_slice(&[0x03]));
+
+        assert_eq!(merge_transaction(&mut tx, &other), ["extension data"]);
+        let value = |key: &str| {
+            tx.extensions()
+                .get("zcashd", key)
+                .unwrap()
+                .as_data()
+                .clone()
+        };
+        assert_eq!(value("a"), zewif::Data::from_slice(&[0x01]));
+        assert_eq!(value("b"), zewif::Data::from_slice(&[0x03]));
+    }
 }
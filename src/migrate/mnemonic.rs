@@ -0,0 +1,277 @@
+use bip0039::Mnemonic;
+use unicode_normalization::UnicodeNormalization;
+use zewif::MnemonicLanguage;
+
+use crate::zcashd_wallet::MnemonicPhrase;
+
+/// A problem with a wallet's BIP-39 mnemonic phrase.
+///
+/// The migration exports the phrase as recorded whatever its problems, so
+/// that no data is lost, but an importing wallet is likely to reject it.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum MnemonicProblem {
+    /// The phrase does not have 12, 15, 18, 21 or 24 words.
+    #[error("mnemonic has {0} words; BIP-39 phrases have 12, 15, 18, 21 or 24")]
+    WordCount(usize),
+
+    /// The recorded language has no BIP-39 wordlist.
+    #[error("mnemonic language {0} has no BIP-39 wordlist")]
+    UnsupportedLanguage(MnemonicLanguage),
+
+    /// The word at `index` (counting from zero) is not in the recorded
+    /// language's wordlist.
+    #[error("mnemonic word {index} is not in the {language} wordlist")]
+    UnknownWord {
+        index: usize,
+        language: MnemonicLanguage,
+    },
+
+    /// The words are all in the wordlist, but their checksum does not match.
+    #[error("mnemonic checksum does not match")]
+    BadChecksum,
+
+    /// The phrase is valid, but in another language than the recorded one.
+    #[error("mnemonic is recorded as {recorded} but is a valid {actual} phrase")]
+    LanguageMismatch {
+        recorded: MnemonicLanguage,
+        actual: MnemonicLanguage,
+    },
+}
+
+/// The languages with a BIP-39 wordlist, in zcashd's language-id order.
+const WORDLIST_LANGUAGES: [MnemonicLanguage; 10] = [
+    MnemonicLanguage::English,
+    MnemonicLanguage::SimplifiedChinese,
+    MnemonicLanguage::TraditionalChinese,
+    MnemonicLanguage::Czech,
+    MnemonicLanguage::French,
+    MnemonicLanguage::Italian,
+    MnemonicLanguage::Japanese,
+    MnemonicLanguage::Korean,
+    MnemonicLanguage::Portuguese,
+    MnemonicLanguage::Spanish,
+];
+
+/// Check a mnemonic phrase against BIP-39: its word count, each word against
+/// the wordlist of its recorded language (English if none is recorded), and
+/// its checksum.
+///
+/// A phrase that is invalid in its recorded language but valid in another is
+/// reported as [`MnemonicProblem::LanguageMismatch`].
+pub fn validate_mnemonic(mnemonic: &MnemonicPhrase) -> Result<(), MnemonicProblem> {
+    let phrase = mnemonic.phrase();
+    let words: Vec<&str> = phrase.split_whitespace().collect();
+    if ![12, 15, 18, 21, 24].contains(&words.len()) {
+        return Err(MnemonicProblem::WordCount(words.len()));
+    }
+
+    let recorded = mnemonic
+        .language()
+        .cloned()
+        .unwrap_or(MnemonicLanguage::English);
+    let Some(result) = check_phrase(&recorded, phrase) else {
+        return Err(MnemonicProblem::UnsupportedLanguage(recorded));
+    };
+    let Err(error) = result else {
+        return Ok(());
+    };
+
+    if let Some(actual) = WORDLIST_LANGUAGES
+        .into_iter()
+        .filter(|language| *language != recorded)
+        .find(|language| matches!(check_phrase(language, phrase), Some(Ok(()))))
+    {
+        return Err(MnemonicProblem::LanguageMismatch { recorded, actual });
+    }
+
+    match error {
+        bip0039::Error::UnknownWord(..) => {
+            let index = words
+                .iter()
+                .position(|word| !in_wordlist(&recorded, word))
+                .unwrap_or_default();
+            Err(MnemonicProblem::UnknownWord {
+                index,
+                language: recorded,
+            })
+        }
+        bip0039::Error::InvalidChecksum => Err(MnemonicProblem::BadChecksum),
+        bip0039::Error::BadWordCount(count) => Err(MnemonicProblem::WordCount(count)),
+        // Only building a phrase from entropy checks its length; a phrase of
+        // a valid word count always encodes valid entropy.
+        bip0039::Error::BadEntropyBitCount(_) => Err(MnemonicProblem::WordCount(words.len())),
+    }
+}
+
+/// Parse `phrase` as a BIP-39 mnemonic in `language`, or `None` if the
+/// language has no wordlist.
+fn check_phrase(language: &MnemonicLanguage, phrase: &str) -> Option<Result<(), bip0039::Error>> {
+    fn check<L: bip0039::Language>(phrase: &str) -> Result<(), bip0039::Error> {
+        Mnemonic::<L>::from_phrase(phrase).map(|_| ())
+    }
+
+    Some(match language {
+        MnemonicLanguage::English => check::<bip0039::English>(phrase),
+        MnemonicLanguage::SimplifiedChinese => check::<bip0039::ChineseSimplified>(phrase),
+        MnemonicLanguage::TraditionalChinese => check::<bip0039::ChineseTraditional>(phrase),
+        MnemonicLanguage::Czech => check::<bip0039::Czech>(phrase),
+        MnemonicLanguage::French => check::<bip0039::French>(phrase),
+        MnemonicLanguage::Italian => check::<bip0039::Italian>(phrase),
+        MnemonicLanguage::Japanese => check::<bip0039::Japanese>(phrase),
+        MnemonicLanguage::Korean => check::<bip0039::Korean>(phrase),
+        MnemonicLanguage::Portuguese => check::<bip0039::Portuguese>(phrase),
+        MnemonicLanguage::Spanish => check::<bip0039::Spanish>(phrase),
+        MnemonicLanguage::Other(_) => return None,
+    })
+}
+
+/// The 64-byte BIP-39 seed of a phrase with the empty passphrase zcashd uses,
+/// or `None` if the phrase is not valid in its recorded language (English if
+/// none is recorded).
+pub(crate) fn mnemonic_seed(mnemonic: &MnemonicPhrase) -> Option<[u8; 64]> {
+    fn seed<L: bip0039::Language>(phrase: &str) -> Option<[u8; 64]> {
+        Mnemonic::<L>::from_phrase(phrase)
+            .ok()
+            .map(|mnemonic| mnemonic.to_seed(""))
+    }
+
+    let phrase = mnemonic.phrase();
+    match mnemonic.language().unwrap_or(&MnemonicLanguage::English) {
+        MnemonicLanguage::English => seed::<bip0039::English>(phrase),
+        MnemonicLanguage::SimplifiedChinese => seed::<bip0039::ChineseSimplified>(phrase),
+        MnemonicLanguage::TraditionalChinese => seed::<bip0039::ChineseTraditional>(phrase),
+        MnemonicLanguage::Czech => seed::<bip0039::Czech>(phrase),
+        MnemonicLanguage::French => seed::<bip0039::French>(phrase),
+        MnemonicLanguage::Italian => seed::<bip0039::Italian>(phrase),
+        MnemonicLanguage::Japanese => seed::<bip0039::Japanese>(phrase),
+        MnemonicLanguage::Korean => seed::<bip0039::Korean>(phrase),
+        MnemonicLanguage::Portuguese => seed::<bip0039::Portuguese>(phrase),
+        MnemonicLanguage::Spanish => seed::<bip0039::Spanish>(phrase),
+        MnemonicLanguage::Other(_) => None,
+    }
+}
+
+/// Whether `word` is in the wordlist of `language`. BIP-39 compares words in
+/// Unicode NFKD form, in which the wordlists are stored, so `word` is
+/// normalized first, as parsing a phrase does.
+fn in_wordlist(language: &MnemonicLanguage, word: &str) -> bool {
+    fn contains<L: bip0039::Language>(word: &str) -> bool {
+        L::index_of(word).is_some()
+    }
+
+    let word: String = word.nfkd().collect();
+    match language {
+        MnemonicLanguage::English => contains::<bip0039::English>(&word),
+        MnemonicLanguage::SimplifiedChinese => contains::<bip0039::ChineseSimplified>(&word),
+        MnemonicLanguage::TraditionalChinese => contains::<bip0039::ChineseTraditional>(&word),
+        MnemonicLanguage::Czech => contains::<bip0039::Czech>(&word),
+        MnemonicLanguage::French => contains::<bip0039::French>(&word),
+        MnemonicLanguage::Italian => contains::<bip0039::Italian>(&word),
+        MnemonicLanguage::Japanese => contains::<bip0039::Japanese>(&word),
+        MnemonicLanguage::Korean => contains::<bip0039::Korean>(&word),
+        MnemonicLanguage::Portuguese => contains::<bip0039::Portuguese>(&word),
+        MnemonicLanguage::Spanish => contains::<bip0039::Spanish>(&word),
+        MnemonicLanguage::Other(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mnemonic(phrase: &str, language: MnemonicLanguage) -> MnemonicPhrase {
+        MnemonicPhrase::new(phrase, Some(language))
+    }
+
+    const VALID: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn valid_phrase_passes() {
+        assert_eq!(
+            validate_mnemonic(&mnemonic(VALID, MnemonicLanguage::English)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn bad_checksum_is_reported() {
+        let phrase = ["abandon"; 12].join(" ");
+        assert_eq!(
+            validate_mnemonic(&mnemonic(&phrase, MnemonicLanguage::English)),
+            Err(MnemonicProblem::BadChecksum)
+        );
+    }
+
+    #[test]
+    fn unknown_word_is_reported_with_its_index() {
+        let phrase = VALID.replacen("abandon abandon abandon", "abandon abandon zcash", 1);
+        assert_eq!(
+            validate_mnemonic(&mnemonic(&phrase, MnemonicLanguage::English)),
+            Err(MnemonicProblem::UnknownWord {
+                index: 2,
+                language: MnemonicLanguage::English,
+            })
+        );
+    }
+
+    /// A word written in composed (NFC) form is found in the wordlist, which
+    /// holds its decomposed form, so the unknown word after it is the one
+    /// reported.
+    #[test]
+    fn unknown_word_index_allows_for_unicode_normalization() {
+        let composed = "\u{3042}\u{3051}\u{304c}\u{305f}"; // あけがた, with が as one code point
+        assert!(in_wordlist(&MnemonicLanguage::Japanese, composed));
+        let phrase = [composed, "zcash"]
+            .into_iter()
+            .chain(std::iter::repeat_n("あいこくしん", 10))
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert_eq!(
+            validate_mnemonic(&mnemonic(&phrase, MnemonicLanguage::Japanese)),
+            Err(MnemonicProblem::UnknownWord {
+                index: 1,
+                language: MnemonicLanguage::Japanese,
+            })
+        );
+    }
+
+    #[test]
+    fn wordlist_lookup_rejects_unknown_words() {
+        assert!(in_wordlist(&MnemonicLanguage::English, "abandon"));
+        assert!(!in_wordlist(&MnemonicLanguage::English, "zcash"));
+        assert!(!in_wordlist(&MnemonicLanguage::Spanish, "abandon"));
+        assert!(!in_wordlist(
+            &MnemonicLanguage::Other("ru".to_string()),
+            "abandon"
+        ));
+    }
+
+    #[test]
+    fn wrong_language_flag_is_reported() {
+        assert_eq!(
+            validate_mnemonic(&mnemonic(VALID, MnemonicLanguage::Spanish)),
+            Err(MnemonicProblem::LanguageMismatch {
+                recorded: MnemonicLanguage::Spanish,
+                actual: MnemonicLanguage::English,
+            })
+        );
+    }
+
+    #[test]
+    fn short_phrase_is_reported() {
+        let phrase = ["abandon"; 11].join(" ");
+        assert_eq!(
+            validate_mnemonic(&mnemonic(&phrase, MnemonicLanguage::English)),
+            Err(MnemonicProblem::WordCount(11))
+        );
+    }
+
+    #[test]
+    fn unknown_language_is_unsupported() {
+        let language = MnemonicLanguage::Other("ru".to_string());
+        assert_eq!(
+            validate_mnemonic(&mnemonic(VALID, language.clone())),
+            Err(MnemonicProblem::UnsupportedLanguage(language))
+        );
+    }
+}
@@ -6,8 +6,13 @@ mod_use!(accounts);
 mod_use!(addresses);
 mod_use!(transactions);
 mod_use!(received_outputs);
+mod_use!(spends);
 mod_use!(sent_outputs);
 mod_use!(address_book);
 mod_use!(secrets);
-
-pub(crate) mod primitives;
+mod_use!(mnemonic);
+mod_use!(explain);
+mod_use!(filter);
+mod_use!(report);
+mod_use!(warning);
+mod_use!(balances);
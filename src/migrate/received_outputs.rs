@@ -1,44 +1,68 @@
 use std::collections::{BTreeMap, HashMap};
 
-use orchard::keys::{IncomingViewingKey as OrchardIvk, Scope};
+use bridgetree::BridgeTree;
+use incrementalmerkletree::{
+    Position, frontier::CommitmentTree, witness::IncrementalWitness as LegacyWitness,
+};
+use orchard::{
+    keys::{IncomingViewingKey as OrchardIvk, Scope},
+    tree::MerkleHashOrchard,
+};
+use zcash_protocol::consensus::BlockHeight;
 
 use zewif::{
-    CommitmentTreeData, OrchardOutputData, ReceivedOutput, ReceivedOutputPool, SaplingOutputData,
-    SproutOutputData, TreePosition, TxId,
+    CommitmentTreeData, IncrementalWitness, Memo, OrchardOutputData, ReceivedOutput,
+    ReceivedOutputPool, SaplingOutputData, SproutOutputData, TreePosition, TxId,
+    orchard::OrchardWitness, sapling::MerkleHashSapling,
 };
 
 use crate::migrate::MigrateError;
 use crate::{
     ZcashdWallet,
-    migrate::WalletAccounts,
+    migrate::{MigrationWarning, TransactionRule, WalletAccounts, extract_transaction_memo},
     zcashd_wallet::{
         IncrementalMerkleTree,
+        orchard::{OrchardNoteCommitmentTree, witness_at_checkpoint},
         sapling::{SaplingNoteData, SaplingWitness},
+        u256,
     },
 };
 
 /// Attach the wallet's received shielded outputs to the accounts that can view
 /// them.
 ///
-/// Sapling and Sprout notes are all attributed to the synthesized legacy
-/// account (standalone shielded addresses in zcashd belong to its legacy
-/// pool). Orchard notes are routed to the unified account whose incoming
-/// viewing key matches the action's, falling back to the legacy account when
-/// no account matches.
+/// Sapling notes received by a unified account, whose UFVK derives the note's
+/// incoming viewing key, are attributed to that account. Other Sapling notes
+/// and Sprout notes are attributed to the synthesized legacy account
+/// (standalone shielded addresses in zcashd belong to its legacy pool), except
+/// Sapling notes received by a key derived from a foreign seed, which go to
+/// that seed's account. Orchard notes are routed to the unified account whose
+/// incoming viewing key matches the action's, or, when the recorded key
+/// matches none, whose incoming viewing key decrypts the action, falling back
+/// to the legacy account when no account matches.
 ///
-/// Note commitment positions are recorded as [`CommitmentTreeData::Position`].
-/// Full incremental witnesses are not reconstructed: zcashd's parsed witness
-/// snapshot exposes only raw tree nodes with no path/root derivation, so
-/// rebuilding a spec witness would require reimplementing the Sapling/Orchard
-/// Merkle hashing. A position plus the account birthday is sufficient for an
-/// importer with chain access to rebuild the witness by scanning forward.
+/// Orchard notes carry a full [`CommitmentTreeData::Witness`] against the last
+/// checkpointed root of `orchard_tree` (the wallet's Orchard commitment tree,
+/// possibly pruned by the caller) whenever it can witness the note's
+/// position, and otherwise just its [`CommitmentTreeData::Position`].
+/// Sapling notes carry a full witness converted from zcashd's cached witness
+/// for the note as of `sapling_anchor_height` (see [`select_sapling_witness`];
+/// with `None`, the most recent one), and otherwise just their position, from
+/// which an importer with chain access can rebuild the witness by scanning
+/// forward from the account birthday.
 ///
-/// Values, memos, and (for Orchard) nullifiers are omitted: they are
-/// recoverable from the raw transaction (which the export carries) plus the
-/// viewing key, and extracting them here would require trial decryption.
+/// The rules that made each transaction relevant are recorded in the
+/// accounts' [`TransactionAttribution`](crate::migrate::TransactionAttribution).
+///
+/// Sapling notes carry their memo when the wallet holds a viewing key that
+/// decrypts them (see [`extract_transaction_memo`]). Values, Orchard memos,
+/// and Orchard nullifiers are omitted: they are recoverable from the raw
+/// transaction (which the export carries) plus the viewing key.
 pub(crate) fn attach_received_outputs(
     wallet: &ZcashdWallet,
     accounts: &mut WalletAccounts,
+    orchard_tree: &OrchardNoteCommitmentTree,
+    sapling_anchor_height: Option<u32>,
 ) -> Result<(), MigrateError> {
     // account index -> txid -> received outputs
     let mut by_account: HashMap<usize, BTreeMap<TxId, Vec<ReceivedOutput>>> = HashMap::new();
@@ -46,20 +70,41 @@ pub(crate) fn attach_received_outputs(
 
     let orchard_routes = orchard_ivk_routes(accounts);
     let orchard_positions = orchard_note_positions(wallet);
+    let orchard_tree = orchard_tree.commitment_tree();
+    let mut witness_problems = Vec::new();
 
     for (txid, wtx) in wallet.transactions() {
-        // Sapling notes -> legacy account.
+        // Sapling notes -> the unified account that received them, else the
+        // legacy account (or the foreign-seed account of their spending key).
         if let Some(note_data) = wtx.sapling_note_data() {
             for (outpoint, nd) in note_data {
-                let tree_data = sapling_note_position(nd)
-                    .map(|p| CommitmentTreeData::Position(TreePosition::new(p)));
+                let account_index = sapling_note_account(wallet, accounts, nd);
+                let witness = match sapling_anchor_height {
+                    Some(anchor_height) => select_sapling_witness(
+                        nd,
+                        anchor_height,
+                        wallet.witnesscachesize(),
+                        |problem| {
+                            witness_problems.push((outpoint.txid(), outpoint.vout(), problem))
+                        },
+                    ),
+                    None => nd.witnesses().first(),
+                };
+                let tree_data = sapling_tree_data(nd, witness);
                 let nullifier = nd.nullifier().map(|n| zewif::Nullifier::new(*n));
-                let output = ReceivedOutput::new(
+                let mut output = ReceivedOutput::new(
                     outpoint.vout(),
                     ReceivedOutputPool::Sapling(SaplingOutputData::new(tree_data, nullifier)),
                 );
+                output.set_memo(
+                    extract_transaction_memo(wallet, wtx, outpoint.vout() as usize)
+                        .map(Memo::from_vec),
+                );
+                accounts
+                    .attribution
+                    .record(outpoint.txid(), TransactionRule::SaplingNote);
                 by_account
-                    .entry(legacy_index)
+                    .entry(account_index)
                     .or_default()
                     .entry(outpoint.txid())
                     .or_default()
@@ -71,14 +116,30 @@ pub(crate) fn attach_received_outputs(
         if let Some(meta) = wtx.orchard_tx_meta() {
             let tx_positions = orchard_positions.get(txid.as_bytes());
             for (action_index, ivk) in meta.receiving_keys() {
-                let account_index = route_orchard(&orchard_routes, ivk).unwrap_or(legacy_index);
+                // A recorded key that does not validate cannot be routed.
+                let routed = ivk
+                    .key()
+                    .and_then(|ivk| route_orchard(&orchard_routes, ivk));
+                // Otherwise, the account whose key decrypts the action.
+                let decrypted = || {
+                    let bundle = wtx.orchard_bundle()?;
+                    decrypting_orchard_account(&orchard_routes, bundle, *action_index as usize)
+                };
+                let (account_index, rule) = match routed {
+                    Some(index) => (index, TransactionRule::OrchardViewingKey),
+                    None => match decrypted() {
+                        Some(index) => (index, TransactionRule::OrchardDecryption),
+                        None => (legacy_index, TransactionRule::OrchardFallback),
+                    },
+                };
                 let tree_data = tx_positions
                     .and_then(|m| m.get(action_index))
-                    .map(|p| CommitmentTreeData::Position(TreePosition::new(*p)));
+                    .map(|p| orchard_tree_data(orchard_tree, *p));
                 let output = ReceivedOutput::new(
                     *action_index,
                     ReceivedOutputPool::Orchard(OrchardOutputData::new(tree_data, None)),
                 );
+                accounts.attribution.record(*txid, rule);
                 by_account
                     .entry(account_index)
                     .or_default()
@@ -97,6 +158,9 @@ pub(crate) fn attach_received_outputs(
                 output_index,
                 ReceivedOutputPool::Sprout(SproutOutputData::new(nullifier)),
             );
+            accounts
+                .attribution
+                .record(sprout_txid, TransactionRule::SproutNote);
             by_account
                 .entry(legacy_index)
                 .or_default()
@@ -116,9 +180,36 @@ pub(crate) fn attach_received_outputs(
         }
     }
 
+    // Stable, so that a note's problems keep the order they were found in.
+    witness_problems.sort_by_key(|(txid, vout, _)| (*txid, *vout));
+    accounts
+        .warnings
+        .extend(witness_problems.into_iter().map(|(txid, vout, problem)| {
+            MigrationWarning::SaplingWitness {
+                txid,
+                vout,
+                problem,
+            }
+        }));
+
     Ok(())
 }
 
+/// The account a Sapling note is attributed to: the unified account whose
+/// UFVK derives the note's incoming viewing key, and otherwise the legacy
+/// account or the foreign-seed account of the note's spending key.
+pub(crate) fn sapling_note_account(
+    wallet: &ZcashdWallet,
+    accounts: &WalletAccounts,
+    note_data: &SaplingNoteData,
+) -> usize {
+    let ivk = note_data.incoming_viewing_key();
+    if let Some(index) = accounts.sapling_ivk_index.get(ivk) {
+        return *index;
+    }
+    accounts.legacy_index_for(wallet.sapling_keys().get(ivk).map(|key| key.metadata()))
+}
+
 /// A stable ordering rank for a received output's pool, so that outputs from
 /// different pools sharing an output index have a deterministic order.
 fn pool_rank(output: &ReceivedOutput) -> u8 {
@@ -154,8 +245,25 @@ fn route_orchard(routes: &[(usize, Vec<OrchardIvk>)], ivk: &OrchardIvk) -> Optio
         .map(|(idx, _)| *idx)
 }
 
+/// The account whose Orchard incoming viewing keys decrypt the action at
+/// `action_index` of `bundle`, by trial decryption.
+fn decrypting_orchard_account<T: orchard::bundle::Authorization, V>(
+    routes: &[(usize, Vec<OrchardIvk>)],
+    bundle: &orchard::Bundle<T, V>,
+    action_index: usize,
+) -> Option<usize> {
+    routes
+        .iter()
+        .find(|(_, ivks)| {
+            ivks.iter()
+                .any(|ivk| bundle.decrypt_output_with_key(action_index, ivk).is_some())
+        })
+        .map(|(idx, _)| *idx)
+}
+
 /// Orchard note commitment positions, keyed by raw txid bytes then by action
-/// index within the transaction.
+/// index within the transaction, including any placeholder positions the
+/// wallet was given in place of an unreadable tree.
 fn orchard_note_positions(wallet: &ZcashdWallet) -> HashMap<[u8; 32], HashMap<u32, u64>> {
     let mut out: HashMap<[u8; 32], HashMap<u32, u64>> = HashMap::new();
     for (txid, positions) in wallet.orchard_note_commitment_tree().note_positions() {
@@ -164,9 +272,160 @@ fn orchard_note_positions(wallet: &ZcashdWallet) -> HashMap<[u8; 32], HashMap<u3
             entry.insert(*action_index, u64::from(*position));
         }
     }
+    for (txid, positions) in wallet.orchard_placeholder_positions() {
+        let entry = out.entry(*txid.as_bytes()).or_default();
+        for (action_index, position) in positions {
+            entry.entry(*action_index).or_insert(*position);
+        }
+    }
     out
 }
 
+/// The commitment tree data for the Orchard note at `position`: a full witness
+/// when one can be produced, else the bare position.
+fn orchard_tree_data(
+    tree: &BridgeTree<MerkleHashOrchard, BlockHeight, 32>,
+    position: u64,
+) -> CommitmentTreeData<OrchardWitness> {
+    match orchard_witness(tree, Position::from(position)) {
+        Some(witness) => CommitmentTreeData::Witness(OrchardWitness::from(witness)),
+        None => CommitmentTreeData::Position(TreePosition::new(position)),
+    }
+}
+
+/// A witness for the marked Orchard note at `position`, valid as of the tree's
+/// last checkpoint (the anchor zcashd would spend against). `None` when the
+/// tree has no checkpoint or does not track the position.
+fn orchard_witness(
+    tree: &BridgeTree<MerkleHashOrchard, BlockHeight, 32>,
+    position: Position,
+) -> Option<IncrementalWitness<32, zewif::orchard::MerkleHashOrchard>> {
+    let last = tree.checkpoints().len().checked_sub(1)?;
+    witness_at_checkpoint(tree, position, last).ok()
+}
+
+/// The commitment tree data for a Sapling note: a full witness when the
+/// selected cached witness converts, else the bare position.
+fn sapling_tree_data(
+    note_data: &SaplingNoteData,
+    witness: Option<&SaplingWitness>,
+) -> Option<CommitmentTreeData<zewif::sapling::SaplingWitness>> {
+    match witness.and_then(sapling_witness) {
+        Some(witness) => Some(CommitmentTreeData::Witness(witness.into())),
+        None => sapling_note_position(note_data)
+            .map(|p| CommitmentTreeData::Position(TreePosition::new(p))),
+    }
+}
+
+/// The cached witness of a Sapling note to export: the one as of the latest
+/// block at or below `anchor_height`.
+///
+/// zcashd caches one witness per block, most recent first, the first as of the
+/// note data's `witness_height`, so the `i`th is as of block
+/// `witness_height - i`. It keeps at most `witnesscachesize` of them, dropping
+/// the oldest, so a note received after the anchor has no witness as of it;
+/// the most recent witness is then used instead. That case and a cache longer
+/// than `cache_size` are reported through `warn`. Where the witness height is
+/// unknown the most recent witness is used.
+fn select_sapling_witness(
+    note_data: &SaplingNoteData,
+    anchor_height: u32,
+    cache_size: i64,
+    mut warn: impl FnMut(String),
+) -> Option<&SaplingWitness> {
+    let witnesses = note_data.witnesses();
+    let most_recent = witnesses.first()?;
+    if cache_size > 0 && witnesses.len() as i64 > cache_size {
+        warn(format!(
+            "{} cached witnesses, more than the wallet's witness cache size of {cache_size}",
+            witnesses.len()
+        ));
+    }
+
+    let witness_height = note_data.witness_height();
+    if witness_height < 0 {
+        return Some(most_recent);
+    }
+    match note_data.witness_at_height(anchor_height) {
+        Some(witness) => Some(witness),
+        None => {
+            let oldest = i64::from(witness_height) - (witnesses.len() as i64 - 1);
+            warn(format!(
+                "no cached witness at or below the anchor height {anchor_height} (the oldest is as of block {oldest}); using the witness as of block {witness_height}"
+            ));
+            Some(most_recent)
+        }
+    }
+}
+
+/// Whether a Sapling note has no cached witness as of `anchor_height`, so that
+/// the importing wallet must rebuild one by scanning before it can spend the
+/// note. A note whose witness height is unknown is taken to be fresh.
+pub(crate) fn sapling_witness_is_stale(note_data: &SaplingNoteData, anchor_height: u32) -> bool {
+    let mut stale = false;
+    select_sapling_witness(note_data, anchor_height, 0, |_| stale = true).is_none() || stale
+}
+
+/// Convert a zcashd Sapling witness into a ZeWIF witness, valid against the
+/// root of the tree as of the last leaf appended to the witness (the anchor
+/// zcashd would spend against). `None` if a node is not a valid Sapling tree
+/// node or the witness is empty.
+///
+/// The witness records the tree's leaves to the right of the note only as
+/// the filled subtree roots along the note's path, so the tree's frontier at
+/// the anchor is not recoverable and the witness carries none.
+fn sapling_witness(witness: &SaplingWitness) -> Option<IncrementalWitness<32, MerkleHashSapling>> {
+    let filled = witness
+        .filled()
+        .iter()
+        .map(|hash| ::sapling::Node::from_bytes(*hash).into_option())
+        .collect::<Option<Vec<_>>>()?;
+    let cursor = match witness.cursor() {
+        Some(cursor) => Some(sapling_commitment_tree(cursor)?),
+        None => None,
+    };
+    let witness =
+        LegacyWitness::from_parts(sapling_commitment_tree(witness.tree())?, filled, cursor)?;
+
+    let note_commitment = witness.tree().leaf()?;
+    let merkle_path = witness.path()?;
+    let note_position = u32::try_from(u64::from(witness.witnessed_position())).ok()?;
+    let anchor_tree_size = u32::try_from(u64::from(witness.tip_position()) + 1).ok()?;
+
+    Some(IncrementalWitness::from_parts(
+        sapling_node(note_commitment),
+        note_position,
+        merkle_path.path_elems().iter().map(sapling_node).collect(),
+        sapling_node(&witness.root()),
+        anchor_tree_size,
+        vec![],
+    ))
+}
+
+/// A zcashd incremental Merkle tree of Sapling nodes as a
+/// [`CommitmentTree`]; `None` if a node is not a valid Sapling tree node.
+fn sapling_commitment_tree(
+    tree: &IncrementalMerkleTree,
+) -> Option<CommitmentTree<::sapling::Node, 32>> {
+    // `Some(None)` for an absent node, `None` for an invalid one.
+    let node = |hash: Option<u256>| match hash {
+        Some(hash) => ::sapling::Node::from_bytes(hash.into_bytes())
+            .into_option()
+            .map(Some),
+        None => Some(None),
+    };
+    let parents = tree
+        .parents()
+        .iter()
+        .map(|parent| node(*parent))
+        .collect::<Option<Vec<_>>>()?;
+    CommitmentTree::from_parts(node(tree.left())?, node(tree.right())?, parents).ok()
+}
+
+fn sapling_node(node: &::sapling::Node) -> MerkleHashSapling {
+    MerkleHashSapling::new(node.to_bytes())
+}
+
 /// The leaf position of a Sapling note, derived from the size of the note
 /// commitment tree captured at the witness's creation (the note is the
 /// most-recently-appended leaf, so `position = size - 1`). All cached witnesses
@@ -191,8 +450,25 @@ fn merkle_tree_size(tree: &IncrementalMerkleTree) -> u64 {
 
 #[cfg(test)]
 mod tests {
-    use super::merkle_tree_size;
-    use crate::zcashd_wallet::{IncrementalMerkleTree, u256};
+    use bridgetree::BridgeTree;
+    use incrementalmerkletree::{
+        MerklePath, Position, frontier::CommitmentTree, witness::IncrementalWitness,
+    };
+    use orchard::tree::MerkleHashOrchard;
+    use zcash_protocol::consensus::BlockHeight;
+
+    use super::{
+        decrypting_orchard_account, merkle_tree_size, orchard_witness, sapling_witness,
+        sapling_witness_is_stale, select_sapling_witness,
+    };
+    use crate::{
+        parse,
+        zcashd_wallet::{
+            IncrementalMerkleTree,
+            sapling::{SaplingNoteData, SaplingWitness},
+            u256,
+        },
+    };
 
     fn node() -> u256 {
         u256::try_from(&[1u8; 32]).unwrap()
@@ -237,4 +513,242 @@ mod tests {
         tree.push_parent(Some(node()));
         assert_eq!(merkle_tree_size(&tree), 1 + 4);
     }
+
+    fn orchard_leaf(n: u8) -> MerkleHashOrchard {
+        let mut bytes = [0u8; 32];
+        bytes[0] = n;
+        MerkleHashOrchard::from_bytes(&bytes).unwrap()
+    }
+
+    #[test]
+    fn orchard_witness_is_taken_at_the_last_checkpoint() {
+        let mut tree: BridgeTree<MerkleHashOrchard, BlockHeight, 32> = BridgeTree::new(10);
+        tree.append(orchard_leaf(1));
+        tree.append(orchard_leaf(2));
+        tree.mark();
+        tree.append(orchard_leaf(3));
+        tree.checkpoint(BlockHeight::from(1));
+        // A leaf appended after the checkpoint is not part of the anchor.
+        tree.append(orchard_leaf(4));
+
+        let witness = orchard_witness(&tree, Position::from(1)).expect("marked leaf");
+        assert_eq!(witness.note_position(), 1);
+        assert_eq!(
+            witness.note_commitment().as_bytes(),
+            &orchard_leaf(2).to_bytes()
+        );
+        assert_eq!(
+            witness.anchor().as_bytes(),
+            &tree.root(1).unwrap().to_bytes()
+        );
+        assert_eq!(witness.anchor_tree_size(), 3);
+        assert_eq!(witness.merkle_path().len(), 32);
+        assert_eq!(
+            witness.anchor_frontier()[0].as_bytes(),
+            &orchard_leaf(3).to_bytes()
+        );
+    }
+
+    #[test]
+    fn orchard_witness_requires_a_marked_leaf_and_checkpoint() {
+        let mut tree: BridgeTree<MerkleHashOrchard, BlockHeight, 32> = BridgeTree::new(10);
+        tree.append(orchard_leaf(1));
+        tree.mark();
+        // No checkpoint yet.
+        assert!(orchard_witness(&tree, Position::from(0)).is_none());
+
+        tree.append(orchard_leaf(2));
+        tree.checkpoint(BlockHeight::from(1));
+        assert!(orchard_witness(&tree, Position::from(0)).is_some());
+        // Position 1 was never marked.
+        assert!(orchard_witness(&tree, Position::from(1)).is_none());
+    }
+
+    fn sapling_leaf(n: u8) -> ::sapling::Node {
+        let mut bytes = [0u8; 32];
+        bytes[0] = n;
+        ::sapling::Node::from_bytes(bytes).unwrap()
+    }
+
+    /// The zcashd form of a Sapling witness, as `SaplingNoteData` holds it.
+    fn zcashd_witness(witness: &IncrementalWitness<::sapling::Node, 32>) -> SaplingWitness {
+        fn tree(tree: &CommitmentTree<::sapling::Node, 32>) -> IncrementalMerkleTree {
+            let node = |n: &::sapling::Node| u256::try_from(&n.to_bytes()).unwrap();
+            IncrementalMerkleTree::with_fields(
+                tree.left().as_ref().map(node),
+                tree.right().as_ref().map(node),
+                tree.parents()
+                    .iter()
+                    .map(|p| p.as_ref().map(node))
+                    .collect(),
+            )
+        }
+        SaplingWitness::with_fields(
+            tree(witness.tree()),
+            witness.filled().iter().map(|n| n.to_bytes()).collect(),
+            witness.cursor().as_ref().map(tree),
+        )
+    }
+
+    #[test]
+    fn sapling_witness_is_valid_against_the_tree_root() {
+        let mut tree = CommitmentTree::<::sapling::Node, 32>::empty();
+        for n in 1..=5 {
+            tree.append(sapling_leaf(n)).unwrap();
+        }
+        let mut witness = IncrementalWitness::from_tree(tree.clone()).unwrap();
+        // Later leaves fill subtrees along the note's path, and leave a
+        // partial subtree in the witness's cursor.
+        for n in 6..=11 {
+            tree.append(sapling_leaf(n)).unwrap();
+            witness.append(sapling_leaf(n)).unwrap();
+        }
+
+        let converted = sapling_witness(&zcashd_witness(&witness)).expect("a valid witness");
+        assert_eq!(converted.note_position(), 4);
+        assert_eq!(
+            converted.note_commitment().as_bytes(),
+            &sapling_leaf(5).to_bytes()
+        );
+        assert_eq!(converted.anchor().as_bytes(), &tree.root().to_bytes());
+        assert_eq!(converted.anchor_tree_size(), 11);
+
+        let path = MerklePath::<::sapling::Node, 32>::from_parts(
+            converted
+                .merkle_path()
+                .iter()
+                .map(|n| ::sapling::Node::from_bytes(*n.as_bytes()).unwrap())
+                .collect(),
+            Position::from(4),
+        )
+        .unwrap();
+        assert_eq!(path.root(sapling_leaf(5)), tree.root());
+    }
+
+    /// Note data caching `count` witnesses, the first as of `witness_height`.
+    /// The `i`th witness has `i` filled hashes, to tell them apart.
+    fn note_data(count: u8, witness_height: i32) -> SaplingNoteData {
+        let mut bytes = 5_060_150i32.to_le_bytes().to_vec(); // version
+        bytes.extend_from_slice(&[0x33; 32]); // ivk
+        bytes.push(0x00); // no nullifier
+        bytes.push(count);
+        for i in 0..count {
+            bytes.extend_from_slice(&[0x00, 0x00, 0x00]); // empty tree
+            bytes.push(i);
+            bytes.extend(std::iter::repeat_n(0x22, 32 * i as usize));
+            bytes.push(0x00); // no cursor
+        }
+        bytes.extend_from_slice(&witness_height.to_le_bytes());
+        parse!(buf = &bytes, SaplingNoteData, "note data").unwrap()
+    }
+
+    /// The index of the witness selected for `note_data`, and the warnings.
+    fn select(note_data: &SaplingNoteData, anchor: u32, cache: i64) -> (usize, Vec<String>) {
+        let mut warnings = vec![];
+        let witness = select_sapling_witness(note_data, anchor, cache, |w| warnings.push(w))
+            .expect("a witness");
+        (witness.filled().len(), warnings)
+    }
+
+    #[test]
+    fn sapling_witness_as_of_the_anchor_is_selected() {
+        let note_data = note_data(5, 1_000);
+        assert_eq!(select(&note_data, 997, 5), (3, vec![]));
+        assert_eq!(select(&note_data, 996, 5), (4, vec![]));
+        // The most recent witness is already at or below the anchor height.
+        assert_eq!(select(&note_data, 1_000, 5), (0, vec![]));
+        assert_eq!(select(&note_data, 2_000, 5), (0, vec![]));
+    }
+
+    #[test]
+    fn note_newer_than_the_anchor_uses_its_most_recent_witness() {
+        // Received at block 998, so no witness is as of block 990.
+        let (index, warnings) = select(&note_data(3, 1_000), 990, 100);
+        assert_eq!(index, 0);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("oldest is as of block 998"));
+    }
+
+    #[test]
+    fn witness_cache_longer_than_the_cache_size_is_reported() {
+        let (index, warnings) = select(&note_data(5, 1_000), 998, 3);
+        assert_eq!(index, 2);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("more than the wallet's witness cache size of 3"));
+    }
+
+    #[test]
+    fn witness_selection_without_a_witness_height_uses_the_most_recent() {
+        assert_eq!(select(&note_data(3, -1), 990, 100), (0, vec![]));
+        let no_witnesses = note_data(0, 1_000);
+        assert!(select_sapling_witness(&no_witnesses, 990, 100, |_| {}).is_none());
+    }
+
+    #[test]
+    fn stale_sapling_witnesses_are_detected() {
+        assert!(!sapling_witness_is_stale(&note_data(5, 1_000), 997));
+        assert!(sapling_witness_is_stale(&note_data(3, 1_000), 990));
+        assert!(sapling_witness_is_stale(&note_data(0, 1_000), 990));
+        assert!(!sapling_witness_is_stale(&note_data(3, -1), 990));
+    }
+
+    #[test]
+    fn empty_sapling_witness_does_not_convert() {
+        let empty = SaplingWitness::with_fields(IncrementalMerkleTree::new(), vec![], None);
+        assert!(sapling_witness(&empty).is_none());
+    }
+
+    /// The external-scope Orchard incoming viewing key of an account derived
+    /// from `seed`, and the address it receives at.
+    fn orchard_receiver(seed: u8) -> (orchard::keys::IncomingViewingKey, orchard::Address) {
+        use orchard::keys::Scope;
+        let ufvk = zcash_keys::keys::UnifiedSpendingKey::from_seed(
+            &zcash_protocol::consensus::MainNetwork,
+            &[seed; 32],
+            zip32::AccountId::ZERO,
+        )
+        .unwrap()
+        .to_unified_full_viewing_key();
+        let fvk = ufvk.orchard().unwrap();
+        (
+            fvk.to_ivk(Scope::External),
+            fvk.address_at(0u32, Scope::External),
+        )
+    }
+
+    #[test]
+    fn orchard_action_is_attributed_to_the_account_that_decrypts_it() {
+        use orchard::{
+            builder::{Builder, BundleType},
+            tree::Anchor,
+            value::NoteValue,
+        };
+        use rand_core::OsRng;
+
+        let (ivk_a, _) = orchard_receiver(0x01);
+        let (ivk_b, address_b) = orchard_receiver(0x02);
+
+        let mut builder = Builder::new(BundleType::DEFAULT, Anchor::empty_tree());
+        builder
+            .add_output(None, address_b, NoteValue::from_raw(5000), [0; 512])
+            .unwrap();
+        let (bundle, metadata) = builder.build::<i64>(OsRng).unwrap().unwrap();
+        let output_index = metadata.output_action_index(0).unwrap();
+
+        let routes = [(3, vec![ivk_a]), (7, vec![ivk_b])];
+        assert_eq!(
+            decrypting_orchard_account(&routes, &bundle, output_index),
+            Some(7)
+        );
+        // The padding action decrypts with neither account's key.
+        let padding_index = 1 - output_index;
+        assert_eq!(
+            decrypting_orchard_account(&routes, &bundle, padding_index),
+            None
+        );
+        assert_eq!(
+            decrypting_orchard_account(&routes[..1], &bundle, output_index),
+            None
+        );
+    }
 }
@@ -0,0 +1,625 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+};
+
+use zewif::{BlockHeight, TxId};
+
+use super::{
+    COINBASE_MATURITY, MigrationWarning, TransactionAttribution, TransactionRule,
+    explain_transaction_attribution, migrate_to_zewif::ANCHOR_DEPTH, migration_warnings,
+    sapling_witness_is_stale,
+};
+use crate::{
+    ZcashdWallet,
+    zcashd_wallet::{ParseWarning, WalletTx, is_confirmed},
+};
+
+/// Facts about a wallet's migration that bear on what the importing wallet
+/// must do next, gathered by [`MigrationReport::from_wallet`]. Turn them into
+/// action items with [`next_steps`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MigrationReport {
+    /// Sapling notes with no cached witness as of the export's anchor height,
+    /// which the importing wallet must rebuild by scanning.
+    pub stale_sapling_witnesses: usize,
+    /// Addresses the wallet can only watch: imported watch-only scripts and
+    /// Sapling viewing keys with no spending key.
+    pub watch_only_addresses: usize,
+    /// Addresses whose keys the wallet does not hold, such as key pool
+    /// entries whose encrypted private key was skipped.
+    pub missing_spending_keys: usize,
+    /// Sprout notes received by the wallet.
+    pub sprout_notes: usize,
+    /// Whether the wallet's mnemonic is encrypted and was not decrypted, so
+    /// the export carries no seed.
+    pub undecrypted_mnemonic: bool,
+    /// Coinbase transactions mined less than 100 blocks below the export
+    /// height, whose outputs cannot yet be spent.
+    pub immature_coinbase_transactions: usize,
+    /// Orchard notes given a placeholder position because the wallet's note
+    /// commitment tree could not be parsed.
+    pub placeholder_orchard_positions: usize,
+    incomplete_transactions: BTreeMap<TxId, BTreeSet<TransactionLimitation>>,
+    warnings: Vec<MigrationWarning>,
+}
+
+/// A way in which the export of a transaction is incomplete, as listed by
+/// [`MigrationReport::incomplete_transactions`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TransactionLimitation {
+    /// A Sapling note it paid the wallet has no cached witness as of the
+    /// anchor height, so the importing wallet must rebuild one by scanning.
+    StaleSaplingWitness,
+    /// An Orchard action the wallet received matches no unified account's
+    /// viewing key, nor is decrypted by one, and is attributed to the legacy
+    /// account by assumption.
+    AssumedOrchardOwnership,
+    /// The wallet sent it but does not hold a transaction one of its
+    /// transparent inputs spends, so its fee cannot be computed.
+    MissingInputTransaction,
+    /// It is not mined and had not expired as of the export height, so
+    /// whether it will be mined is unknown.
+    UnknownMinedStatus,
+    /// An Orchard note it paid the wallet was given a placeholder position,
+    /// not its position in the note commitment tree, which could not be
+    /// parsed.
+    PlaceholderOrchardPosition,
+}
+
+impl fmt::Display for TransactionLimitation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TransactionLimitation::StaleSaplingWitness => "a Sapling note has no witness",
+            TransactionLimitation::AssumedOrchardOwnership => {
+                "an Orchard output's owner is assumed"
+            }
+            TransactionLimitation::MissingInputTransaction => {
+                "a spent transaction is missing, so the fee is unknown"
+            }
+            TransactionLimitation::UnknownMinedStatus => "whether it will be mined is unknown",
+            TransactionLimitation::PlaceholderOrchardPosition => {
+                "an Orchard note's position is a placeholder"
+            }
+        })
+    }
+}
+
+impl MigrationReport {
+    /// Gather the report for migrating `wallet` at `export_height`.
+    pub fn from_wallet(wallet: &ZcashdWallet, export_height: BlockHeight) -> Self {
+        let anchor_height = u32::from(export_height.saturating_sub(ANCHOR_DEPTH));
+        let stale_sapling_witnesses = wallet
+            .transactions()
+            .values()
+            .filter_map(|wtx| wtx.sapling_note_data())
+            .flat_map(|notes| notes.values())
+            .filter(|note_data| sapling_witness_is_stale(note_data, anchor_height))
+            .count();
+
+        let sapling_keys = wallet.sapling_keys();
+        let view_only_sapling = wallet
+            .sapling_extended_full_viewing_keys()
+            .keys()
+            .filter(|ivk| sapling_keys.get(ivk).is_none())
+            .count();
+        let watch_only_addresses = wallet.watch_scripts().len() + view_only_sapling;
+
+        let keyless_pool_entries = wallet
+            .key_pool()
+            .values()
+            .filter(|entry| wallet.keys().keypair_for_pubkey(entry.key()).is_none())
+            .count();
+        let keyless_sapling_addresses = wallet
+            .sapling_z_addresses()
+            .values()
+            .filter(|ivk| {
+                sapling_keys.get(ivk).is_none()
+                    && !wallet
+                        .sapling_extended_full_viewing_keys()
+                        .contains_key(*ivk)
+            })
+            .count();
+        let missing_spending_keys = keyless_pool_entries + keyless_sapling_addresses;
+
+        let sprout_notes = wallet
+            .transactions()
+            .values()
+            .map(|wtx| wtx.map_sprout_note_data().len())
+            .sum();
+
+        let attribution = explain_transaction_attribution(wallet).unwrap_or_default();
+
+        let undecrypted_mnemonic =
+            wallet.encrypted_mnemonic().is_some() && wallet.bip39_mnemonic().is_none();
+
+        let export_height = u32::from(export_height);
+        let incomplete_transactions = incomplete_transactions(
+            wallet.transactions(),
+            &attribution,
+            wallet.orchard_placeholder_positions(),
+            anchor_height,
+            export_height,
+        );
+        let placeholder_orchard_positions = wallet
+            .orchard_placeholder_positions()
+            .values()
+            .map(BTreeMap::len)
+            .sum();
+        let immature_coinbase_transactions = wallet
+            .transactions()
+            .iter()
+            .filter(|(_, wtx)| wtx.is_coinbase())
+            .filter_map(|(txid, _)| wallet.block_height_for_tx(txid))
+            .filter(|height| height.saturating_add(COINBASE_MATURITY) > export_height)
+            .count();
+
+        let warnings = wallet
+            .parse_warnings()
+            .iter()
+            .cloned()
+            .map(MigrationWarning::Parse)
+            .chain(migration_warnings(wallet, BlockHeight::from(export_height)))
+            .collect();
+
+        Self {
+            stale_sapling_witnesses,
+            watch_only_addresses,
+            missing_spending_keys,
+            sprout_notes,
+            undecrypted_mnemonic,
+            immature_coinbase_transactions,
+            placeholder_orchard_positions,
+            incomplete_transactions,
+            warnings,
+        }
+    }
+
+    /// The transactions whose export is incomplete, each with the ways in
+    /// which it is.
+    pub fn incomplete_transactions(&self) -> &BTreeMap<TxId, BTreeSet<TransactionLimitation>> {
+        &self.incomplete_transactions
+    }
+
+    /// What reading and migrating the wallet warned of: the wallet's parse
+    /// warnings, then those of its migration, each in the order found.
+    pub fn warnings(&self) -> &[MigrationWarning] {
+        &self.warnings
+    }
+}
+
+/// How urgently the importing wallet must act on `warning`: most warnings
+/// mark data that was dropped or approximated, but a normalized record or a
+/// linked address is only worth knowing of.
+fn warning_severity(warning: &MigrationWarning) -> ActionSeverity {
+    match warning {
+        MigrationWarning::Parse(
+            ParseWarning::NormalizedKeyname { .. } | ParseWarning::NormalizedAddress { .. },
+        )
+        | MigrationWarning::SaplingAddressLinked { .. } => ActionSeverity::Info,
+        _ => ActionSeverity::Warning,
+    }
+}
+
+/// The limitations of the export of each of `transactions` that has any,
+/// given the wallet's transaction `attribution` and the placeholder Orchard
+/// positions it was given.
+fn incomplete_transactions(
+    transactions: &HashMap<TxId, WalletTx>,
+    attribution: &TransactionAttribution,
+    placeholder_positions: &BTreeMap<TxId, BTreeMap<u32, u64>>,
+    anchor_height: u32,
+    export_height: u32,
+) -> BTreeMap<TxId, BTreeSet<TransactionLimitation>> {
+    let mut incomplete: BTreeMap<TxId, BTreeSet<TransactionLimitation>> = BTreeMap::new();
+    for (txid, wtx) in transactions {
+        let limitations = incomplete.entry(*txid).or_default();
+        if wtx.sapling_note_data().is_some_and(|notes| {
+            notes
+                .values()
+                .any(|note_data| sapling_witness_is_stale(note_data, anchor_height))
+        }) {
+            limitations.insert(TransactionLimitation::StaleSaplingWitness);
+        }
+        if attribution
+            .rules()
+            .get(txid)
+            .is_some_and(|rules| rules.contains(&TransactionRule::OrchardFallback))
+        {
+            limitations.insert(TransactionLimitation::AssumedOrchardOwnership);
+        }
+        if wtx.is_from_me()
+            && !wtx.is_coinbase()
+            && wtx
+                .vin()
+                .iter()
+                .any(|txin| !transactions.contains_key(&TxId::from_bytes(*txin.prevout().hash())))
+        {
+            limitations.insert(TransactionLimitation::MissingInputTransaction);
+        }
+        let expiry_height = u32::from(wtx.transaction().expiry_height());
+        let expired = expiry_height != 0 && expiry_height <= export_height;
+        if !is_confirmed(wtx) && !expired {
+            limitations.insert(TransactionLimitation::UnknownMinedStatus);
+        }
+        if placeholder_positions.contains_key(txid) {
+            limitations.insert(TransactionLimitation::PlaceholderOrchardPosition);
+        }
+    }
+    incomplete.retain(|_, limitations| !limitations.is_empty());
+    incomplete
+}
+
+/// How urgently the importing wallet must act on an [`ActionItem`]. Ordered
+/// from least to most urgent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ActionSeverity {
+    /// Worth knowing; nothing is lost by ignoring it.
+    Info,
+    /// Some funds are not spendable until the importing wallet acts.
+    Warning,
+    /// Funds may be unrecoverable from the export alone.
+    Critical,
+}
+
+impl fmt::Display for ActionSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ActionSeverity::Info => "info",
+            ActionSeverity::Warning => "warning",
+            ActionSeverity::Critical => "critical",
+        })
+    }
+}
+
+/// Something the importing wallet needs to do after importing the export.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActionItem {
+    pub severity: ActionSeverity,
+    pub description: String,
+}
+
+impl fmt::Display for ActionItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.severity, self.description)
+    }
+}
+
+/// The importing wallet's next steps for `report`, most urgent first.
+pub fn next_steps(report: &MigrationReport) -> Vec<ActionItem> {
+    let mut items = vec![];
+    let mut push = |severity, description: String| {
+        items.push(ActionItem {
+            severity,
+            description,
+        })
+    };
+
+    if report.undecrypted_mnemonic {
+        push(
+            ActionSeverity::Critical,
+            "the wallet's mnemonic is encrypted and was not exported; re-run the migration \
+             with the wallet passphrase to include it"
+                .to_string(),
+        );
+    }
+    if report.missing_spending_keys > 0 {
+        push(
+            ActionSeverity::Critical,
+            format!(
+                "{} address(es) have no key in the export; funds they receive cannot be spent \
+                 without the original wallet",
+                report.missing_spending_keys
+            ),
+        );
+    }
+    if report.placeholder_orchard_positions > 0 {
+        push(
+            ActionSeverity::Warning,
+            format!(
+                "{} Orchard note(s) were given placeholder positions because the wallet's note \
+                 commitment tree could not be read; rescan from the account birthday before \
+                 spending them",
+                report.placeholder_orchard_positions
+            ),
+        );
+    }
+    if report.stale_sapling_witnesses > 0 {
+        push(
+            ActionSeverity::Warning,
+            format!(
+                "{} Sapling note(s) have no witness as of the anchor height; rescan from the \
+                 account birthday before spending them",
+                report.stale_sapling_witnesses
+            ),
+        );
+    }
+    if report.sprout_notes > 0 {
+        push(
+            ActionSeverity::Warning,
+            format!(
+                "{} Sprout note(s) were received; Sprout funds can only be spent by a wallet \
+                 that supports Sprout, so migrate them to a newer pool with zcashd first",
+                report.sprout_notes
+            ),
+        );
+    }
+    if report.immature_coinbase_transactions > 0 {
+        push(
+            ActionSeverity::Info,
+            format!(
+                "{} coinbase transaction(s) are not yet mature; their outputs become spendable \
+                 {COINBASE_MATURITY} blocks after they were mined",
+                report.immature_coinbase_transactions
+            ),
+        );
+    }
+    if report.watch_only_addresses > 0 {
+        push(
+            ActionSeverity::Info,
+            format!(
+                "{} watch-only address(es) are exported; their funds are visible but cannot be \
+                 spent",
+                report.watch_only_addresses
+            ),
+        );
+    }
+
+    for warning in &report.warnings {
+        push(warning_severity(warning), warning.to_string());
+    }
+
+    items.sort_by_key(|item| std::cmp::Reverse(item.severity));
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parse;
+
+    fn severities(report: &MigrationReport) -> Vec<ActionSeverity> {
+        next_steps(report)
+            .iter()
+            .map(|item| item.severity)
+            .collect()
+    }
+
+    /// A wallet transaction holding a version 1 transaction that spends the
+    /// first output of each of `inputs` to one output, mined unless
+    /// `unmined`.
+    fn wallet_tx(inputs: &[TxId], from_me: bool, unmined: bool) -> WalletTx {
+        let mut bytes = 1u32.to_le_bytes().to_vec();
+        bytes.push(inputs.len() as u8);
+        for txid in inputs {
+            bytes.extend_from_slice(txid.as_bytes());
+            bytes.extend_from_slice(&0u32.to_le_bytes());
+            bytes.push(0);
+            bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        }
+        bytes.push(1);
+        bytes.extend_from_slice(&1_000i64.to_le_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        // CMerkleTx, then CWalletTx with empty collections.
+        bytes.extend_from_slice(&[if unmined { 0 } else { 0x42 }; 32]);
+        bytes.push(0);
+        bytes.extend_from_slice(&0i32.to_le_bytes());
+        bytes.extend_from_slice(&[0, 0, 0, 0]);
+        bytes.extend_from_slice(&0i32.to_le_bytes());
+        bytes.extend_from_slice(&1_600_000_000i32.to_le_bytes());
+        bytes.extend_from_slice(&[from_me as u8, 0]);
+        WalletTx::parse_buf(&bytes, false).unwrap()
+    }
+
+    fn txid(byte: u8) -> TxId {
+        TxId::from_bytes([byte; 32])
+    }
+
+    #[test]
+    fn incomplete_transactions_list_each_limitation() {
+        let transactions = HashMap::from([
+            (txid(1), wallet_tx(&[], false, false)),
+            // Spends a transaction the wallet holds and one it does not.
+            (txid(2), wallet_tx(&[txid(1), txid(9)], true, false)),
+            (txid(3), wallet_tx(&[txid(1)], true, true)),
+            // Received, so its inputs' transactions are not expected.
+            (txid(4), wallet_tx(&[txid(9)], false, false)),
+        ]);
+        let mut attribution = TransactionAttribution::default();
+        attribution.record(txid(1), TransactionRule::OrchardFallback);
+        attribution.record(txid(4), TransactionRule::OrchardViewingKey);
+
+        let incomplete =
+            incomplete_transactions(&transactions, &attribution, &BTreeMap::new(), 100, 110);
+        assert_eq!(
+            incomplete,
+            BTreeMap::from([
+                (
+                    txid(1),
+                    BTreeSet::from([TransactionLimitation::AssumedOrchardOwnership])
+                ),
+                (
+                    txid(2),
+                    BTreeSet::from([TransactionLimitation::MissingInputTransaction])
+                ),
+                (
+                    txid(3),
+                    BTreeSet::from([TransactionLimitation::UnknownMinedStatus])
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn placeholder_positions_are_reported() {
+        let transactions = HashMap::from([
+            (txid(1), wallet_tx(&[], false, false)),
+            (txid(2), wallet_tx(&[], false, false)),
+        ]);
+        let placeholders = BTreeMap::from([(txid(2), BTreeMap::from([(0, 0), (3, 1)]))]);
+
+        let incomplete = incomplete_transactions(
+            &transactions,
+            &TransactionAttribution::default(),
+            &placeholders,
+            100,
+            110,
+        );
+        assert_eq!(
+            incomplete,
+            BTreeMap::from([(
+                txid(2),
+                BTreeSet::from([TransactionLimitation::PlaceholderOrchardPosition])
+            )])
+        );
+    }
+
+    #[test]
+    fn clean_report_has_no_next_steps() {
+        assert!(next_steps(&MigrationReport::default()).is_empty());
+    }
+
+    #[test]
+    fn each_fact_yields_one_item() {
+        let cases = [
+            (
+                MigrationReport {
+                    undecrypted_mnemonic: true,
+                    ..Default::default()
+                },
+                ActionSeverity::Critical,
+                "passphrase",
+            ),
+            (
+                MigrationReport {
+                    missing_spending_keys: 2,
+                    ..Default::default()
+                },
+                ActionSeverity::Critical,
+                "2 address(es) have no key",
+            ),
+            (
+                MigrationReport {
+                    stale_sapling_witnesses: 3,
+                    ..Default::default()
+                },
+                ActionSeverity::Warning,
+                "3 Sapling note(s)",
+            ),
+            (
+                MigrationReport {
+                    placeholder_orchard_positions: 2,
+                    ..Default::default()
+                },
+                ActionSeverity::Warning,
+                "2 Orchard note(s)",
+            ),
+            (
+                MigrationReport {
+                    sprout_notes: 1,
+                    ..Default::default()
+                },
+                ActionSeverity::Warning,
+                "1 Sprout note(s)",
+            ),
+            (
+                MigrationReport {
+                    immature_coinbase_transactions: 4,
+                    ..Default::default()
+                },
+                ActionSeverity::Info,
+                "4 coinbase transaction(s)",
+            ),
+            (
+                MigrationReport {
+                    watch_only_addresses: 5,
+                    ..Default::default()
+                },
+                ActionSeverity::Info,
+                "5 watch-only address(es)",
+            ),
+        ];
+        for (report, severity, text) in cases {
+            let items = next_steps(&report);
+            assert_eq!(items.len(), 1, "{report:?}");
+            assert_eq!(items[0].severity, severity);
+            assert!(items[0].description.contains(text), "{}", items[0]);
+        }
+    }
+
+    #[test]
+    fn most_urgent_items_come_first() {
+        let report = MigrationReport {
+            stale_sapling_witnesses: 1,
+            watch_only_addresses: 1,
+            missing_spending_keys: 1,
+            sprout_notes: 1,
+            undecrypted_mnemonic: true,
+            immature_coinbase_transactions: 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            severities(&report),
+            vec![
+                ActionSeverity::Critical,
+                ActionSeverity::Critical,
+                ActionSeverity::Warning,
+                ActionSeverity::Warning,
+                ActionSeverity::Info,
+                ActionSeverity::Info,
+            ]
+        );
+    }
+
+    #[test]
+    fn wallet_warnings_are_reported_parse_warnings_first() {
+        use crate::zcashd_wallet::{BlockLocator, ClientVersion, NetworkInfo, ZcashdWalletBuilder};
+
+        let normalized = ParseWarning::NormalizedKeyname {
+            found: " tx".to_string(),
+            keyname: "tx".to_string(),
+        };
+        let wallet = ZcashdWalletBuilder::new()
+            .bestblock(BlockLocator::new(ClientVersion::from_integer(0), vec![]))
+            .network_info(NetworkInfo::new(
+                zewif::Network::Regtest(Default::default()),
+            ))
+            .parse_warnings(vec![normalized.clone()])
+            .build()
+            .unwrap();
+
+        let report = MigrationReport::from_wallet(&wallet, BlockHeight::from(1_000u32));
+        assert_eq!(
+            report.warnings(),
+            [
+                MigrationWarning::Parse(normalized),
+                MigrationWarning::NoFundsDetected
+            ]
+        );
+        let items = next_steps(&report);
+        assert_eq!(
+            severities(&report),
+            [ActionSeverity::Warning, ActionSeverity::Info]
+        );
+        assert!(
+            items[0].description.starts_with("no funds detected"),
+            "{}",
+            items[0]
+        );
+        assert!(
+            items[1].description.contains("surrounding whitespace"),
+            "{}",
+            items[1]
+        );
+    }
+
+    #[test]
+    fn items_render_with_their_severity() {
+        let item = ActionItem {
+            severity: ActionSeverity::Warning,
+            description: "rescan".to_string(),
+        };
+        assert_eq!(item.to_string(), "[warning] rescan");
+    }
+}
@@ -5,23 +5,46 @@ use zewif::{
     sprout::SproutSpendingKey, transparent::TransparentSpendingKey,
 };
 
-use crate::migrate::MigrateError;
-use crate::{ZcashdWallet, migrate::addresses::sprout_address_string};
+use crate::migrate::{MigrateError, MigrationWarning};
+use crate::{
+    ZcashdWallet,
+    migrate::{addresses::sprout_address_string, mnemonic::mnemonic_seed, validate_mnemonic},
+};
 
 /// The ZIP-32 seed fingerprint of the wallet's mnemonic seed, if a mnemonic is
-/// present. Taken from the mnemonic HD chain, where zcashd records it directly.
+/// present. See [`mnemonic_seed_fingerprint_bytes`].
 pub(crate) fn mnemonic_seed_fingerprint(wallet: &ZcashdWallet) -> Option<SeedFingerprint> {
+    mnemonic_seed_fingerprint_from_bytes(wallet, mnemonic_seed_fingerprint_bytes(wallet))
+}
+
+/// [`mnemonic_seed_fingerprint`], given the wallet's
+/// [`mnemonic_seed_fingerprint_bytes`], for a caller that needs both and so
+/// derives the mnemonic seed only once.
+pub(crate) fn mnemonic_seed_fingerprint_from_bytes(
+    wallet: &ZcashdWallet,
+    bytes: Option<[u8; 32]>,
+) -> Option<SeedFingerprint> {
     let mnemonic = wallet.bip39_mnemonic()?;
-    if mnemonic.mnemonic().is_empty() {
+    if mnemonic.phrase().is_empty() {
         return None;
     }
-    let bytes: [u8; 32] = wallet
-        .mnemonic_hd_chain()?
-        .seed_fp()
-        .as_slice()
-        .try_into()
-        .ok()?;
-    Some(crate::zcashd_wallet::encode_seed_fingerprint(&bytes))
+    bytes.map(|bytes| crate::zcashd_wallet::encode_seed_fingerprint(&bytes))
+}
+
+/// The raw ZIP-32 seed fingerprint of the wallet's mnemonic seed.
+///
+/// This is computed from the phrase, which is what an importer recomputes, so
+/// a mnemonic HD chain left over from a replaced mnemonic cannot name the
+/// wallet's seed. The chain's recorded fingerprint is used only when the
+/// phrase is unavailable (e.g. encrypted and skipped) or cannot be read.
+pub(crate) fn mnemonic_seed_fingerprint_bytes(wallet: &ZcashdWallet) -> Option<[u8; 32]> {
+    wallet
+        .bip39_mnemonic()
+        .filter(|mnemonic| !mnemonic.phrase().is_empty())
+        .and_then(mnemonic_seed)
+        .and_then(|seed| zip32::fingerprint::SeedFingerprint::from_seed(&seed))
+        .map(|fp| fp.to_bytes())
+        .or_else(|| wallet.mnemonic_hd_chain().map(|chain| *chain.seed_fp()))
 }
 
 /// The BIP-39 mnemonic and ZIP-32 seed fingerprint that zcashd derives from a
@@ -82,7 +105,10 @@ pub(crate) fn legacy_seed_fingerprint(wallet: &ZcashdWallet) -> Result<Option<Se
 /// Sprout spending keys (keyed by address).
 ///
 /// Returns `None` when no secret material is present (a viewing-only export).
-pub(crate) fn build_secret_store(wallet: &ZcashdWallet) -> Result<Option<SecretStore>, MigrateError> {
+pub(crate) fn build_secret_store(
+    wallet: &ZcashdWallet,
+    warnings: &mut Vec<MigrationWarning>,
+) -> Result<Option<SecretStore>, MigrateError> {
     let mut store = SecretStore::new();
 
     // Seeds. The mnemonic seed is recorded directly on v4.7.0+ wallets;
@@ -91,12 +117,24 @@ pub(crate) fn build_secret_store(wallet: &ZcashdWallet) -> Result<Option<SecretS
     // be imported as a seed-derived account.
     match (mnemonic_seed_fingerprint(wallet), wallet.bip39_mnemonic()) {
         (Some(fp), Some(mnemonic)) => {
+            if let Err(problem) = validate_mnemonic(mnemonic) {
+                warnings.push(MigrationWarning::InvalidMnemonic(problem));
+            }
             store.add_seed(SeedEntry::new(
                 fp,
-                SeedMaterial::Bip39Mnemonic(mnemonic.clone()),
+                SeedMaterial::Bip39Mnemonic(mnemonic.to_bip39_mnemonic()),
             ));
         }
         _ => {
+            // A recorded phrase has a fingerprint unless it is invalid and no
+            // mnemonic HD chain records one, in which case it cannot be keyed
+            // in the secret store and is left out.
+            if let Some(mnemonic) = wallet.bip39_mnemonic()
+                && !mnemonic.phrase().is_empty()
+                && let Err(problem) = validate_mnemonic(mnemonic)
+            {
+                warnings.push(MigrationWarning::UnexportableMnemonic(problem));
+            }
             if let Some((mnemonic, fp)) = legacy_mnemonic_seed(wallet)? {
                 store.add_seed(SeedEntry::new(fp, SeedMaterial::Bip39Mnemonic(mnemonic)));
             }
@@ -131,7 +169,9 @@ pub(crate) fn build_secret_store(wallet: &ZcashdWallet) -> Result<Option<SecretS
     for (pubkey, privkey) in transparent_sources {
         match transparent_key_entry(pubkey, privkey, wallet.network()) {
             Ok(entry) => store.add_transparent_key(entry),
-            Err(e) => eprintln!("warning: skipping transparent key: {e}"),
+            Err(e) => warnings.push(MigrationWarning::SkippedTransparentKey {
+                error: e.to_string(),
+            }),
         }
     }
 
@@ -147,7 +187,7 @@ pub(crate) fn build_secret_store(wallet: &ZcashdWallet) -> Result<Option<SecretS
             let efvk = extsk.to_extended_full_viewing_key();
             (
                 zcash_keys::encoding::encode_extended_full_viewing_key(extfvk_hrp, &efvk),
-                zcash_keys::encoding::encode_extended_spending_key(extsk_hrp, extsk),
+                zcash_keys::encoding::encode_extended_spending_key(extsk_hrp, &extsk),
             )
         })
         .collect();
@@ -199,7 +239,7 @@ pub(crate) fn build_secret_store(wallet: &ZcashdWallet) -> Result<Option<SecretS
 /// viewing key into its canonical 169-byte ZIP-32 encoding.
 /// The ZIP 32 Bech32 Human-Readable Parts for Sapling extended keys on the
 /// given network: (extended spending key, extended full viewing key).
-fn sapling_hrps(network: &zewif::Network) -> (&'static str, &'static str) {
+pub(crate) fn sapling_hrps(network: &zewif::Network) -> (&'static str, &'static str) {
     use zcash_protocol::constants::{mainnet, regtest, testnet};
     match network {
         zewif::Network::Mainnet => (
@@ -272,4 +312,60 @@ mod tests {
         assert!(fp.encoding().starts_with("zip32seedfp1"));
         assert_eq!(fp, derive_legacy_mnemonic_seed(&seed).unwrap().1);
     }
+
+    /// A wallet recording `phrase` as its mnemonic, and a mnemonic HD chain
+    /// if `chain_fp` is given.
+    fn wallet_with_mnemonic(phrase: &str, chain_fp: Option<[u8; 32]>) -> ZcashdWallet {
+        use crate::zcashd_wallet::{
+            BlockLocator, ClientVersion, MnemonicHDChain, MnemonicPhrase, NetworkInfo,
+            ZcashdWalletBuilder,
+        };
+
+        let mut builder = ZcashdWalletBuilder::new()
+            .bestblock(BlockLocator::new(ClientVersion::from_integer(0), vec![]))
+            .network_info(NetworkInfo::new(
+                zewif::Network::Regtest(Default::default()),
+            ))
+            .bip39_mnemonic(MnemonicPhrase::new(phrase, Some(MnemonicLanguage::English)));
+        if let Some(fp) = chain_fp {
+            let mut bytes = 1i32.to_le_bytes().to_vec();
+            bytes.extend_from_slice(&fp);
+            bytes.extend_from_slice(&[0; 8 + 4 * 4 + 1]);
+            let chain = crate::parse!(buf = &bytes, MnemonicHDChain, "chain").unwrap();
+            builder = builder.mnemonic_hd_chain(chain);
+        }
+        builder.build().unwrap()
+    }
+
+    const BAD_CHECKSUM: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon";
+
+    #[test]
+    fn invalid_mnemonic_is_exported_under_the_chain_fingerprint() {
+        let wallet = wallet_with_mnemonic(BAD_CHECKSUM, Some([0xab; 32]));
+        let mut warnings = vec![];
+        let store = build_secret_store(&wallet, &mut warnings).unwrap().unwrap();
+
+        assert_eq!(
+            warnings,
+            [MigrationWarning::InvalidMnemonic(
+                crate::migrate::MnemonicProblem::BadChecksum
+            )]
+        );
+        assert_eq!(store.seeds().len(), 1);
+    }
+
+    #[test]
+    fn invalid_mnemonic_without_a_fingerprint_is_reported() {
+        let wallet = wallet_with_mnemonic(BAD_CHECKSUM, None);
+        let mut warnings = vec![];
+        let store = build_secret_store(&wallet, &mut warnings).unwrap();
+
+        assert_eq!(
+            warnings,
+            [MigrationWarning::UnexportableMnemonic(
+                crate::migrate::MnemonicProblem::BadChecksum
+            )]
+        );
+        assert!(store.is_none());
+    }
 }
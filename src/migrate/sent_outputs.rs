@@ -1,6 +1,6 @@
 use zcash_transparent::address::TransparentAddress;
 
-use zewif::{Amount, Network, SentOutput, transparent::TransparentSentOutput};
+use zewif::{Amount, SentOutput, transparent::TransparentSentOutput};
 
 use crate::migrate::MigrateError;
 use crate::{
@@ -21,7 +21,7 @@ use crate::{
 /// wallet's outgoing viewing keys to recover their value and index, which is
 /// not attempted; the raw transaction (carried in the export) remains
 /// authoritative, and the destination unified addresses are preserved in the
-/// address book.
+/// address book and in each transaction's recipient mappings extension.
 ///
 /// The sending account is not recoverable from `recipientmapping` alone, so
 /// sent outputs are attributed to the legacy account, which holds the
@@ -37,9 +37,10 @@ pub(crate) fn attach_sent_outputs(
         let Some(wtx) = wallet.transactions().get(txid) else {
             continue;
         };
-        let Some(bundle) = wtx.transaction().transparent_bundle() else {
+        let vout = wtx.vout();
+        if vout.is_empty() {
             continue;
-        };
+        }
 
         let mut outputs = Vec::new();
         for mapping in mappings {
@@ -47,11 +48,11 @@ pub(crate) fn attach_sent_outputs(
                 continue;
             };
 
-            for (idx, tx_out) in bundle.vout.iter().enumerate() {
+            for (idx, tx_out) in vout.iter().enumerate() {
                 if tx_out.recipient_address() == Some(target) {
                     let value = Amount::from_u64(tx_out.value().into_u64())?;
                     let recipient = if mapping.unified_address.is_empty() {
-                        transparent_recipient_string(&mapping.recipient_address, network)
+                        mapping.recipient_address.to_string(network)
                     } else {
                         mapping.unified_address.clone()
                     };
@@ -89,17 +90,6 @@ fn key_id_bytes(key_id: &KeyId) -> [u8; 20] {
     *AsRef::<[u8; 20]>::as_ref(&u160::from(key_id.clone()))
 }
 
-fn script_id_bytes(script_id: &ScriptId) -> [u8; 20] {
+pub(crate) fn script_id_bytes(script_id: &ScriptId) -> [u8; 20] {
     *AsRef::<[u8; 20]>::as_ref(&u160::from(script_id.clone()))
 }
-
-/// The canonical t-address string for a transparent recipient.
-fn transparent_recipient_string(recipient: &RecipientAddress, network: &Network) -> String {
-    match recipient {
-        RecipientAddress::KeyId(key_id) => key_id.to_string(network),
-        RecipientAddress::ScriptId(script_id) => script_id.to_string(network),
-        // Not reached: callers only pass transparent recipients here.
-        RecipientAddress::Sapling(addr) => addr.to_string(network),
-        RecipientAddress::Orchard(addr) => addr.to_string(network),
-    }
-}
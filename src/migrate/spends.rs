@@ -0,0 +1,396 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use orchard::keys::{OutgoingViewingKey as OrchardOvk, Scope};
+use sapling::{
+    keys::OutgoingViewingKey as SaplingOvk,
+    note_encryption::{Zip212Enforcement, try_sapling_output_recovery},
+};
+use secp256k1::PublicKey;
+use zcash_transparent::{address::TransparentAddress, bundle::TxOut};
+use zewif::TxId;
+
+use crate::{
+    ZcashdWallet,
+    migrate::{
+        AccountFingerprint, TransactionAccounts, TransactionClassification, TransactionRule,
+        WalletAccounts, received_outputs::sapling_note_account, sent_outputs::script_id_bytes,
+    },
+    zcashd_wallet::WalletTx,
+};
+
+/// Make each transaction spending the wallet's funds relevant to the accounts
+/// it spends from and pays, and classify every transaction by which of the
+/// wallet's accounts it spends from and pays, and whether it pays outside the
+/// wallet.
+///
+/// A spend is traced to the account that received what it spends: a Sapling
+/// spend by the nullifier zcashd recorded for the note, an Orchard spend by
+/// the nullifier of a note a unified account's viewing key decrypts, and a
+/// transparent input by the key the spent output paid, when the wallet holds
+/// the transaction creating it. A transaction the wallet created whose spends
+/// trace to no account is classified as [`TransactionClassification::Sent`].
+///
+/// A transaction pays outside the wallet if it has a transparent output of
+/// value to an address none of the wallet's keys or redeem scripts produce,
+/// or a Sapling or Orchard output of value the wallet did not receive but
+/// recovers with one of its outgoing viewing keys. zcashd encrypts the
+/// shielded outputs of a transaction spending only transparent funds to an
+/// outgoing viewing key derived from its legacy seed, which is not derived
+/// here, so those outputs go unseen.
+///
+/// This reads the relevant transactions attached by `attach_received_outputs`,
+/// so must run after it.
+pub(crate) fn attach_spends(wallet: &ZcashdWallet, accounts: &mut WalletAccounts) {
+    let owners = SpendOwners::from_wallet(wallet, accounts);
+
+    for (txid, wtx) in wallet.transactions() {
+        let sapling_spends = wtx
+            .sapling_bundle()
+            .into_iter()
+            .flat_map(|bundle| bundle.shielded_spends())
+            .filter_map(|spend| owners.sapling_notes.get(&spend.nullifier().0))
+            .map(|&index| (index, TransactionRule::SaplingSpend));
+        let orchard_spends = wtx
+            .orchard_bundle()
+            .into_iter()
+            .flat_map(|bundle| bundle.actions())
+            .filter_map(|action| owners.orchard_notes.get(&action.nullifier().to_bytes()))
+            .map(|&index| (index, TransactionRule::OrchardSpend));
+        let transparent_spends = wtx
+            .vin()
+            .iter()
+            .filter_map(|input| {
+                let prevout = input.prevout();
+                let spent = wallet
+                    .transactions()
+                    .get(&TxId::from_bytes(*prevout.hash()))?
+                    .vout()
+                    .get(prevout.n() as usize)?;
+                owners.transparent_owner(spent)
+            })
+            .map(|index| (index, TransactionRule::TransparentSpend));
+        let spends: BTreeSet<(usize, TransactionRule)> = sapling_spends
+            .chain(orchard_spends)
+            .chain(transparent_spends)
+            .collect();
+        let spent_from: BTreeSet<usize> = spends.iter().map(|(index, _)| *index).collect();
+
+        let transparent_payees: BTreeSet<usize> = wtx
+            .vout()
+            .iter()
+            .filter_map(|output| owners.transparent_owner(output))
+            .collect();
+        let paid_to: BTreeSet<usize> = accounts
+            .accounts
+            .iter()
+            .enumerate()
+            .filter(|(_, account)| account.relevant_transactions().contains_key(txid))
+            .map(|(index, _)| index)
+            .chain(transparent_payees.iter().copied())
+            .collect();
+
+        if !spent_from.is_empty() {
+            let payments = transparent_payees
+                .iter()
+                .map(|&index| (index, TransactionRule::TransparentPayment));
+            for (account_index, rule) in spends.iter().copied().chain(payments) {
+                let account = &mut accounts.accounts[account_index];
+                if !account.relevant_transactions().contains_key(txid) {
+                    account.add_relevant_transaction(*txid, vec![]);
+                }
+                accounts.attribution.record(*txid, rule);
+            }
+        }
+
+        let pays_outside = !spent_from.is_empty() && owners.pays_outside(wtx);
+        if let Some(classification) = classify(
+            wtx.is_from_me(),
+            &spent_from,
+            &paid_to,
+            pays_outside,
+            &accounts.fingerprints,
+        ) {
+            let fingerprints = |indices: &BTreeSet<usize>| {
+                indices
+                    .iter()
+                    .map(|&index| accounts.fingerprints[index].clone())
+                    .collect()
+            };
+            let transaction_accounts = TransactionAccounts {
+                spent_from: fingerprints(&spent_from),
+                paid_to: fingerprints(&paid_to),
+            };
+            accounts
+                .attribution
+                .classify(*txid, classification, transaction_accounts);
+        }
+    }
+}
+
+/// The accounts owning the wallet's notes and transparent outputs, and the
+/// outgoing viewing keys its shielded payments are recovered with.
+struct SpendOwners {
+    /// The account that received each Sapling note, by its nullifier.
+    sapling_notes: HashMap<[u8; 32], usize>,
+    /// The account that received each Orchard note, by its nullifier.
+    orchard_notes: HashMap<[u8; 32], usize>,
+    /// The account holding each P2PKH key and P2SH redeem script.
+    transparent: HashMap<TransparentAddress, usize>,
+    sapling_ovks: Vec<SaplingOvk>,
+    orchard_ovks: Vec<OrchardOvk>,
+}
+
+impl SpendOwners {
+    fn from_wallet(wallet: &ZcashdWallet, accounts: &WalletAccounts) -> Self {
+        let mut sapling_notes = HashMap::new();
+        let mut orchard_notes = HashMap::new();
+        for wtx in wallet.transactions().values() {
+            for note_data in wtx.sapling_note_data().into_iter().flat_map(|m| m.values()) {
+                if let Some(nullifier) = note_data.nullifier() {
+                    sapling_notes.insert(
+                        *nullifier,
+                        sapling_note_account(wallet, accounts, note_data),
+                    );
+                }
+            }
+            // zcashd records no nullifiers for Orchard notes; derive them
+            // from the notes the unified accounts' viewing keys decrypt.
+            let (Some(meta), Some(bundle)) = (wtx.orchard_tx_meta(), wtx.orchard_bundle()) else {
+                continue;
+            };
+            for action_index in meta.receiving_keys().keys() {
+                let decrypted = accounts.unified.iter().find_map(|(index, ufvk)| {
+                    let fvk = ufvk.orchard()?;
+                    [Scope::External, Scope::Internal]
+                        .into_iter()
+                        .find_map(|scope| {
+                            let ivk = fvk.to_ivk(scope);
+                            let (note, _, _) =
+                                bundle.decrypt_output_with_key(*action_index as usize, &ivk)?;
+                            Some((note.nullifier(fvk).to_bytes(), *index))
+                        })
+                });
+                orchard_notes.extend(decrypted);
+            }
+        }
+
+        let mut transparent = HashMap::new();
+        for keypair in wallet.keys().keypairs() {
+            // Unparsable keys are rejected when the addresses are attached.
+            if let Ok(pk) = PublicKey::from_slice(keypair.pubkey().as_slice()) {
+                transparent.insert(
+                    TransparentAddress::from_pubkey(&pk),
+                    accounts.legacy_index_for(Some(keypair.metadata())),
+                );
+            }
+        }
+        for script_id in wallet.cscripts().keys() {
+            transparent.insert(
+                TransparentAddress::ScriptHash(script_id_bytes(script_id)),
+                accounts.legacy_index,
+            );
+        }
+
+        let legacy_sapling_ovks = wallet.sapling_keys().keypairs().map(|key| {
+            key.extsk()
+                .to_diversifiable_full_viewing_key()
+                .to_ovk(zip32::Scope::External)
+        });
+        let unified_sapling_ovks = accounts
+            .unified
+            .iter()
+            .filter_map(|(_, ufvk)| ufvk.sapling())
+            .flat_map(|dfvk| {
+                [zip32::Scope::External, zip32::Scope::Internal].map(|scope| dfvk.to_ovk(scope))
+            });
+        let orchard_ovks = accounts
+            .unified
+            .iter()
+            .filter_map(|(_, ufvk)| ufvk.orchard())
+            .flat_map(|fvk| [Scope::External, Scope::Internal].map(|scope| fvk.to_ovk(scope)))
+            .collect();
+
+        SpendOwners {
+            sapling_notes,
+            orchard_notes,
+            transparent,
+            sapling_ovks: legacy_sapling_ovks.chain(unified_sapling_ovks).collect(),
+            orchard_ovks,
+        }
+    }
+
+    /// The account holding the key or redeem script a transparent output
+    /// pays, if the wallet has it.
+    fn transparent_owner(&self, output: &TxOut) -> Option<usize> {
+        self.transparent.get(&output.recipient_address()?).copied()
+    }
+
+    /// Whether `wtx` pays value to an address outside the wallet.
+    fn pays_outside(&self, wtx: &WalletTx) -> bool {
+        let transparent = wtx.vout().iter().any(|output| {
+            output.value().into_u64() > 0 && self.transparent_owner(output).is_none()
+        });
+
+        let received_sapling: HashSet<u32> = wtx
+            .sapling_note_data()
+            .into_iter()
+            .flat_map(|notes| notes.keys())
+            .map(|outpoint| outpoint.vout())
+            .collect();
+        let sapling = wtx.sapling_bundle().is_some_and(|bundle| {
+            bundle
+                .shielded_outputs()
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| !received_sapling.contains(&(*index as u32)))
+                .any(|(_, output)| {
+                    self.sapling_ovks.iter().any(|ovk| {
+                        // Under the grace period both note plaintext versions
+                        // decrypt.
+                        try_sapling_output_recovery(ovk, output, Zip212Enforcement::GracePeriod)
+                            .is_some_and(|(note, _, _)| note.value().inner() > 0)
+                    })
+                })
+        });
+
+        let received_orchard: HashSet<u32> = wtx
+            .orchard_tx_meta()
+            .into_iter()
+            .flat_map(|meta| meta.receiving_keys().keys())
+            .copied()
+            .collect();
+        let orchard = wtx.orchard_bundle().is_some_and(|bundle| {
+            (0..bundle.actions().len())
+                .filter(|index| !received_orchard.contains(&(*index as u32)))
+                .any(|index| {
+                    self.orchard_ovks.iter().any(|ovk| {
+                        bundle
+                            .recover_output_with_ovk(index, ovk)
+                            .is_some_and(|(note, _, _)| note.value().inner() > 0)
+                    })
+                })
+        });
+
+        transparent || sapling || orchard
+    }
+}
+
+/// Classify a transaction from the accounts whose funds it spends, the
+/// accounts it pays and whether it pays outside the wallet; `None` if it
+/// neither spends from nor pays the wallet. Accounts are given by their
+/// indices in `fingerprints`.
+fn classify(
+    is_from_me: bool,
+    spent_from: &BTreeSet<usize>,
+    paid_to: &BTreeSet<usize>,
+    pays_outside: bool,
+    fingerprints: &[AccountFingerprint],
+) -> Option<TransactionClassification> {
+    let spent_from: Vec<usize> = spent_from.iter().copied().collect();
+    match spent_from.as_slice() {
+        [] if is_from_me => Some(TransactionClassification::Sent),
+        [] if paid_to.is_empty() => None,
+        [] => Some(TransactionClassification::Received),
+        [from] if !pays_outside => {
+            let others: Vec<usize> = paid_to.iter().copied().filter(|to| to != from).collect();
+            match others.as_slice() {
+                [to] => Some(TransactionClassification::InternalTransfer {
+                    from: fingerprints[*from].clone(),
+                    to: fingerprints[*to].clone(),
+                }),
+                _ => Some(TransactionClassification::Sent),
+            }
+        }
+        _ => Some(TransactionClassification::Sent),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accounts(indices: &[usize]) -> BTreeSet<usize> {
+        indices.iter().copied().collect()
+    }
+
+    /// Three accounts: two of one seed and one of another.
+    fn fingerprints() -> Vec<AccountFingerprint> {
+        let seed = |byte| Some(crate::zcashd_wallet::encode_seed_fingerprint(&[byte; 32]));
+        vec![
+            AccountFingerprint::new(seed(1), 0),
+            AccountFingerprint::new(seed(1), 0x7FFF_FFFF),
+            AccountFingerprint::new(seed(2), 0x7FFF_FFFF),
+        ]
+    }
+
+    fn classify(
+        is_from_me: bool,
+        spent_from: &[usize],
+        paid_to: &[usize],
+        pays_outside: bool,
+    ) -> Option<TransactionClassification> {
+        super::classify(
+            is_from_me,
+            &accounts(spent_from),
+            &accounts(paid_to),
+            pays_outside,
+            &fingerprints(),
+        )
+    }
+
+    #[test]
+    fn transfer_between_two_accounts_is_internal() {
+        let internal = TransactionClassification::InternalTransfer {
+            from: fingerprints()[1].clone(),
+            to: fingerprints()[0].clone(),
+        };
+        assert_eq!(classify(true, &[1], &[0], false), Some(internal.clone()));
+        // Change returned to the sending account.
+        assert_eq!(classify(true, &[1], &[0, 1], false), Some(internal));
+    }
+
+    #[test]
+    fn transfer_also_paying_outside_the_wallet_is_sent() {
+        assert_eq!(
+            classify(true, &[1], &[0], true),
+            Some(TransactionClassification::Sent)
+        );
+        assert_eq!(
+            classify(true, &[1], &[0, 1], true),
+            Some(TransactionClassification::Sent)
+        );
+    }
+
+    #[test]
+    fn payments_out_of_one_account_are_sent() {
+        assert_eq!(
+            classify(true, &[1], &[1], false),
+            Some(TransactionClassification::Sent)
+        );
+        assert_eq!(
+            classify(true, &[1], &[], true),
+            Some(TransactionClassification::Sent)
+        );
+        assert_eq!(
+            classify(true, &[1], &[0, 2], false),
+            Some(TransactionClassification::Sent)
+        );
+        assert_eq!(
+            classify(true, &[0, 1], &[2], false),
+            Some(TransactionClassification::Sent)
+        );
+    }
+
+    #[test]
+    fn untraced_spends_fall_back_to_the_from_me_flag() {
+        assert_eq!(
+            classify(true, &[], &[0], false),
+            Some(TransactionClassification::Sent)
+        );
+        assert_eq!(
+            classify(false, &[], &[0], false),
+            Some(TransactionClassification::Received)
+        );
+        assert_eq!(classify(false, &[], &[], false), None);
+    }
+}
@@ -1,11 +1,21 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, convert::Infallible};
 
+use minicbor::Encoder;
+use sapling::{
+    keys::PreparedIncomingViewingKey,
+    note_encryption::{Zip212Enforcement, try_sapling_note_decryption},
+};
+use zcash_protocol::value::{BalanceError, ZatBalance};
 use zewif::{
-    BlockHash, BlockHeight, Data, RawTxData, Transaction, TransactionData, TxBlockPosition, TxId,
+    Amount, BlockHash, BlockHeight, Data, Network, RawTxData, Transaction, TransactionData,
+    TxBlockPosition, TxId, ZewifWallet,
 };
 
-use crate::migrate::MigrateError;
-use crate::{ZcashdWallet, zcashd_wallet::WalletTx};
+use crate::migrate::{MigrateError, MigrationWarning};
+use crate::{
+    ZcashdWallet,
+    zcashd_wallet::{KnownOrderFormData, RecipientMapping, WalletTx, is_confirmed},
+};
 
 /// Build the global transaction table and, as a by-product, a map from txid to
 /// the block height at which each transaction was mined (recoverable only for
@@ -13,18 +23,322 @@ use crate::{ZcashdWallet, zcashd_wallet::WalletTx};
 pub(crate) fn convert_transactions(
     wallet: &ZcashdWallet,
 ) -> Result<HashMap<TxId, Transaction>, MigrateError> {
-    let tx_heights = collect_tx_heights(wallet);
     let mut transactions = HashMap::new();
-    for (txid, wtx) in wallet.transactions() {
-        let tx = convert_transaction(*txid, wtx, &tx_heights).map_err(|source| {
+    for_each_converted_transaction(wallet, |txid, tx| {
+        transactions.insert(txid, tx);
+        Ok(())
+    })?;
+    Ok(transactions)
+}
+
+/// Convert the wallet's transactions one at a time, in txid order, handing
+/// each to `f` as soon as it is converted. Each conversion depends only on its
+/// own wallet transaction and the per-transaction heights, so no more than one
+/// converted transaction need be held at once.
+pub(crate) fn for_each_converted_transaction(
+    wallet: &ZcashdWallet,
+    mut f: impl FnMut(TxId, Transaction) -> Result<(), MigrateError>,
+) -> Result<(), MigrateError> {
+    let tx_heights = collect_tx_heights(wallet);
+    let spent_by = collect_transparent_spends(wallet.transactions());
+    let mut txids: Vec<&TxId> = wallet.transactions().keys().collect();
+    txids.sort();
+    for txid in txids {
+        let wtx = &wallet.transactions()[txid];
+        let mut tx = convert_transaction(*txid, wtx, &tx_heights).map_err(|source| {
             MigrateError::TransactionConversion {
                 txid: *txid,
                 source: Box::new(source),
             }
         })?;
-        transactions.insert(*txid, tx);
+        if let Some(mappings) = wallet.send_recipients().get(txid) {
+            attach_recipient_mappings(&mut tx, mappings, wallet.network());
+        }
+        attach_spent_outputs(&mut tx, wtx.vout().len(), &spent_by);
+        if let Some(fee) = transaction_fee(wtx, wallet.transactions()) {
+            tx.set_fee(fee);
+        }
+        f(*txid, tx)?;
     }
-    Ok(transactions)
+    Ok(())
+}
+
+/// The extension vendor under which zcashd-specific metadata is recorded.
+pub const ZCASHD_EXTENSION_VENDOR: &str = "zcashd";
+
+/// The extension key, under [`ZCASHD_EXTENSION_VENDOR`], of a sent
+/// transaction's recipient mappings.
+///
+/// The value is a CBOR array with one map per `recipientmapping` record,
+/// holding the unified address the user paid (`unified_address`) and the
+/// receiver of it the transaction actually pays (`receiver`, encoded as an
+/// address). The chain shows only the receiver, so this is the only record of
+/// which unified address a payment went to.
+pub const RECIPIENT_MAPPINGS_EXTENSION_KEY: &str = "recipient_mappings";
+
+/// The extension key, under [`ZCASHD_EXTENSION_VENDOR`], of a transaction's
+/// position in zcashd's ordering of the wallet's history (`n`), as a CBOR
+/// integer. Only transactions for which zcashd recorded a position have one.
+pub const ORDER_POSITION_EXTENSION_KEY: &str = "order_position";
+
+/// The extension key, under [`ZCASHD_EXTENSION_VENDOR`], of the wallet's next
+/// order position (`orderposnext`), as a CBOR integer. It is recorded on the
+/// exported wallet when zcashd recorded one.
+///
+/// An importer that goes on appending transactions to the migrated history
+/// should number new entries from `max(orderposnext, max(order positions) + 1)`
+/// (see [`ORDER_POSITION_EXTENSION_KEY`]), rather than restart at 0 and
+/// collide with the migrated positions.
+pub const ORDER_POS_NEXT_EXTENSION_KEY: &str = "orderposnext";
+
+/// The extension key, under [`ZCASHD_EXTENSION_VENDOR`], of the height of the
+/// tip of the wallet's best-block locator (`bestblock`), as a CBOR integer:
+/// the height zcashd had synced the wallet to, from which an importer can
+/// resume scanning. The tip's hash is the document's export block hash.
+///
+/// The locator records only hashes, so the height is recovered from a locator
+/// block that a wallet transaction of known height was mined in, and is
+/// absent if there is none.
+pub const BEST_BLOCK_HEIGHT_EXTENSION_KEY: &str = "bestblock_height";
+
+/// The extension key, under [`ZCASHD_EXTENSION_VENDOR`], of the known fields
+/// of a transaction's order form (`vOrderForm`), as a CBOR map holding the
+/// plaintext `memo` and `message`, as text, and the serialized BIP 70
+/// `payment_request`, as bytes. Only the fields zcashd recorded are present,
+/// and transactions without any have no entry.
+///
+/// zcashd records these when the wallet front end supplies them; they are not
+/// on chain, and the memo is not the memo of any shielded output.
+pub const ORDER_FORM_EXTENSION_KEY: &str = "order_form";
+
+/// The extension key, under [`ZCASHD_EXTENSION_VENDOR`], of the extended full
+/// viewing keys of an account's legacy Sapling keys, as a CBOR array of their
+/// ZIP 32 encodings in sorted order.
+///
+/// It is recorded only by an [`ExportMode::ViewOnly`] export, which omits the
+/// secret store that otherwise carries the viewing keys of Sapling spending
+/// keys, so that an importer can still detect the account's Sapling notes.
+///
+/// [`ExportMode::ViewOnly`]: super::ExportMode::ViewOnly
+pub const SAPLING_VIEWING_KEYS_EXTENSION_KEY: &str = "sapling_extended_full_viewing_keys";
+
+/// The extension key, under [`ZCASHD_EXTENSION_VENDOR`], of the spent status
+/// of a transaction's transparent outputs, as a CBOR array with one entry per
+/// output: the txid of the wallet transaction spending it, as 32 bytes in
+/// internal byte order, or null if no wallet transaction does. Transactions
+/// without transparent outputs have no entry.
+///
+/// Only spends by transactions in the wallet are seen, so a null entry does
+/// not mean the output is unspent on chain: it may have been spent by a
+/// transaction the wallet did not record, such as one sent from another
+/// wallet holding the same keys.
+pub const SPENT_OUTPUTS_EXTENSION_KEY: &str = "spent_outputs";
+
+/// The extension key, under [`ZCASHD_EXTENSION_VENDOR`], that marks a coinbase
+/// transaction. Its value is the height from which the transaction's outputs
+/// can be spent, [`COINBASE_MATURITY`] blocks above the height it was mined
+/// at, as a CBOR integer, or null if that height is not known. Other
+/// transactions have no entry.
+pub const COINBASE_MATURITY_EXTENSION_KEY: &str = "coinbase_maturity_height";
+
+/// The extension key, under [`ZCASHD_EXTENSION_VENDOR`], of a transaction's
+/// shielded value balances, as a CBOR map holding the net value, in zatoshis,
+/// that it moves out of the Orchard pool (`orchard`) and the Sapling pool
+/// (`sapling`), as integers. A negative balance is value moved into the pool.
+/// Only the pools the transaction has a bundle for are present, and
+/// transactions with neither have no entry.
+pub const VALUE_BALANCES_EXTENSION_KEY: &str = "value_balances";
+
+/// The number of blocks a coinbase transaction must be buried under before
+/// its outputs can be spent.
+pub(crate) const COINBASE_MATURITY: u32 = 100;
+
+/// The extension key, under [`ZCASHD_EXTENSION_VENDOR`], of the derivation of
+/// a legacy Sapling address's spending key, from its zcashd key metadata, as a
+/// CBOR map holding the ZIP 32 fingerprint of the seed it was derived from,
+/// `seed_fp`, as 32 bytes, and its derivation path, `hd_keypath`, as text.
+/// Only the fields zcashd recorded are present, and addresses of imported or
+/// view-only keys have no entry.
+///
+/// The fingerprint tells keys derived from the wallet's mnemonic seed apart
+/// from those derived from its pre-mnemonic legacy HD seed (`hdseed`), whose
+/// paths may otherwise coincide.
+pub const KEY_DERIVATION_EXTENSION_KEY: &str = "key_derivation";
+
+/// The extension key, under [`ZCASHD_EXTENSION_VENDOR`], of the unified
+/// addresses whose Sapling receiver is a legacy Sapling address, as a CBOR
+/// array of their encodings in sorted order.
+///
+/// zcashd records a unified address's Sapling receiver in a `sapzaddr` record
+/// of its own, so the receiver is also exported as a standalone address; the
+/// entry links it to the unified addresses it is a component of, so that an
+/// importer does not treat them as unrelated addresses.
+pub const UNIFIED_ADDRESS_COMPONENT_EXTENSION_KEY: &str = "unified_address_component";
+
+/// Record the unified addresses a transaction paid, from its zcashd recipient
+/// mappings, as a transaction extension. See
+/// [`RECIPIENT_MAPPINGS_EXTENSION_KEY`].
+fn attach_recipient_mappings(
+    tx: &mut Transaction,
+    mappings: &[RecipientMapping],
+    network: &Network,
+) {
+    let mut e = Encoder::new(Vec::new());
+    encode_recipient_mappings(&mut e, mappings, network).expect("writing to a Vec cannot fail");
+    tx.extensions_mut().add(
+        ZCASHD_EXTENSION_VENDOR,
+        RECIPIENT_MAPPINGS_EXTENSION_KEY,
+        Data::from_vec(e.into_writer()),
+    );
+}
+
+fn encode_recipient_mappings(
+    e: &mut Encoder<Vec<u8>>,
+    mappings: &[RecipientMapping],
+    network: &Network,
+) -> Result<(), minicbor::encode::Error<Infallible>> {
+    e.array(mappings.len() as u64)?;
+    for mapping in mappings {
+        let receiver = mapping.recipient_address.to_string(network);
+        // Keys in RFC 8949 deterministic order: shorter first.
+        e.map(2)?
+            .str("receiver")?
+            .str(&receiver)?
+            .str("unified_address")?
+            .str(&mapping.unified_address)?;
+    }
+    Ok(())
+}
+
+/// The wallet transaction spending each transparent output of a wallet
+/// transaction, found by matching every input's previous output against the
+/// wallet's transactions.
+///
+/// Where conflicting transactions spend the same output, a confirmed spender
+/// is preferred, then the one with the lowest txid, so the result does not
+/// depend on the map's iteration order.
+pub(crate) fn collect_transparent_spends(
+    transactions: &HashMap<TxId, WalletTx>,
+) -> HashMap<(TxId, u32), TxId> {
+    let mut spent_by: HashMap<(TxId, u32), TxId> = HashMap::new();
+    for (txid, wtx) in transactions {
+        for input in wtx.vin() {
+            let prevout = input.prevout();
+            let spent = TxId::from_bytes(*prevout.hash());
+            if !transactions.contains_key(&spent) {
+                continue;
+            }
+            let rank = |txid: &TxId| (!is_confirmed(&transactions[txid]), *txid);
+            spent_by
+                .entry((spent, prevout.n()))
+                .and_modify(|spender| {
+                    if rank(txid) < rank(spender) {
+                        *spender = *txid;
+                    }
+                })
+                .or_insert(*txid);
+        }
+    }
+    spent_by
+}
+
+/// Record which of a transaction's `output_count` transparent outputs are
+/// spent, and by which wallet transaction, as a transaction extension. See
+/// [`SPENT_OUTPUTS_EXTENSION_KEY`].
+fn attach_spent_outputs(
+    tx: &mut Transaction,
+    output_count: usize,
+    spent_by: &HashMap<(TxId, u32), TxId>,
+) {
+    if output_count == 0 {
+        return;
+    }
+    let txid = tx.txid();
+    let mut e = Encoder::new(Vec::new());
+    e.array(output_count as u64)
+        .expect("writing to a Vec cannot fail");
+    for n in 0..output_count as u32 {
+        match spent_by.get(&(txid, n)) {
+            Some(spender) => e.bytes(spender.as_bytes()),
+            None => e.null(),
+        }
+        .expect("writing to a Vec cannot fail");
+    }
+    tx.extensions_mut().add(
+        ZCASHD_EXTENSION_VENDOR,
+        SPENT_OUTPUTS_EXTENSION_KEY,
+        Data::from_vec(e.into_writer()),
+    );
+}
+
+/// Record the wallet's next order position on the exported wallet. See
+/// [`ORDER_POS_NEXT_EXTENSION_KEY`], and [`order_pos_next_warning`] for when
+/// it cannot be trusted.
+pub(crate) fn attach_order_pos_next(zewif_wallet: &mut ZewifWallet, wallet: &ZcashdWallet) {
+    let Some(orderposnext) = wallet.orderposnext() else {
+        return;
+    };
+    zewif_wallet.extensions_mut().add(
+        ZCASHD_EXTENSION_VENDOR,
+        ORDER_POS_NEXT_EXTENSION_KEY,
+        encode_i64(orderposnext),
+    );
+}
+
+/// Record the height of the wallet's best-block tip on the exported wallet,
+/// when it can be recovered. See [`BEST_BLOCK_HEIGHT_EXTENSION_KEY`].
+pub(crate) fn attach_best_block_height(zewif_wallet: &mut ZewifWallet, wallet: &ZcashdWallet) {
+    if let Some(height) = best_block_height(wallet) {
+        zewif_wallet.extensions_mut().add(
+            ZCASHD_EXTENSION_VENDOR,
+            BEST_BLOCK_HEIGHT_EXTENSION_KEY,
+            encode_i64(i64::from(height)),
+        );
+    }
+}
+
+/// The height of the tip of the wallet's best-block locator, from the blocks
+/// that the wallet's transactions of known height were mined in.
+pub(crate) fn best_block_height(wallet: &ZcashdWallet) -> Option<u32> {
+    let tx_heights = collect_tx_heights(wallet);
+    let block_heights: HashMap<BlockHash, u32> = wallet
+        .transactions()
+        .iter()
+        .filter_map(|(txid, wtx)| Some((wtx.hash_block(), *tx_heights.get(txid.as_bytes())?)))
+        .collect();
+    wallet
+        .bestblock()
+        .height(|hash| block_heights.get(hash).copied())
+}
+
+/// A warning if a transaction's order position is not below the wallet's
+/// `orderposnext`, since an importer continuing from it would then reuse a
+/// position.
+pub(crate) fn order_pos_next_warning(wallet: &ZcashdWallet) -> Option<MigrationWarning> {
+    let orderposnext = wallet.orderposnext()?;
+    let positions = wallet
+        .transactions()
+        .values()
+        .filter_map(WalletTx::order_position);
+    let max = order_position_at_or_above(orderposnext, positions)?;
+    Some(MigrationWarning::OrderPosNextNotAbove { orderposnext, max })
+}
+
+/// The largest of `positions`, if it is not below `orderposnext`.
+fn order_position_at_or_above(
+    orderposnext: i64,
+    positions: impl IntoIterator<Item = i64>,
+) -> Option<i64> {
+    positions
+        .into_iter()
+        .max()
+        .filter(|max| *max >= orderposnext)
+}
+
+fn encode_i64(value: i64) -> Data {
+    let mut e = Encoder::new(Vec::new());
+    e.i64(value).expect("writing to a Vec cannot fail");
+    Data::from_vec(e.into_writer())
 }
 
 /// The mined height of each transaction whose height zcashd records, keyed by
@@ -38,6 +352,40 @@ pub(crate) fn collect_tx_heights(wallet: &ZcashdWallet) -> HashMap<[u8; 32], u32
     heights
 }
 
+/// The memo of the Sapling output at `output_index` in `tx`, if the wallet
+/// received a note there and can decrypt it.
+///
+/// zcashd records the incoming viewing key of each Sapling note the wallet
+/// received; the output is decrypted with that key, found among the wallet's
+/// Sapling spending keys or imported viewing keys. Returns `None` if the
+/// wallet recorded no note at the output, holds no key for it, or the output
+/// does not decrypt.
+pub fn extract_transaction_memo(
+    wallet: &ZcashdWallet,
+    tx: &WalletTx,
+    output_index: usize,
+) -> Option<Vec<u8>> {
+    let note_data = tx
+        .sapling_note_data()?
+        .iter()
+        .find(|(outpoint, _)| outpoint.vout() as usize == output_index)
+        .map(|(_, note_data)| note_data)?;
+    let ivk = note_data.incoming_viewing_key();
+    let dfvk = match wallet.sapling_keys().get(ivk) {
+        Some(key) => key.extsk().to_diversifiable_full_viewing_key(),
+        None => wallet
+            .sapling_extended_full_viewing_keys()
+            .get(ivk)?
+            .to_diversifiable_full_viewing_key(),
+    };
+    let ivk = PreparedIncomingViewingKey::new(&dfvk.to_ivk(::zip32::Scope::External));
+    let output = tx.sapling_bundle()?.shielded_outputs().get(output_index)?;
+    // Under the grace period both note plaintext versions decrypt, so the
+    // memo is recovered whichever version the sender used.
+    let (_, _, memo) = try_sapling_note_decryption(&ivk, output, Zip212Enforcement::GracePeriod)?;
+    Some(memo.to_vec())
+}
+
 fn convert_transaction(
     txid: TxId,
     wtx: &WalletTx,
@@ -64,15 +412,493 @@ fn convert_transaction(
         tx.set_mined_height(BlockHeight::from_u32(*height));
     }
 
+    attach_value_balances(
+        &mut tx,
+        wtx.sapling_bundle().map(|bundle| *bundle.value_balance()),
+        wtx.orchard_bundle().map(|bundle| *bundle.value_balance()),
+    );
+
     let expiry = u32::from(wtx.transaction().expiry_height());
     if expiry != 0 {
         tx.set_expiry_height(BlockHeight::from_u32(expiry));
     }
 
-    let time_received = wtx.time_received();
-    if time_received > 0 {
-        tx.set_created_time(time_received as i64);
+    // Prefer the wallet's smart time, which zcashd clamps to the block time
+    // for transactions received during a rescan, over the raw receive time.
+    let created_time = match wtx.time_smart() {
+        Some(time_smart) if time_smart > 0 => time_smart as i64,
+        _ => wtx.time_received() as i64,
+    };
+    if created_time > 0 {
+        tx.set_created_time(created_time);
+    }
+
+    if let Some(position) = wtx.order_position() {
+        tx.extensions_mut().add(
+            ZCASHD_EXTENSION_VENDOR,
+            ORDER_POSITION_EXTENSION_KEY,
+            encode_i64(position),
+        );
+    }
+
+    attach_order_form(&mut tx, &wtx.known_order_form());
+
+    if wtx.is_coinbase() {
+        attach_coinbase_maturity(&mut tx, tx_heights.get(txid.as_bytes()).copied());
     }
 
     Ok(tx)
 }
+
+/// Record a transaction's Sapling and Orchard value balances, for the pools it
+/// has a bundle for, as a transaction extension. See
+/// [`VALUE_BALANCES_EXTENSION_KEY`].
+fn attach_value_balances(
+    tx: &mut Transaction,
+    sapling: Option<ZatBalance>,
+    orchard: Option<ZatBalance>,
+) {
+    let len = sapling.is_some() as u64 + orchard.is_some() as u64;
+    if len == 0 {
+        return;
+    }
+    let mut e = Encoder::new(Vec::new());
+    e.map(len).expect("writing to a Vec cannot fail");
+    // Keys in RFC 8949 deterministic order.
+    for (pool, balance) in [("orchard", orchard), ("sapling", sapling)] {
+        if let Some(balance) = balance {
+            e.str(pool)
+                .and_then(|e| e.i64(i64::from(balance)))
+                .expect("writing to a Vec cannot fail");
+        }
+    }
+    tx.extensions_mut().add(
+        ZCASHD_EXTENSION_VENDOR,
+        VALUE_BALANCES_EXTENSION_KEY,
+        Data::from_vec(e.into_writer()),
+    );
+}
+
+/// The fee a wallet transaction paid: the value of its transparent inputs,
+/// less that of its transparent outputs, plus its Sprout, Sapling and Orchard
+/// value balances.
+///
+/// The value of a transparent input is known only if the transaction it
+/// spends is in the wallet, so this is `None` unless every input's previous
+/// output is found among `transactions`. It is also `None` for a coinbase
+/// transaction, which pays no fee, and for one whose balances do not add up
+/// to a valid amount.
+pub(crate) fn transaction_fee(
+    wtx: &WalletTx,
+    transactions: &HashMap<TxId, WalletTx>,
+) -> Option<Amount> {
+    if wtx.is_coinbase() {
+        return None;
+    }
+    let fee = wtx
+        .transaction()
+        .fee_paid(|prevout| {
+            Ok::<_, BalanceError>(
+                transactions
+                    .get(&TxId::from_bytes(*prevout.hash()))
+                    .and_then(|prev| prev.vout().get(prevout.n() as usize))
+                    .map(|output| output.value()),
+            )
+        })
+        .ok()??;
+    Amount::from_u64(fee.into_u64()).ok()
+}
+
+/// Mark a coinbase transaction mined at `mined_height`, if known, with the
+/// height at which its outputs mature. See [`COINBASE_MATURITY_EXTENSION_KEY`].
+fn attach_coinbase_maturity(tx: &mut Transaction, mined_height: Option<u32>) {
+    let mut e = Encoder::new(Vec::new());
+    match mined_height {
+        Some(height) => e.u32(height.saturating_add(COINBASE_MATURITY)),
+        None => e.null(),
+    }
+    .expect("writing to a Vec cannot fail");
+    tx.extensions_mut().add(
+        ZCASHD_EXTENSION_VENDOR,
+        COINBASE_MATURITY_EXTENSION_KEY,
+        Data::from_vec(e.into_writer()),
+    );
+}
+
+/// Record the known fields of a transaction's order form, if it has any, as a
+/// transaction extension. See [`ORDER_FORM_EXTENSION_KEY`].
+fn attach_order_form(tx: &mut Transaction, order_form: &KnownOrderFormData) {
+    if order_form.is_empty() {
+        return;
+    }
+    let mut e = Encoder::new(Vec::new());
+    encode_order_form(&mut e, order_form).expect("writing to a Vec cannot fail");
+    tx.extensions_mut().add(
+        ZCASHD_EXTENSION_VENDOR,
+        ORDER_FORM_EXTENSION_KEY,
+        Data::from_vec(e.into_writer()),
+    );
+}
+
+fn encode_order_form(
+    e: &mut Encoder<Vec<u8>>,
+    order_form: &KnownOrderFormData,
+) -> Result<(), minicbor::encode::Error<Infallible>> {
+    let len = [
+        order_form.memo.is_some(),
+        order_form.message.is_some(),
+        order_form.payment_request.is_some(),
+    ]
+    .into_iter()
+    .filter(|present| *present)
+    .count();
+    // Keys in RFC 8949 deterministic order: shorter first.
+    e.map(len as u64)?;
+    if let Some(memo) = &order_form.memo {
+        e.str("memo")?.str(memo)?;
+    }
+    if let Some(message) = &order_form.message {
+        e.str("message")?.str(message)?;
+    }
+    if let Some(payment_request) = &order_form.payment_request {
+        e.str("payment_request")?.bytes(payment_request)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use minicbor::Decoder;
+
+    use super::*;
+    use crate::{
+        parser::prelude::*,
+        zcashd_wallet::{RecipientAddress, transparent::KeyId, u160},
+    };
+
+    const UA: &str = "u1exampleunifiedaddress";
+
+    #[test]
+    fn recipient_mappings_are_recorded_as_an_extension() {
+        let key_id = KeyId::from(u160::from_slice(&[0x42; 20]).unwrap());
+        let receiver = key_id.to_string(&Network::Mainnet);
+        let mappings = [RecipientMapping::new(
+            RecipientAddress::KeyId(key_id),
+            UA.to_string(),
+        )];
+
+        let mut tx = Transaction::new(TxId::from_bytes([1; 32]));
+        attach_recipient_mappings(&mut tx, &mappings, &Network::Mainnet);
+
+        let value = tx
+            .extensions()
+            .get(ZCASHD_EXTENSION_VENDOR, RECIPIENT_MAPPINGS_EXTENSION_KEY)
+            .expect("recipient mappings extension");
+        let mut d = Decoder::new(value.as_data().as_slice());
+        assert_eq!(d.array().unwrap(), Some(1));
+        assert_eq!(d.map().unwrap(), Some(2));
+        assert_eq!(d.str().unwrap(), "receiver");
+        assert_eq!(d.str().unwrap(), receiver);
+        assert_eq!(d.str().unwrap(), "unified_address");
+        assert_eq!(d.str().unwrap(), UA);
+        assert_eq!(d.position(), value.as_data().len());
+    }
+
+    #[test]
+    fn order_form_fields_are_recorded_as_an_extension() {
+        let order_form = KnownOrderFormData {
+            payment_request: Some(vec![0x12, 0xff]),
+            memo: Some("rent for May".to_string()),
+            message: None,
+        };
+        let mut tx = Transaction::new(TxId::from_bytes([1; 32]));
+        attach_order_form(&mut tx, &order_form);
+
+        let value = tx
+            .extensions()
+            .get(ZCASHD_EXTENSION_VENDOR, ORDER_FORM_EXTENSION_KEY)
+            .expect("order form extension");
+        let mut d = Decoder::new(value.as_data().as_slice());
+        assert_eq!(d.map().unwrap(), Some(2));
+        assert_eq!(d.str().unwrap(), "memo");
+        assert_eq!(d.str().unwrap(), "rent for May");
+        assert_eq!(d.str().unwrap(), "payment_request");
+        assert_eq!(d.bytes().unwrap(), [0x12, 0xff]);
+        assert_eq!(d.position(), value.as_data().len());
+
+        let message = KnownOrderFormData {
+            message: Some("thanks".to_string()),
+            ..Default::default()
+        };
+        let mut tx = Transaction::new(TxId::from_bytes([2; 32]));
+        attach_order_form(&mut tx, &message);
+        let value = tx
+            .extensions()
+            .get(ZCASHD_EXTENSION_VENDOR, ORDER_FORM_EXTENSION_KEY)
+            .expect("order form extension");
+        let mut d = Decoder::new(value.as_data().as_slice());
+        assert_eq!(d.map().unwrap(), Some(1));
+        assert_eq!(d.str().unwrap(), "message");
+        assert_eq!(d.str().unwrap(), "thanks");
+
+        let mut tx = Transaction::new(TxId::from_bytes([3; 32]));
+        attach_order_form(&mut tx, &KnownOrderFormData::default());
+        assert!(
+            tx.extensions()
+                .get(ZCASHD_EXTENSION_VENDOR, ORDER_FORM_EXTENSION_KEY)
+                .is_none()
+        );
+    }
+
+    /// A confirmed wallet transaction holding a version 1 transaction that
+    /// spends `inputs` to `outputs` empty-script outputs of 1,000 zatoshis.
+    fn wallet_tx(inputs: &[(TxId, u32)], outputs: u8) -> WalletTx {
+        let mut bytes = 1u32.to_le_bytes().to_vec();
+        bytes.push(inputs.len() as u8);
+        for (txid, n) in inputs {
+            bytes.extend_from_slice(txid.as_bytes());
+            bytes.extend_from_slice(&n.to_le_bytes());
+            bytes.push(0); // empty script_sig
+            bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        }
+        bytes.push(outputs);
+        for _ in 0..outputs {
+            bytes.extend_from_slice(&1_000i64.to_le_bytes());
+            bytes.push(0); // empty script_pubkey
+        }
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        wallet_tx_holding(bytes)
+    }
+
+    /// A confirmed wallet transaction holding the serialized `transaction`.
+    fn wallet_tx_holding(mut bytes: Vec<u8>) -> WalletTx {
+        // CMerkleTx, then CWalletTx with empty collections.
+        bytes.extend_from_slice(&[0x42; 32]);
+        bytes.push(0);
+        bytes.extend_from_slice(&0i32.to_le_bytes());
+        bytes.extend_from_slice(&[0, 0, 0, 0]);
+        bytes.extend_from_slice(&0i32.to_le_bytes());
+        bytes.extend_from_slice(&1_600_000_000i32.to_le_bytes());
+        bytes.extend_from_slice(&[1, 0]);
+        WalletTx::parse_buf(&bytes, false).unwrap()
+    }
+
+    /// The serialized JoinSplit description of a version 2 transaction that
+    /// moves 5,000 zatoshis into the Sprout pool, each field filled with its
+    /// own byte.
+    fn sprout_shielding_joinsplit() -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(&5_000u64.to_le_bytes()); // vpub_old
+        bytes.extend_from_slice(&0u64.to_le_bytes()); // vpub_new
+        bytes.extend_from_slice(&[0x01; 32]); // anchor
+        bytes.extend_from_slice(&[0x02; 64]); // nullifiers
+        bytes.extend_from_slice(&[0x03; 64]); // commitments
+        bytes.extend_from_slice(&[0x04; 32]); // ephemeral key
+        bytes.extend_from_slice(&[0x05; 32]); // random seed
+        bytes.extend_from_slice(&[0x06; 64]); // MACs
+        bytes.extend_from_slice(&[0x07; 296]); // PHGR proof
+        bytes.extend_from_slice(&[0x08; 601]); // ciphertexts
+        bytes.extend_from_slice(&[0x09; 601]);
+        bytes
+    }
+
+    /// The spent outputs extension of `tx`, one entry per output.
+    fn spent_outputs(tx: &Transaction) -> Vec<Option<TxId>> {
+        let value = tx
+            .extensions()
+            .get(ZCASHD_EXTENSION_VENDOR, SPENT_OUTPUTS_EXTENSION_KEY)
+            .expect("spent outputs extension");
+        let mut d = Decoder::new(value.as_data().as_slice());
+        let len = d.array().unwrap().unwrap();
+        (0..len)
+            .map(|_| match d.datatype().unwrap() {
+                minicbor::data::Type::Null => {
+                    d.null().unwrap();
+                    None
+                }
+                _ => Some(TxId::from_bytes(d.bytes().unwrap().try_into().unwrap())),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn spends_within_the_wallet_mark_outputs_spent() {
+        let (first, second) = (TxId::from_bytes([1; 32]), TxId::from_bytes([2; 32]));
+        let transactions = HashMap::from([
+            (first, wallet_tx(&[], 2)),
+            // Spends the first transaction's second output, and an output of
+            // a transaction the wallet does not hold.
+            (
+                second,
+                wallet_tx(&[(first, 1), (TxId::from_bytes([9; 32]), 0)], 1),
+            ),
+        ]);
+        let spent_by = collect_transparent_spends(&transactions);
+        assert_eq!(spent_by, HashMap::from([((first, 1), second)]));
+
+        let mut tx = Transaction::new(first);
+        attach_spent_outputs(&mut tx, 2, &spent_by);
+        assert_eq!(spent_outputs(&tx), vec![None, Some(second)]);
+
+        let mut tx = Transaction::new(second);
+        attach_spent_outputs(&mut tx, 1, &spent_by);
+        assert_eq!(spent_outputs(&tx), vec![None]);
+
+        let mut tx = Transaction::new(TxId::from_bytes([3; 32]));
+        attach_spent_outputs(&mut tx, 0, &spent_by);
+        assert!(
+            tx.extensions()
+                .get(ZCASHD_EXTENSION_VENDOR, SPENT_OUTPUTS_EXTENSION_KEY)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn coinbase_transactions_record_their_maturity() {
+        let coinbase_txid = TxId::from_bytes([1; 32]);
+        let coinbase = wallet_tx(&[(TxId::from_bytes([0; 32]), u32::MAX)], 1);
+        assert!(coinbase.is_coinbase());
+        let spend_txid = TxId::from_bytes([2; 32]);
+        let spend = wallet_tx(&[(coinbase_txid, 0)], 1);
+        assert!(!spend.is_coinbase());
+
+        let maturity = |tx: &Transaction| {
+            tx.extensions()
+                .get(ZCASHD_EXTENSION_VENDOR, COINBASE_MATURITY_EXTENSION_KEY)
+                .map(|value| {
+                    let mut d = Decoder::new(value.as_data().as_slice());
+                    match d.datatype().unwrap() {
+                        minicbor::data::Type::Null => None,
+                        _ => Some(d.u32().unwrap()),
+                    }
+                })
+        };
+        let heights = HashMap::from([(*coinbase_txid.as_bytes(), 1_000)]);
+        let tx = convert_transaction(coinbase_txid, &coinbase, &heights).unwrap();
+        assert_eq!(maturity(&tx), Some(Some(1_100)));
+        let tx = convert_transaction(coinbase_txid, &coinbase, &HashMap::new()).unwrap();
+        assert_eq!(maturity(&tx), Some(None));
+        let tx = convert_transaction(spend_txid, &spend, &heights).unwrap();
+        assert_eq!(maturity(&tx), None);
+
+        // The null outpoint spends nothing, while the coinbase output itself
+        // is spent like any other.
+        let transactions = HashMap::from([(coinbase_txid, coinbase), (spend_txid, spend)]);
+        assert_eq!(
+            collect_transparent_spends(&transactions),
+            HashMap::from([((coinbase_txid, 0), spend_txid)])
+        );
+    }
+
+    #[test]
+    fn fees_are_computed_when_every_input_is_in_the_wallet() {
+        let (first, second) = (TxId::from_bytes([1; 32]), TxId::from_bytes([2; 32]));
+        let outside = TxId::from_bytes([9; 32]);
+        let transactions = HashMap::from([
+            (first, wallet_tx(&[(outside, 0)], 3)),
+            // Spends two 1,000-zatoshi outputs to one of 1,000 zatoshis.
+            (second, wallet_tx(&[(first, 0), (first, 1)], 1)),
+        ]);
+        assert_eq!(
+            transaction_fee(&transactions[&second], &transactions),
+            Some(Amount::from_u64(1_000).unwrap())
+        );
+
+        let tx = wallet_tx(&[(first, 2), (outside, 1)], 1);
+        assert_eq!(transaction_fee(&tx, &transactions), None);
+        assert_eq!(transaction_fee(&transactions[&first], &transactions), None);
+        let coinbase = wallet_tx(&[(TxId::from_bytes([0; 32]), u32::MAX)], 1);
+        assert_eq!(transaction_fee(&coinbase, &transactions), None);
+    }
+
+    #[test]
+    fn value_balances_are_recorded_as_an_extension() {
+        let value_balances = |tx: &Transaction| -> Option<Vec<(String, i64)>> {
+            let value = tx
+                .extensions()
+                .get(ZCASHD_EXTENSION_VENDOR, VALUE_BALANCES_EXTENSION_KEY)?;
+            let mut d = Decoder::new(value.as_data().as_slice());
+            let len = d.map().unwrap().unwrap();
+            Some(
+                (0..len)
+                    .map(|_| (d.str().unwrap().to_string(), d.i64().unwrap()))
+                    .collect(),
+            )
+        };
+
+        let mut tx = Transaction::new(TxId::from_bytes([1; 32]));
+        attach_value_balances(
+            &mut tx,
+            Some(ZatBalance::from_i64(-2_500).unwrap()),
+            Some(ZatBalance::from_i64(10_000).unwrap()),
+        );
+        assert_eq!(
+            value_balances(&tx),
+            Some(vec![
+                ("orchard".to_string(), 10_000),
+                ("sapling".to_string(), -2_500),
+            ])
+        );
+
+        let mut tx = Transaction::new(TxId::from_bytes([2; 32]));
+        attach_value_balances(&mut tx, Some(ZatBalance::zero()), None);
+        assert_eq!(value_balances(&tx), Some(vec![("sapling".to_string(), 0)]));
+
+        let transparent = wallet_tx(&[(TxId::from_bytes([9; 32]), 0)], 1);
+        let tx =
+            convert_transaction(TxId::from_bytes([3; 32]), &transparent, &HashMap::new()).unwrap();
+        assert_eq!(value_balances(&tx), None);
+    }
+
+    #[test]
+    fn order_positions_at_or_above_orderposnext_are_reported() {
+        assert_eq!(order_position_at_or_above(5, [0, 3, 4]), None);
+        assert_eq!(order_position_at_or_above(5, []), None);
+        assert_eq!(order_position_at_or_above(5, [2, 5]), Some(5));
+        assert_eq!(order_position_at_or_above(3, [7, 1]), Some(7));
+    }
+
+    #[test]
+    fn order_positions_are_encoded_as_cbor_integers() {
+        let data = encode_i64(17);
+        assert_eq!(Decoder::new(data.as_slice()).i64().unwrap(), 17);
+    }
+
+    #[test]
+    fn sprout_joinsplits_are_kept_byte_for_byte() {
+        let joinsplit = sprout_shielding_joinsplit();
+        let mut bytes = 2u32.to_le_bytes().to_vec();
+        bytes.push(1);
+        bytes.extend_from_slice(&[0x11; 32]);
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.push(1);
+        bytes.extend_from_slice(&joinsplit);
+        bytes.extend_from_slice(&[0x0a; 32]); // joinSplitPubKey
+        bytes.extend_from_slice(&[0x0b; 64]); // joinSplitSig
+
+        let txid = TxId::from_bytes([3; 32]);
+        let tx =
+            convert_transaction(txid, &wallet_tx_holding(bytes.clone()), &HashMap::new()).unwrap();
+        let Some(TransactionData::Raw(raw)) = tx.tx_data() else {
+            panic!("the transaction is exported in its raw encoding");
+        };
+        assert_eq!(raw.data().as_slice(), bytes.as_slice());
+
+        let exported = zcash_primitives::transaction::Transaction::read(
+            raw.data().as_slice(),
+            zcash_protocol::consensus::BranchId::Sprout,
+        )
+        .unwrap();
+        let bundle = exported.sprout_bundle().unwrap();
+        assert_eq!(bundle.joinsplit_pubkey, [0x0a; 32]);
+        assert_eq!(bundle.joinsplit_sig, [0x0b; 64]);
+        assert_eq!(bundle.joinsplits.len(), 1);
+        let mut description = vec![];
+        bundle.joinsplits[0].write(&mut description).unwrap();
+        assert_eq!(description, joinsplit);
+    }
+}
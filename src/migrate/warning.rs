@@ -0,0 +1,187 @@
+use zewif::TxId;
+
+use crate::{
+    migrate::{ANCHOR_DEPTH, MnemonicProblem},
+    zcashd_wallet::{ParseWarning, transparent::WatchScriptKind},
+};
+
+/// Something about a wallet that does not stop its migration, but that its
+/// owner should know of: data that was dropped, approximated or exported as
+/// recorded despite a problem.
+///
+/// [`MigrationReport::warnings`](super::MigrationReport::warnings) lists them
+/// for a wallet, the parse warnings first.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MigrationWarning {
+    /// Something a lenient parse tolerated in reading the wallet.
+    Parse(ParseWarning),
+    /// The wallet holds no unspent Sapling notes or transparent outputs, and
+    /// received no Orchard or Sprout notes: often a sign of the wrong wallet
+    /// file or an unsynced copy.
+    NoFundsDetected,
+    /// The Orchard tree's last checkpoint lags the export height or the best
+    /// block by more than 100 blocks, so witnesses are anchored at it and the
+    /// importing wallet must scan forward from there.
+    LaggingOrchardCheckpoint {
+        last_checkpoint: u32,
+        export_height: u32,
+        best_block_height: Option<u32>,
+        anchor_height: u32,
+    },
+    /// A unified account records a BIP-44 coin type other than its network's,
+    /// which is used instead.
+    CoinTypeMismatch {
+        zip32_account_id: u32,
+        recorded: u32,
+        used: u32,
+    },
+    /// The wallet's seed does not derive the Orchard viewing key of a unified
+    /// account, so its Orchard notes cannot be spent with the seed.
+    OrchardKeyNotDerived { zip32_account_id: u32 },
+    /// Legacy keys were derived at or beyond the counters the mnemonic HD
+    /// chain records, as in a wallet restored from an older backup.
+    KeysBeyondCounters { count: usize },
+    /// Keys derived from a seed the wallet does not hold are exported in an
+    /// account of their own.
+    ForeignSeedKeys {
+        seed_fingerprint: [u8; 32],
+        key_count: usize,
+    },
+    /// A watch-only P2PK script's public key could not be read, so the
+    /// script was dropped.
+    UnparsableWatchOnlyPublicKey { error: String },
+    /// A watch-only script with no standard transparent address encoding was
+    /// dropped.
+    NonStandardWatchOnlyScript(WatchScriptKind),
+    /// A Sapling address is the Sapling receiver of unified addresses, and is
+    /// linked to them.
+    SaplingAddressLinked {
+        address: String,
+        unified_addresses: Vec<String>,
+    },
+    /// A transparent private key could not be exported and was skipped.
+    SkippedTransparentKey { error: String },
+    /// The wallet's mnemonic phrase is invalid; it is exported as recorded.
+    InvalidMnemonic(MnemonicProblem),
+    /// The wallet's mnemonic phrase is invalid and no seed fingerprint is
+    /// recorded for it, so it cannot be exported.
+    UnexportableMnemonic(MnemonicProblem),
+    /// A Sapling note's cached witnesses are not as expected; see
+    /// `select_sapling_witness`.
+    SaplingWitness {
+        txid: TxId,
+        vout: u32,
+        problem: String,
+    },
+    /// The wallet's `orderposnext` is not above every transaction's order
+    /// position, so an importer continuing from it would reuse a position.
+    OrderPosNextNotAbove { orderposnext: i64, max: i64 },
+    /// Wallets merged into one document record a transaction with different
+    /// values of `fields`; the earliest wallet's are kept.
+    ConflictingTransaction {
+        wallet_index: usize,
+        txid: TxId,
+        fields: Vec<&'static str>,
+    },
+}
+
+impl From<ParseWarning> for MigrationWarning {
+    fn from(warning: ParseWarning) -> Self {
+        Self::Parse(warning)
+    }
+}
+
+impl std::fmt::Display for MigrationWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Parse(warning) => write!(f, "{warning}"),
+            Self::NoFundsDetected => write!(
+                f,
+                "no funds detected: the wallet has no unspent Sapling notes or transparent outputs, and received no Orchard or Sprout notes"
+            ),
+            Self::LaggingOrchardCheckpoint {
+                last_checkpoint,
+                export_height,
+                best_block_height,
+                anchor_height,
+            } => {
+                let best_block = best_block_height.map_or("unknown".to_string(), |h| h.to_string());
+                write!(
+                    f,
+                    "the Orchard tree's last checkpoint ({last_checkpoint}) is more than {ANCHOR_DEPTH} blocks below the export height ({export_height}) or the best block ({best_block}); witnesses are anchored at {anchor_height} and the importing wallet must scan forward from there"
+                )
+            }
+            Self::CoinTypeMismatch {
+                zip32_account_id,
+                recorded,
+                used,
+            } => write!(
+                f,
+                "unified account {zip32_account_id} records BIP-44 coin type {recorded} but the wallet network uses {used}; using {used}"
+            ),
+            Self::OrchardKeyNotDerived { zip32_account_id } => write!(
+                f,
+                "the wallet's seed does not derive the Orchard viewing key of unified account {zip32_account_id}; its Orchard notes cannot be spent with the seed"
+            ),
+            Self::KeysBeyondCounters { count } => write!(
+                f,
+                "{count} legacy keys were derived at or beyond the wallet's recorded key counters; the wallet may have been restored from an older backup"
+            ),
+            Self::ForeignSeedKeys {
+                seed_fingerprint,
+                key_count,
+            } => write!(
+                f,
+                "{key_count} keys were derived from seed {}, which is not the wallet's seed; exporting them in a separate account",
+                hex::encode(seed_fingerprint)
+            ),
+            Self::UnparsableWatchOnlyPublicKey { error } => {
+                write!(
+                    f,
+                    "watch-only P2PK script with unparsable public key dropped: {error}"
+                )
+            }
+            Self::NonStandardWatchOnlyScript(kind) => write!(
+                f,
+                "watch-only script with no standard t-address encoding ({kind:?}) dropped"
+            ),
+            Self::SaplingAddressLinked {
+                address,
+                unified_addresses,
+            } => write!(
+                f,
+                "linking Sapling address {address} to the unified addresses it is the Sapling receiver of: {}",
+                unified_addresses.join(", ")
+            ),
+            Self::SkippedTransparentKey { error } => {
+                write!(f, "skipping transparent key: {error}")
+            }
+            Self::InvalidMnemonic(problem) => {
+                write!(f, "{problem}; exporting the phrase as recorded")
+            }
+            Self::UnexportableMnemonic(problem) => write!(
+                f,
+                "{problem}; the wallet records no seed fingerprint for the phrase, so it is not exported"
+            ),
+            Self::SaplingWitness {
+                txid,
+                vout,
+                problem,
+            } => write!(f, "Sapling note {txid}:{vout}: {problem}"),
+            Self::OrderPosNextNotAbove { orderposnext, max } => write!(
+                f,
+                "orderposnext ({orderposnext}) is not above the largest transaction order position ({max}); new entries should be numbered from {}",
+                max + 1
+            ),
+            Self::ConflictingTransaction {
+                wallet_index,
+                txid,
+                fields,
+            } => write!(
+                f,
+                "wallet {wallet_index} records transaction {txid} with a different {}; keeping the earlier wallet's",
+                fields.join(", ")
+            ),
+        }
+    }
+}
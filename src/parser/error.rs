@@ -92,6 +92,18 @@ pub enum ParseErrorKind {
     #[error("non-canonical compact size: {prefix:#04x}-prefixed encoding holds {value}")]
     NonCanonicalCompactSize { prefix: u8, value: u64 },
 
+    /// A database key's keyname is not made of the characters zcashd uses for
+    /// keynames, which suggests its length prefix is miscounted.
+    #[error("implausible keyname {0:?}")]
+    InvalidKeyname(String),
+
+    /// A database key's keyname differs from a keyname zcashd writes only in
+    /// case, which zcashd itself never does.
+    #[error(
+        "found keyname {found:?} — expected {expected:?}; dump may be corrupted or produced by an incompatible tool"
+    )]
+    MiscasedKeyname { found: String, expected: String },
+
     /// An optional-value discriminant byte was neither 0x00 nor 0x01.
     #[error("invalid optional discriminant: {0:#04x}")]
     InvalidOptionalDiscriminant(u8),
@@ -100,14 +112,27 @@ pub enum ParseErrorKind {
     #[error("invalid data length: expected {expected} bytes, got {actual}")]
     InvalidLength { expected: usize, actual: usize },
 
-    /// An amount was outside the valid Zat balance range.
-    #[error("invalid Zat balance: {0}")]
-    InvalidAmount(i64),
+    /// An amount, which zcashd never records as negative, was negative.
+    #[error("negative amount: {0} zatoshis")]
+    NegativeAmount(i64),
+
+    /// An amount exceeded `MAX_MONEY`, the total supply of ZEC.
+    #[error("amount of {0} zatoshis exceeds MAX_MONEY ({max})", max = zewif::MAX_MONEY)]
+    AmountExceedsMaxMoney(i64),
 
     /// Bytes did not form a valid Orchard incoming viewing key.
     #[error("not a valid Orchard incoming viewing key")]
     InvalidOrchardIvk,
 
+    /// Bytes did not form a valid Orchard spending key.
+    #[error("not a valid Orchard spending key")]
+    InvalidOrchardSpendingKey,
+
+    /// The `ak` of a Sapling full viewing key was not a valid spend
+    /// validating key.
+    #[error("not a valid Sapling spend validating key")]
+    InvalidSaplingSpendValidatingKey,
+
     /// A transparent public key had a length other than 33 or 65 bytes.
     #[error("invalid public key length: {0}")]
     InvalidPubKeyLength(usize),
@@ -140,6 +165,20 @@ pub enum ParseErrorKind {
     #[error("unrecognized zcashd network identifier: {0:?}")]
     UnrecognizedNetwork(String),
 
+    /// An incremental Merkle tree or witness holds more nodes than a tree of
+    /// its protocol's depth can have.
+    #[error("{field} has {len} entries, more than a depth-{depth} tree allows")]
+    ExceedsTreeDepth {
+        field: &'static str,
+        len: usize,
+        depth: usize,
+    },
+
+    /// A versioned record was written by a newer zcashd than this parser
+    /// understands.
+    #[error("unsupported {record} version: {version}")]
+    UnsupportedVersion { record: &'static str, version: i32 },
+
     /// An embedded structure read via `std::io` could not be decoded.
     #[error("decoding embedded structure: {0}")]
     Io(#[from] std::io::Error),
@@ -148,6 +187,25 @@ pub enum ParseErrorKind {
     #[error(transparent)]
     Zewif(#[from] zewif::Error),
 
+    /// A string is not a valid Zcash address encoding.
+    #[error("decoding address: {0}")]
+    AddressDecode(#[from] zcash_address::ParseError),
+
+    /// A string is not a valid unified address encoding.
+    #[error("decoding unified address: {0}")]
+    UnifiedAddressDecode(#[source] zcash_address::unified::ParseError),
+
+    /// A Zcash address is of a kind that could not be accepted.
+    #[error("unsupported address: {0}")]
+    UnsupportedAddress(#[source] zcash_address::UnsupportedAddress),
+
+    /// A Zcash address is encoded for a different network than expected.
+    #[error("address is for network {actual:?}, expected {expected:?}")]
+    AddressNetworkMismatch {
+        expected: zcash_protocol::consensus::NetworkType,
+        actual: zcash_protocol::consensus::NetworkType,
+    },
+
     /// A unified full viewing key string could not be decoded.
     #[error("decoding unified full viewing key: {0}")]
     UfvkDecode(#[from] zcash_address::unified::ParseError),
@@ -225,10 +283,18 @@ impl std::error::Error for ParseError {
 pub trait ParseResultExt<T> {
     /// Attaches an (outer) context frame to the error, if any.
     fn with_frame(self, frame: impl Into<String>) -> Result<T, ParseError>;
+
+    /// Like [`Self::with_frame`], but builds the frame only if there is an
+    /// error, so that a formatted frame costs nothing on success.
+    fn with_context<C: Into<String>>(self, frame: impl FnOnce() -> C) -> Result<T, ParseError>;
 }
 
 impl<T, E: Into<ParseError>> ParseResultExt<T> for core::result::Result<T, E> {
     fn with_frame(self, frame: impl Into<String>) -> Result<T, ParseError> {
         self.map_err(|e| e.into().with_frame(frame))
     }
+
+    fn with_context<C: Into<String>>(self, frame: impl FnOnce() -> C) -> Result<T, ParseError> {
+        self.map_err(|e| e.into().with_frame(frame()))
+    }
 }
@@ -9,6 +9,7 @@
 //! - **Parser**: The core parsing engine that tracks position and context in a binary stream
 //! - **Parse Trait**: A standardized interface for types that can be parsed from binary data
 //! - **parse! Macro**: A context-aware macro that simplifies parsing with improved error messages
+//!   (and `parse_be!`, its counterpart for the few big-endian integer fields)
 //! - **Standard Implementations**: Built-in parsers for common types (numbers, vectors, etc.)
 //!
 //! ## Design Philosophy
@@ -50,7 +50,16 @@
 ///
 /// # Error Handling
 /// The macro automatically adds context to errors, making debugging easier by
-/// describing what was being parsed when an error occurred.
+/// describing what was being parsed when an error occurred. The context
+/// expression is evaluated only when an error occurs or the parser is
+/// recording fields, so a `format!` context does not allocate on success.
+///
+/// # Field Recording
+/// When the parser is recording fields (see [`Parser::record_fields`]), each
+/// invocation that reads from a parser also records the byte range it
+/// consumed under its context.
+///
+/// [`Parser::record_fields`]: crate::parser::Parser::record_fields
 ///
 /// # Relation to ZCash Data Formats
 /// This macro is particularly useful when parsing ZCash wallet and transaction data,
@@ -58,61 +67,110 @@
 /// provided helps identify which part of a structure failed to parse.
 #[macro_export]
 macro_rules! parse {
+    // Reads one field, recording its byte range when the parser is recording
+    // fields, and attaches the context as an error frame. The context is only
+    // built when it is used, so a formatted context costs nothing otherwise.
+    (@field $parser:expr, |$p:ident| $read:expr, $context:expr) => {{
+        let $p: &mut $crate::parser::Parser = $parser;
+        let start = $crate::parser::Parser::begin_field($p);
+        let result = $read;
+        let context = || $context;
+        $crate::parser::Parser::end_field($p, start, &context);
+        $crate::parser::ParseResultExt::with_context(result, context)
+    }};
     (buf = $buf:expr, $type:ty, $context:expr) => {
-        $crate::parser::ParseResultExt::with_frame(
+        $crate::parser::ParseResultExt::with_context(
             <$type as $crate::parser::Parse>::parse_buf($buf, false),
-            $context,
+            || $context,
         )
     };
     (buf = $buf:expr, $type:ty, param = $param:expr, $context:expr) => {
-        $crate::parser::ParseResultExt::with_frame(
+        $crate::parser::ParseResultExt::with_context(
             <$type as $crate::parser::ParseWithParam<_>>::parse_buf($buf, $param, false),
-            $context,
+            || $context,
         )
     };
     (buf = $buf:expr, $type:ty, $context:expr, $trace: expr) => {
-        $crate::parser::ParseResultExt::with_frame(
+        $crate::parser::ParseResultExt::with_context(
             <$type as $crate::parser::Parse>::parse_buf($buf, $trace),
-            $context,
+            || $context,
         )
     };
     (buf = $buf:expr, $type:ty, param = $param:expr, $context:expr, $trace:expr) => {
-        $crate::parser::ParseResultExt::with_frame(
+        $crate::parser::ParseResultExt::with_context(
             <$type as $crate::parser::ParseWithParam<_>>::parse_buf($buf, $param, $trace),
-            $context,
+            || $context,
         )
     };
     ($parser:expr, $type:ty, $context:expr) => {
-        $crate::parser::ParseResultExt::with_frame(
-            <$type as $crate::parser::Parse>::parse($parser),
-            $context,
+        $crate::parse!(
+            @field $parser,
+            |field_parser| <$type as $crate::parser::Parse>::parse(field_parser),
+            $context
         )
     };
     ($parser:expr, $type:ty, param = $param:expr, $context:expr) => {
-        $crate::parser::ParseResultExt::with_frame(
-            <$type as $crate::parser::ParseWithParam<_>>::parse($parser, $param),
-            $context,
+        $crate::parse!(
+            @field $parser,
+            |field_parser| {
+                <$type as $crate::parser::ParseWithParam<_>>::parse(field_parser, $param)
+            },
+            $context
         )
     };
     ($parser:expr, bytes = $length:expr, $context:expr) => {
-        $crate::parser::ParseResultExt::with_frame(
-            $crate::parser::Parser::next($parser, $length),
-            $context,
+        $crate::parse!(
+            @field $parser,
+            |field_parser| $crate::parser::Parser::next(field_parser, $length),
+            $context
         )
     };
     ($parser:expr, data = $length:expr, $context:expr) => {
-        $crate::parser::ParseResultExt::with_frame(
-            $crate::parser::Parser::next($parser, $length).map(zewif::Data::from_slice),
-            $context,
+        $crate::parse!(
+            @field $parser,
+            |field_parser| {
+                $crate::parser::Parser::next(field_parser, $length).map(zewif::Data::from_slice)
+            },
+            $context
         )
     };
     ($parser:expr, $context:expr) => {
-        $crate::parser::ParseResultExt::with_frame($crate::parser::Parse::parse($parser), $context)
+        $crate::parse!(
+            @field $parser,
+            |field_parser| $crate::parser::Parse::parse(field_parser),
+            $context
+        )
     };
     ($parser:expr, param = $param:expr, $context:expr) => {
-        $crate::parser::ParseResultExt::with_frame(
-            $crate::parser::ParseWithParam::parse($parser, $param),
-            $context,
+        $crate::parse!(
+            @field $parser,
+            |field_parser| $crate::parser::ParseWithParam::parse(field_parser, $param),
+            $context
+        )
+    };
+}
+
+/// Like [`parse!`], but reads a big-endian integer (see
+/// [`Parser::next_be`]) from a parser.
+///
+/// ```no_run
+/// # use zewif_zcashd::{parser::prelude::*, parse_be};
+/// # fn example(parser: &mut Parser) -> Result<()> {
+/// let value = parse_be!(parser, u64, "big-endian value")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Parser::next_be`]: crate::parser::Parser::next_be
+#[macro_export]
+macro_rules! parse_be {
+    ($parser:expr, $type:ty, $context:expr) => {
+        $crate::parse!(
+            @field $parser,
+            |field_parser| {
+                $crate::parser::Parser::next_be::<$type>(field_parser, stringify!($type))
+            },
+            $context
         )
     };
 }
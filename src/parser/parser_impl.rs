@@ -7,7 +7,7 @@
 
 use zewif::Data;
 
-use super::error::{ParseErrorKind, Result};
+use super::error::{ParseErrorKind, ParseResultExt, Result};
 
 /// A trait for types that can be parsed from a binary data stream.
 ///
@@ -72,6 +72,22 @@ pub trait Parse {
         p.check_finished()?;
         Ok(result)
     }
+
+    /// Parses an instance of this type from a complete byte buffer, also
+    /// returning the byte range consumed by each field read through `parse!`.
+    ///
+    /// This lets a caller check exactly which bytes of a record were
+    /// attributed to which field. See [`Parser::record_fields`].
+    fn parse_buf_recording_fields(buf: &dyn AsRef<[u8]>) -> Result<(Self, Vec<ParsedField>)>
+    where
+        Self: Sized,
+    {
+        let mut p = Parser::new(&buf);
+        p.record_fields();
+        let result = Self::parse(&mut p)?;
+        p.check_finished()?;
+        Ok((result, p.take_fields()))
+    }
 }
 
 /// A trait for types that require additional parameters during parsing.
@@ -115,6 +131,65 @@ pub trait ParseWithParam<P> {
     fn parse(p: &mut Parser, param: P) -> Result<Self>
     where
         Self: Sized;
+
+    /// Parses an instance of this type from a complete byte buffer, using the
+    /// provided parameter for context.
+    ///
+    /// As with [`Parse::parse_buf`], it is an error for bytes to remain in the
+    /// buffer after parsing.
+    fn parse_buf(buf: &dyn AsRef<[u8]>, param: P, trace: bool) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut p = Parser::new(&buf);
+        p.set_trace(trace);
+        let result = Self::parse(&mut p, param)?;
+        p.check_finished()?;
+        Ok(result)
+    }
+}
+
+/// An integer type that can be read in big-endian byte order with
+/// [`Parser::next_be`].
+///
+/// zcashd serializes integers little-endian, but a few older structures carry
+/// big-endian fields; this keeps those reads explicit at the call site.
+pub trait FromBE: Sized {
+    /// The size of the encoded integer, in bytes.
+    const SIZE: usize;
+
+    /// Decodes the integer from exactly [`Self::SIZE`] big-endian bytes.
+    fn from_be_slice(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_from_be {
+    ($($type:ty),*) => {
+        $(
+            impl FromBE for $type {
+                const SIZE: usize = std::mem::size_of::<$type>();
+
+                fn from_be_slice(bytes: &[u8]) -> Self {
+                    let bytes = bytes.try_into().expect("caller passes exactly SIZE bytes");
+                    <$type>::from_be_bytes(bytes)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_be!(u16, u32, u64, i32, i64);
+
+/// A field consumed while a [`Parser`] was recording fields.
+///
+/// `name` is the context given to the `parse!` invocation that read the
+/// field, and `range` is the span of the parser's buffer it consumed. Fields
+/// nested inside another field have a greater `depth`; they are recorded as
+/// they complete, so they precede the field that contains them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedField {
+    pub name: String,
+    pub depth: usize,
+    pub range: std::ops::Range<usize>,
 }
 
 /// A binary data stream parser for Zcash wallet and blockchain data.
@@ -162,6 +237,12 @@ pub struct Parser<'a> {
 
     /// Whether to print debug information during parsing
     pub trace: bool,
+
+    /// The fields consumed so far, when recording is enabled
+    fields: Option<Vec<ParsedField>>,
+
+    /// The nesting depth of the `parse!` invocation currently being read
+    depth: usize,
 }
 
 impl std::fmt::Debug for Parser<'_> {
@@ -180,6 +261,8 @@ impl<'a> Parser<'a> {
             buffer: buffer.as_ref(),
             offset: 0,
             trace: false,
+            fields: None,
+            depth: 0,
         }
     }
 
@@ -228,6 +311,12 @@ impl<'a> Parser<'a> {
         Ok(bytes)
     }
 
+    /// Reads a big-endian integer, attaching `context` as an error frame.
+    pub fn next_be<T: FromBE>(&mut self, context: &str) -> Result<T> {
+        let bytes = self.next(T::SIZE).with_frame(context)?;
+        Ok(T::from_be_slice(bytes))
+    }
+
     pub fn peek(&self, n: usize) -> &'a [u8] {
         let available = std::cmp::min(n, self.remaining());
         &self.buffer[self.offset..self.offset + available]
@@ -251,6 +340,40 @@ impl<'a> Parser<'a> {
             println!("🔵 {}: {:?}", msg, self.peek_rest());
         }
     }
+
+    /// Starts recording the byte range consumed by each field read through
+    /// `parse!`, discarding anything recorded previously.
+    pub fn record_fields(&mut self) {
+        self.fields = Some(Vec::new());
+    }
+
+    /// Returns the fields recorded so far, leaving recording enabled.
+    pub fn take_fields(&mut self) -> Vec<ParsedField> {
+        self.fields.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    /// Marks the start of a field; used by `parse!`.
+    #[doc(hidden)]
+    pub fn begin_field(&mut self) -> usize {
+        if self.fields.is_some() {
+            self.depth += 1;
+        }
+        self.offset
+    }
+
+    /// Marks the end of a field begun at `start`, calling `name` for its name
+    /// only if the parser is recording fields; used by `parse!`.
+    #[doc(hidden)]
+    pub fn end_field<C: AsRef<str>>(&mut self, start: usize, name: impl FnOnce() -> C) {
+        if let Some(fields) = self.fields.as_mut() {
+            self.depth = self.depth.saturating_sub(1);
+            fields.push(ParsedField {
+                name: name().as_ref().to_string(),
+                depth: self.depth,
+                range: start..self.offset,
+            });
+        }
+    }
 }
 
 impl std::io::Read for &mut Parser<'_> {
@@ -262,3 +385,127 @@ impl std::io::Read for &mut Parser<'_> {
         Ok(n)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    struct Inner {
+        tag: u8,
+        value: u32,
+    }
+
+    impl Parse for Inner {
+        fn parse(p: &mut Parser) -> Result<Self> {
+            Ok(Self {
+                tag: parse!(p, "tag")?,
+                value: parse!(p, "value")?,
+            })
+        }
+    }
+
+    struct Outer {
+        version: u16,
+        inner: Inner,
+        payload: Vec<u8>,
+    }
+
+    impl Parse for Outer {
+        fn parse(p: &mut Parser) -> Result<Self> {
+            Ok(Self {
+                version: parse!(p, "version")?,
+                inner: parse!(p, "inner")?,
+                payload: parse!(p, bytes = 3, "payload")?.to_vec(),
+            })
+        }
+    }
+
+    fn field(name: &str, depth: usize, range: std::ops::Range<usize>) -> ParsedField {
+        ParsedField {
+            name: name.to_string(),
+            depth,
+            range,
+        }
+    }
+
+    #[test]
+    fn records_field_ranges() {
+        let bytes = [0x02, 0x00, 0x07, 0x01, 0x00, 0x00, 0x00, 0xAA, 0xBB, 0xCC];
+        let (outer, fields) = Outer::parse_buf_recording_fields(&bytes).unwrap();
+        assert_eq!(outer.version, 2);
+        assert_eq!(outer.inner.tag, 7);
+        assert_eq!(outer.inner.value, 1);
+        assert_eq!(outer.payload, [0xAA, 0xBB, 0xCC]);
+
+        assert_eq!(
+            fields,
+            vec![
+                field("version", 0, 0..2),
+                field("tag", 1, 2..3),
+                field("value", 1, 3..7),
+                field("inner", 0, 2..7),
+                field("payload", 0, 7..10),
+            ]
+        );
+    }
+
+    /// Every value round-trips through its big-endian encoding.
+    fn assert_be_round_trip<T>(values: &[T], to_be_bytes: impl Fn(T) -> Vec<u8>)
+    where
+        T: FromBE + Copy + PartialEq + std::fmt::Debug,
+    {
+        for &value in values {
+            let bytes = to_be_bytes(value);
+            assert_eq!(bytes.len(), T::SIZE);
+            let mut p = Parser::new(&bytes);
+            assert_eq!(p.next_be::<T>("value").unwrap(), value);
+            assert_eq!(p.remaining(), 0);
+        }
+    }
+
+    #[test]
+    fn big_endian_integers_round_trip() {
+        assert_be_round_trip(&[0, 1, 0x0102, 0x8000, u16::MAX], |v: u16| {
+            v.to_be_bytes().to_vec()
+        });
+        assert_be_round_trip(&[0, 1, 0x0102_0304, 0x8000_0000, u32::MAX], |v: u32| {
+            v.to_be_bytes().to_vec()
+        });
+        assert_be_round_trip(
+            &[0, 1, 0x0102_0304_0506_0708, 1 << 63, u64::MAX],
+            |v: u64| v.to_be_bytes().to_vec(),
+        );
+        assert_be_round_trip(&[i32::MIN, -1, 0, 1, 0x0102_0304, i32::MAX], |v: i32| {
+            v.to_be_bytes().to_vec()
+        });
+        assert_be_round_trip(
+            &[i64::MIN, -1, 0, 1, 0x0102_0304_0506_0708, i64::MAX],
+            |v: i64| v.to_be_bytes().to_vec(),
+        );
+    }
+
+    #[test]
+    fn big_endian_reads_most_significant_byte_first() {
+        let bytes = [0x01, 0x02, 0x03, 0x04];
+        let mut p = Parser::new(&bytes);
+        assert_eq!(p.next_be::<u32>("value").unwrap(), 0x0102_0304);
+    }
+
+    #[test]
+    fn parse_be_frames_a_short_read() {
+        let bytes = [0x01, 0x02];
+        let mut p = Parser::new(&bytes);
+        let err = crate::parse_be!(&mut p, u32, "counter").unwrap_err();
+        assert!(matches!(err.kind(), ParseErrorKind::UnexpectedEof { .. }));
+        assert_eq!(err.frames(), ["u32", "counter"]);
+    }
+
+    #[test]
+    fn records_nothing_unless_enabled() {
+        let bytes = [0x02, 0x00, 0x07, 0x01, 0x00, 0x00, 0x00, 0xAA, 0xBB, 0xCC];
+        let mut p = Parser::new(&bytes);
+        Outer::parse(&mut p).unwrap();
+        assert!(p.take_fields().is_empty());
+    }
+}
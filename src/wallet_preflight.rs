@@ -0,0 +1,173 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+/// The default for how recently a `wallet.dat` may have been modified before
+/// [`check_wallet_not_in_use`] considers it live. A running zcashd flushes
+/// the wallet at least every few minutes while it has activity.
+pub const DEFAULT_RECENT_MODIFICATION_THRESHOLD: Duration = Duration::from_secs(10 * 60);
+
+/// Reasons [`check_wallet_not_in_use`] refuses a `wallet.dat` that looks like
+/// it is still open in a running zcashd.
+#[derive(Debug, thiserror::Error)]
+pub enum WalletInUseError {
+    /// Berkeley DB environment region files (`__db.*`) sit next to the
+    /// wallet. zcashd removes them when it closes the environment cleanly.
+    #[error(
+        "{} appears to be in use: Berkeley DB environment files are present ({}); stop zcashd \
+         or migrate a copy of the wallet",
+        wallet.display(),
+        files.iter().map(|f| f.display().to_string()).collect::<Vec<_>>().join(", ")
+    )]
+    EnvironmentFiles {
+        wallet: PathBuf,
+        files: Vec<PathBuf>,
+    },
+
+    /// The wallet was modified within the threshold.
+    #[error(
+        "{} appears to be in use: it was modified {}s ago; stop zcashd or migrate a copy of the \
+         wallet",
+        wallet.display(),
+        age.as_secs()
+    )]
+    RecentlyModified { wallet: PathBuf, age: Duration },
+
+    /// The wallet or its directory could not be inspected.
+    #[error("cannot inspect {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+/// Check that the `wallet.dat` at `wallet` does not look like it is open in
+/// a running zcashd, before reading it with [`BDBDump::from_file`].
+///
+/// Dumping a wallet that zcashd is writing to yields torn reads, which
+/// surface as confusing parse errors. The wallet is refused if Berkeley DB
+/// environment files (`__db.*`) are present in its directory, or if it was
+/// modified less than `recent_modification_threshold` ago (a modification time
+/// in the future counts as recent). Pass [`Duration::ZERO`] to skip the
+/// modification-time check.
+///
+/// The check is a heuristic and is not run by the library's entry points;
+/// command-line tools should run it and offer an override.
+///
+/// [`BDBDump::from_file`]: crate::BDBDump::from_file
+pub fn check_wallet_not_in_use(
+    wallet: &Path,
+    recent_modification_threshold: Duration,
+) -> Result<(), WalletInUseError> {
+    let io_error = |path: &Path| {
+        let path = path.to_path_buf();
+        move |source: std::io::Error| WalletInUseError::Io { path, source }
+    };
+
+    let directory = match wallet.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut files = vec![];
+    for entry in fs::read_dir(directory).map_err(io_error(directory))? {
+        let entry = entry.map_err(io_error(directory))?;
+        if entry.file_name().to_string_lossy().starts_with("__db.") {
+            files.push(entry.path());
+        }
+    }
+    if !files.is_empty() {
+        files.sort();
+        return Err(WalletInUseError::EnvironmentFiles {
+            wallet: wallet.to_path_buf(),
+            files,
+        });
+    }
+
+    if !recent_modification_threshold.is_zero() {
+        let modified = fs::metadata(wallet)
+            .and_then(|metadata| metadata.modified())
+            .map_err(io_error(wallet))?;
+        let age = SystemTime::now()
+            .duration_since(modified)
+            .unwrap_or(Duration::ZERO);
+        if age < recent_modification_threshold {
+            return Err(WalletInUseError::RecentlyModified {
+                wallet: wallet.to_path_buf(),
+                age,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::*;
+
+    const HOUR: Duration = Duration::from_secs(60 * 60);
+
+    /// A fresh directory holding a `wallet.dat` last modified `age` ago.
+    fn wallet_dir(name: &str, age: Duration) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "zewif-zcashd-preflight-{}-{name}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let wallet = File::create(dir.join("wallet.dat")).unwrap();
+        wallet.set_modified(SystemTime::now() - age).unwrap();
+        dir
+    }
+
+    #[test]
+    fn idle_wallet_passes() {
+        let dir = wallet_dir("idle", 2 * HOUR);
+        check_wallet_not_in_use(&dir.join("wallet.dat"), HOUR).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn environment_files_are_refused() {
+        let dir = wallet_dir("env", 2 * HOUR);
+        File::create(dir.join("__db.002")).unwrap();
+        File::create(dir.join("__db.001")).unwrap();
+
+        let result = check_wallet_not_in_use(&dir.join("wallet.dat"), HOUR);
+        match result {
+            Err(WalletInUseError::EnvironmentFiles { files, .. }) => {
+                assert_eq!(files, vec![dir.join("__db.001"), dir.join("__db.002")]);
+            }
+            other => panic!("expected EnvironmentFiles, got {other:?}"),
+        }
+        // The environment files are refused even without a threshold.
+        assert!(check_wallet_not_in_use(&dir.join("wallet.dat"), Duration::ZERO).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn recently_modified_wallet_is_refused() {
+        let dir = wallet_dir("recent", Duration::from_secs(30));
+        let wallet = dir.join("wallet.dat");
+        assert!(matches!(
+            check_wallet_not_in_use(&wallet, HOUR),
+            Err(WalletInUseError::RecentlyModified { age, .. }) if age < HOUR
+        ));
+        // A zero threshold skips the modification-time check.
+        check_wallet_not_in_use(&wallet, Duration::ZERO).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn missing_wallet_is_an_io_error() {
+        let dir = wallet_dir("missing", 2 * HOUR);
+        assert!(matches!(
+            check_wallet_not_in_use(&dir.join("other.dat"), HOUR),
+            Err(WalletInUseError::Io { .. })
+        ));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -4,7 +4,7 @@ use std::fmt::Write;
 
 use super::BDBDump;
 use crate::{parse, parser::error::ParseError, parser::prelude::*};
-use zewif::Data;
+use zewif::{Data, TxId};
 
 /// Errors arising while querying the records recovered from a zcashd
 /// `wallet.dat` BDB dump.
@@ -21,6 +21,30 @@ pub enum DumpError {
     /// More than one record exists where exactly one was expected.
     #[error("expected exactly one record for keyname {keyname:?}, found {count}")]
     MultipleRecords { keyname: String, count: usize },
+
+    /// Two dumps being merged hold different values for the same key.
+    #[error("merged dumps hold different values for key: {key}")]
+    ConflictingRecord { key: String },
+
+    /// The key built for a keyname is not the key stored for it.
+    #[error("key built for keyname {keyname:?} is {built}, but the stored key is {stored}")]
+    KeyFormatMismatch {
+        keyname: String,
+        /// The hex encoding of the key built from the keyname.
+        built: String,
+        /// The hex encoding of the stored key.
+        stored: String,
+    },
+}
+
+/// A key that two merged dumps both hold, with different values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeConflict {
+    pub key: DBKey,
+    /// The value in the dump merged into.
+    pub value_a: DBValue,
+    /// The value in the dump merged from, which was discarded.
+    pub value_b: DBValue,
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -53,13 +77,162 @@ impl DBKey {
         }
     }
 
+    /// Split a raw database key into its keyname and key data.
+    ///
+    /// zcashd keynames are made of lowercase ASCII letters, digits and
+    /// underscores. A keyname with any other character is rejected, as its
+    /// length prefix has most likely been miscounted, which would shift every
+    /// field of the key data.
     pub fn parse_data(key_data: &Data) -> Result<Self> {
         let mut parser = Parser::new(&key_data);
-        let keyname = parse!(&mut parser, "keyname")?;
+        let keyname: String = parse!(&mut parser, "keyname")?;
+        if !is_plausible_keyname(&keyname) {
+            return Err(ParseErrorKind::InvalidKeyname(keyname).into());
+        }
+        let data = parser.rest();
+        parser.check_finished()?;
+        let key = Self { keyname, data };
+        debug_assert_eq!(key.to_bytes(), key_data.as_slice());
+        Ok(key)
+    }
+
+    /// Split a raw database key as [`Self::parse_data`] does, first trimming
+    /// any whitespace around the keyname, which some tools that rewrite dumps
+    /// leave. Returns the key and, if trimming changed the keyname, the
+    /// keyname as found.
+    ///
+    /// A keyname that differs from a [known keyname](ZCASHD_KEYNAMES) only in
+    /// case is a [`ParseErrorKind::MiscasedKeyname`] error: no lookup would
+    /// find its records, so the dump is not one zcashd wrote.
+    pub fn parse_data_normalized(key_data: &Data) -> Result<(Self, Option<String>)> {
+        let mut parser = Parser::new(&key_data);
+        let found: String = parse!(&mut parser, "keyname")?;
+        let keyname = found.trim();
+        if !is_plausible_keyname(keyname) {
+            if let Some(expected) = known_keyname_ignoring_case(keyname) {
+                return Err(ParseErrorKind::MiscasedKeyname {
+                    found,
+                    expected: expected.to_string(),
+                }
+                .into());
+            }
+            return Err(ParseErrorKind::InvalidKeyname(found).into());
+        }
         let data = parser.rest();
         parser.check_finished()?;
-        Ok(Self { keyname, data })
+        let key = Self {
+            keyname: keyname.to_string(),
+            data,
+        };
+        let found = (key.keyname != found).then_some(found);
+        Ok((key, found))
     }
+
+    /// The key as stored in the database: the keyname as a length-prefixed
+    /// string, followed by the key data.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(9 + self.keyname.len() + self.data.len());
+        write_compact_size(&mut bytes, self.keyname.len());
+        bytes.extend_from_slice(self.keyname.as_bytes());
+        bytes.extend_from_slice(self.data.as_slice());
+        bytes
+    }
+
+    /// The fields of the key data, for keynames whose key data holds more
+    /// than one field; `None` for any other keyname.
+    ///
+    /// Fails if the key data does not hold exactly the fields its keyname's
+    /// layout calls for.
+    pub fn components(&self) -> Result<Option<KeyComponents>> {
+        let mut p = Parser::new(&self.data);
+        let components = match self.keyname.as_str() {
+            "recipientmapping" => KeyComponents::RecipientMapping {
+                txid: parse!(&mut p, TxId, "txid")?,
+                recipient_address: parse!(&mut p, RecipientAddress, "recipient_address")?,
+            },
+            "destdata" => KeyComponents::DestData {
+                address: parse!(&mut p, Address, "address")?,
+                key: parse!(&mut p, String, "key")?,
+            },
+            _ => return Ok(None),
+        };
+        p.check_finished()?;
+        Ok(Some(components))
+    }
+}
+
+/// Whether `keyname` is made of the characters zcashd uses for keynames.
+fn is_plausible_keyname(keyname: &str) -> bool {
+    !keyname.is_empty()
+        && keyname
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_')
+}
+
+/// The keynames of the records zcashd writes to a `wallet.dat`.
+pub const ZCASHD_KEYNAMES: &[&str] = &[
+    "acc",
+    "acentry",
+    "bestblock",
+    "bestblock_nomerkle",
+    "chdseed",
+    "ckey",
+    "cmnemonicphrase",
+    "csapzkey",
+    "cscript",
+    "czkey",
+    "defaultkey",
+    "destdata",
+    "hdchain",
+    "hdseed",
+    "key",
+    "keymeta",
+    "minversion",
+    "mkey",
+    "mnemonichdchain",
+    "mnemonicphrase",
+    "name",
+    "networkinfo",
+    "orchard_note_commitment_tree",
+    "orderposnext",
+    "pool",
+    "purpose",
+    "recipientmapping",
+    "sapextfvk",
+    "sapzaddr",
+    "sapzkey",
+    "sapzkeymeta",
+    "tx",
+    "unifiedaccount",
+    "unifiedaddrmeta",
+    "unifiedfvk",
+    "version",
+    "vkey",
+    "watchs",
+    "witnesscachesize",
+    "wkey",
+    "zkey",
+    "zkeymeta",
+];
+
+/// The known keyname that `keyname` matches ignoring ASCII case.
+fn known_keyname_ignoring_case(keyname: &str) -> Option<&'static str> {
+    ZCASHD_KEYNAMES
+        .iter()
+        .copied()
+        .find(|known| known.eq_ignore_ascii_case(keyname))
+}
+
+/// The fields of a database key whose key data holds more than one field.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyComponents {
+    /// A `recipientmapping` key: a sent transaction and the receiver it paid.
+    RecipientMapping {
+        txid: TxId,
+        recipient_address: RecipientAddress,
+    },
+    /// A `destdata` key: an address and the key of the data attached to it.
+    DestData { address: Address, key: String },
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -105,16 +278,28 @@ impl AsRef<[u8]> for DBValue {
 pub struct ZcashdDump {
     records: BTreeMap<DBKey, DBValue>,
     keys_by_keyname: BTreeMap<String, BTreeSet<DBKey>>,
+    normalized_keynames: BTreeMap<String, String>,
+    dump_problems: Vec<String>,
 }
 
 impl ZcashdDump {
+    /// Split the records of a BDB dump into keys and values, normalizing
+    /// keynames as [`DBKey::parse_data_normalized`] does.
+    ///
+    /// A key that cannot be split is an error if `strict`, and is otherwise
+    /// skipped with a warning, except that a miscased keyname is always an
+    /// error.
     pub fn from_bdb_dump(berkeley_dump: &BDBDump, strict: bool) -> Result<Self, ParseError> {
         let mut records: BTreeMap<DBKey, DBValue> = BTreeMap::new();
         let mut keys_by_keyname: BTreeMap<String, BTreeSet<DBKey>> = BTreeMap::new();
+        let mut normalized_keynames: BTreeMap<String, String> = BTreeMap::new();
 
         for (key_data, value_data) in &berkeley_dump.data_records {
-            match DBKey::parse_data(key_data) {
-                Ok(key) => {
+            match DBKey::parse_data_normalized(key_data) {
+                Ok((key, found)) => {
+                    if let Some(found) = found {
+                        normalized_keynames.insert(found, key.keyname.clone());
+                    }
                     let value = DBValue::new(value_data.clone());
                     records.insert(key.clone(), value.clone());
 
@@ -122,7 +307,9 @@ impl ZcashdDump {
                     let keyname_keys = keys_by_keyname.entry(keyname).or_default();
                     keyname_keys.insert(key);
                 }
-                Err(e) if !strict => {
+                Err(e)
+                    if !strict && !matches!(e.kind(), ParseErrorKind::MiscasedKeyname { .. }) =>
+                {
                     eprintln!(
                         "Unable to parse database key {}: {}",
                         key_data.encode_hex::<String>(),
@@ -138,13 +325,70 @@ impl ZcashdDump {
         Ok(ZcashdDump {
             records,
             keys_by_keyname,
+            normalized_keynames,
+            dump_problems: berkeley_dump
+                .problems
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
         })
     }
 
+    /// Combine two partial dumps of the same wallet, such as one per key
+    /// type. A key both dumps hold is kept once if its values agree, and is
+    /// an error if they differ.
+    pub fn merge(self, other: ZcashdDump) -> Result<ZcashdDump, DumpError> {
+        let (merged, conflicts) = self.merge_lenient(other);
+        match conflicts.into_iter().next() {
+            Some(conflict) => Err(DumpError::ConflictingRecord {
+                key: conflict.key.to_string(),
+            }),
+            None => Ok(merged),
+        }
+    }
+
+    /// Like [`Self::merge`], but for a key whose values differ, keep the
+    /// value in `self` and report the conflict rather than failing.
+    pub fn merge_lenient(mut self, other: ZcashdDump) -> (ZcashdDump, Vec<MergeConflict>) {
+        let mut conflicts = Vec::new();
+        self.normalized_keynames.extend(other.normalized_keynames);
+        self.dump_problems.extend(other.dump_problems);
+        for (key, value_b) in other.records {
+            match self.records.get(&key) {
+                Some(value_a) if *value_a != value_b => conflicts.push(MergeConflict {
+                    key,
+                    value_a: value_a.clone(),
+                    value_b,
+                }),
+                Some(_) => {}
+                None => {
+                    self.keys_by_keyname
+                        .entry(key.keyname.clone())
+                        .or_default()
+                        .insert(key.clone());
+                    self.records.insert(key, value_b);
+                }
+            }
+        }
+        (self, conflicts)
+    }
+
     pub fn records(&self) -> &BTreeMap<DBKey, DBValue> {
         &self.records
     }
 
+    /// The keynames that were found with surrounding whitespace, each mapped
+    /// to the keyname its records are looked up by.
+    pub fn normalized_keynames(&self) -> &BTreeMap<String, String> {
+        &self.normalized_keynames
+    }
+
+    /// The problems with an incomplete `db_dump` output that were tolerated
+    /// in reading the records; see [`BDBDump::problems`].
+    pub fn dump_problems(&self) -> &[String] {
+        &self.dump_problems
+    }
+
     pub fn value_for_key(&self, key: &DBKey) -> Result<&DBValue, DumpError> {
         match self.records.get(key) {
             Some(value) => Ok(value),
@@ -154,10 +398,29 @@ impl ZcashdDump {
         }
     }
 
+    /// The key of a record stored under `keyname` alone, with no key data,
+    /// as zcashd stores single records such as `version` and `bestblock`.
     pub fn key_for_keyname(&self, keyname: &str) -> DBKey {
         DBKey::new(keyname.to_string(), Data::new())
     }
 
+    /// Check that [`Self::key_for_keyname`] builds `expected_key`, a key
+    /// stored in the dump: that the two have the same bytes, length prefix
+    /// of the keyname included.
+    pub fn verify_key_format(&self, keyname: &str, expected_key: &DBKey) -> Result<(), DumpError> {
+        self.value_for_key(expected_key)?;
+        let built = self.key_for_keyname(keyname).to_bytes();
+        let stored = expected_key.to_bytes();
+        if built != stored {
+            return Err(DumpError::KeyFormatMismatch {
+                keyname: keyname.to_string(),
+                built: hex::encode(built),
+                stored: hex::encode(stored),
+            });
+        }
+        Ok(())
+    }
+
     pub fn value_for_keyname(&self, keyname: &str) -> Result<&DBValue, DumpError> {
         let key = self.key_for_keyname(keyname);
         self.value_for_key(&key)
@@ -194,18 +457,27 @@ impl ZcashdDump {
         self.keys_by_keyname.contains_key(keyname)
     }
 
+    /// The single record for `keyname`, cloned. See
+    /// [`Self::require_record_for_keyname`].
     pub fn record_for_keyname(&self, keyname: &str) -> Result<(DBKey, DBValue), DumpError> {
-        let keys = self
-            .keys_by_keyname
-            .get(keyname)
-            .ok_or_else(|| DumpError::KeynameNotFound {
-                keyname: keyname.to_string(),
-            })?;
+        let (key, value) = self.require_record_for_keyname(keyname)?;
+        Ok((key.clone(), value.clone()))
+    }
+
+    /// The single record for `keyname`, or `None` if the dump has none.
+    ///
+    /// Unlike [`Self::has_value_for_keyname`], this finds records whose key
+    /// carries data after the keyname, such as `hdseed`, which is keyed by its
+    /// seed fingerprint. More than one record is an error.
+    pub fn try_record_for_keyname(
+        &self,
+        keyname: &str,
+    ) -> Result<Option<(&DBKey, &DBValue)>, DumpError> {
+        let Some(keys) = self.keys_by_keyname.get(keyname) else {
+            return Ok(None);
+        };
         match keys.iter().next() {
-            Some(key) if keys.len() == 1 => {
-                let value = self.value_for_key(key)?;
-                Ok((key.clone(), value.clone()))
-            }
+            Some(key) if keys.len() == 1 => Ok(Some((key, self.value_for_key(key)?))),
             _ => Err(DumpError::MultipleRecords {
                 keyname: keyname.to_string(),
                 count: keys.len(),
@@ -213,6 +485,19 @@ impl ZcashdDump {
         }
     }
 
+    /// The single record for `keyname`, which must be present: its absence is
+    /// a [`DumpError::KeynameNotFound`] error, as is more than one record a
+    /// [`DumpError::MultipleRecords`] error.
+    pub fn require_record_for_keyname(
+        &self,
+        keyname: &str,
+    ) -> Result<(&DBKey, &DBValue), DumpError> {
+        self.try_record_for_keyname(keyname)?
+            .ok_or_else(|| DumpError::KeynameNotFound {
+                keyname: keyname.to_string(),
+            })
+    }
+
     fn sorted_key_names(&self) -> Vec<String> {
         let mut keynames: Vec<String> = self.keys_by_keyname.keys().cloned().collect();
         keynames.sort();
@@ -263,3 +548,263 @@ impl ZcashdDump {
         output
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    /// A raw database key: a keyname shorter than 253 bytes, with its
+    /// one-byte length prefix, then the key data.
+    fn raw_key(keyname: &str, key_data: &[u8]) -> Data {
+        let mut key = vec![keyname.len() as u8];
+        key.extend_from_slice(keyname.as_bytes());
+        key.extend_from_slice(key_data);
+        Data::from_vec(key)
+    }
+
+    /// A BDB dump of `(keyname, key data, value)` records.
+    fn bdb_dump(records: &[(&str, &[u8], &[u8])]) -> BDBDump {
+        let data_records = records
+            .iter()
+            .map(|(keyname, key_data, value)| (raw_key(keyname, key_data), Data::from_slice(value)))
+            .collect();
+        BDBDump {
+            header_records: HashMap::new(),
+            data_records,
+            problems: vec![],
+        }
+    }
+
+    /// A dump of `(keyname, key data, value)` records.
+    fn dump(records: &[(&str, &[u8], &[u8])]) -> ZcashdDump {
+        ZcashdDump::from_bdb_dump(&bdb_dump(records), true).expect("from_bdb_dump")
+    }
+
+    #[test]
+    fn merges_disjoint_dumps() {
+        let merged = dump(&[("key", &[1], &[0xaa])])
+            .merge(dump(&[("key", &[2], &[0xbb]), ("version", &[], &[1])]))
+            .unwrap();
+        assert_eq!(merged.records().len(), 3);
+        assert_eq!(merged.keys_by_keyname()["key"].len(), 2);
+        assert!(merged.has_value_for_keyname("version"));
+    }
+
+    #[test]
+    fn identical_records_merge_once() {
+        let merged = dump(&[("version", &[], &[1])])
+            .merge(dump(&[("version", &[], &[1])]))
+            .unwrap();
+        assert_eq!(merged.records().len(), 1);
+    }
+
+    #[test]
+    fn conflicting_records_are_reported() {
+        let a = || dump(&[("version", &[], &[1]), ("key", &[1], &[0xaa])]);
+        let b = || dump(&[("version", &[], &[2]), ("key", &[2], &[0xbb])]);
+
+        assert_eq!(
+            a().merge(b()).unwrap_err(),
+            DumpError::ConflictingRecord {
+                key: "version".to_string()
+            }
+        );
+
+        let (merged, conflicts) = a().merge_lenient(b());
+        assert_eq!(merged.records().len(), 3);
+        assert_eq!(
+            merged
+                .value_for_keyname("version")
+                .unwrap()
+                .as_data()
+                .as_slice(),
+            &[1]
+        );
+        assert_eq!(
+            conflicts,
+            vec![MergeConflict {
+                key: DBKey::new("version", Data::new()),
+                value_a: DBValue::new(Data::from_slice(&[1])),
+                value_b: DBValue::new(Data::from_slice(&[2])),
+            }]
+        );
+    }
+
+    #[test]
+    fn records_keyed_by_data_are_found_by_keyname() {
+        let fingerprint = [0x11; 32];
+        let d = dump(&[("hdseed", &fingerprint, &[0xaa])]);
+        // Not found by the bare keyname, whose key holds no data.
+        assert!(!d.has_value_for_keyname("hdseed"));
+
+        let (key, value) = d.try_record_for_keyname("hdseed").unwrap().unwrap();
+        assert_eq!(key.data.as_slice(), &fingerprint);
+        assert_eq!(value.as_data().as_slice(), &[0xaa]);
+        assert_eq!(
+            d.require_record_for_keyname("hdseed").unwrap(),
+            (key, value)
+        );
+    }
+
+    #[test]
+    fn absent_and_repeated_records_are_told_apart() {
+        let d = dump(&[("key", &[1], &[0xaa]), ("key", &[2], &[0xbb])]);
+        assert_eq!(d.try_record_for_keyname("hdseed"), Ok(None));
+        assert_eq!(
+            d.require_record_for_keyname("hdseed").unwrap_err(),
+            DumpError::KeynameNotFound {
+                keyname: "hdseed".to_string()
+            }
+        );
+
+        let multiple = DumpError::MultipleRecords {
+            keyname: "key".to_string(),
+            count: 2,
+        };
+        assert_eq!(d.try_record_for_keyname("key").unwrap_err(), multiple);
+        assert_eq!(d.require_record_for_keyname("key").unwrap_err(), multiple);
+    }
+
+    /// A string as zcashd serializes it: a compact size length, then bytes.
+    fn string_bytes(s: &str) -> Vec<u8> {
+        let mut bytes = vec![s.len() as u8];
+        bytes.extend_from_slice(s.as_bytes());
+        bytes
+    }
+
+    #[test]
+    fn recipientmapping_key_splits_into_txid_and_receiver() {
+        // The txid, then a P2PKH receiver: receiver type 0, then the key ID.
+        let mut key_data = vec![0x5a; 32];
+        key_data.push(0x00);
+        key_data.extend_from_slice(&[0x3c; 20]);
+        let key = DBKey::parse_data(&raw_key("recipientmapping", &key_data)).unwrap();
+        assert_eq!(key.keyname, "recipientmapping");
+        assert_eq!(key.data.as_slice(), key_data.as_slice());
+
+        match key.components().unwrap() {
+            Some(KeyComponents::RecipientMapping {
+                txid,
+                recipient_address,
+            }) => {
+                assert_eq!(txid, TxId::from_bytes([0x5a; 32]));
+                assert!(matches!(recipient_address, RecipientAddress::KeyId(_)));
+            }
+            other => panic!("expected recipient mapping components, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn destdata_key_splits_into_address_and_key() {
+        let mut key_data = string_bytes("tmGys6dBuEGjch5LFnhdo5gpSa7jiNRWse3");
+        key_data.extend(string_bytes("rr0"));
+        let key = DBKey::parse_data(&raw_key("destdata", &key_data)).unwrap();
+
+        assert_eq!(
+            key.components().unwrap(),
+            Some(KeyComponents::DestData {
+                address: Address::from("tmGys6dBuEGjch5LFnhdo5gpSa7jiNRWse3"),
+                key: "rr0".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn compound_keys_must_hold_exactly_their_fields() {
+        let mut key_data = string_bytes("tmGys6dBuEGjch5LFnhdo5gpSa7jiNRWse3");
+        key_data.extend(string_bytes("rr0"));
+        key_data.push(0x00);
+        let key = DBKey::parse_data(&raw_key("destdata", &key_data)).unwrap();
+        assert!(matches!(
+            key.components().unwrap_err().kind(),
+            ParseErrorKind::TrailingData { remaining: 1 }
+        ));
+
+        let key = DBKey::parse_data(&raw_key("recipientmapping", &[0x5a; 32])).unwrap();
+        assert!(key.components().is_err());
+
+        let key = DBKey::parse_data(&raw_key("version", &[])).unwrap();
+        assert_eq!(key.components().unwrap(), None);
+    }
+
+    #[test]
+    fn miscounted_keyname_length_is_rejected() {
+        // A length prefix one too long takes the first byte of the key data
+        // (here 'A') into the keyname.
+        let mut bytes = vec![17];
+        bytes.extend_from_slice(b"recipientmapping");
+        bytes.extend_from_slice(&[0x41; 32]);
+        let err = DBKey::parse_data(&Data::from_vec(bytes)).unwrap_err();
+        assert!(
+            matches!(err.kind(), ParseErrorKind::InvalidKeyname(name) if name == "recipientmappingA")
+        );
+
+        assert!(DBKey::parse_data(&raw_key("", &[0x00])).is_err());
+    }
+
+    #[test]
+    fn keys_round_trip_through_to_bytes() {
+        let short = raw_key("sapzkeymeta", &[0x01; 32]);
+        assert_eq!(
+            DBKey::parse_data(&short).unwrap().to_bytes(),
+            short.as_slice()
+        );
+
+        // A keyname long enough for a three-byte length prefix.
+        let key = DBKey::new("a".repeat(300), Data::from_slice(&[0x02; 4]));
+        let bytes = key.to_bytes();
+        assert_eq!(&bytes[..3], &[0xfd, 0x2c, 0x01]);
+        assert_eq!(DBKey::parse_data(&Data::from_vec(bytes)).unwrap(), key);
+    }
+
+    #[test]
+    fn whitespace_around_keynames_is_trimmed() {
+        let txid = [0x5a; 32];
+        let d = dump(&[(" tx ", &txid, &[0xaa]), ("version\t", &[], &[1])]);
+
+        assert!(d.has_keys_for_keyname("tx"));
+        let records = d.records_for_keyname("tx").unwrap();
+        let (key, value) = records.iter().next().unwrap();
+        assert_eq!(key, &DBKey::new("tx", Data::from_slice(&txid)));
+        assert_eq!(value.as_data().as_slice(), &[0xaa]);
+        assert!(d.has_value_for_keyname("version"));
+
+        let normalized: Vec<_> = d
+            .normalized_keynames()
+            .iter()
+            .map(|(found, keyname)| (found.as_str(), keyname.as_str()))
+            .collect();
+        assert_eq!(normalized, [(" tx ", "tx"), ("version\t", "version")]);
+        assert!(
+            dump(&[("tx", &txid, &[0xaa])])
+                .normalized_keynames()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn miscased_keynames_are_an_error_even_when_not_strict() {
+        for (found, expected) in [("TX", "tx"), ("Tx", "tx"), (" SapZKey ", "sapzkey")] {
+            let bdb = bdb_dump(&[(found, &[0x5a; 32], &[0xaa]), ("version", &[], &[1])]);
+            for strict in [true, false] {
+                let err = ZcashdDump::from_bdb_dump(&bdb, strict).unwrap_err();
+                assert!(matches!(
+                    err.kind(),
+                    ParseErrorKind::MiscasedKeyname { found: f, expected: e }
+                        if f == found && e == expected
+                ));
+                assert!(err.to_string().contains(&format!(
+                    "found keyname {found:?} — expected {expected:?}; dump may be corrupted"
+                )));
+            }
+        }
+
+        // An implausible keyname that is not a known one is still skipped.
+        let bdb = bdb_dump(&[("Foo", &[], &[0xaa]), ("version", &[], &[1])]);
+        let d = ZcashdDump::from_bdb_dump(&bdb, false).unwrap();
+        assert_eq!(d.records().len(), 1);
+        assert!(ZcashdDump::from_bdb_dump(&bdb, true).is_err());
+    }
+}
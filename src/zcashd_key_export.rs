@@ -0,0 +1,565 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use zcash_address::{ConversionError, TryFromAddress, ZcashAddress};
+use zcash_protocol::consensus::{NetworkConstants, NetworkType};
+use zewif::{LegacySeed, MnemonicLanguage, Network, sapling::SaplingIncomingViewingKey};
+
+use crate::{
+    Error, ZcashdWallet, parse,
+    parser::prelude::*,
+    zcashd_wallet::{
+        Address, BlockLocator, ClientVersion, KeyMetadata, MnemonicPhrase, NetworkInfo,
+        ParseWarning, SecondsSinceEpoch, ZcashdWalletBuilder,
+        sapling::{SaplingKey, SaplingKeys, SaplingZPaymentAddress},
+        sprout::{SproutKeys, SproutPaymentAddress, SproutSpendingKey},
+        transparent::{KeyPair, Keys, PubKey},
+        u252, u256,
+    },
+};
+
+/// Errors arising while reading a key export written by zcashd's
+/// `z_exportwallet` RPC.
+#[derive(Debug, thiserror::Error)]
+pub enum KeyExportError {
+    /// The export file could not be read.
+    #[error("reading {}: {source}", path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    /// A line of the export could not be interpreted.
+    #[error("line {line}: {reason}")]
+    MalformedLine { line: usize, reason: String },
+
+    /// The export's keys are encoded for more than one network.
+    #[error("key export mixes keys for different networks")]
+    MixedNetworks,
+
+    /// The export holds no key whose encoding identifies its network.
+    #[error("key export has no keys identifying its network")]
+    UnknownNetwork,
+}
+
+/// Reads the text key export written by zcashd's `z_exportwallet` RPC, for
+/// users who still have an export but no longer the `wallet.dat` it was
+/// taken from.
+///
+/// The export records keys and the addresses they were exported with, and
+/// in its header the wallet's mnemonic phrase. It records no transactions,
+/// note witnesses or unified accounts, so the [`ZcashdWallet`] read from it
+/// has none of those, and migrating it exports the keys and seeds alone.
+/// zcashd does not write unified viewing keys to the export.
+pub struct KeyExportParser;
+
+impl KeyExportParser {
+    /// Read the key export at `path`.
+    pub fn parse_file(path: &Path) -> Result<ZcashdWallet, Error> {
+        let text = std::fs::read_to_string(path).map_err(|source| KeyExportError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::parse_export(&text)
+    }
+
+    /// Read a key export from its text.
+    pub fn parse_export(text: &str) -> Result<ZcashdWallet, Error> {
+        let mut export = KeyExport::default();
+        for (index, line) in text.lines().enumerate() {
+            export
+                .parse_line(line.trim())
+                .map_err(|reason| KeyExportError::MalformedLine {
+                    line: index + 1,
+                    reason,
+                })?;
+        }
+        export.into_wallet()
+    }
+}
+
+/// The contents of a key export, accumulated line by line.
+#[derive(Default)]
+struct KeyExport {
+    network_types: HashSet<NetworkType>,
+    client_version: Option<ClientVersion>,
+    best_block: Option<u256>,
+    recovery_phrase: Option<String>,
+    language: Option<MnemonicLanguage>,
+    legacy_seed: Option<LegacySeed>,
+    address_names: BTreeMap<Address, String>,
+    keys: HashMap<PubKey, KeyPair>,
+    sapling_keys: HashMap<SaplingIncomingViewingKey, SaplingKey>,
+    sapling_z_addresses: HashMap<SaplingZPaymentAddress, SaplingIncomingViewingKey>,
+    sprout_keys: HashMap<SproutPaymentAddress, SproutSpendingKey>,
+}
+
+impl KeyExport {
+    /// Read one line: a `#` comment, which in the header carries the
+    /// wallet's version, best block and seeds, or a key line of the form
+    /// `<key> <created> [<attribute>=<value> ...] # <addr|zaddr>=<address>`.
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        if line.is_empty() {
+            return Ok(());
+        }
+        if let Some(comment) = line.strip_prefix('#') {
+            return self.parse_comment(comment.trim());
+        }
+
+        let (fields, comment) = line
+            .split_once(" # ")
+            .ok_or("key line has no address comment")?;
+        let mut fields = fields.split_whitespace();
+        let secret = fields.next().ok_or("key line has no key")?;
+        let created = fields.next().ok_or("key line has no creation time")?;
+        let attributes: Vec<&str> = fields.chain(comment.split_whitespace()).collect();
+        let metadata = KeyMetadata::new(
+            parse_time(created)?,
+            attribute(&attributes, "hdkeypath").map(str::to_string),
+            None,
+        );
+
+        if let Some(address) = attribute(&attributes, "addr") {
+            self.add_transparent_key(secret, address, attribute(&attributes, "label"), metadata)
+        } else if let Some(address) = attribute(&attributes, "zaddr") {
+            self.add_shielded_key(secret, address, metadata)
+        } else {
+            Err("key line names no address".to_string())
+        }
+    }
+
+    fn parse_comment(&mut self, comment: &str) -> Result<(), String> {
+        if let Some(version) = comment.strip_prefix("Wallet dump created by Zcash ") {
+            self.client_version = parse_client_version(version);
+        } else if let Some(best_block) = comment.strip_prefix("* Best block at time of backup was ")
+        {
+            let hash = best_block
+                .split_once('(')
+                .and_then(|(_, rest)| rest.split_once(')'))
+                .ok_or("best block line has no block hash")?
+                .0;
+            self.best_block =
+                Some(u256::from_hex(hash).map_err(|e| format!("invalid best block hash: {e}"))?);
+        } else if let Some(phrase) = comment.strip_prefix("- recovery_phrase=") {
+            self.recovery_phrase = Some(phrase.trim_matches('"').to_string());
+        } else if let Some(language) = comment.strip_prefix("- language=") {
+            self.language = Some(mnemonic_language(language));
+        } else if let Some(seed) = comment.strip_prefix("- legacy_seed=") {
+            let seed = hex::decode(seed)
+                .ok()
+                .and_then(|seed| LegacySeed::from_vec(seed).ok())
+                .ok_or("legacy seed is not 32 bytes of hex")?;
+            self.legacy_seed = Some(seed);
+        }
+        Ok(())
+    }
+
+    /// Add a transparent key, exported in Wallet Import Format.
+    fn add_transparent_key(
+        &mut self,
+        secret: &str,
+        address: &str,
+        label: Option<&str>,
+        metadata: KeyMetadata,
+    ) -> Result<(), String> {
+        let decoded = bs58::decode(secret)
+            .with_check(None)
+            .into_vec()
+            .map_err(|e| format!("invalid transparent key: {e}"))?;
+        let (network_type, payload) = match decoded.split_first() {
+            Some((0x80, payload)) => (NetworkType::Main, payload),
+            Some((0xef, payload)) => (NetworkType::Test, payload),
+            _ => return Err("transparent key has an unknown version byte".to_string()),
+        };
+        // A trailing 0x01 marks a key whose public key is compressed.
+        let compressed = match payload.len() {
+            33 if payload[32] == 0x01 => true,
+            32 => false,
+            _ => return Err("transparent key has the wrong length".to_string()),
+        };
+        let scalar: [u8; 32] = payload[..32].try_into().expect("length checked above");
+
+        let secret_key = secp256k1::SecretKey::from_slice(&scalar)
+            .map_err(|_| "transparent key is not a valid secp256k1 secret key")?;
+        let public_key = secp256k1::PublicKey::from_secret_key(
+            &secp256k1::Secp256k1::signing_only(),
+            &secret_key,
+        );
+        let pubkey = if compressed {
+            PubKey::from_slice(&public_key.serialize())
+        } else {
+            PubKey::from_slice(&public_key.serialize_uncompressed())
+        }
+        .map_err(|e| e.to_string())?;
+
+        self.network_types.insert(network_type);
+        if let Some(label) = label {
+            self.address_names.insert(
+                Address::from(address.to_string()),
+                decode_dump_string(label)?,
+            );
+        }
+        let keypair = KeyPair::from_decrypted_scalar(pubkey.clone(), &scalar, metadata);
+        self.keys.insert(pubkey, keypair);
+        Ok(())
+    }
+
+    /// Add a Sapling or Sprout spending key, according to the protocol of
+    /// the address it was exported with.
+    fn add_shielded_key(
+        &mut self,
+        secret: &str,
+        address: &str,
+        metadata: KeyMetadata,
+    ) -> Result<(), String> {
+        let shielded = ZcashAddress::try_from_encoded(address)
+            .map_err(|e| format!("invalid address {address}: {e}"))?
+            .convert::<ShieldedAddress>()
+            .map_err(|_| format!("{address} is not a Sapling or Sprout address"))?;
+        match shielded {
+            ShieldedAddress::Sapling(network_type, bytes) => {
+                let extsk = zcash_keys::encoding::decode_extended_spending_key(
+                    network_type.hrp_sapling_extended_spending_key(),
+                    secret,
+                )
+                .map_err(|e| format!("invalid Sapling spending key: {e}"))?;
+                let ivk = SaplingIncomingViewingKey::new(
+                    extsk
+                        .to_diversifiable_full_viewing_key()
+                        .to_ivk(::zip32::Scope::External)
+                        .to_repr(),
+                );
+                let payment_address =
+                    parse!(buf = &bytes, SaplingZPaymentAddress, "Sapling address")
+                        .map_err(|e| e.to_string())?;
+
+                self.network_types.insert(network_type);
+                self.sapling_z_addresses.insert(payment_address, ivk);
+                self.sapling_keys.insert(
+                    ivk,
+                    SaplingKey::new(ivk, extsk, metadata).map_err(|e| e.to_string())?,
+                );
+            }
+            ShieldedAddress::Sprout(network_type, bytes) => {
+                // The 2-byte version prefix of zcashd's `ZCSPENDING_KEY`,
+                // followed by the 252-bit `a_sk`.
+                let decoded = bs58::decode(secret)
+                    .with_check(None)
+                    .into_vec()
+                    .map_err(|e| format!("invalid Sprout spending key: {e}"))?;
+                let expected_prefix: [u8; 2] = match network_type {
+                    NetworkType::Main => [0xab, 0x36],
+                    _ => [0xac, 0x08],
+                };
+                let key = match decoded.split_at_checked(2) {
+                    Some((prefix, key)) if prefix == expected_prefix => key,
+                    _ => {
+                        return Err(format!(
+                            "Sprout spending key is not for the network of {address}"
+                        ));
+                    }
+                };
+                let key = u252::from_slice(key).map_err(|e| e.to_string())?;
+                let payment_address = parse!(buf = &bytes, SproutPaymentAddress, "Sprout address")
+                    .map_err(|e| e.to_string())?;
+
+                self.network_types.insert(network_type);
+                self.sprout_keys
+                    .insert(payment_address, SproutSpendingKey::new(key, metadata));
+            }
+        }
+        Ok(())
+    }
+
+    fn into_wallet(self) -> Result<ZcashdWallet, Error> {
+        let network = self.network()?;
+        let client_version = self
+            .client_version
+            .unwrap_or_else(|| ClientVersion::from_integer(0));
+        let bip39_mnemonic = self.recovery_phrase.map(|phrase| {
+            MnemonicPhrase::new(
+                phrase,
+                Some(self.language.unwrap_or(MnemonicLanguage::English)),
+            )
+        });
+
+        let mut builder = ZcashdWalletBuilder::new()
+            .address_names(self.address_names)
+            .bestblock(BlockLocator::new(
+                client_version,
+                self.best_block.into_iter().collect(),
+            ))
+            .client_version(client_version)
+            .keys(Keys::new(self.keys))
+            .parse_warnings(vec![ParseWarning::NoTransactionHistory])
+            .network_info(NetworkInfo::new(network))
+            .sapling_keys(SaplingKeys::new(self.sapling_keys))
+            .sapling_z_addresses(self.sapling_z_addresses);
+        if let Some(seed) = self.legacy_seed {
+            builder = builder.legacy_hd_seed(seed);
+        }
+        if let Some(mnemonic) = bip39_mnemonic {
+            builder = builder.bip39_mnemonic(mnemonic);
+        }
+        if !self.sprout_keys.is_empty() {
+            builder = builder.sprout_keys(SproutKeys::new(self.sprout_keys));
+        }
+        Ok(builder.build()?)
+    }
+
+    /// The network the export's keys are encoded for. Testnet and regtest
+    /// share transparent and Sprout encodings, so testnet keys are taken as
+    /// regtest if any key is regtest-encoded.
+    fn network(&self) -> Result<Network, KeyExportError> {
+        let has = |network_type| self.network_types.contains(&network_type);
+        match (
+            has(NetworkType::Main),
+            has(NetworkType::Test),
+            has(NetworkType::Regtest),
+        ) {
+            (true, false, false) => Ok(Network::Mainnet),
+            (false, true, false) => Ok(Network::Testnet),
+            (false, _, true) => Ok(Network::Regtest(Default::default())),
+            (false, false, false) => Err(KeyExportError::UnknownNetwork),
+            _ => Err(KeyExportError::MixedNetworks),
+        }
+    }
+}
+
+/// A Sapling or Sprout address, as its raw bytes.
+enum ShieldedAddress {
+    Sapling(NetworkType, [u8; 43]),
+    Sprout(NetworkType, [u8; 64]),
+}
+
+impl TryFromAddress for ShieldedAddress {
+    type Error = std::convert::Infallible;
+
+    fn try_from_sprout(
+        net: NetworkType,
+        data: [u8; 64],
+    ) -> Result<Self, ConversionError<Self::Error>> {
+        Ok(Self::Sprout(net, data))
+    }
+
+    fn try_from_sapling(
+        net: NetworkType,
+        data: [u8; 43],
+    ) -> Result<Self, ConversionError<Self::Error>> {
+        Ok(Self::Sapling(net, data))
+    }
+}
+
+/// The value of the `name=value` attribute among `attributes`.
+fn attribute<'a>(attributes: &[&'a str], name: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find_map(|attribute| attribute.strip_prefix(name)?.strip_prefix('='))
+}
+
+/// A key's ISO 8601 creation time; `None` for the epoch, which zcashd writes
+/// for keys of unknown age.
+fn parse_time(time: &str) -> Result<Option<SecondsSinceEpoch>, String> {
+    let time = chrono::DateTime::parse_from_rfc3339(time)
+        .map_err(|e| format!("invalid key creation time {time:?}: {e}"))?;
+    Ok(u64::try_from(time.timestamp())
+        .ok()
+        .filter(|&seconds| seconds != 0)
+        .map(SecondsSinceEpoch::from))
+}
+
+/// The client version of a `v<major>.<minor>.<revision>[-<suffix>]` version
+/// string, taken as a release build; `None` if it is not one.
+fn parse_client_version(version: &str) -> Option<ClientVersion> {
+    let version = version.split_whitespace().next()?.strip_prefix('v')?;
+    let version = version.split('-').next()?;
+    let mut parts = version.split('.').map(|part| part.parse::<u32>().ok());
+    let (major, minor, revision) = (parts.next()??, parts.next()??, parts.next()??);
+    // zcashd numbers release builds 50.
+    Some(ClientVersion::from_integer(
+        major * 1_000_000 + minor * 10_000 + revision * 100 + 50,
+    ))
+}
+
+/// The language of a `language=` header, named as by zcashd's
+/// `MnemonicSeed::LanguageName`.
+fn mnemonic_language(name: &str) -> MnemonicLanguage {
+    match name {
+        "English" => MnemonicLanguage::English,
+        "SimplifiedChinese" => MnemonicLanguage::SimplifiedChinese,
+        "TraditionalChinese" => MnemonicLanguage::TraditionalChinese,
+        "Czech" => MnemonicLanguage::Czech,
+        "French" => MnemonicLanguage::French,
+        "Italian" => MnemonicLanguage::Italian,
+        "Japanese" => MnemonicLanguage::Japanese,
+        "Korean" => MnemonicLanguage::Korean,
+        "Portuguese" => MnemonicLanguage::Portuguese,
+        "Spanish" => MnemonicLanguage::Spanish,
+        other => MnemonicLanguage::Other(other.to_string()),
+    }
+}
+
+/// Undo zcashd's `EncodeDumpString`, which writes spaces, control characters,
+/// non-ASCII bytes and `%` as `%` followed by two hex digits.
+fn decode_dump_string(encoded: &str) -> Result<String, String> {
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut rest = encoded.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let escape = tail
+                .get(..2)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| format!("invalid escape in label {encoded:?}"))?;
+            bytes.push(escape);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| format!("label {encoded:?} is not UTF-8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use ripemd::Ripemd160;
+    use sha2::{Digest, Sha256};
+    use zcash_address::ToAddress;
+    use zewif::BlockHeight;
+
+    use super::*;
+    use crate::migrate_to_zewif;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    /// A compressed mainnet WIF key for `scalar`, and its P2PKH address.
+    fn transparent_key(scalar: [u8; 32]) -> (String, String) {
+        let mut payload = vec![0x80];
+        payload.extend_from_slice(&scalar);
+        payload.push(0x01);
+        let secret_key = secp256k1::SecretKey::from_slice(&scalar).unwrap();
+        let pubkey = secp256k1::PublicKey::from_secret_key(
+            &secp256k1::Secp256k1::signing_only(),
+            &secret_key,
+        );
+        let hash: [u8; 20] = Ripemd160::digest(Sha256::digest(pubkey.serialize())).into();
+        (
+            bs58::encode(payload).with_check().into_string(),
+            ZcashAddress::from_transparent_p2pkh(NetworkType::Main, hash).encode(),
+        )
+    }
+
+    /// A Sapling spending key for `network_type`, and its default address.
+    fn sapling_key(network_type: NetworkType) -> (String, String) {
+        let extsk = ::sapling::zip32::ExtendedSpendingKey::master(&[7; 32]);
+        let (_, address) = extsk.default_address();
+        (
+            zcash_keys::encoding::encode_extended_spending_key(
+                network_type.hrp_sapling_extended_spending_key(),
+                &extsk,
+            ),
+            ZcashAddress::from_sapling(network_type, address.to_bytes()).encode(),
+        )
+    }
+
+    fn export(sapling_network: NetworkType) -> String {
+        let (wif, taddr) = transparent_key([1; 32]);
+        let (extsk, zaddr) = sapling_key(sapling_network);
+        format!(
+            "# Wallet dump created by Zcash v5.4.2\n\
+             # * Created on 2023-03-01T00:00:00Z\n\
+             # * Best block at time of backup was 2000000 (00000000019a3a19e0bd03e14afd8b2cc2c8d1c0a82ddc1bdb9aa01fd6d2d06d),\n\
+             #   mined on 2023-03-01T00:00:00Z\n\
+             # Emergency Recovery Information:\n\
+             # - recovery_phrase=\"{PHRASE}\"\n\
+             # - language=English\n\
+             \n\
+             {wif} 2023-01-02T03:04:05Z label=Savings%20account # addr={taddr}\n\
+             \n\
+             # Sapling keys\n\
+             \n\
+             {extsk} 1970-01-01T00:00:00Z # zaddr={zaddr}\n\
+             \n\
+             # End of dump\n"
+        )
+    }
+
+    #[test]
+    fn reads_keys_and_seeds() {
+        let wallet = KeyExportParser::parse_export(&export(NetworkType::Main)).unwrap();
+
+        assert_eq!(wallet.network(), &Network::Mainnet);
+        assert_eq!(wallet.client_version().major(), 5);
+        assert_eq!(wallet.bestblock().blocks().len(), 1);
+        assert!(wallet.transactions().is_empty());
+        assert_eq!(wallet.bip39_mnemonic().unwrap().phrase(), PHRASE);
+
+        let keypair = wallet.keys().keypairs().next().unwrap();
+        assert!(keypair.pubkey().is_compressed());
+        assert_eq!(
+            u64::from(keypair.metadata().create_time().unwrap()),
+            1_672_628_645
+        );
+        let (_, taddr) = transparent_key([1; 32]);
+        assert_eq!(
+            wallet.address_names()[&Address::from(taddr)],
+            "Savings account"
+        );
+
+        let sapling_key = wallet.sapling_keys().keypairs().next().unwrap();
+        assert_eq!(sapling_key.metadata().create_time(), None);
+        assert_eq!(
+            wallet.sapling_z_addresses().values().next(),
+            Some(sapling_key.ivk())
+        );
+    }
+
+    #[test]
+    fn migrates_keys_without_history() {
+        let wallet = KeyExportParser::parse_export(&export(NetworkType::Main)).unwrap();
+        let zewif = migrate_to_zewif(&wallet, BlockHeight::from_u32(2_000_000), None).unwrap();
+
+        assert!(zewif.transactions().is_empty());
+        let Some(zewif::Secrets::Plain(store)) = zewif.secrets() else {
+            panic!("expected a plaintext secret store");
+        };
+        assert_eq!(store.seeds().len(), 1);
+        assert_eq!(store.transparent_keys().len(), 1);
+        assert_eq!(store.sapling_keys().len(), 1);
+    }
+
+    #[test]
+    fn keys_for_different_networks_are_rejected() {
+        assert!(matches!(
+            KeyExportParser::parse_export(&export(NetworkType::Test)),
+            Err(Error::KeyExport(KeyExportError::MixedNetworks))
+        ));
+        assert!(matches!(
+            KeyExportParser::parse_export("# Wallet dump created by Zcash v5.4.2\n"),
+            Err(Error::KeyExport(KeyExportError::UnknownNetwork))
+        ));
+    }
+
+    #[test]
+    fn malformed_lines_are_reported_by_number() {
+        let (wif, taddr) = transparent_key([1; 32]);
+        let text = format!("# header\n{wif} yesterday # addr={taddr}\n");
+        assert!(matches!(
+            KeyExportParser::parse_export(&text),
+            Err(Error::KeyExport(KeyExportError::MalformedLine {
+                line: 2,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn dump_strings_are_decoded() {
+        assert_eq!(decode_dump_string("a%20b%25c").unwrap(), "a b%c");
+        assert_eq!(decode_dump_string("%c3%a9").unwrap(), "é");
+        assert!(decode_dump_string("%2").is_err());
+    }
+}
@@ -1,25 +1,24 @@
-use hex::ToHex as _;
 use secrecy::SecretVec;
 use sha2::{Digest, Sha256};
 use std::{
     cell::RefCell,
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet, btree_map::Entry},
 };
 use zcash_keys::keys::UnifiedFullViewingKey;
 use zeroize::Zeroizing;
-use zewif::{
-    Bip39Mnemonic, Data, LegacySeed, SeedFingerprint, TxId, sapling::SaplingIncomingViewingKey,
-};
+use zcash_protocol::consensus::NetworkType;
+use zewif::{Data, LegacySeed, Network, SeedFingerprint, TxId, sapling::SaplingIncomingViewingKey};
 
 use crate::{
-    DBValue, Error, ZcashdDump, ZcashdWallet, parse,
+    DBValue, DumpError, Error, ZcashdDump, ZcashdWallet, parse,
     parser::prelude::*,
-    zcashd_dump::DBKey,
+    zcashd_dump::{DBKey, KeyComponents},
     zcashd_wallet::{
-        Address, BlockLocator, ClientVersion, DecryptionError, KeyMetadata, MasterKeyParams,
-        MnemonicHDChain, NetworkInfo, RecipientAddress, RecipientMapping, UfvkFingerprint,
-        UnifiedAccountMetadata, UnifiedAccounts, UnifiedAddressMetadata, decrypt_master_key,
-        decrypt_secret,
+        Address, BlockLocator, ClientVersion, DecryptionError, EncryptedLegacySeed,
+        EncryptedMnemonic, KeyMetadata, MasterKeyParams, MnemonicHDChain, MnemonicPhrase,
+        NetworkInfo, ParseWarning, RecipientMapping, UfvkFingerprint, UndecodableKey,
+        UnifiedAccountMetadata, UnifiedAccounts, UnifiedAddressMetadata, ZcashdWalletBuilder,
+        check_supported_versions, decrypt_master_key, decrypt_secret,
         orchard::OrchardNoteCommitmentTree,
         sapling::{SaplingKey, SaplingKeys, SaplingZPaymentAddress},
         sprout::{SproutKeys, SproutPaymentAddress, SproutSpendingKey},
@@ -58,12 +57,83 @@ pub enum EncryptedKeyPolicy {
     Skip,
 }
 
+/// Options selecting which parts of a `zcashd` wallet are parsed, and so
+/// migrated.
+///
+/// Options may be added in later releases, so build one from
+/// [`MigrationOptions::default`] with its setters:
+///
+/// ```
+/// # use zewif_zcashd::MigrationOptions;
+/// let options = MigrationOptions::default().skip_transactions(true);
+/// assert!(options.skip_transactions);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MigrationOptions {
+    /// Skip the wallet's transaction records (`tx`) without parsing them.
+    ///
+    /// The parsed wallet then has no transactions, so its migration exports
+    /// keys, accounts, addresses and the address book but no history. This is
+    /// for recovering the keys of a large wallet, or of one whose transaction
+    /// records are corrupt, to sweep its funds elsewhere.
+    pub skip_transactions: bool,
+
+    /// Give the wallet's Orchard notes placeholder positions when its note
+    /// commitment tree cannot be parsed, rather than failing the parse.
+    ///
+    /// The placeholders are assigned sequentially, in the order zcashd
+    /// recorded the transactions, and are not the notes' positions in the
+    /// global tree: the importing wallet must rebuild each note's witness by
+    /// scanning before spending it. They are listed by
+    /// [`ZcashdWallet::orchard_placeholder_positions`], and the
+    /// [`MigrationReport`](crate::migrate::MigrationReport) counts them and
+    /// flags each transaction given one. Without this, an unreadable tree
+    /// fails the parse, strict or not.
+    pub allow_placeholder_witnesses: bool,
+}
+
+impl MigrationOptions {
+    /// Set [`Self::skip_transactions`].
+    pub fn skip_transactions(mut self, skip_transactions: bool) -> Self {
+        self.skip_transactions = skip_transactions;
+        self
+    }
+
+    /// Set [`Self::allow_placeholder_witnesses`].
+    pub fn allow_placeholder_witnesses(mut self, allow_placeholder_witnesses: bool) -> Self {
+        self.allow_placeholder_witnesses = allow_placeholder_witnesses;
+        self
+    }
+}
+
+/// Placeholder Orchard note positions, keyed by txid then by action index.
+type PlaceholderPositions = BTreeMap<TxId, BTreeMap<u32, u64>>;
+
+/// Parses a wallet dump strictly, rejecting an encrypted wallet, as
+/// [`ZcashdParser::parse_dump`] does with `strict` set. The keys left unparsed
+/// are dropped.
+impl TryFrom<&ZcashdDump> for ZcashdWallet {
+    type Error = Error;
+
+    fn try_from(dump: &ZcashdDump) -> Result<Self, Self::Error> {
+        ZcashdParser::parse_dump(dump, true).map(|(wallet, _)| wallet)
+    }
+}
+
 pub struct ZcashdParser<'a> {
     pub dump: &'a ZcashdDump,
     pub unparsed_keys: RefCell<HashSet<DBKey>>,
     pub strict: bool,
+    /// Key material skipped by a lenient parse; see
+    /// [`Self::skip_undecodable_key`].
+    undecodable_keys: RefCell<Vec<UndecodableKey>>,
+    /// What a lenient parse tolerated, in the order found, starting with what
+    /// reading the dump tolerated.
+    warnings: RefCell<Vec<ParseWarning>>,
     /// How to handle encrypted key material.
     policy: EncryptedKeyPolicy,
+    options: MigrationOptions,
 }
 
 impl<'a> ZcashdParser<'a> {
@@ -86,13 +156,60 @@ impl<'a> ZcashdParser<'a> {
         parser.parse()
     }
 
+    /// Parse a wallet dump as [`Self::parse_dump_with_policy`] does, parsing
+    /// only the parts of the wallet that `options` selects.
+    pub fn parse_dump_with_options(
+        dump: &ZcashdDump,
+        strict: bool,
+        policy: EncryptedKeyPolicy,
+        options: MigrationOptions,
+    ) -> Result<(ZcashdWallet, HashSet<DBKey>), Error> {
+        let mut parser = ZcashdParser::new(dump, strict, policy);
+        parser.options = options;
+        parser.parse()
+    }
+
+    /// The network a wallet's addresses are encoded for, from the addresses
+    /// keying its `name` and `purpose` records, or `None` if they name no
+    /// network or more than one. Sapling addresses appear there encoded;
+    /// `sapzaddr` records key them by their raw bytes, which carry no network.
+    ///
+    /// Parsing uses this when a wallet's `networkinfo` record is missing or
+    /// unreadable.
+    pub fn infer_network(dump: &ZcashdDump) -> Option<Network> {
+        let addresses: Vec<Address> = ["name", "purpose"]
+            .into_iter()
+            .filter_map(|keyname| dump.keys_by_keyname().get(keyname))
+            .flatten()
+            .filter_map(|key| parse!(buf = &key.data, Address, "address").ok())
+            .map(|address| address.normalized().unwrap_or(address))
+            .collect();
+        infer_network(&addresses).ok()
+    }
+
     fn new(dump: &'a ZcashdDump, strict: bool, policy: EncryptedKeyPolicy) -> Self {
         let unparsed_keys = RefCell::new(dump.records().keys().cloned().collect());
+        let warnings = dump
+            .dump_problems()
+            .iter()
+            .map(|problem| ParseWarning::IncompleteDump {
+                problem: problem.clone(),
+            })
+            .chain(dump.normalized_keynames().iter().map(|(found, keyname)| {
+                ParseWarning::NormalizedKeyname {
+                    found: found.clone(),
+                    keyname: keyname.clone(),
+                }
+            }))
+            .collect();
         Self {
             dump,
             unparsed_keys,
             strict,
+            undecodable_keys: RefCell::new(vec![]),
+            warnings: RefCell::new(warnings),
             policy,
+            options: MigrationOptions::default(),
         }
     }
 
@@ -132,6 +249,38 @@ impl<'a> ZcashdParser<'a> {
         self.unparsed_keys.borrow_mut().remove(key);
     }
 
+    /// Handle key material in `key`'s record that its upstream crate refused
+    /// to decode (see [`UndecodableKey`]): an error when parsing strictly, and
+    /// otherwise a warning, keeping the record's raw value among the wallet's
+    /// undecodable keys and marking the record parsed.
+    fn skip_undecodable_key(
+        &self,
+        key: &DBKey,
+        value: &DBValue,
+        kind: &'static str,
+        error: ParseError,
+    ) -> Result<(), Error> {
+        if self.strict {
+            return Err(error.into());
+        }
+        let undecodable = UndecodableKey::new(
+            key.clone(),
+            value.as_data().clone(),
+            kind,
+            error.to_string(),
+        );
+        self.warn(ParseWarning::UndecodableKey(undecodable.clone()));
+        self.undecodable_keys.borrow_mut().push(undecodable);
+        self.mark_key_parsed(key);
+        Ok(())
+    }
+
+    /// Record something a lenient parse tolerated; see
+    /// [`ZcashdWallet::parse_warnings`].
+    fn warn(&self, warning: ParseWarning) {
+        self.warnings.borrow_mut().push(warning);
+    }
+
     fn value_for_keyname(&self, keyname: &str) -> Result<&DBValue, Error> {
         let key = self.dump.key_for_keyname(keyname);
         self.mark_key_parsed(&key);
@@ -139,10 +288,43 @@ impl<'a> ZcashdParser<'a> {
     }
 
     fn parse(&self) -> Result<(ZcashdWallet, HashSet<DBKey>), Error> {
+        // Read the wallet's version first, so that a record missing because
+        // the wallet predates it can be reported as such.
+
+        // **version**
+        let client_version = self.parse_client_version("version")?;
+
+        // **minversion**
+        let min_version = self.parse_client_version("minversion")?;
+
+        // Refuse a wallet outside the supported range before any record
+        // parser can fail on it.
+        check_supported_versions(client_version, min_version)?;
+
+        self.parse_records(client_version, min_version)
+            .map_err(|error| match error {
+                Error::Dump(
+                    source @ (DumpError::RecordNotFound { .. } | DumpError::KeynameNotFound { .. }),
+                ) => Error::WalletTooOld {
+                    client_version,
+                    source,
+                },
+                error => error,
+            })
+    }
+
+    fn parse_records(
+        &self,
+        client_version: ClientVersion,
+        min_version: ClientVersion,
+    ) -> Result<(ZcashdWallet, HashSet<DBKey>), Error> {
+        // **mkey**
+        let master_keys = self.parse_master_keys()?;
+
         // When the wallet is encrypted, derive its master key from the
         // passphrase; the transparent, Sapling, mnemonic, and legacy-seed
         // parsers below use it to decrypt their `c`-prefixed record variants.
-        let master_key = self.derive_master_key()?;
+        let master_key = self.derive_master_key(&master_keys)?;
         let master_key = master_key.as_deref();
 
         //
@@ -156,6 +338,7 @@ impl<'a> ZcashdParser<'a> {
         let bestblock = self.parse_block_locator("bestblock")?;
 
         // ~~**chdseed**~~: Removed in 5.0.0
+        let encrypted_legacy_hd_seed = self.parse_encrypted_hdseed()?;
 
         // ckey
 
@@ -180,10 +363,7 @@ impl<'a> ZcashdParser<'a> {
         // keymeta
         let keys = self.parse_keys(master_key)?;
 
-        // **minversion**
-        let min_version = self.parse_client_version("minversion")?;
-
-        // **mkey**
+        // **minversion**: read by `parse`
 
         // name
         let address_names = self.parse_address_names()?;
@@ -209,8 +389,7 @@ impl<'a> ZcashdParser<'a> {
         // tx
         let transactions = self.parse_transactions(self.strict)?;
 
-        // **version**
-        let client_version = self.parse_client_version("version")?;
+        // **version**: read by `parse`
 
         // vkey
 
@@ -232,10 +411,11 @@ impl<'a> ZcashdParser<'a> {
         //
 
         // **networkinfo**
-        let network_info = self.parse_network_info()?;
+        let network_info = self.parse_network_info(&address_names, &address_purposes)?;
 
         // **orchard_note_commitment_tree**
-        let orchard_note_commitment_tree = self.parse_orchard_note_commitment_tree()?;
+        let (orchard_note_commitment_tree, orchard_tree_history, orchard_placeholder_positions) =
+            self.parse_orchard_trees_or_placeholders(&transactions)?;
 
         // unifiedaccount
 
@@ -244,13 +424,15 @@ impl<'a> ZcashdParser<'a> {
         // unifiedaddrmeta
         let unified_accounts = self.parse_unified_accounts()?;
 
-        // **mnemonicphrase**
-        let mnemonic_phrase = self.parse_mnemonic_phrase(master_key)?;
-
         // **cmnemonicphrase**
+        let encrypted_mnemonic = self.parse_encrypted_mnemonic()?;
+
+        // **mnemonicphrase**
+        let mnemonic_phrase =
+            self.parse_mnemonic_phrase(encrypted_mnemonic.as_ref(), master_key)?;
 
         // **mnemonichdchain**
-        let mnemonic_hd_chain = self.parse_mnemonic_hd_chain()?;
+        let mnemonic_hd_chain = self.parse_opt_mnemonic_hd_chain()?;
 
         // recipientmapping
         let send_recipients = self.parse_send_recipients()?;
@@ -262,34 +444,59 @@ impl<'a> ZcashdParser<'a> {
         // **bestblock_nomerkle**
         let bestblock_nomerkle = self.parse_opt_block_locator("bestblock_nomerkle")?;
 
-        let wallet = ZcashdWallet::new(
-            address_names,
-            address_purposes,
-            bestblock_nomerkle,
-            bestblock,
-            client_version,
-            cscripts,
-            default_key,
-            key_pool,
-            keys,
-            min_version,
-            legacy_hd_seed,
-            mnemonic_hd_chain,
-            mnemonic_phrase,
-            network_info,
-            orchard_note_commitment_tree,
-            orderposnext,
-            sapling_extended_full_viewing_keys,
-            sapling_keys,
-            sapling_z_addresses,
-            send_recipients,
-            sprout_keys,
-            wallet_keys,
-            transactions,
-            unified_accounts,
-            watch_scripts,
-            witnesscachesize,
-        );
+        let mut builder = ZcashdWalletBuilder::new()
+            .address_names(address_names)
+            .address_purposes(address_purposes)
+            .bestblock(bestblock)
+            .client_version(client_version)
+            .cscripts(cscripts)
+            .default_key(default_key)
+            .key_pool(key_pool)
+            .keys(keys)
+            .min_version(min_version)
+            .master_keys(master_keys)
+            .network_info(network_info)
+            .orchard_note_commitment_tree(orchard_note_commitment_tree)
+            .orchard_tree_history(orchard_tree_history)
+            .orchard_placeholder_positions(orchard_placeholder_positions)
+            .sapling_extended_full_viewing_keys(sapling_extended_full_viewing_keys)
+            .sapling_keys(sapling_keys)
+            .sapling_z_addresses(sapling_z_addresses)
+            .send_recipients(send_recipients)
+            .transactions(transactions)
+            .undecodable_keys(self.undecodable_keys.take())
+            .parse_warnings(self.warnings.take())
+            .unified_accounts(unified_accounts)
+            .watch_scripts(watch_scripts)
+            .witnesscachesize(witnesscachesize);
+        if let Some(bestblock_nomerkle) = bestblock_nomerkle {
+            builder = builder.bestblock_nomerkle(bestblock_nomerkle);
+        }
+        if let Some(seed) = legacy_hd_seed {
+            builder = builder.legacy_hd_seed(seed);
+        }
+        if let Some(seed) = encrypted_legacy_hd_seed {
+            builder = builder.encrypted_legacy_hd_seed(seed);
+        }
+        if let Some(chain) = mnemonic_hd_chain {
+            builder = builder.mnemonic_hd_chain(chain);
+        }
+        if let Some(mnemonic) = mnemonic_phrase {
+            builder = builder.bip39_mnemonic(mnemonic);
+        }
+        if let Some(mnemonic) = encrypted_mnemonic {
+            builder = builder.encrypted_mnemonic(mnemonic);
+        }
+        if let Some(orderposnext) = orderposnext {
+            builder = builder.orderposnext(orderposnext);
+        }
+        if let Some(sprout_keys) = sprout_keys {
+            builder = builder.sprout_keys(sprout_keys);
+        }
+        if let Some(wallet_keys) = wallet_keys {
+            builder = builder.wallet_keys(wallet_keys);
+        }
+        let wallet = builder.build()?;
 
         Ok((wallet, self.unparsed_keys.borrow().clone()))
     }
@@ -346,7 +553,11 @@ impl<'a> ZcashdParser<'a> {
         // per-key `keymeta` is retained unencrypted in both cases.
         if !self.dump.has_keys_for_keyname("key") && self.dump.has_keys_for_keyname("ckey") {
             return match master_key {
-                Some(master_key) => self.parse_encrypted_keys(master_key),
+                Some(master_key) => {
+                    let mut keys = self.parse_encrypted_keys(master_key)?;
+                    self.parse_key_metadata_creation_time_correction(&mut keys);
+                    Ok(keys)
+                }
                 None => {
                     self.skip_or_reject_encrypted(&["ckey", "keymeta"])?;
                     Ok(Keys::new(HashMap::new()))
@@ -381,7 +592,25 @@ impl<'a> ZcashdParser<'a> {
             self.mark_key_parsed(&key);
             self.mark_key_parsed(&metakey);
         }
-        Ok(Keys::new(keys_map))
+        let mut keys = Keys::new(keys_map);
+        self.parse_key_metadata_creation_time_correction(&mut keys);
+        Ok(keys)
+    }
+
+    /// Fill in the creation time of each transparent key whose `keymeta`
+    /// records none (a time of 0, which some zcashd versions wrote for keys
+    /// generated by earlier ones) with the earliest creation time of any other
+    /// key, warning for each key corrected.
+    fn parse_key_metadata_creation_time_correction(&self, keys: &mut Keys) {
+        let Some(earliest) = keys.correct_creation_times() else {
+            return;
+        };
+        for pubkey in keys.timestamp_corrections() {
+            self.warn(ParseWarning::KeyTimestampCorrected {
+                pubkey: pubkey.clone(),
+                creation_time: earliest,
+            });
+        }
     }
 
     /// Decrypt the transparent `ckey` records of an encrypted wallet into the
@@ -490,12 +719,24 @@ impl<'a> ZcashdParser<'a> {
         }
         for (key, value) in key_records {
             let ivk = parse!(buf = &key.data, SaplingIncomingViewingKey, "ivk")?;
-            let spending_key = parse!(
+            let metakey = DBKey::new("sapzkeymeta", &key.data);
+            let spending_key = match parse!(
                 buf = value.as_data(),
                 ::sapling::zip32::ExtendedSpendingKey,
                 "spending_key"
-            )?;
-            let metakey = DBKey::new("sapzkeymeta", &key.data);
+            ) {
+                Ok(spending_key) => spending_key,
+                Err(error) => {
+                    self.skip_undecodable_key(
+                        &key,
+                        &value,
+                        "Sapling extended spending key",
+                        error,
+                    )?;
+                    self.mark_key_parsed(&metakey);
+                    continue;
+                }
+            };
             let metadata_binary = self
                 .dump
                 .value_for_key(&metakey)?;
@@ -521,11 +762,22 @@ impl<'a> ZcashdParser<'a> {
             .dump
             .records_for_keyname("sapextfvk")?;
         for (key, value) in records {
-            let extfvk = parse!(
+            let extfvk = match parse!(
                 buf = &key.data,
                 ::sapling::zip32::ExtendedFullViewingKey,
                 "sapextfvk extended full viewing key"
-            )?;
+            ) {
+                Ok(extfvk) => extfvk,
+                Err(error) => {
+                    self.skip_undecodable_key(
+                        &key,
+                        &value,
+                        "Sapling extended full viewing key",
+                        error,
+                    )?;
+                    continue;
+                }
+            };
             // zcashd writes a single byte `'1'` (0x31) and treats any other
             // value as "do not load this key" (see zcashd
             // walletdb.cpp:486-499). Mirror that contract: anything else
@@ -606,7 +858,7 @@ impl<'a> ZcashdParser<'a> {
         Ok(parse!(buf = value, PubKey, "defaultkey")?)
     }
 
-    fn parse_mnemonic_hd_chain(&self) -> Result<Option<MnemonicHDChain>, Error> {
+    fn parse_opt_mnemonic_hd_chain(&self) -> Result<Option<MnemonicHDChain>, Error> {
         // Absent in wallets predating zcashd's v4.7.0 mnemonic support.
         if !self.dump.has_value_for_keyname("mnemonichdchain") {
             return Ok(None);
@@ -624,10 +876,13 @@ impl<'a> ZcashdParser<'a> {
             .dump
             .records_for_keyname("recipientmapping")?;
         for (key, value) in records {
-            let mut p = Parser::new(&key.data);
-            let txid = parse!(&mut p, TxId, "txid")?;
-            let recipient_address = parse!(&mut p, RecipientAddress, "recipient_address")?;
-            p.check_finished()?;
+            let Some(KeyComponents::RecipientMapping {
+                txid,
+                recipient_address,
+            }) = key.components()?
+            else {
+                unreachable!("recipientmapping keys have recipient mapping components");
+            };
             let unified_address = parse!(buf = &value, String, "unified_address")?;
             let recipient_mapping = RecipientMapping::new(recipient_address, unified_address);
             send_recipients
@@ -661,35 +916,54 @@ impl<'a> ZcashdParser<'a> {
         }
 
         let account_metadata_records = self.dump.records_for_keyname("unifiedaccount")?;
-        let mut account_metadata = HashMap::new();
+        let mut account_metadata = BTreeMap::new();
         for (key, value) in account_metadata_records {
             let metadata = parse!(
                 buf = &key.data,
                 UnifiedAccountMetadata,
                 "UnifiedAccountMetadata key"
             )?;
-            account_metadata.insert(*metadata.ufvk_fingerprint(), metadata);
             let v: u32 = parse!(buf = value.as_data(), u32, "UnifiedAccountMetadata value")?;
             if v != 0 {
                 return Err(Error::UnexpectedUnifiedAccountMetadataValue(v));
             }
+            let fingerprint = *metadata.ufvk_fingerprint();
+            match account_metadata.entry(fingerprint) {
+                Entry::Occupied(_) => {
+                    self.duplicate_ufvk_fingerprint("unifiedaccount", &fingerprint)?
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(metadata);
+                }
+            }
             self.mark_key_parsed(&key);
         }
 
         let full_viewing_keys_records = self.dump.records_for_keyname("unifiedfvk")?;
-        let mut full_viewing_keys = HashMap::new();
+        let mut full_viewing_keys = BTreeMap::new();
         for (key, value) in full_viewing_keys_records {
             let key_id = parse!(
                 buf = &key.data,
                 UfvkFingerprint,
                 "UnifiedFullViewingKey key"
             )?;
-            let fvk = parse!(
+            let fvk = match parse!(
                 buf = value.as_data(),
                 UnifiedFullViewingKey,
                 "UnifiedFullViewingKey value"
-            )?;
-            full_viewing_keys.insert(key_id, fvk);
+            ) {
+                Ok(fvk) => fvk,
+                Err(error) => {
+                    self.skip_undecodable_key(&key, &value, "unified full viewing key", error)?;
+                    continue;
+                }
+            };
+            match full_viewing_keys.entry(key_id) {
+                Entry::Occupied(_) => self.duplicate_ufvk_fingerprint("unifiedfvk", &key_id)?,
+                Entry::Vacant(entry) => {
+                    entry.insert(fvk);
+                }
+            }
             self.mark_key_parsed(&key);
         }
 
@@ -700,21 +974,43 @@ impl<'a> ZcashdParser<'a> {
         ))
     }
 
+    /// Report a second `keyname` record for `fingerprint`, which would
+    /// otherwise silently replace the first: an error when parsing strictly,
+    /// and otherwise a warning, keeping the first record in key order.
+    fn duplicate_ufvk_fingerprint(
+        &self,
+        keyname: &'static str,
+        fingerprint: &UfvkFingerprint,
+    ) -> Result<(), Error> {
+        let fingerprint = fingerprint.to_hex();
+        if self.strict {
+            return Err(Error::DuplicateUfvkFingerprint {
+                keyname,
+                fingerprint,
+            });
+        }
+        self.warn(ParseWarning::DuplicateUfvkRecord {
+            keyname,
+            fingerprint,
+        });
+        Ok(())
+    }
+
     fn parse_hdseed(&self, master_key: Option<&[u8; 32]>) -> Result<Option<LegacySeed>, Error> {
-        if self.dump.has_value_for_keyname("hdseed") {
-            let (key, value) = self
-                .dump
-                .record_for_keyname("hdseed")?;
+        // The `hdseed` key carries the seed fingerprint, so its presence is
+        // found by keyname; it is absent from wallets created after zcashd's
+        // v4.7.0 mnemonic support.
+        if let Some((key, value)) = self.dump.try_record_for_keyname("hdseed")? {
             // The `hdseed` record is keyed by the seed's ZIP 32 fingerprint;
             // it is recomputed from the seed bytes during migration, so the
             // key is not retained here.
             let _fingerprint = parse!(buf = &key.data, SeedFingerprint, "seed fingerprint")?;
-            let seed_data = parse!(buf = &value, Data, "legacy seed data")?;
-            self.mark_key_parsed(&key);
+            let seed_data = parse!(buf = value, Data, "legacy seed data")?;
+            self.mark_key_parsed(key);
             let seed = LegacySeed::from_vec(seed_data.into())
                 .map_err(|_| Error::InvalidLegacySeedLength)?;
             Ok(Some(seed))
-        } else if self.dump.has_keys_for_keyname("chdseed") {
+        } else if let Some(encrypted) = self.parse_encrypted_hdseed()? {
             // An encrypted wallet stores its legacy HD seed as a `chdseed`
             // record; the AES IV is the seed's ZIP-32 fingerprint (the record
             // key).
@@ -722,69 +1018,112 @@ impl<'a> ZcashdParser<'a> {
                 self.skip_or_reject_encrypted(&["chdseed"])?;
                 return Ok(None);
             };
-            let (key, value) = self.dump.record_for_keyname("chdseed")?;
-            // The record key is the 32-byte ZIP-32 seed fingerprint, used
-            // directly as the AES IV source (its first 16 bytes).
-            let _fingerprint = parse!(buf = &key.data, SeedFingerprint, "seed fingerprint")?;
-            let ciphertext = parse!(buf = &value, Data, "chdseed ciphertext")?;
-            let seed_bytes = decrypt_secret(master_key, ciphertext.as_slice(), key.data.as_slice())?;
-            self.mark_key_parsed(&key);
-            let seed = LegacySeed::from_vec(seed_bytes.to_vec())
-                .map_err(|_| Error::InvalidLegacySeedLength)?;
-            Ok(Some(seed))
+            Ok(Some(encrypted.decrypt(master_key)?))
         } else {
             Ok(None)
         }
     }
 
+    /// The encrypted legacy HD seed of an encrypted wallet (`chdseed`), kept
+    /// so that it can be decrypted later with
+    /// [`ZcashdWallet::decrypt_legacy_hd_seed`].
+    fn parse_encrypted_hdseed(&self) -> Result<Option<EncryptedLegacySeed>, Error> {
+        let Some((key, value)) = self.dump.try_record_for_keyname("chdseed")? else {
+            return Ok(None);
+        };
+        // The record key is the 32-byte ZIP-32 seed fingerprint, used
+        // directly as the AES IV source (its first 16 bytes).
+        let fingerprint = parse!(buf = &key.data, SeedFingerprint, "seed fingerprint")?;
+        let ciphertext = parse!(buf = value, Data, "chdseed ciphertext")?;
+        let mut iv = [0u8; 16];
+        iv.copy_from_slice(&key.data.as_slice()[..16]);
+        self.mark_key_parsed(key);
+        Ok(Some(EncryptedLegacySeed {
+            fingerprint,
+            ciphertext: ciphertext.into(),
+            iv,
+        }))
+    }
+
+    /// The encrypted mnemonic of an encrypted wallet (`cmnemonicphrase`), kept
+    /// so that it can be decrypted later with
+    /// [`ZcashdWallet::decrypt_mnemonic`].
+    fn parse_encrypted_mnemonic(&self) -> Result<Option<EncryptedMnemonic>, Error> {
+        let Some((key, value)) = self.dump.try_record_for_keyname("cmnemonicphrase")? else {
+            return Ok(None);
+        };
+        // The record key is the 32-byte ZIP-32 seed fingerprint, used
+        // directly as the AES IV source (its first 16 bytes).
+        let _fingerprint = parse!(buf = &key.data, SeedFingerprint, "seed fingerprint")?;
+        let ciphertext = parse!(buf = value, Data, "cmnemonicphrase ciphertext")?;
+        let mut iv = [0u8; 16];
+        iv.copy_from_slice(&key.data.as_slice()[..16]);
+        self.mark_key_parsed(key);
+        Ok(Some(EncryptedMnemonic {
+            ciphertext: ciphertext.into(),
+            iv,
+        }))
+    }
+
+    /// The wallet's mnemonic: its `mnemonicphrase` record, or else its
+    /// `encrypted` mnemonic (see [`Self::parse_encrypted_mnemonic`])
+    /// decrypted under `master_key`.
     fn parse_mnemonic_phrase(
         &self,
+        encrypted: Option<&EncryptedMnemonic>,
         master_key: Option<&[u8; 32]>,
-    ) -> Result<Option<Bip39Mnemonic>, Error> {
+    ) -> Result<Option<MnemonicPhrase>, Error> {
         // Absent in wallets predating zcashd's v4.7.0 mnemonic support.
-        if !self.dump.has_keys_for_keyname("mnemonicphrase") {
+        let Some((key, value)) = self.dump.try_record_for_keyname("mnemonicphrase")? else {
             // An encrypted wallet stores its mnemonic as a `cmnemonicphrase`
             // record; the AES IV is the seed's ZIP-32 fingerprint (the record
             // key). The decrypted plaintext is a serialized `MnemonicSeed`,
             // identical to a plaintext `mnemonicphrase` value.
-            if self.dump.has_keys_for_keyname("cmnemonicphrase") {
+            if let Some(encrypted) = encrypted {
                 let Some(master_key) = master_key else {
                     self.skip_or_reject_encrypted(&["cmnemonicphrase"])?;
                     return Ok(None);
                 };
-                let (key, value) = self.dump.record_for_keyname("cmnemonicphrase")?;
-                // The record key is the 32-byte ZIP-32 seed fingerprint, used
-                // directly as the AES IV source (its first 16 bytes).
-                let _fingerprint = parse!(buf = &key.data, SeedFingerprint, "seed fingerprint")?;
-                let ciphertext = parse!(buf = &value, Data, "cmnemonicphrase ciphertext")?;
-                let plaintext =
-                    decrypt_secret(master_key, ciphertext.as_slice(), key.data.as_slice())?;
-                let bip39_mnemonic =
-                    parse!(buf = &plaintext.as_slice(), Bip39Mnemonic, "mnemonic phrase")?;
-                self.mark_key_parsed(&key);
-                return Ok(Some(bip39_mnemonic));
+                return Ok(Some(encrypted.decrypt(master_key)?));
             }
             return Ok(None);
-        }
-        let (key, value) = self
-            .dump
-            .record_for_keyname("mnemonicphrase")?;
+        };
         // The `mnemonicphrase` record is keyed by the seed's ZIP 32
         // fingerprint; the same value is recorded in the mnemonic HD chain
         // (`seed_fp`), which is the source used during migration.
         let _fingerprint = parse!(buf = &key.data, SeedFingerprint, "seed fingerprint")?;
-        let bip39_mnemonic = parse!(buf = &value, Bip39Mnemonic, "mnemonic phrase")?;
-        self.mark_key_parsed(&key);
+        let bip39_mnemonic = parse!(buf = value, MnemonicPhrase, "mnemonic phrase")?;
+        self.mark_key_parsed(key);
         Ok(Some(bip39_mnemonic))
     }
 
-    fn parse_address_names(&self) -> Result<HashMap<Address, String>, Error> {
+    /// The canonical form of an address recorded as an address book key, with
+    /// a warning if it had to be normalized. Wallets edited by third-party
+    /// tools may carry whitespace or mixed-case Bech32 in these keys, which
+    /// would defeat every later lookup by address string.
+    fn normalize_address(&self, address: Address) -> Address {
+        match address.normalized() {
+            Some(normalized) => {
+                self.warn(ParseWarning::NormalizedAddress {
+                    found: address,
+                    normalized: normalized.clone(),
+                });
+                normalized
+            }
+            None => address,
+        }
+    }
+
+    fn parse_address_names(&self) -> Result<BTreeMap<Address, String>, Error> {
+        let mut address_names = BTreeMap::new();
+        if !self.dump.has_keys_for_keyname("name") {
+            return Ok(address_names);
+        }
         let records = self
             .dump
             .records_for_keyname("name")?;
-        let mut address_names = HashMap::new();
         for (key, value) in records {
-            let address = parse!(buf = &key.data, Address, "address")?;
+            let address = self.normalize_address(parse!(buf = &key.data, Address, "address")?);
             let name = parse!(buf = value.as_data(), String, "name")?;
             if address_names.contains_key(&address) {
                 return Err(Error::DuplicateAddressName {
@@ -798,13 +1137,16 @@ impl<'a> ZcashdParser<'a> {
         Ok(address_names)
     }
 
-    fn parse_address_purposes(&self) -> Result<HashMap<Address, String>, Error> {
+    fn parse_address_purposes(&self) -> Result<BTreeMap<Address, String>, Error> {
+        let mut address_purposes = BTreeMap::new();
+        if !self.dump.has_keys_for_keyname("purpose") {
+            return Ok(address_purposes);
+        }
         let records = self
             .dump
             .records_for_keyname("purpose")?;
-        let mut address_purposes = HashMap::new();
         for (key, value) in records {
-            let address = parse!(buf = &key.data, Address, "address")?;
+            let address = self.normalize_address(parse!(buf = &key.data, Address, "address")?);
             let purpose = parse!(buf = value.as_data(), String, "purpose")?;
             if address_purposes.contains_key(&address) {
                 return Err(Error::DuplicateAddressPurpose {
@@ -848,22 +1190,110 @@ impl<'a> ZcashdParser<'a> {
         Ok(sapling_z_addresses)
     }
 
-    fn parse_network_info(&self) -> Result<NetworkInfo, Error> {
+    fn parse_network_info(
+        &self,
+        address_names: &BTreeMap<Address, String>,
+        address_purposes: &BTreeMap<Address, String>,
+    ) -> Result<NetworkInfo, Error> {
+        // Absent in wallets predating zcashd v5.0.0; fall back to the network
+        // the wallet's addresses are encoded for.
+        if !self.dump.has_value_for_keyname("networkinfo") {
+            let network = infer_network(address_names.keys().chain(address_purposes.keys()))?;
+            self.warn(ParseWarning::MissingNetworkInfo {
+                network: network.clone(),
+            });
+            return Ok(NetworkInfo::new(network));
+        }
         let value = self
             .value_for_keyname("networkinfo")?;
-        let network_info = parse!(buf = value.as_data(), NetworkInfo, "network info")?;
-        Ok(network_info)
+        match parse!(buf = value.as_data(), NetworkInfo, "network info") {
+            Ok(network_info) => Ok(network_info),
+            // A corrupted record: the addresses may still settle the network.
+            Err(error) => {
+                let Ok(network) =
+                    infer_network(address_names.keys().chain(address_purposes.keys()))
+                else {
+                    return Err(error.into());
+                };
+                self.warn(ParseWarning::UnreadableNetworkInfo {
+                    network: network.clone(),
+                    error: error.to_string(),
+                });
+                Ok(NetworkInfo::new(network))
+            }
+        }
     }
 
-    fn parse_orchard_note_commitment_tree(&self) -> Result<OrchardNoteCommitmentTree, Error> {
-        let value = self
-            .value_for_keyname("orchard_note_commitment_tree")?;
-        let orchard_note_commitment_tree = parse!(
-            buf = &&value.as_data()[4..],
+    /// The wallet's Orchard note commitment tree, and any others it holds.
+    ///
+    /// zcashd writes a single `orchard_note_commitment_tree` record, but a
+    /// wallet may hold more than one, such as trees from before and after a
+    /// reorg. The one with the latest checkpoint is returned first, and the
+    /// others follow, latest checkpoint first, with a warning.
+    fn parse_orchard_note_commitment_tree(
+        &self,
+    ) -> Result<(OrchardNoteCommitmentTree, Vec<OrchardNoteCommitmentTree>), Error> {
+        const KEYNAME: &str = "orchard_note_commitment_tree";
+        // Absent in wallets last written by zcashd 4.x, before Orchard.
+        if !self.dump.has_keys_for_keyname(KEYNAME) {
+            return Ok((OrchardNoteCommitmentTree::empty(), vec![]));
+        }
+        let mut trees = vec![];
+        for (key, value) in self.dump.records_for_keyname(KEYNAME)? {
+            let mut p = Parser::new(value.as_data());
+            // The tree is preceded by the client version that wrote it.
+            parse!(&mut p, bytes = 4, "client version")?;
+            trees.push(parse!(
+                &mut p,
+                OrchardNoteCommitmentTree,
+                "orchard note commitment tree"
+            )?);
+            p.check_finished()?;
+            self.mark_key_parsed(&key);
+        }
+        // Stable, so that trees with the same checkpoint keep key order.
+        trees.sort_by_key(|tree| std::cmp::Reverse(tree.last_checkpoint()));
+        let primary = trees.remove(0);
+        if !trees.is_empty() {
+            self.warn(ParseWarning::ExtraOrchardTrees {
+                count: trees.len() + 1,
+                checkpoint: primary.last_checkpoint().map(u32::from),
+            });
+        }
+        Ok((primary, trees))
+    }
+
+    /// The wallet's Orchard note commitment trees, as
+    /// [`Self::parse_orchard_note_commitment_tree`] reads them, and the
+    /// placeholder note positions assigned in their place if they cannot be
+    /// read; see [`MigrationOptions::allow_placeholder_witnesses`].
+    ///
+    /// With placeholders allowed, an unreadable tree is replaced by the empty
+    /// one, so that only the placeholders give notes a position. Otherwise it
+    /// is an error.
+    fn parse_orchard_trees_or_placeholders(
+        &self,
+        transactions: &HashMap<TxId, WalletTx>,
+    ) -> Result<
+        (
             OrchardNoteCommitmentTree,
-            "orchard note commitment tree"
-        )?;
-        Ok(orchard_note_commitment_tree)
+            Vec<OrchardNoteCommitmentTree>,
+            PlaceholderPositions,
+        ),
+        Error,
+    > {
+        let error = match self.parse_orchard_note_commitment_tree() {
+            Ok((tree, history)) => return Ok((tree, history, BTreeMap::new())),
+            Err(error) => error,
+        };
+        if !self.options.allow_placeholder_witnesses {
+            return Err(error);
+        }
+        Ok((
+            OrchardNoteCommitmentTree::empty(),
+            vec![],
+            placeholder_orchard_positions(transactions),
+        ))
     }
 
     fn parse_key_pool(&self) -> Result<HashMap<i64, KeyPoolEntry>, Error> {
@@ -925,6 +1355,10 @@ impl<'a> ZcashdParser<'a> {
 
     fn parse_transactions(&self, strict: bool) -> Result<HashMap<TxId, WalletTx>, Error> {
         let mut transactions = HashMap::new();
+        if self.options.skip_transactions {
+            self.mark_records_parsed(&["tx"])?;
+            return Ok(transactions);
+        }
         // Some wallet files don't have any transactions
         if self.dump.has_keys_for_keyname("tx") {
             let records = self
@@ -943,11 +1377,10 @@ impl<'a> ZcashdParser<'a> {
                         transactions.insert(txid, transaction);
                     }
                     Err(e) if !strict => {
-                        eprintln!(
-                            "Unable to parse transaction data {}: {}",
-                            value.as_data().encode_hex::<String>(),
-                            e
-                        );
+                        self.warn(ParseWarning::UnparsedTransaction {
+                            txid,
+                            error: e.to_string(),
+                        });
                     }
                     err => {
                         err?;
@@ -960,16 +1393,19 @@ impl<'a> ZcashdParser<'a> {
         Ok(transactions)
     }
 
-    /// Derive the wallet master key for an encrypted wallet (a `mkey` record is
-    /// present), according to the configured [`EncryptedKeyPolicy`]:
+    /// Derive the wallet master key for an encrypted wallet (one with
+    /// `master_keys`), according to the configured [`EncryptedKeyPolicy`]:
     ///
     /// - `Decrypt`: derive and verify the master key from the passphrase.
     /// - `Reject`: fail — the wallet is encrypted but decryption was not asked for.
     /// - `Skip`: return `None`; the encrypted records are skipped downstream.
     ///
     /// Returns `None` for an unencrypted wallet regardless of policy.
-    fn derive_master_key(&self) -> Result<Option<Zeroizing<[u8; 32]>>, Error> {
-        if !self.dump.has_keys_for_keyname("mkey") {
+    fn derive_master_key(
+        &self,
+        master_keys: &[MasterKeyParams],
+    ) -> Result<Option<Zeroizing<[u8; 32]>>, Error> {
+        if master_keys.is_empty() {
             // Unencrypted wallet (a passphrase, if supplied, is simply unused).
             return Ok(None);
         }
@@ -993,26 +1429,11 @@ impl<'a> ZcashdParser<'a> {
         // A wallet normally has exactly one `mkey`, but the format allows
         // several; try each, and accept the first whose derived master key
         // decrypts a known key correctly.
-        let records = self.dump.records_for_keyname("mkey")?;
-        for value in records.values() {
-            let mut p = Parser::new(value.as_data());
-            let encrypted_key = parse!(&mut p, Data, "mkey vchCryptedKey")?;
-            let salt = parse!(&mut p, Data, "mkey vchSalt")?;
-            let derivation_method = parse!(&mut p, u32, "mkey nDerivationMethod")?;
-            let derive_iterations = parse!(&mut p, u32, "mkey nDeriveIterations")?;
-
-            let params = MasterKeyParams {
-                encrypted_key: encrypted_key.into(),
-                salt: salt.into(),
-                derivation_method,
-                derive_iterations,
-            };
-            match decrypt_master_key(&params, passphrase) {
+        for params in master_keys {
+            match decrypt_master_key(params, passphrase) {
                 Ok(master_key) => {
                     if self.master_key_verifies(&master_key)? {
-                        for key in records.keys() {
-                            self.mark_key_parsed(key);
-                        }
+                        self.mark_records_parsed(&["mkey"])?;
                         return Ok(Some(master_key));
                     }
                 }
@@ -1029,6 +1450,29 @@ impl<'a> ZcashdParser<'a> {
         Err(Error::WrongWalletPassphrase)
     }
 
+    /// The master-key derivation parameters of each of an encrypted wallet's
+    /// `mkey` records, or none for an unencrypted wallet.
+    fn parse_master_keys(&self) -> Result<Vec<MasterKeyParams>, Error> {
+        if !self.dump.has_keys_for_keyname("mkey") {
+            return Ok(vec![]);
+        }
+        let mut master_keys = vec![];
+        for value in self.dump.records_for_keyname("mkey")?.values() {
+            let mut p = Parser::new(value.as_data());
+            let encrypted_key = parse!(&mut p, Data, "mkey vchCryptedKey")?;
+            let salt = parse!(&mut p, Data, "mkey vchSalt")?;
+            let derivation_method = parse!(&mut p, u32, "mkey nDerivationMethod")?;
+            let derive_iterations = parse!(&mut p, u32, "mkey nDeriveIterations")?;
+            master_keys.push(MasterKeyParams {
+                encrypted_key: encrypted_key.into(),
+                salt: salt.into(),
+                derivation_method,
+                derive_iterations,
+            });
+        }
+        Ok(master_keys)
+    }
+
     /// Check that a candidate master key is correct by decrypting the first
     /// `ckey` record and confirming the recovered scalar derives the record's
     /// public key. Returns `true` when there is no `ckey` to check against
@@ -1064,14 +1508,26 @@ impl<'a> ZcashdParser<'a> {
             // The value is the extended full viewing key followed by the
             // encrypted extended spending key.
             let mut p = Parser::new(value.as_data());
-            let extfvk = parse!(
+            let metakey = DBKey::new("sapzkeymeta", &key.data);
+            let extfvk = match parse!(
                 &mut p,
                 ::sapling::zip32::ExtendedFullViewingKey,
                 "csapzkey extfvk"
-            )?;
+            ) {
+                Ok(extfvk) => extfvk,
+                Err(error) => {
+                    self.skip_undecodable_key(
+                        &key,
+                        &value,
+                        "encrypted Sapling key's extended full viewing key",
+                        error,
+                    )?;
+                    self.mark_key_parsed(&metakey);
+                    continue;
+                }
+            };
             let ciphertext = parse!(&mut p, Data, "csapzkey ciphertext")?;
 
-            let metakey = DBKey::new("sapzkeymeta", &key.data);
             let metadata_binary = self.dump.value_for_key(&metakey)?;
             let metadata = parse!(buf = metadata_binary, KeyMetadata, "sapzkeymeta metadata")?;
 
@@ -1113,6 +1569,28 @@ fn sha256d(bytes: &[u8]) -> [u8; 32] {
     out
 }
 
+/// The network of a wallet without a `networkinfo` record, from the encoding
+/// of its address book's addresses. Testnet and regtest share transparent and
+/// Sprout encodings, so a testnet address is taken as regtest if any address
+/// is regtest-encoded.
+fn infer_network<'a>(addresses: impl IntoIterator<Item = &'a Address>) -> Result<Network, Error> {
+    let network_types: HashSet<NetworkType> = addresses
+        .into_iter()
+        .filter_map(Address::network_type)
+        .collect();
+    let has = |network_type| network_types.contains(&network_type);
+    match (
+        has(NetworkType::Main),
+        has(NetworkType::Test),
+        has(NetworkType::Regtest),
+    ) {
+        (true, false, false) => Ok(Network::Mainnet),
+        (false, true, false) => Ok(Network::Testnet),
+        (false, _, true) => Ok(Network::Regtest(Default::default())),
+        _ => Err(Error::UnknownNetwork),
+    }
+}
+
 /// The 169-byte ZIP-32 serialization of a Sapling extended full viewing key.
 fn extfvk_bytes(extfvk: &::sapling::zip32::ExtendedFullViewingKey) -> Vec<u8> {
     let mut bytes = Vec::with_capacity(169);
@@ -1138,6 +1616,42 @@ fn sapling_fvk_fingerprint(extfvk: &::sapling::zip32::ExtendedFullViewingKey) ->
     out
 }
 
+/// Sequential placeholder positions for the Orchard notes `transactions` paid
+/// the wallet, standing in for those of an unreadable note commitment tree.
+/// Mined transactions come first, each group in the order zcashd recorded
+/// them (`mapValue["n"]`), then by txid, and each transaction's notes in
+/// action order; the block heights that would order them properly are only
+/// known from the tree itself.
+fn placeholder_orchard_positions(transactions: &HashMap<TxId, WalletTx>) -> PlaceholderPositions {
+    let mut ordered: Vec<_> = transactions
+        .iter()
+        .filter_map(|(txid, wtx)| Some((txid, wtx, wtx.orchard_tx_meta()?)))
+        .filter(|(_, _, meta)| !meta.receiving_keys().is_empty())
+        .collect();
+    ordered.sort_by_key(|(txid, wtx, _)| {
+        (
+            !crate::zcashd_wallet::is_confirmed(wtx),
+            wtx.order_position(),
+            **txid,
+        )
+    });
+    let mut next = 0u64;
+    let mut positions = BTreeMap::new();
+    for (txid, _, meta) in ordered {
+        let mut action_indices: Vec<u32> = meta.receiving_keys().keys().copied().collect();
+        action_indices.sort_unstable();
+        let tx_positions: BTreeMap<u32, u64> = action_indices
+            .into_iter()
+            .map(|action_index| {
+                next += 1;
+                (action_index, next - 1)
+            })
+            .collect();
+        positions.insert(*txid, tx_positions);
+    }
+    positions
+}
+
 /// Whether a 32-byte secp256k1 scalar derives the given public key, used to
 /// confirm a decrypted transparent key (and thus the wallet passphrase).
 fn derived_pubkey_matches(scalar: &[u8; 32], pubkey: &PubKey) -> bool {
@@ -1202,6 +1716,63 @@ mod tests {
         assert_eq!(parsed_ivk, expected_ivk);
     }
 
+    /// A `sapextfvk` record whose extended full viewing key has a
+    /// non-canonical `ak` encoding, which `sapling-crypto` rejects.
+    fn non_canonical_sapextfvk_record() -> (Data, Data) {
+        let xsk = ExtendedSpendingKey::master(b"sapextfvk-test-seed");
+        #[allow(deprecated)]
+        let efvk = xsk.to_extended_full_viewing_key();
+        let mut bytes = Vec::new();
+        efvk.write(&mut bytes).unwrap();
+        // depth (1), parent FVK tag (4), child index (4), chain code (32),
+        // then `ak`: a y-coordinate of 2^255 - 1 is not below the field
+        // modulus.
+        bytes[41..73].copy_from_slice(&[0xff; 32]);
+        (make_bdb_key("sapextfvk", &bytes), Data::from_slice(b"1"))
+    }
+
+    #[test]
+    fn undecodable_extfvk_fails_a_strict_parse() {
+        let dump = dump_with_records(vec![non_canonical_sapextfvk_record()]);
+        let parser = ZcashdParser::new(&dump, true, EncryptedKeyPolicy::Reject);
+        assert!(matches!(
+            parser.parse_sapling_extended_full_viewing_keys(),
+            Err(Error::Parse(_))
+        ));
+        assert!(parser.undecodable_keys.borrow().is_empty());
+    }
+
+    #[test]
+    fn undecodable_extfvk_is_kept_by_a_lenient_parse() {
+        let (bdb_key, value) = non_canonical_sapextfvk_record();
+        let dump = dump_with_records(vec![(bdb_key.clone(), value.clone())]);
+        let parser = ZcashdParser::new(&dump, false, EncryptedKeyPolicy::Reject);
+
+        let viewing_keys = parser
+            .parse_sapling_extended_full_viewing_keys()
+            .expect("lenient parse continues");
+        assert!(viewing_keys.is_empty());
+
+        let undecodable = parser.undecodable_keys.take();
+        assert_eq!(undecodable.len(), 1);
+        assert_eq!(
+            parser.warnings.take(),
+            [ParseWarning::UndecodableKey(undecodable[0].clone())]
+        );
+        let key = DBKey::parse_data(&bdb_key).unwrap();
+        assert_eq!(undecodable[0].key(), &key);
+        assert_eq!(undecodable[0].value(), &value);
+        assert_eq!(undecodable[0].kind(), "Sapling extended full viewing key");
+        assert!(
+            undecodable[0]
+                .error()
+                .contains("sapextfvk extended full viewing key"),
+            "{}",
+            undecodable[0]
+        );
+        assert!(!parser.unparsed_keys.borrow().contains(&key));
+    }
+
     /// Serializes a BDB key as a length-prefixed keyname followed by raw key
     /// bytes — the exact wire format `ZcashdDump::from_bdb_dump` consumes.
     /// Only the keynames used by these tests are short enough that
@@ -1229,6 +1800,7 @@ mod tests {
         let bdb = BDBDump {
             header_records: HashMap::new(),
             data_records: records.into_iter().collect(),
+            problems: vec![],
         };
         ZcashdDump::from_bdb_dump(&bdb, true).expect("from_bdb_dump")
     }
@@ -1342,6 +1914,102 @@ mod tests {
         assert!(entry.to_address_string(&Network::Mainnet).is_none());
     }
 
+    /// A dump with one unified address, one UFVK, and a `unifiedaccount`
+    /// record for each of `account_ids`, all under the same UFVK fingerprint.
+    fn unified_accounts_dump(account_ids: &[u32]) -> ZcashdDump {
+        use zcash_keys::keys::UnifiedSpendingKey;
+        use zcash_protocol::consensus::MainNetwork;
+
+        let fingerprint = [0x3c; 32];
+        let zero = Data::from_slice(&0u32.to_le_bytes());
+
+        let mut address_metadata = fingerprint.to_vec();
+        address_metadata.extend_from_slice(&[0; 11]);
+        // No receiver types.
+        address_metadata.push(0);
+        let mut records = vec![(
+            make_bdb_key("unifiedaddrmeta", &address_metadata),
+            zero.clone(),
+        )];
+
+        for account_id in account_ids {
+            let mut account = [0x5e; 32].to_vec();
+            account.extend_from_slice(&133u32.to_le_bytes());
+            account.extend_from_slice(&account_id.to_le_bytes());
+            account.extend_from_slice(&fingerprint);
+            records.push((make_bdb_key("unifiedaccount", &account), zero.clone()));
+        }
+
+        let ufvk = UnifiedSpendingKey::from_seed(&MainNetwork, &[0x5a; 32], zip32::AccountId::ZERO)
+            .unwrap()
+            .to_unified_full_viewing_key()
+            .encode(&MainNetwork);
+        let mut value = vec![];
+        crate::zcashd_wallet::write_compact_size(&mut value, ufvk.len());
+        value.extend_from_slice(ufvk.as_bytes());
+        records.push((
+            make_bdb_key("unifiedfvk", &fingerprint),
+            Data::from_vec(value),
+        ));
+
+        dump_with_records(records)
+    }
+
+    /// Two `unifiedaccount` records sharing a UFVK fingerprint fail a strict
+    /// parse, and otherwise keep the first account rather than the last.
+    #[test]
+    fn duplicate_ufvk_fingerprints_are_reported() {
+        let dump = unified_accounts_dump(&[1, 0]);
+
+        let parser = ZcashdParser::new(&dump, true, EncryptedKeyPolicy::Reject);
+        assert!(matches!(
+            parser.parse_unified_accounts(),
+            Err(Error::DuplicateUfvkFingerprint {
+                keyname: "unifiedaccount",
+                ..
+            })
+        ));
+
+        let parser = ZcashdParser::new(&dump, false, EncryptedKeyPolicy::Reject);
+        let accounts = parser.parse_unified_accounts().unwrap();
+        assert_eq!(accounts.account_metadata.len(), 1);
+        let metadata = accounts.account_metadata.values().next().unwrap();
+        assert_eq!(metadata.zip32_account_id(), 0);
+        assert_eq!(accounts.full_viewing_keys.len(), 1);
+        assert!(matches!(
+            parser.warnings.take()[..],
+            [
+                ParseWarning::DuplicateUfvkRecord {
+                    keyname: "unifiedaccount",
+                    ..
+                },
+                ..
+            ]
+        ));
+
+        let dump = unified_accounts_dump(&[0]);
+        let parser = ZcashdParser::new(&dump, true, EncryptedKeyPolicy::Reject);
+        let accounts = parser.parse_unified_accounts().unwrap();
+        assert_eq!(accounts.account_metadata.len(), 1);
+    }
+
+    /// With `skip_transactions`, `tx` records are not parsed, so a corrupt one
+    /// does not fail even a strict parse, and are not reported as unparsed.
+    #[test]
+    fn skip_transactions_bypasses_tx_records() {
+        let dump = dump_with_records(vec![(
+            make_bdb_key("tx", &[0x5a; 32]),
+            Data::from_slice(&[0xff, 0xff]),
+        )]);
+        let parser = ZcashdParser::new(&dump, true, EncryptedKeyPolicy::Reject);
+        assert!(parser.parse_transactions(true).is_err());
+
+        let mut parser = ZcashdParser::new(&dump, true, EncryptedKeyPolicy::Reject);
+        parser.options = MigrationOptions::default().skip_transactions(true);
+        assert!(parser.parse_transactions(true).unwrap().is_empty());
+        assert!(parser.unparsed_keys.borrow().is_empty());
+    }
+
     /// When neither key is present in the dump, both parsers must return
     /// empty collections rather than erroring.
     #[test]
@@ -1352,4 +2020,465 @@ mod tests {
         assert!(parser.parse_cscripts().expect("parse_cscripts").is_empty());
         assert!(parser.parse_watch_scripts().expect("parse_watch_scripts").is_empty());
     }
+
+    /// Wallets created before zcashd v4.7.0 have no `mnemonichdchain` record;
+    /// that is not an error, just a wallet without a mnemonic seed.
+    #[test]
+    fn parse_opt_mnemonic_hd_chain_is_none_for_pre_mnemonic_wallets() {
+        let dump = dump_with_records(vec![]);
+        let parser = ZcashdParser::new(&dump, true, EncryptedKeyPolicy::Reject);
+
+        let chain = parser
+            .parse_opt_mnemonic_hd_chain()
+            .expect("parse_opt_mnemonic_hd_chain");
+        assert!(chain.is_none());
+    }
+
+    /// Wallets last written by zcashd 4.x have no Orchard note commitment
+    /// tree record; they parse with an empty tree.
+    #[test]
+    fn parse_orchard_note_commitment_tree_is_empty_when_absent() {
+        let dump = dump_with_records(vec![]);
+        let parser = ZcashdParser::new(&dump, true, EncryptedKeyPolicy::Reject);
+
+        let (tree, history) = parser
+            .parse_orchard_note_commitment_tree()
+            .expect("parse_orchard_note_commitment_tree");
+        assert!(history.is_empty());
+        assert_eq!(tree.last_checkpoint(), None);
+        assert!(tree.note_positions().is_empty());
+        assert!(tree.commitment_tree().checkpoints().is_empty());
+    }
+
+    /// A record too short to hold its client version prefix is a parse error,
+    /// not a panic.
+    #[test]
+    fn parse_orchard_note_commitment_tree_rejects_a_truncated_record() {
+        let dump = dump_with_records(vec![(
+            make_bdb_key("orchard_note_commitment_tree", &[]),
+            Data::from_slice(&[0x01, 0x02]),
+        )]);
+        let parser = ZcashdParser::new(&dump, true, EncryptedKeyPolicy::Reject);
+
+        let err = parser.parse_orchard_note_commitment_tree().unwrap_err();
+        let Error::Parse(err) = err else {
+            panic!("expected a parse error, got {err:?}");
+        };
+        assert!(matches!(
+            err.kind(),
+            ParseErrorKind::UnexpectedEof {
+                needed: 4,
+                remaining: 2,
+                ..
+            }
+        ));
+    }
+
+    /// A mined wallet transaction holding a version 5 transaction with no
+    /// bundles, at order position `order_position`, whose Orchard metadata
+    /// records the wallet receiving the outputs of `actions`.
+    fn orchard_wallet_tx(order_position: &str, actions: &[u32]) -> WalletTx {
+        // Header, version group ID, NU5 branch ID, lock time, expiry height.
+        let mut bytes = 0x8000_0005u32.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&0x26a7_270au32.to_le_bytes());
+        bytes.extend_from_slice(&0xc2d6_d0b4u32.to_le_bytes());
+        bytes.extend_from_slice(&[0; 8]);
+        // No transparent inputs or outputs, Sapling spends or outputs, or
+        // Orchard actions.
+        bytes.extend_from_slice(&[0; 5]);
+        // CMerkleTx: block hash, empty merkle branch, index.
+        bytes.extend_from_slice(&[0x42; 32]);
+        bytes.push(0);
+        bytes.extend_from_slice(&0i32.to_le_bytes());
+        // vtxPrev, then mapValue holding the order position.
+        bytes.extend_from_slice(&[0, 1]);
+        for s in ["n", order_position] {
+            bytes.push(s.len() as u8);
+            bytes.extend_from_slice(s.as_bytes());
+        }
+        // mapSproutNoteData, vOrderForm, fTimeReceivedIsTxTime,
+        // nTimeReceived, fFromMe, fSpent, mapSaplingNoteData.
+        bytes.extend_from_slice(&[0, 0]);
+        bytes.extend_from_slice(&0i32.to_le_bytes());
+        bytes.extend_from_slice(&1_600_000_000i32.to_le_bytes());
+        bytes.extend_from_slice(&[0, 0, 0]);
+        // OrchardTxMeta: version, receiving keys (not valid IVKs), and no
+        // actions spending the wallet's notes.
+        bytes.extend_from_slice(&5_060_150u32.to_le_bytes());
+        bytes.push(actions.len() as u8);
+        for action in actions {
+            bytes.extend_from_slice(&action.to_le_bytes());
+            bytes.extend_from_slice(&[0xff; 64]);
+        }
+        bytes.push(0);
+        WalletTx::parse_buf(&bytes, false).unwrap()
+    }
+
+    /// An unreadable tree fails the parse, strict or not, unless
+    /// `allow_placeholder_witnesses` is set, and only then are placeholder
+    /// positions assigned.
+    #[test]
+    fn placeholder_orchard_positions_need_the_option() {
+        let dump = dump_with_records(vec![(
+            make_bdb_key("orchard_note_commitment_tree", &[]),
+            Data::from_slice(&[0x01, 0x02]),
+        )]);
+        let transactions = HashMap::from([
+            (TxId::from_bytes([1; 32]), orchard_wallet_tx("9", &[0])),
+            (TxId::from_bytes([2; 32]), orchard_wallet_tx("7", &[3, 1])),
+        ]);
+
+        for strict in [true, false] {
+            let parser = ZcashdParser::new(&dump, strict, EncryptedKeyPolicy::Reject);
+            assert!(
+                parser
+                    .parse_orchard_trees_or_placeholders(&transactions)
+                    .is_err()
+            );
+        }
+
+        let mut parser = ZcashdParser::new(&dump, true, EncryptedKeyPolicy::Reject);
+        parser.options = MigrationOptions::default().allow_placeholder_witnesses(true);
+        let (tree, history, positions) = parser
+            .parse_orchard_trees_or_placeholders(&transactions)
+            .unwrap();
+        assert!(tree.note_positions().is_empty());
+        assert!(history.is_empty());
+        assert_eq!(
+            positions,
+            BTreeMap::from([
+                (TxId::from_bytes([1; 32]), BTreeMap::from([(0, 2)])),
+                (TxId::from_bytes([2; 32]), BTreeMap::from([(1, 0), (3, 1)])),
+            ])
+        );
+    }
+
+    /// An `orchard_note_commitment_tree` record holding an empty tree whose
+    /// last checkpoint is `last_checkpoint`, keyed by `key_data`.
+    fn orchard_tree_record(key_data: &[u8], last_checkpoint: Option<u32>) -> (Data, Data) {
+        let mut bytes = 6_000_050u32.to_le_bytes().to_vec(); // client version
+        bytes.push(1); // note state version
+        match last_checkpoint {
+            Some(height) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&height.to_le_bytes());
+            }
+            None => bytes.push(0),
+        }
+        bytes.push(3); // tree serialization version
+        bytes.push(0); // prior bridges
+        bytes.push(0); // no current bridge
+        bytes.push(0); // marked positions
+        bytes.push(0); // checkpoints
+        bytes.extend_from_slice(&100u64.to_le_bytes()); // max checkpoints
+        bytes.push(0); // note positions
+        (
+            make_bdb_key("orchard_note_commitment_tree", key_data),
+            Data::from_vec(bytes),
+        )
+    }
+
+    /// Of several tree records, the one with the latest checkpoint is the
+    /// wallet's tree and the others are kept as its history.
+    #[test]
+    fn parse_orchard_note_commitment_tree_prefers_the_latest_checkpoint() {
+        use zcash_protocol::consensus::BlockHeight;
+
+        let dump = dump_with_records(vec![
+            orchard_tree_record(&[], Some(1_700_000)),
+            orchard_tree_record(&[1], Some(1_800_000)),
+            orchard_tree_record(&[2], None),
+        ]);
+        let parser = ZcashdParser::new(&dump, true, EncryptedKeyPolicy::Reject);
+
+        let (tree, history) = parser
+            .parse_orchard_note_commitment_tree()
+            .expect("parse_orchard_note_commitment_tree");
+        let height = |height: u32| Some(BlockHeight::from(height));
+        assert_eq!(tree.last_checkpoint(), height(1_800_000));
+        let checkpoints: Vec<_> = history.iter().map(|tree| tree.last_checkpoint()).collect();
+        assert_eq!(checkpoints, [height(1_700_000), None]);
+        assert!(parser.unparsed_keys.borrow().is_empty());
+        assert_eq!(
+            parser.warnings.take(),
+            [ParseWarning::ExtraOrchardTrees {
+                count: 3,
+                checkpoint: Some(1_800_000)
+            }]
+        );
+
+        let dump = dump_with_records(vec![orchard_tree_record(&[], Some(1_700_000))]);
+        let parser = ZcashdParser::new(&dump, true, EncryptedKeyPolicy::Reject);
+        let (tree, history) = parser.parse_orchard_note_commitment_tree().unwrap();
+        assert_eq!(tree.last_checkpoint(), height(1_700_000));
+        assert!(history.is_empty());
+    }
+
+    /// Address book keys written with padding or in mixed case by other tools
+    /// are normalized, so that lookups by canonical address string find them.
+    #[test]
+    fn parse_address_names_normalizes_keys() {
+        use zcash_address::{ToAddress, ZcashAddress};
+        use zcash_protocol::consensus::NetworkType;
+
+        let sapling = ZcashAddress::from_sapling(NetworkType::Main, [3; 43]).encode();
+        let transparent = "t1Hsc1LR8yKnbbe3twRp88p6vFfC5t7DLbs";
+        let padded = format!("{transparent}  ");
+        let mixed_case = format!("ZS{}", &sapling[2..]);
+
+        let dump = dump_with_records(
+            [(&padded, "padded"), (&mixed_case, "mixed case")]
+                .into_iter()
+                .map(|(address, name)| {
+                    (
+                        make_bdb_key("name", make_script_value(address.as_bytes()).as_slice()),
+                        make_script_value(name.as_bytes()),
+                    )
+                })
+                .collect(),
+        );
+        let parser = ZcashdParser::new(&dump, true, EncryptedKeyPolicy::Reject);
+
+        let names = parser.parse_address_names().expect("parse_address_names");
+        assert_eq!(names.len(), 2);
+        assert_eq!(names[&Address::from(transparent)], "padded");
+        assert_eq!(names[&Address::from(sapling.clone())], "mixed case");
+
+        let warnings = parser.warnings.take();
+        assert_eq!(warnings.len(), 2);
+        for (found, normalized) in [(padded, transparent.to_string()), (mixed_case, sapling)] {
+            assert!(warnings.contains(&ParseWarning::NormalizedAddress {
+                found: Address::from(found),
+                normalized: Address::from(normalized),
+            }));
+        }
+    }
+
+    /// A wallet without a `networkinfo` record takes its network from the
+    /// encoding of its addresses.
+    #[test]
+    fn infer_network_from_address_encodings() {
+        use zcash_address::{ToAddress, ZcashAddress};
+
+        let p2pkh = |network| {
+            Address::from(ZcashAddress::from_transparent_p2pkh(network, [1; 20]).encode())
+        };
+        let sapling =
+            |network| Address::from(ZcashAddress::from_sapling(network, [3; 43]).encode());
+
+        let network = |addresses: &[Address]| infer_network(addresses).ok();
+        assert_eq!(network(&[p2pkh(NetworkType::Main)]), Some(Network::Mainnet));
+        assert_eq!(
+            network(&[p2pkh(NetworkType::Test), sapling(NetworkType::Test)]),
+            Some(Network::Testnet)
+        );
+        // A regtest transparent address is encoded as on testnet.
+        assert!(matches!(
+            network(&[p2pkh(NetworkType::Regtest), sapling(NetworkType::Regtest)]),
+            Some(Network::Regtest(_))
+        ));
+        assert_eq!(
+            network(&[p2pkh(NetworkType::Main), sapling(NetworkType::Test)]),
+            None
+        );
+        assert_eq!(network(&[Address::from("not an address")]), None);
+    }
+
+    fn name_records(addresses: &[Address]) -> Vec<(Data, Data)> {
+        addresses
+            .iter()
+            .map(|address| {
+                (
+                    make_bdb_key(
+                        "name",
+                        make_script_value(address.to_string().as_bytes()).as_slice(),
+                    ),
+                    make_script_value(b""),
+                )
+            })
+            .collect()
+    }
+
+    /// `ZcashdParser::infer_network` settles on a network only when every
+    /// address in the dump agrees.
+    #[test]
+    fn infer_network_from_dump() {
+        use zcash_address::{ToAddress, ZcashAddress};
+
+        let p2pkh = |network| {
+            Address::from(ZcashAddress::from_transparent_p2pkh(network, [1; 20]).encode())
+        };
+        let sapling =
+            |network| Address::from(ZcashAddress::from_sapling(network, [3; 43]).encode());
+        let infer = |addresses: &[Address]| {
+            ZcashdParser::infer_network(&dump_with_records(name_records(addresses)))
+        };
+
+        assert_eq!(
+            infer(&[p2pkh(NetworkType::Main), sapling(NetworkType::Main)]),
+            Some(Network::Mainnet)
+        );
+        assert_eq!(
+            infer(&[p2pkh(NetworkType::Test), sapling(NetworkType::Test)]),
+            Some(Network::Testnet)
+        );
+        assert_eq!(
+            infer(&[p2pkh(NetworkType::Main), sapling(NetworkType::Test)]),
+            None
+        );
+        assert_eq!(infer(&[]), None);
+    }
+
+    /// A corrupted `networkinfo` record falls back to the addresses' network,
+    /// and is an error if they do not settle it.
+    #[test]
+    fn parse_network_info_recovers_from_a_corrupted_record() {
+        let dump = dump_with_records(vec![(
+            make_bdb_key("networkinfo", &[]),
+            Data::from_vec(vec![0xff]),
+        )]);
+        let parser = ZcashdParser::new(&dump, true, EncryptedKeyPolicy::Reject);
+
+        let names = BTreeMap::from([(
+            Address::from("t1Hsc1LR8yKnbbe3twRp88p6vFfC5t7DLbs"),
+            String::new(),
+        )]);
+        let info = parser
+            .parse_network_info(&names, &BTreeMap::new())
+            .expect("parse_network_info");
+        assert_eq!(info.network(), &Network::Mainnet);
+
+        assert!(
+            parser
+                .parse_network_info(&BTreeMap::new(), &BTreeMap::new())
+                .is_err()
+        );
+    }
+
+    /// The recorded `networkinfo` is used when present, whatever the
+    /// addresses' encoding.
+    #[test]
+    fn parse_network_info_prefers_the_record() {
+        let mut value = vec![];
+        for s in ["Zcash", "test"] {
+            value.push(s.len() as u8);
+            value.extend_from_slice(s.as_bytes());
+        }
+        let dump = dump_with_records(vec![(
+            make_bdb_key("networkinfo", &[]),
+            Data::from_vec(value),
+        )]);
+        let parser = ZcashdParser::new(&dump, true, EncryptedKeyPolicy::Reject);
+
+        let names = BTreeMap::from([(
+            Address::from("t1Hsc1LR8yKnbbe3twRp88p6vFfC5t7DLbs"),
+            String::new(),
+        )]);
+        let info = parser
+            .parse_network_info(&names, &BTreeMap::new())
+            .expect("parse_network_info");
+        assert_eq!(info.network(), &Network::Testnet);
+
+        let dump = dump_with_records(vec![]);
+        let parser = ZcashdParser::new(&dump, true, EncryptedKeyPolicy::Reject);
+        let info = parser
+            .parse_network_info(&names, &BTreeMap::new())
+            .expect("parse_network_info");
+        assert_eq!(info.network(), &Network::Mainnet);
+    }
+
+    /// A `chdseed` record is kept, keyed by its seed fingerprint, when the
+    /// wallet is parsed without its passphrase, and decrypts to the seed.
+    #[test]
+    fn parse_encrypted_hdseed_keeps_fingerprint_and_ciphertext() {
+        use crate::zcashd_wallet::{encode_seed_fingerprint, test_encryption::encrypt_secret};
+
+        let master_key = [0x42; 32];
+        let fingerprint = [0x5e; 32];
+        let seed = [0x17; 32];
+        let ciphertext = encrypt_secret(&master_key, &seed, &fingerprint);
+        let mut value = vec![ciphertext.len() as u8];
+        value.extend_from_slice(&ciphertext);
+
+        let dump = dump_with_records(vec![(
+            make_bdb_key("chdseed", &fingerprint),
+            Data::from_slice(&value),
+        )]);
+        let parser = ZcashdParser::new(&dump, true, EncryptedKeyPolicy::Skip);
+
+        let encrypted = parser
+            .parse_encrypted_hdseed()
+            .expect("parse_encrypted_hdseed")
+            .expect("chdseed record");
+        assert_eq!(encrypted.fingerprint, encode_seed_fingerprint(&fingerprint));
+        assert_eq!(encrypted.ciphertext, ciphertext);
+        assert_eq!(encrypted.iv, fingerprint[..16]);
+        assert!(parser.unparsed_keys.borrow().is_empty());
+
+        assert_eq!(parser.parse_hdseed(None).expect("parse_hdseed"), None);
+        let decrypted = encrypted.decrypt(&master_key).expect("decrypt");
+        assert_eq!(decrypted.as_slice(), &seed[..]);
+    }
+
+    /// Parse a dump holding only a `version` and a `minversion` record.
+    fn parse_versions_only(version: u32, min_version: u32) -> Result<(), Error> {
+        let dump = dump_with_records(vec![
+            (
+                make_bdb_key("version", &[]),
+                Data::from_slice(&version.to_le_bytes()),
+            ),
+            (
+                make_bdb_key("minversion", &[]),
+                Data::from_slice(&min_version.to_le_bytes()),
+            ),
+        ]);
+        ZcashdParser::parse_dump(&dump, true).map(|_| ())
+    }
+
+    /// A wallet outside the supported version range is refused as soon as
+    /// its versions are read; one within it goes on to fail on its first
+    /// missing record.
+    #[test]
+    fn parse_checks_the_supported_version_range() {
+        let oldest = ClientVersion::OLDEST_SUPPORTED.version();
+        let newest = ClientVersion::NEWEST_SUPPORTED_FEATURE.version();
+
+        match parse_versions_only(oldest - 1, newest) {
+            Err(Error::UnsupportedClientVersion {
+                client_version,
+                oldest,
+            }) => {
+                assert_eq!(client_version.to_string(), "3.0.0-rc25");
+                assert_eq!(oldest.to_string(), "3.0.0");
+            }
+            other => panic!("expected an unsupported client version, got {other:?}"),
+        }
+
+        match parse_versions_only(oldest, newest + 1) {
+            Err(Error::UnsupportedWalletFormat { min_version, .. }) => {
+                assert_eq!(min_version.version(), 60_001);
+            }
+            other => panic!("expected an unsupported wallet format, got {other:?}"),
+        }
+
+        let error = parse_versions_only(oldest, newest).unwrap_err();
+        assert!(matches!(error, Error::WalletTooOld { .. }), "{error}");
+        let error = parse_versions_only(6_020_050, 60_000).unwrap_err();
+        assert!(matches!(error, Error::WalletTooOld { .. }), "{error}");
+    }
+
+    /// Converting a dump into a wallet parses it strictly, failing as
+    /// `parse_dump` does.
+    #[test]
+    fn wallet_try_from_dump_parses_it() {
+        let dump = dump_with_records(vec![(
+            make_bdb_key("version", &[]),
+            Data::from_slice(&6_020_050u32.to_le_bytes()),
+        )]);
+        let error = ZcashdWallet::try_from(&dump).expect_err("no minversion record");
+        assert!(
+            matches!(error, Error::Dump(DumpError::RecordNotFound { .. })),
+            "{error}"
+        );
+    }
 }
@@ -1,15 +1,22 @@
 use zewif::{LegacySeed, mod_use};
 
 mod_use!(address);
+mod_use!(address_type_counts);
 mod_use!(block_locator);
 mod_use!(client_version);
 mod_use!(compact_size);
 mod_use!(crypto);
+mod_use!(encrypted_legacy_seed);
+mod_use!(encrypted_mnemonic);
 mod_use!(key_metadata);
 mod_use!(incremental_merkle_tree);
 mod_use!(incremental_witness);
 mod_use!(mnemonic_hd_chain);
+mod_use!(mnemonic_phrase);
+mod_use!(network_address);
 mod_use!(network_info);
+mod_use!(order_form);
+mod_use!(parse_warning);
 mod_use!(parseable_types);
 mod_use!(receiver_type);
 mod_use!(recipient_address);
@@ -21,7 +28,9 @@ mod_use!(unified_address_metadata);
 mod_use!(u160_type);
 mod_use!(u252_type);
 mod_use!(u256_type);
+mod_use!(undecodable_key);
 mod_use!(wallet_tx);
+mod_use!(zcashd_wallet_builder);
 
 /// Encodes raw ZIP 32 seed fingerprint bytes in their canonical string
 /// form: Bech32m with the Human-Readable Part "zip32seedfp".
@@ -37,18 +46,26 @@ pub mod sapling;
 pub mod sprout;
 pub mod transparent;
 
-use std::collections::HashMap;
-use zewif::{Bip39Mnemonic, Network, Script, TxId, sapling::SaplingIncomingViewingKey};
+use secrecy::SecretVec;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::OnceLock,
+};
+use zcash_transparent::{address::TransparentAddress, bundle::TxOut};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+use zewif::{BlockHash, Network, Script, TxId, sapling::SaplingIncomingViewingKey};
+
+use crate::migrate::{p2pkh_address_string, unified_address_string};
 
 use orchard::OrchardNoteCommitmentTree;
-use sapling::{SaplingKeys, SaplingZPaymentAddress};
+use sapling::{SaplingKeys, SaplingNoteData, SaplingZPaymentAddress};
 use sprout::SproutKeys;
-use transparent::{KeyPoolEntry, Keys, PubKey, ScriptId, WalletKeys, WatchScript};
+use transparent::{KeyPoolEntry, Keys, PubKey, SaplingOutPoint, ScriptId, WalletKeys, WatchScript};
 
 #[derive(Debug)]
 pub struct ZcashdWallet {
-    address_names: HashMap<Address, String>,
-    address_purposes: HashMap<Address, String>,
+    address_names: BTreeMap<Address, String>,
+    address_purposes: BTreeMap<Address, String>,
     bestblock_nomerkle: Option<BlockLocator>,
     bestblock: BlockLocator,
     client_version: ClientVersion,
@@ -57,12 +74,18 @@ pub struct ZcashdWallet {
     key_pool: HashMap<i64, KeyPoolEntry>,
     keys: Keys,
     min_version: ClientVersion,
-    legacy_hd_seed: Option<LegacySeed>,
+    legacy_hd_seed: Option<LegacyHdSeed>,
+    encrypted_legacy_hd_seed: Option<EncryptedLegacySeed>,
     mnemonic_hd_chain: Option<MnemonicHDChain>,
-    bip39_mnemonic: Option<Bip39Mnemonic>,
+    bip39_mnemonic: Option<MnemonicPhrase>,
+    encrypted_mnemonic: Option<EncryptedMnemonic>,
+    master_keys: Vec<MasterKeyParams>,
     network_info: NetworkInfo,
     orchard_note_commitment_tree: OrchardNoteCommitmentTree,
+    orchard_tree_history: Vec<OrchardNoteCommitmentTree>,
+    orchard_placeholder_positions: BTreeMap<TxId, BTreeMap<u32, u64>>,
     orderposnext: Option<i64>,
+    parse_warnings: Vec<ParseWarning>,
     sapling_extended_full_viewing_keys:
         HashMap<SaplingIncomingViewingKey, ::sapling::zip32::ExtendedFullViewingKey>,
     sapling_keys: SaplingKeys,
@@ -71,16 +94,24 @@ pub struct ZcashdWallet {
     sprout_keys: Option<SproutKeys>,
     wallet_keys: Option<WalletKeys>,
     transactions: HashMap<TxId, WalletTx>,
+    undecodable_keys: Vec<UndecodableKey>,
     unified_accounts: UnifiedAccounts,
     watch_scripts: Vec<WatchScript>,
     witnesscachesize: i64,
+    /// The index built by [`Self::account_for_address`] on first use.
+    address_accounts: OnceLock<HashMap<String, UfvkFingerprint>>,
+    /// The index built by [`Self::sapling_address_for_ivk`] on first use.
+    sapling_addresses_by_ivk: OnceLock<HashMap<SaplingIncomingViewingKey, SaplingZPaymentAddress>>,
+    /// The index built by [`Self::address_by_name`] on first use.
+    addresses_by_name: OnceLock<HashMap<String, Vec<Address>>>,
 }
 
 impl ZcashdWallet {
+    #[deprecated(note = "use `ZcashdWalletBuilder`, which names each field")]
     #[allow(clippy::too_many_arguments)]
     pub fn new(
-        address_names: HashMap<Address, String>,
-        address_purposes: HashMap<Address, String>,
+        address_names: BTreeMap<Address, String>,
+        address_purposes: BTreeMap<Address, String>,
         bestblock_nomerkle: Option<BlockLocator>,
         bestblock: BlockLocator,
         client_version: ClientVersion,
@@ -90,10 +121,14 @@ impl ZcashdWallet {
         keys: Keys,
         min_version: ClientVersion,
         legacy_hd_seed: Option<LegacySeed>,
+        encrypted_legacy_hd_seed: Option<EncryptedLegacySeed>,
         mnemonic_hd_chain: Option<MnemonicHDChain>,
-        bip39_mnemonic: Option<Bip39Mnemonic>,
+        bip39_mnemonic: Option<MnemonicPhrase>,
+        encrypted_mnemonic: Option<EncryptedMnemonic>,
+        master_keys: Vec<MasterKeyParams>,
         network_info: NetworkInfo,
         orchard_note_commitment_tree: OrchardNoteCommitmentTree,
+        orchard_tree_history: Vec<OrchardNoteCommitmentTree>,
         orderposnext: Option<i64>,
         sapling_extended_full_viewing_keys: HashMap<
             SaplingIncomingViewingKey,
@@ -105,6 +140,7 @@ impl ZcashdWallet {
         sprout_keys: Option<SproutKeys>,
         wallet_keys: Option<WalletKeys>,
         transactions: HashMap<TxId, WalletTx>,
+        undecodable_keys: Vec<UndecodableKey>,
         unified_accounts: UnifiedAccounts,
         watch_scripts: Vec<WatchScript>,
         witnesscachesize: i64,
@@ -120,12 +156,18 @@ impl ZcashdWallet {
             key_pool,
             keys,
             min_version,
-            legacy_hd_seed,
+            legacy_hd_seed: legacy_hd_seed.map(LegacyHdSeed),
+            encrypted_legacy_hd_seed,
             mnemonic_hd_chain,
             bip39_mnemonic,
+            encrypted_mnemonic,
+            master_keys,
             network_info,
             orchard_note_commitment_tree,
+            orchard_tree_history,
+            orchard_placeholder_positions: BTreeMap::new(),
             orderposnext,
+            parse_warnings: vec![],
             sapling_extended_full_viewing_keys,
             sapling_keys,
             sapling_z_addresses,
@@ -133,16 +175,41 @@ impl ZcashdWallet {
             sprout_keys,
             wallet_keys,
             transactions,
+            undecodable_keys,
             unified_accounts,
             watch_scripts,
             witnesscachesize,
+            address_accounts: OnceLock::new(),
+            sapling_addresses_by_ivk: OnceLock::new(),
+            addresses_by_name: OnceLock::new(),
         }
     }
-    pub fn address_names(&self) -> &HashMap<Address, String> {
+    pub fn address_names(&self) -> &BTreeMap<Address, String> {
         &self.address_names
     }
 
-    pub fn address_purposes(&self) -> &HashMap<Address, String> {
+    /// The addresses named `name` in the wallet's address book, the reverse
+    /// of [`Self::address_names`], in address order. zcashd does not keep
+    /// names unique, so several addresses may share one; the name must match
+    /// exactly.
+    ///
+    /// The first call indexes the address book by name, so that later calls
+    /// are a single map lookup.
+    pub fn address_by_name(&self, name: &str) -> Vec<&Address> {
+        self.addresses_by_name
+            .get_or_init(|| {
+                let mut index: HashMap<String, Vec<Address>> = HashMap::new();
+                for (address, name) in &self.address_names {
+                    index.entry(name.clone()).or_default().push(address.clone());
+                }
+                index
+            })
+            .get(name)
+            .map(|addresses| addresses.iter().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn address_purposes(&self) -> &BTreeMap<Address, String> {
         &self.address_purposes
     }
 
@@ -177,12 +244,40 @@ impl ZcashdWallet {
         &self.keys
     }
 
+    /// The transparent keys whose creation time was missing from their
+    /// metadata and was approximated by the earliest creation time of the
+    /// wallet's other keys; see [`Keys::has_corrected_timestamps`].
+    pub fn key_timestamp_corrections(&self) -> &[PubKey] {
+        self.keys.timestamp_corrections()
+    }
+
     pub fn min_version(&self) -> &ClientVersion {
         &self.min_version
     }
 
+    /// Check that the wallet's `version` and `minversion` records are within
+    /// the range this crate parses.
+    ///
+    /// Fails with [`Error::UnsupportedClientVersion`](crate::Error::UnsupportedClientVersion)
+    /// if the wallet was last written by a release older than
+    /// [`ClientVersion::OLDEST_SUPPORTED`], or with
+    /// [`Error::UnsupportedWalletFormat`](crate::Error::UnsupportedWalletFormat)
+    /// if it requires a wallet format newer than
+    /// [`ClientVersion::NEWEST_SUPPORTED_FEATURE`].
+    pub fn check_supported(&self) -> Result<(), crate::Error> {
+        check_supported_versions(self.client_version, self.min_version)
+    }
+
     pub fn legacy_hd_seed(&self) -> Option<&LegacySeed> {
-        self.legacy_hd_seed.as_ref()
+        self.legacy_hd_seed.as_ref().map(|seed| &seed.0)
+    }
+
+    /// The wallet's encrypted legacy HD seed (`chdseed`), present only for
+    /// legacy HD wallets encrypted before zcashd 5.0.0. Unless the wallet was
+    /// parsed with its passphrase, [`Self::legacy_hd_seed`] is then `None`;
+    /// see [`Self::decrypt_legacy_hd_seed`].
+    pub fn encrypted_legacy_hd_seed(&self) -> Option<&EncryptedLegacySeed> {
+        self.encrypted_legacy_hd_seed.as_ref()
     }
 
     /// The mnemonic HD chain metadata, present only for wallets created or
@@ -193,10 +288,83 @@ impl ZcashdWallet {
 
     /// The wallet's BIP-39 mnemonic, present only for wallets created or
     /// upgraded by zcashd v4.7.0 or later.
-    pub fn bip39_mnemonic(&self) -> Option<&Bip39Mnemonic> {
+    ///
+    /// An encrypted wallet parsed without its passphrase has no mnemonic
+    /// here, not an empty one: a placeholder would be migrated as if it were
+    /// the wallet's seed. Its [`Self::encrypted_mnemonic`] is kept instead.
+    pub fn bip39_mnemonic(&self) -> Option<&MnemonicPhrase> {
         self.bip39_mnemonic.as_ref()
     }
 
+    /// The wallet's encrypted BIP-39 mnemonic (`cmnemonicphrase`), present
+    /// only for encrypted wallets with a mnemonic. Unless the wallet was
+    /// parsed with its passphrase, [`Self::bip39_mnemonic`] is then `None`;
+    /// see [`Self::decrypt_mnemonic`].
+    pub fn encrypted_mnemonic(&self) -> Option<&EncryptedMnemonic> {
+        self.encrypted_mnemonic.as_ref()
+    }
+
+    /// The master-key records (`mkey`) of an encrypted wallet.
+    pub fn master_keys(&self) -> &[MasterKeyParams] {
+        &self.master_keys
+    }
+
+    /// Whether the wallet is encrypted with a passphrase (has `mkey` records).
+    pub fn is_encrypted(&self) -> bool {
+        !self.master_keys.is_empty()
+    }
+
+    /// Decrypt the wallet's encrypted mnemonic with its passphrase, or `None`
+    /// if the wallet has no encrypted mnemonic.
+    ///
+    /// Fails with [`Error::WrongWalletPassphrase`](crate::Error::WrongWalletPassphrase)
+    /// if the passphrase does not decrypt it.
+    pub fn decrypt_mnemonic(
+        &self,
+        passphrase: &SecretVec<u8>,
+    ) -> Result<Option<MnemonicPhrase>, crate::Error> {
+        self.encrypted_mnemonic
+            .as_ref()
+            .map(|encrypted| encrypted.decrypt_with_passphrase(&self.master_keys, passphrase))
+            .transpose()
+    }
+
+    /// Decrypt the wallet's encrypted legacy HD seed with its passphrase, or
+    /// `None` if the wallet has no encrypted legacy HD seed.
+    ///
+    /// Fails with [`Error::WrongWalletPassphrase`](crate::Error::WrongWalletPassphrase)
+    /// if the passphrase does not decrypt it.
+    pub fn decrypt_legacy_hd_seed(
+        &self,
+        passphrase: &SecretVec<u8>,
+    ) -> Result<Option<LegacySeed>, crate::Error> {
+        self.encrypted_legacy_hd_seed
+            .as_ref()
+            .map(|encrypted| encrypted.decrypt_with_passphrase(&self.master_keys, passphrase))
+            .transpose()
+    }
+
+    /// Clear the wallet's secret key material from memory, for a caller that
+    /// keeps the wallet after exporting it. The transparent private keys and
+    /// Sprout spending keys are zeroed in place, and the legacy HD seed, the
+    /// mnemonic and the Sapling spending keys are removed. Each of these is
+    /// also zeroed when dropped, so a wallet that is dropped needs no call.
+    ///
+    /// Master keys decrypted from the wallet passphrase are never kept on the
+    /// wallet.
+    pub fn zeroize_secrets(&mut self) {
+        self.keys.zeroize();
+        if let Some(wallet_keys) = &mut self.wallet_keys {
+            wallet_keys.zeroize();
+        }
+        if let Some(sprout_keys) = &mut self.sprout_keys {
+            sprout_keys.zeroize();
+        }
+        self.legacy_hd_seed = None;
+        self.bip39_mnemonic = None;
+        self.sapling_keys = SaplingKeys::new(HashMap::new());
+    }
+
     pub fn network_info(&self) -> &NetworkInfo {
         &self.network_info
     }
@@ -205,6 +373,29 @@ impl ZcashdWallet {
         &self.orchard_note_commitment_tree
     }
 
+    /// The wallet's other Orchard note commitment trees, when it has more
+    /// than one `orchard_note_commitment_tree` record, latest checkpoint
+    /// first. [`Self::orchard_note_commitment_tree`] is the one with the
+    /// latest checkpoint of all, and is the one the migration uses.
+    pub fn orchard_tree_history(&self) -> &[OrchardNoteCommitmentTree] {
+        &self.orchard_tree_history
+    }
+
+    /// Placeholder Orchard note commitment positions, keyed by txid then by
+    /// action index, assigned in place of the positions of a note commitment
+    /// tree that could not be parsed. They are sequential, not the notes'
+    /// positions in the global tree, so a note's witness must be rebuilt by
+    /// scanning before it is spent.
+    ///
+    /// Empty unless the wallet was parsed with
+    /// [`MigrationOptions::allow_placeholder_witnesses`](crate::MigrationOptions::allow_placeholder_witnesses)
+    /// set and its tree was unreadable.
+    pub fn orchard_placeholder_positions(&self) -> &BTreeMap<TxId, BTreeMap<u32, u64>> {
+        &self.orchard_placeholder_positions
+    }
+
+    /// The order position zcashd would give the wallet's next transaction
+    /// (`orderposnext`), if recorded.
     pub fn orderposnext(&self) -> Option<i64> {
         self.orderposnext
     }
@@ -230,6 +421,35 @@ impl ZcashdWallet {
         &self.sapling_z_addresses
     }
 
+    /// The Sapling address whose incoming viewing key is `ivk`, the reverse
+    /// of [`Self::sapling_z_addresses`]. Of several addresses of the same key
+    /// (with different diversifiers), the one with the least diversifier.
+    ///
+    /// The first call indexes the wallet's Sapling addresses by key, so that
+    /// later calls are a single map lookup.
+    pub fn sapling_address_for_ivk(
+        &self,
+        ivk: &SaplingIncomingViewingKey,
+    ) -> Option<&SaplingZPaymentAddress> {
+        self.sapling_addresses_by_ivk
+            .get_or_init(|| {
+                let mut index: HashMap<SaplingIncomingViewingKey, SaplingZPaymentAddress> =
+                    HashMap::new();
+                for (address, ivk) in &self.sapling_z_addresses {
+                    index
+                        .entry(*ivk)
+                        .and_modify(|existing| {
+                            if address.diversifier() < existing.diversifier() {
+                                *existing = address.clone();
+                            }
+                        })
+                        .or_insert_with(|| address.clone());
+                }
+                index
+            })
+            .get(ivk)
+    }
+
     pub fn send_recipients(&self) -> &HashMap<TxId, Vec<RecipientMapping>> {
         &self.send_recipients
     }
@@ -246,6 +466,20 @@ impl ZcashdWallet {
         self.wallet_keys.as_ref()
     }
 
+    /// What a lenient parse tolerated in reading the wallet rather than
+    /// failing on it, in the order found. Each is also reported by
+    /// [`MigrationReport::warnings`](crate::migrate::MigrationReport::warnings).
+    pub fn parse_warnings(&self) -> &[ParseWarning] {
+        &self.parse_warnings
+    }
+
+    /// Key material a lenient parse could not decode, kept as raw records.
+    /// Always empty after a strict parse, which fails on such records
+    /// instead.
+    pub fn undecodable_keys(&self) -> &[UndecodableKey] {
+        &self.undecodable_keys
+    }
+
     pub fn unified_accounts(&self) -> &UnifiedAccounts {
         &self.unified_accounts
     }
@@ -267,4 +501,361 @@ impl ZcashdWallet {
     pub fn network(&self) -> &Network {
         self.network_info.network()
     }
+
+    /// The height of the block a transaction was mined in, where the wallet
+    /// records it.
+    ///
+    /// zcashd records heights only for the transactions that appended notes
+    /// to the Orchard commitment tree. Every other transaction mined in one of
+    /// those blocks is at the same height.
+    pub fn block_height_for_tx(&self, txid: &TxId) -> Option<u32> {
+        let wtx = self.transactions.get(txid)?;
+        if !is_confirmed(wtx) {
+            return None;
+        }
+        self.block_heights().get(&wtx.hash_block()).copied()
+    }
+
+    /// The height of each block that a transaction of known height was
+    /// mined in.
+    fn block_heights(&self) -> HashMap<BlockHash, u32> {
+        self.orchard_note_commitment_tree
+            .note_positions()
+            .iter()
+            .filter_map(|(txid, positions)| {
+                let wtx = self.transactions.get(&TxId::from_bytes(*txid.as_ref()))?;
+                Some((wtx.hash_block(), u32::from(positions.tx_height())))
+            })
+            .collect()
+    }
+
+    /// The wallet's transactions in chain order: confirmed transactions by
+    /// block height (see [`Self::block_height_for_tx`]) and position in their
+    /// block, then confirmed transactions of unknown height, then unconfirmed
+    /// ones (those with a zero `hash_block`). Ties are broken by the order
+    /// position zcashd assigned, then by txid, so the order is deterministic.
+    pub fn transactions_by_height(&self) -> Vec<(&TxId, &WalletTx)> {
+        let block_heights = self.block_heights();
+        let mut transactions: Vec<_> = self.transactions.iter().collect();
+        transactions.sort_by_key(|(txid, wtx)| {
+            let height = is_confirmed(wtx)
+                .then(|| block_heights.get(&wtx.hash_block()).copied())
+                .flatten();
+            (
+                !is_confirmed(wtx),
+                height.is_none(),
+                height,
+                wtx.index(),
+                wtx.order_position(),
+                **txid,
+            )
+        });
+        transactions
+    }
+
+    /// The wallet's transactions that are mined in a block.
+    pub fn confirmed_transactions(&self) -> impl Iterator<Item = (&TxId, &WalletTx)> {
+        self.transactions
+            .iter()
+            .filter(|(_, wtx)| is_confirmed(wtx))
+    }
+
+    /// The wallet's transactions that are not mined in a block.
+    pub fn unconfirmed_transactions(&self) -> impl Iterator<Item = (&TxId, &WalletTx)> {
+        self.transactions
+            .iter()
+            .filter(|(_, wtx)| !is_confirmed(wtx))
+    }
+
+    /// The Sapling notes the wallet received that no wallet transaction
+    /// spends, with the transaction and output each was received in, ordered
+    /// by txid and output.
+    ///
+    /// A note is spent when a wallet transaction reveals its nullifier. zcashd
+    /// records a note's nullifier only once it can compute it, so a note with
+    /// no recorded nullifier is unspent.
+    pub fn unspent_sapling_notes(&self) -> Vec<(&TxId, &SaplingOutPoint, &SaplingNoteData)> {
+        let spent: HashSet<[u8; 32]> = self
+            .transactions
+            .values()
+            .filter_map(|wtx| wtx.sapling_bundle())
+            .flat_map(|bundle| bundle.shielded_spends())
+            .map(|spend| spend.nullifier().0)
+            .collect();
+        let mut notes: Vec<_> = self
+            .transactions
+            .iter()
+            .flat_map(|(txid, wtx)| {
+                wtx.sapling_note_data()
+                    .into_iter()
+                    .flatten()
+                    .map(move |(outpoint, note_data)| (txid, outpoint, note_data))
+            })
+            .filter(|(_, _, note_data)| {
+                note_data
+                    .nullifier()
+                    .is_none_or(|nullifier| !spent.contains(nullifier))
+            })
+            .collect();
+        notes.sort_by_key(|(txid, outpoint, _)| (**txid, outpoint.vout()));
+        notes
+    }
+
+    /// Whether the wallet has any unspent Sapling note; see
+    /// [`Self::unspent_sapling_notes`].
+    pub fn has_sapling_funds(&self) -> bool {
+        !self.unspent_sapling_notes().is_empty()
+    }
+
+    /// The transparent outputs paid to a P2PKH address of the wallet's key
+    /// store that no wallet transaction spends, with the transaction and
+    /// output index of each, ordered by txid and index. Outputs of
+    /// unconfirmed transactions are included.
+    pub fn unspent_transparent_outputs(&self) -> Vec<(&TxId, usize, &TxOut)> {
+        let owned: HashSet<TransparentAddress> = self
+            .keys
+            .keypairs()
+            .filter_map(|keypair| {
+                secp256k1::PublicKey::from_slice(keypair.pubkey().as_slice()).ok()
+            })
+            .map(|pk| TransparentAddress::from_pubkey(&pk))
+            .collect();
+        let spent: HashSet<(TxId, u32)> = self
+            .transactions
+            .values()
+            .flat_map(|wtx| wtx.vin())
+            .map(|input| {
+                let prevout = input.prevout();
+                (TxId::from_bytes(*prevout.hash()), prevout.n())
+            })
+            .collect();
+        let mut outputs: Vec<_> = self
+            .transactions
+            .iter()
+            .flat_map(|(txid, wtx)| {
+                wtx.vout()
+                    .iter()
+                    .enumerate()
+                    .map(move |(n, output)| (txid, n, output))
+            })
+            .filter(|(txid, n, output)| {
+                output
+                    .recipient_address()
+                    .is_some_and(|address| owned.contains(&address))
+                    && !spent.contains(&(**txid, *n as u32))
+            })
+            .collect();
+        outputs.sort_by_key(|(txid, n, _)| (**txid, *n));
+        outputs
+    }
+
+    /// Count the wallet's addresses by protocol.
+    ///
+    /// Transparent addresses are counted from the address book, since zcashd
+    /// names every transparent address it generates; watch-only scripts are
+    /// counted separately, and their addresses only as watch-only.
+    pub fn address_count_by_type(&self) -> AddressTypeCounts {
+        let network = self.network();
+        let watched: HashSet<String> = self
+            .watch_scripts
+            .iter()
+            .filter_map(|script| script.to_address_string(network))
+            .collect();
+        AddressTypeCounts {
+            transparent: count_transparent_names(self.address_names.keys(), &watched),
+            sapling: self.sapling_z_addresses.len(),
+            sprout: self
+                .sprout_keys
+                .as_ref()
+                .map_or(0, |keys| keys.keypairs().count()),
+            orchard_unified: count_orchard_unified(&self.unified_accounts.address_metadata),
+            watch_only: self.watch_scripts.len(),
+        }
+    }
+
+    /// The unified account an encoded address belongs to, if any: a unified
+    /// address rederived from its metadata, a Sapling address whose incoming
+    /// viewing key a unified account's UFVK derives, or the P2PKH address of
+    /// a transparent key derived under a unified account's BIP 44 path from
+    /// the wallet's mnemonic seed.
+    ///
+    /// The first call indexes every such address of the wallet, so that
+    /// later calls are a single map lookup. For one lookup,
+    /// [`Self::find_account_for_address`] avoids building the index.
+    pub fn account_for_address(&self, address: &str) -> Option<UfvkFingerprint> {
+        self.address_accounts
+            .get_or_init(|| self.unified_account_addresses().collect())
+            .get(address)
+            .copied()
+    }
+
+    /// As [`Self::account_for_address`], searching the wallet's records
+    /// without building or consulting its index.
+    pub fn find_account_for_address(&self, address: &str) -> Option<UfvkFingerprint> {
+        self.unified_account_addresses()
+            .find(|(encoded, _)| encoded == address)
+            .map(|(_, fingerprint)| fingerprint)
+    }
+
+    /// Each encoded address of the wallet that belongs to a unified account,
+    /// with the account's fingerprint.
+    fn unified_account_addresses(&self) -> impl Iterator<Item = (String, UfvkFingerprint)> + '_ {
+        let network = self.network();
+        let params = self.network_info.to_address_encoding_network();
+        let accounts = &self.unified_accounts;
+
+        let unified = accounts
+            .address_metadata
+            .iter()
+            .filter_map(move |metadata| {
+                let ufvk = accounts.full_viewing_keys.get(&metadata.key_id)?;
+                let encoded = unified_address_string(metadata, ufvk, &params).ok()?;
+                Some((encoded, metadata.key_id))
+            });
+        let sapling = self
+            .sapling_z_addresses
+            .iter()
+            .filter_map(move |(address, ivk)| {
+                let fingerprint = accounts.account_for_sapling_ivk(ivk)?;
+                Some((address.to_string(network), *fingerprint))
+            });
+        let transparent = self.keys.keypairs().filter_map(move |keypair| {
+            let metadata = keypair.metadata();
+            let account = bip44_account(metadata.hd_keypath()?)?;
+            let seed_fingerprint = encode_seed_fingerprint(metadata.seed_fp()?);
+            let (fingerprint, _) = accounts.account_metadata.iter().find(|(_, meta)| {
+                meta.zip32_account_id() == account && *meta.seed_fingerprint() == seed_fingerprint
+            })?;
+            let pk = secp256k1::PublicKey::from_slice(keypair.pubkey().as_slice()).ok()?;
+            Some((p2pkh_address_string(&pk, network), *fingerprint))
+        });
+        unified.chain(sapling).chain(transparent)
+    }
+}
+
+/// The check of [`ZcashdWallet::check_supported`], which the parser runs on
+/// the wallet's version records before parsing any other record.
+pub(crate) fn check_supported_versions(
+    client_version: ClientVersion,
+    min_version: ClientVersion,
+) -> Result<(), crate::Error> {
+    if client_version < ClientVersion::OLDEST_SUPPORTED {
+        return Err(crate::Error::UnsupportedClientVersion {
+            client_version,
+            oldest: ClientVersion::OLDEST_SUPPORTED,
+        });
+    }
+    if min_version > ClientVersion::NEWEST_SUPPORTED_FEATURE {
+        return Err(crate::Error::UnsupportedWalletFormat {
+            min_version,
+            newest: ClientVersion::NEWEST_SUPPORTED_FEATURE,
+        });
+    }
+    Ok(())
+}
+
+/// The wallet's legacy HD seed, zeroed when dropped.
+#[derive(Debug)]
+struct LegacyHdSeed(LegacySeed);
+
+impl Zeroize for LegacyHdSeed {
+    fn zeroize(&mut self) {
+        // SAFETY: `LegacySeed`, which gives no mutable access to its bytes, is
+        // a `[u8; 32]` with no pointers or drop glue, for which all zeroes is
+        // a valid value.
+        unsafe { zeroize::zeroize_flat_type(&mut self.0) }
+    }
+}
+
+impl Drop for LegacyHdSeed {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for LegacyHdSeed {}
+
+/// Whether a wallet transaction is mined in a block: zcashd records a zero
+/// `hashBlock` for a transaction that is not.
+pub(crate) fn is_confirmed(wtx: &WalletTx) -> bool {
+    wtx.hash_block() != BlockHash::from_bytes([0u8; 32])
+}
+
+/// The hardened account index of a BIP 44 keypath
+/// (`m/44'/<coin>'/<account>'/...`).
+fn bip44_account(keypath: &str) -> Option<u32> {
+    let mut parts = keypath.split('/');
+    if parts.next()? != "m" || parts.next()? != "44'" {
+        return None;
+    }
+    parts.next()?;
+    parts.next()?.strip_suffix('\'')?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::ManuallyDrop;
+
+    use zewif::Network;
+
+    use super::*;
+    use crate::zcashd_wallet::transparent::{KeyPair, Keys};
+
+    /// The bytes of the `T` at `ptr`, which may since have been dropped.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to memory still allocated for a `T`, every byte of
+    /// which is initialized.
+    unsafe fn bytes_at<T>(ptr: *const T) -> Vec<u8> {
+        unsafe { std::slice::from_raw_parts(ptr.cast::<u8>(), size_of::<T>()) }.to_vec()
+    }
+
+    #[test]
+    fn legacy_hd_seed_is_zeroed_when_dropped() {
+        let mut seed = ManuallyDrop::new(LegacyHdSeed(LegacySeed::new([7; 32])));
+        let ptr: *const LegacyHdSeed = &*seed;
+        // SAFETY: the seed is not used again, and `bytes_at` only reads the
+        // memory it occupied, which stays allocated.
+        unsafe { ManuallyDrop::drop(&mut seed) };
+        assert_eq!(unsafe { bytes_at(ptr) }, [0; 32]);
+    }
+
+    #[test]
+    fn mnemonic_is_zeroed() {
+        let mut mnemonic = MnemonicPhrase::new("abandon ".repeat(23) + "art", None);
+        let (ptr, len) = (mnemonic.phrase().as_ptr(), mnemonic.phrase().len());
+        // As it is when dropped.
+        mnemonic.zeroize();
+        assert!(mnemonic.phrase().is_empty());
+        // Zeroing the phrase keeps its allocation.
+        let bytes = unsafe { std::slice::from_raw_parts(ptr, len) };
+        assert!(bytes.iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn zeroize_secrets_clears_the_wallet() {
+        let pubkey = PubKey::from_slice(&[0x02; 33]).unwrap();
+        let keypair = KeyPair::from_decrypted_scalar(
+            pubkey.clone(),
+            &[0x42; 32],
+            KeyMetadata::new(None, None, None),
+        );
+        let mut wallet = ZcashdWalletBuilder::new()
+            .bestblock(BlockLocator::new(ClientVersion::from_integer(0), vec![]))
+            .network_info(NetworkInfo::new(Network::Regtest(Default::default())))
+            .keys(Keys::new(HashMap::from([(pubkey.clone(), keypair)])))
+            .legacy_hd_seed(LegacySeed::new([7; 32]))
+            .bip39_mnemonic(MnemonicPhrase::new("abandon ".repeat(23) + "art", None))
+            .build()
+            .unwrap();
+
+        wallet.zeroize_secrets();
+        let privkey = wallet.keys().keypair_for_pubkey(&pubkey).unwrap().privkey();
+        assert!(!privkey.as_slice().is_empty());
+        assert!(privkey.as_slice().iter().all(|b| *b == 0));
+        assert!(wallet.legacy_hd_seed().is_none());
+        assert!(wallet.bip39_mnemonic().is_none());
+        assert_eq!(wallet.sapling_keys().keypairs().count(), 0);
+    }
 }
@@ -1,6 +1,350 @@
+use crate::parser::prelude::*;
 use crate::string_parse;
-use zewif::string;
+use crate::zcashd_wallet::address_network_from_zewif;
+use zcash_address::{ConversionError, TryFromAddress, ZcashAddress, unified};
+use zcash_protocol::consensus::NetworkType;
+use zewif::{Network, string};
 
 string!(Address, "A Zcash address.");
 
 string_parse!(Address);
+
+/// Addresses are ordered by their encoded strings, compared bytewise as `str`
+/// is, so the order agrees with the string's `Eq` and `Hash` and does not
+/// depend on locale. Equal addresses in different encodings (see
+/// [`Address::normalized`]) are distinct keys, as they are for `Eq`.
+impl PartialOrd for Address {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Address {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.as_bytes().cmp(other.0.as_bytes())
+    }
+}
+
+impl Address {
+    /// Validates `addr_str` as a Zcash address encoded for `network`.
+    ///
+    /// Fails with [`ParseErrorKind::AddressDecode`] if the string is not a
+    /// Zcash address, and with [`ParseErrorKind::AddressNetworkMismatch`] if it
+    /// is encoded for a different network. As in `zcash_address`, a testnet
+    /// Sprout or transparent address is accepted on regtest, whose encodings
+    /// share testnet's prefixes.
+    pub fn from_address_string_on_network(addr_str: &str, network: &Network) -> Result<Self> {
+        let expected = address_network_from_zewif(network);
+        match ZcashAddress::try_from_encoded(addr_str)?.convert_if_network::<AnyAddress>(expected) {
+            Ok(AnyAddress) => Ok(Self::from(addr_str.to_string())),
+            Err(ConversionError::IncorrectNetwork { expected, actual }) => {
+                Err(ParseErrorKind::AddressNetworkMismatch { expected, actual }.into())
+            }
+            Err(ConversionError::Unsupported(unsupported)) => {
+                Err(ParseErrorKind::UnsupportedAddress(unsupported).into())
+            }
+            Err(ConversionError::User(never)) => match never {},
+        }
+    }
+
+    /// The canonical form of an address string as recorded by a tool other
+    /// than zcashd, or `None` if it is already canonical.
+    ///
+    /// Surrounding whitespace is trimmed, and a Bech32 or Bech32m address
+    /// (Sapling, unified or TEX) written in upper or mixed case is lowercased.
+    /// Base58 addresses (transparent and Sprout) are case-sensitive and are
+    /// left as they are: a string is only lowercased if its lowercase form
+    /// decodes, which a Base58 address's does not (its checksum covers the
+    /// case of every character).
+    pub fn normalized(&self) -> Option<Self> {
+        let trimmed = self.0.trim();
+        let lowercase = trimmed.to_lowercase();
+        let canonical =
+            if lowercase != trimmed && ZcashAddress::try_from_encoded(&lowercase).is_ok() {
+                lowercase
+            } else {
+                trimmed.to_string()
+            };
+        (canonical != self.0).then(|| Self::from(canonical))
+    }
+
+    /// The network the address is encoded for, or `None` if it is not a Zcash
+    /// address. Testnet and regtest share their Sprout and transparent
+    /// encodings, which report [`NetworkType::Test`].
+    pub fn network_type(&self) -> Option<NetworkType> {
+        let address = ZcashAddress::try_from_encoded(&self.0).ok()?;
+        address.convert::<EncodedNetwork>().ok().map(|n| n.0)
+    }
+}
+
+/// Accepts every kind of address, so that decoding checks only the network.
+struct AnyAddress;
+
+impl TryFromAddress for AnyAddress {
+    type Error = std::convert::Infallible;
+
+    fn try_from_sprout(_: NetworkType, _: [u8; 64]) -> Result<Self, ConversionError<Self::Error>> {
+        Ok(AnyAddress)
+    }
+
+    fn try_from_sapling(_: NetworkType, _: [u8; 43]) -> Result<Self, ConversionError<Self::Error>> {
+        Ok(AnyAddress)
+    }
+
+    fn try_from_unified(
+        _: NetworkType,
+        _: unified::Address,
+    ) -> Result<Self, ConversionError<Self::Error>> {
+        Ok(AnyAddress)
+    }
+
+    fn try_from_transparent_p2pkh(
+        _: NetworkType,
+        _: [u8; 20],
+    ) -> Result<Self, ConversionError<Self::Error>> {
+        Ok(AnyAddress)
+    }
+
+    fn try_from_transparent_p2sh(
+        _: NetworkType,
+        _: [u8; 20],
+    ) -> Result<Self, ConversionError<Self::Error>> {
+        Ok(AnyAddress)
+    }
+
+    fn try_from_tex(_: NetworkType, _: [u8; 20]) -> Result<Self, ConversionError<Self::Error>> {
+        Ok(AnyAddress)
+    }
+}
+
+/// The network of a decoded address, whatever its kind.
+struct EncodedNetwork(NetworkType);
+
+impl TryFromAddress for EncodedNetwork {
+    type Error = std::convert::Infallible;
+
+    fn try_from_sprout(
+        net: NetworkType,
+        _: [u8; 64],
+    ) -> Result<Self, ConversionError<Self::Error>> {
+        Ok(EncodedNetwork(net))
+    }
+
+    fn try_from_sapling(
+        net: NetworkType,
+        _: [u8; 43],
+    ) -> Result<Self, ConversionError<Self::Error>> {
+        Ok(EncodedNetwork(net))
+    }
+
+    fn try_from_unified(
+        net: NetworkType,
+        _: unified::Address,
+    ) -> Result<Self, ConversionError<Self::Error>> {
+        Ok(EncodedNetwork(net))
+    }
+
+    fn try_from_transparent_p2pkh(
+        net: NetworkType,
+        _: [u8; 20],
+    ) -> Result<Self, ConversionError<Self::Error>> {
+        Ok(EncodedNetwork(net))
+    }
+
+    fn try_from_transparent_p2sh(
+        net: NetworkType,
+        _: [u8; 20],
+    ) -> Result<Self, ConversionError<Self::Error>> {
+        Ok(EncodedNetwork(net))
+    }
+
+    fn try_from_tex(net: NetworkType, _: [u8; 20]) -> Result<Self, ConversionError<Self::Error>> {
+        Ok(EncodedNetwork(net))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zcash_address::{ToAddress, ZcashAddress};
+    use zcash_protocol::consensus::NetworkType;
+    use zewif::{Network, RegtestParams};
+
+    use super::Address;
+    use crate::parser::prelude::ParseErrorKind;
+
+    const MAINNET_P2PKH: &str = "t1Hsc1LR8yKnbbe3twRp88p6vFfC5t7DLbs";
+    const TESTNET_P2PKH: &str = "tm9iMLAuYMzJ6jtFLcA7rzUmfreGuKvr7Ma";
+
+    #[test]
+    fn accepts_an_address_on_its_own_network() {
+        let addr = Address::from_address_string_on_network(MAINNET_P2PKH, &Network::Mainnet)
+            .expect("mainnet address on mainnet");
+        assert_eq!(String::from(addr), MAINNET_P2PKH);
+        assert!(Address::from_address_string_on_network(TESTNET_P2PKH, &Network::Testnet).is_ok());
+    }
+
+    #[test]
+    fn rejects_a_mainnet_address_on_testnet() {
+        let err =
+            Address::from_address_string_on_network(MAINNET_P2PKH, &Network::Testnet).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ParseErrorKind::AddressNetworkMismatch {
+                expected: NetworkType::Test,
+                actual: NetworkType::Main,
+            }
+        ));
+    }
+
+    #[test]
+    fn rejects_a_testnet_address_on_mainnet() {
+        let err =
+            Address::from_address_string_on_network(TESTNET_P2PKH, &Network::Mainnet).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ParseErrorKind::AddressNetworkMismatch {
+                expected: NetworkType::Main,
+                actual: NetworkType::Test,
+            }
+        ));
+    }
+
+    #[test]
+    fn accepts_a_testnet_transparent_address_on_regtest() {
+        let regtest = Network::Regtest(RegtestParams::default());
+        assert!(Address::from_address_string_on_network(TESTNET_P2PKH, &regtest).is_ok());
+    }
+
+    #[test]
+    fn normalizes_padding_and_mixed_case_bech32() {
+        let sapling = ZcashAddress::from_sapling(NetworkType::Main, [3; 43]).encode();
+
+        let padded = Address::from(format!(" {sapling}\t\n"));
+        assert_eq!(padded.normalized(), Some(Address::from(sapling.as_str())));
+
+        let mixed_case: String = sapling
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                if i % 2 == 0 {
+                    c.to_ascii_uppercase()
+                } else {
+                    c
+                }
+            })
+            .collect();
+        let mixed_case = Address::from(mixed_case);
+        assert_eq!(
+            mixed_case.normalized(),
+            Some(Address::from(sapling.as_str()))
+        );
+
+        assert_eq!(Address::from(sapling.as_str()).normalized(), None);
+    }
+
+    #[test]
+    fn leaves_base58_case_alone() {
+        assert_eq!(Address::from(MAINNET_P2PKH).normalized(), None);
+        assert_eq!(
+            Address::from(format!("{MAINNET_P2PKH} ")).normalized(),
+            Some(Address::from(MAINNET_P2PKH))
+        );
+    }
+
+    /// The order is total and agrees with `Eq` and `Hash` over addresses of
+    /// every kind, and is the bytewise order of their encodings.
+    #[test]
+    fn order_agrees_with_eq_and_hash() {
+        use std::{
+            cmp::Ordering,
+            collections::hash_map::DefaultHasher,
+            hash::{Hash, Hasher},
+        };
+
+        let hash = |address: &Address| {
+            let mut hasher = DefaultHasher::new();
+            address.hash(&mut hasher);
+            hasher.finish()
+        };
+        let mut addresses = vec![
+            Address::from(MAINNET_P2PKH),
+            Address::from(TESTNET_P2PKH),
+            Address::from(format!("{MAINNET_P2PKH} ")),
+            Address::from(""),
+        ];
+        for byte in [0, 3, 0x7f, 0xff] {
+            for network in [NetworkType::Main, NetworkType::Test, NetworkType::Regtest] {
+                addresses.push(
+                    ZcashAddress::from_sapling(network, [byte; 43])
+                        .encode()
+                        .into(),
+                );
+            }
+            // Testnet and regtest share transparent encodings, so only one of
+            // them yields a distinct address.
+            for network in [NetworkType::Main, NetworkType::Test] {
+                addresses.push(
+                    ZcashAddress::from_transparent_p2sh(network, [byte; 20])
+                        .encode()
+                        .into(),
+                );
+            }
+        }
+
+        for a in &addresses {
+            for b in &addresses {
+                assert_eq!(a.cmp(b) == Ordering::Equal, a == b);
+                assert_eq!(a.cmp(b), b.cmp(a).reverse());
+                assert_eq!(a.partial_cmp(b), Some(a.cmp(b)));
+                assert_eq!(
+                    a.cmp(b),
+                    String::from(a).as_bytes().cmp(String::from(b).as_bytes())
+                );
+                if a == b {
+                    assert_eq!(hash(a), hash(b));
+                }
+                for c in &addresses {
+                    if a <= b && b <= c {
+                        assert!(a <= c);
+                    }
+                }
+            }
+        }
+
+        let mut sorted = addresses.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sorted.len(), addresses.len());
+        assert_eq!(sorted[0], Address::from(""));
+        assert!(
+            sorted
+                .windows(2)
+                .all(|w| String::from(&w[0]) < String::from(&w[1]))
+        );
+    }
+
+    #[test]
+    fn rejects_a_string_that_is_not_an_address() {
+        let err = Address::from_address_string_on_network("not an address", &Network::Mainnet)
+            .unwrap_err();
+        assert!(matches!(err.kind(), ParseErrorKind::AddressDecode(_)));
+    }
+
+    #[test]
+    fn reports_the_encoded_network() {
+        let sapling = ZcashAddress::from_sapling(NetworkType::Regtest, [3; 43]).encode();
+        assert_eq!(
+            Address::from(MAINNET_P2PKH).network_type(),
+            Some(NetworkType::Main)
+        );
+        assert_eq!(
+            Address::from(TESTNET_P2PKH).network_type(),
+            Some(NetworkType::Test)
+        );
+        assert_eq!(
+            Address::from(sapling).network_type(),
+            Some(NetworkType::Regtest)
+        );
+        assert_eq!(Address::from("not an address").network_type(), None);
+    }
+}
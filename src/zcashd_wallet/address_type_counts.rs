@@ -0,0 +1,158 @@
+use std::{collections::HashSet, fmt};
+
+use zcash_address::ZcashAddress;
+
+use crate::zcashd_wallet::{Address, ReceiverType, UnifiedAddressMetadata};
+
+/// The number of addresses a wallet holds for each protocol, for reporting on
+/// a wallet before it is migrated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AddressTypeCounts {
+    /// Named transparent addresses.
+    pub transparent: usize,
+    /// Sapling payment addresses.
+    pub sapling: usize,
+    /// Sprout payment addresses.
+    pub sprout: usize,
+    /// Unified addresses with an Orchard receiver.
+    pub orchard_unified: usize,
+    /// Watch-only scripts imported via `importaddress` or `importpubkey`.
+    /// Their address book entries are not counted in [`Self::transparent`].
+    pub watch_only: usize,
+}
+
+impl AddressTypeCounts {
+    /// The total number of addresses counted, each once.
+    pub fn total(&self) -> usize {
+        self.transparent + self.sapling + self.sprout + self.orchard_unified + self.watch_only
+    }
+}
+
+impl fmt::Display for AddressTypeCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} addresses:", self.total())?;
+        writeln!(f, "  transparent:     {}", self.transparent)?;
+        writeln!(f, "  sapling:         {}", self.sapling)?;
+        writeln!(f, "  sprout:          {}", self.sprout)?;
+        writeln!(f, "  orchard unified: {}", self.orchard_unified)?;
+        write!(f, "  watch-only:      {}", self.watch_only)
+    }
+}
+
+/// Count the transparent addresses among the keys of the wallet's address
+/// book. zcashd also names the Sapling and Sprout addresses it generates, so
+/// those, and any entry that does not decode, are skipped. So are the
+/// `watched` addresses: `importaddress` names the address it imports, and it
+/// is counted as watch-only instead.
+pub(crate) fn count_transparent_names<'a>(
+    names: impl IntoIterator<Item = &'a Address>,
+    watched: &HashSet<String>,
+) -> usize {
+    names
+        .into_iter()
+        .map(String::from)
+        .filter(|address| {
+            !watched.contains(address)
+                && ZcashAddress::try_from_encoded(address)
+                    .is_ok_and(|address| address.is_transparent_only())
+        })
+        .count()
+}
+
+/// Count the unified addresses with an Orchard receiver.
+pub(crate) fn count_orchard_unified(address_metadata: &[UnifiedAddressMetadata]) -> usize {
+    address_metadata
+        .iter()
+        .filter(|metadata| metadata.receiver_types.contains(&ReceiverType::Orchard))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use zcash_address::ToAddress;
+    use zcash_protocol::consensus::NetworkType;
+
+    use super::*;
+    use crate::zcashd_wallet::UfvkFingerprint;
+
+    fn name(address: ZcashAddress) -> Address {
+        Address::from(address.encode())
+    }
+
+    fn unified(receiver_types: &[ReceiverType]) -> UnifiedAddressMetadata {
+        UnifiedAddressMetadata {
+            key_id: UfvkFingerprint::new([0x11; 32]),
+            diversifier_index: [0; 11],
+            receiver_types: receiver_types.iter().copied().collect::<HashSet<_>>(),
+        }
+    }
+
+    #[test]
+    fn only_transparent_names_are_counted() {
+        let names = [
+            name(ZcashAddress::from_transparent_p2pkh(
+                NetworkType::Main,
+                [1; 20],
+            )),
+            name(ZcashAddress::from_transparent_p2sh(
+                NetworkType::Main,
+                [2; 20],
+            )),
+            name(ZcashAddress::from_sapling(NetworkType::Main, [3; 43])),
+            name(ZcashAddress::from_sprout(NetworkType::Main, [4; 64])),
+            Address::from("not an address"),
+        ];
+        assert_eq!(count_transparent_names(&names, &HashSet::new()), 2);
+    }
+
+    #[test]
+    fn watched_names_are_not_counted_as_transparent() {
+        let watched = name(ZcashAddress::from_transparent_p2pkh(
+            NetworkType::Main,
+            [1; 20],
+        ));
+        let names = [
+            watched.clone(),
+            name(ZcashAddress::from_transparent_p2pkh(
+                NetworkType::Main,
+                [2; 20],
+            )),
+        ];
+        let watched = HashSet::from([String::from(&watched)]);
+        assert_eq!(count_transparent_names(&names, &watched), 1);
+    }
+
+    #[test]
+    fn only_unified_addresses_with_orchard_are_counted() {
+        let metadata = [
+            unified(&[ReceiverType::Orchard]),
+            unified(&[
+                ReceiverType::Orchard,
+                ReceiverType::Sapling,
+                ReceiverType::P2PKH,
+            ]),
+            unified(&[ReceiverType::Sapling, ReceiverType::P2PKH]),
+        ];
+        assert_eq!(count_orchard_unified(&metadata), 2);
+    }
+
+    #[test]
+    fn display_lists_each_protocol() {
+        let counts = AddressTypeCounts {
+            transparent: 3,
+            sapling: 2,
+            sprout: 1,
+            orchard_unified: 4,
+            watch_only: 0,
+        };
+        assert_eq!(
+            counts.to_string(),
+            "10 addresses:\n\
+             \x20 transparent:     3\n\
+             \x20 sapling:         2\n\
+             \x20 sprout:          1\n\
+             \x20 orchard unified: 4\n\
+             \x20 watch-only:      0"
+        );
+    }
+}
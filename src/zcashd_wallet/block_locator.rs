@@ -1,3 +1,4 @@
+use zewif::BlockHash;
 
 use crate::{
     parse,
@@ -6,6 +7,11 @@ use crate::{
 };
 
 /// Vector of block hashes
+///
+/// zcashd records its chain position (`bestblock`, `bestblock_nomerkle`) as a
+/// block locator built by `CChain::GetLocator`: the tip first, then the
+/// eleven blocks below it, then blocks at doubling steps back, ending with the
+/// genesis block. The locator holds only hashes; see [`BlockLocator::height`].
 #[derive(Debug, Clone, PartialEq)]
 pub struct BlockLocator {
     version: ClientVersion,
@@ -13,6 +19,10 @@ pub struct BlockLocator {
 }
 
 impl BlockLocator {
+    pub fn new(version: ClientVersion, blocks: Vec<u256>) -> Self {
+        Self { version, blocks }
+    }
+
     pub fn version(&self) -> ClientVersion {
         self.version
     }
@@ -20,6 +30,53 @@ impl BlockLocator {
     pub fn blocks(&self) -> &[u256] {
         &self.blocks
     }
+
+    /// The locator's block hashes, tip first.
+    pub fn block_hashes(&self) -> impl Iterator<Item = BlockHash> + '_ {
+        self.blocks
+            .iter()
+            .map(|hash| BlockHash::from_bytes(hash.into_bytes()))
+    }
+
+    /// The hash of the tip block, or `None` for an empty locator (a freshly
+    /// initialized wallet).
+    pub fn tip(&self) -> Option<BlockHash> {
+        self.block_hashes().next()
+    }
+
+    /// Whether the tip is the genesis block: `CChain::GetLocator` always ends
+    /// with the genesis block, so a locator of a single hash is one built at
+    /// height 0.
+    pub fn is_at_genesis(&self) -> bool {
+        self.blocks.len() == 1
+    }
+
+    /// The height of the tip block, from the first of the locator's blocks
+    /// whose height `known_height` supplies.
+    ///
+    /// Each entry's depth below the tip follows from its position in the
+    /// locator, except for the final genesis entry, whose step is clamped and
+    /// which is therefore not used (unless it is the tip itself).
+    pub fn height(&self, known_height: impl Fn(&BlockHash) -> Option<u32>) -> Option<u32> {
+        let last = self.blocks.len().saturating_sub(1);
+        self.block_hashes()
+            .enumerate()
+            .filter(|(index, _)| *index == 0 || *index < last)
+            .find_map(|(index, hash)| known_height(&hash)?.checked_add(locator_depth(index)?))
+    }
+}
+
+/// The depth below the tip of the block at `index` in a locator built by
+/// `CChain::GetLocator`, which steps back one block at a time until it holds
+/// eleven hashes and doubles the step after each hash from then on. `None`
+/// if the depth does not fit a `u32`.
+fn locator_depth(index: usize) -> Option<u32> {
+    let index = u32::try_from(index).ok()?;
+    if index <= 11 {
+        Some(index)
+    } else {
+        2u32.checked_pow(index - 10)?.checked_add(9)
+    }
 }
 
 impl Parse for BlockLocator {
@@ -30,3 +87,96 @@ impl Parse for BlockLocator {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(n: u8) -> u256 {
+        u256::try_from(&[n; 32]).unwrap()
+    }
+
+    /// A locator of `len` distinct hashes, tip first.
+    fn locator(len: u8) -> BlockLocator {
+        BlockLocator::new(
+            ClientVersion::from_integer(5_000_000),
+            (0..len).map(hash).collect(),
+        )
+    }
+
+    #[test]
+    fn tip_and_hashes_are_exposed() {
+        let three = locator(3);
+        assert_eq!(three.tip(), Some(BlockHash::from_bytes([0; 32])));
+        let hashes: Vec<_> = three.block_hashes().collect();
+        assert_eq!(hashes.len(), 3);
+        assert_eq!(hashes[2], BlockHash::from_bytes([2; 32]));
+        assert_eq!(locator(0).tip(), None);
+    }
+
+    #[test]
+    fn locators_are_parsed() {
+        // A wallet that has not yet seen a block records an empty locator.
+        let mut bytes = 6_020_050i32.to_le_bytes().to_vec();
+        bytes.push(0);
+        let empty = BlockLocator::parse_buf(&bytes, false).unwrap();
+        assert_eq!(empty.version(), ClientVersion::from_integer(6_020_050));
+        assert!(empty.blocks().is_empty());
+        assert!(!empty.is_at_genesis());
+
+        bytes.pop();
+        bytes.push(2);
+        bytes.extend_from_slice(&[1; 32]);
+        bytes.extend_from_slice(&[0; 32]);
+        let locator = BlockLocator::parse_buf(&bytes, false).unwrap();
+        assert_eq!(locator.blocks(), [hash(1), hash(0)]);
+        assert_eq!(locator.tip(), Some(BlockHash::from_bytes([1; 32])));
+        assert!(!locator.is_at_genesis());
+
+        // A hash list shorter than its count is an error.
+        assert!(BlockLocator::parse_buf(&&bytes[..bytes.len() - 1], false).is_err());
+    }
+
+    #[test]
+    fn a_single_hash_locator_is_at_genesis() {
+        assert!(locator(1).is_at_genesis());
+        assert!(!locator(2).is_at_genesis());
+        assert!(!locator(0).is_at_genesis());
+    }
+
+    #[test]
+    fn depths_follow_get_locator() {
+        // A chain at height 1000: GetLocator steps 1000, 999, ..., 989, then
+        // 987, 983, 975, 959, ...
+        let depths: Vec<_> = (0..16).map(|i| locator_depth(i).unwrap()).collect();
+        assert_eq!(
+            depths,
+            [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 17, 25, 41]
+        );
+        assert_eq!(locator_depth(100), None);
+    }
+
+    #[test]
+    fn height_is_found_from_the_tip() {
+        let locator = locator(20);
+        let tip = locator.tip().unwrap();
+        let height = locator.height(|h| (*h == tip).then_some(1000));
+        assert_eq!(height, Some(1000));
+    }
+
+    #[test]
+    fn height_is_found_from_a_deeper_block() {
+        let locator = locator(20);
+        // The 14th entry is 17 blocks below the tip.
+        let known = BlockHash::from_bytes([13; 32]);
+        assert_eq!(locator.height(|h| (*h == known).then_some(983)), Some(1000));
+        assert_eq!(locator.height(|_| None), None);
+    }
+
+    #[test]
+    fn genesis_entry_is_not_used() {
+        let locator = locator(20);
+        let genesis = BlockHash::from_bytes([19; 32]);
+        assert_eq!(locator.height(|h| (*h == genesis).then_some(0)), None);
+    }
+}
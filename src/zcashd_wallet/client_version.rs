@@ -53,6 +53,15 @@ impl std::hash::Hash for ClientVersion {
 }
 
 impl ClientVersion {
+    /// The oldest zcashd release whose wallets this crate parses, 3.0.0. The
+    /// parser relies on records that older wallets lack.
+    pub const OLDEST_SUPPORTED: ClientVersion = ClientVersion::from_integer(3_000_050);
+
+    /// The newest wallet format a wallet's `minversion` record may require.
+    /// zcashd records a wallet feature level there rather than a release, and
+    /// has not raised it past `FEATURE_LATEST` (60000).
+    pub const NEWEST_SUPPORTED_FEATURE: ClientVersion = ClientVersion::from_integer(60_000);
+
     /// Parses a combined version integer into its components.
     ///
     /// Per zcashd's `clientversion.h`, the version is a 32-bit integer
@@ -67,7 +76,7 @@ impl ClientVersion {
     /// # Returns
     ///
     /// A `ClientVersion` struct with separated version components.
-    pub fn from_integer(version: u32) -> Self {
+    pub const fn from_integer(version: u32) -> Self {
         let major = version / 1_000_000;
         let remainder = version % 1_000_000;
 
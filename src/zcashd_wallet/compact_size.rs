@@ -80,6 +80,26 @@ pub fn parse_compact_size(p: &mut Parser) -> Result<usize> {
     }
 }
 
+/// Appends `size` to `out` in the canonical compact size encoding, the
+/// inverse of [`parse_compact_size`].
+pub fn write_compact_size(out: &mut Vec<u8>, size: usize) {
+    match size {
+        0..=252 => out.push(size as u8),
+        253..=0xffff => {
+            out.push(0xfd);
+            out.extend_from_slice(&(size as u16).to_le_bytes());
+        }
+        0x10000..=0xffff_ffff => {
+            out.push(0xfe);
+            out.extend_from_slice(&(size as u32).to_le_bytes());
+        }
+        _ => {
+            out.push(0xff);
+            out.extend_from_slice(&(size as u64).to_le_bytes());
+        }
+    }
+}
+
 /// A Bitcoin/Zcash-style variable-length integer used for size encoding in binary formats.
 ///
 /// `CompactSize` is a wrapper around a `usize` that represents a value encoded in the
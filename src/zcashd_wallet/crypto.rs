@@ -184,6 +184,54 @@ fn aes256_cbc_decrypt(
     Ok(buf)
 }
 
+/// The encrypting counterparts of [`decrypt_master_key`] and
+/// [`decrypt_secret`], for building encrypted records in tests.
+#[cfg(test)]
+pub(crate) mod test_encryption {
+    use cbc::cipher::{BlockEncryptMut, KeyIvInit, block_padding::Pkcs7};
+
+    use super::*;
+
+    type Aes256CbcEnc = cbc::Encryptor<Aes256>;
+
+    /// An `mkey` record encrypting `master_key` under `passphrase`.
+    pub(crate) fn encrypt_master_key(
+        master_key: &[u8; KEY_SIZE],
+        passphrase: &[u8],
+        salt: [u8; SALT_SIZE],
+        derive_iterations: u32,
+    ) -> MasterKeyParams {
+        let (key, iv) = bytes_to_key_sha512(&salt, passphrase, derive_iterations);
+        MasterKeyParams {
+            encrypted_key: aes256_cbc_encrypt(&key, &iv[..], master_key),
+            salt: salt.to_vec(),
+            derivation_method: 0,
+            derive_iterations,
+        }
+    }
+
+    /// Encrypt a secret under the wallet master key, as `zcashd`'s
+    /// `EncryptSecret` does.
+    pub(crate) fn encrypt_secret(
+        master_key: &[u8; KEY_SIZE],
+        plaintext: &[u8],
+        iv_source: &[u8],
+    ) -> Vec<u8> {
+        aes256_cbc_encrypt(master_key, &iv_source[..IV_SIZE], plaintext)
+    }
+
+    fn aes256_cbc_encrypt(key: &[u8; KEY_SIZE], iv: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        // PKCS#7 always adds between 1 and 16 bytes of padding.
+        let mut buf = plaintext.to_vec();
+        buf.resize((plaintext.len() / IV_SIZE + 1) * IV_SIZE, 0);
+        Aes256CbcEnc::new_from_slices(key, iv)
+            .unwrap()
+            .encrypt_padded_mut::<Pkcs7>(&mut buf, plaintext.len())
+            .unwrap();
+        buf
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -316,6 +364,15 @@ mod tests {
         assert_eq!(plaintext.as_slice(), &SECRET_PLAINTEXT);
     }
 
+    #[test]
+    fn test_encryption_round_trips() {
+        let params = test_encryption::encrypt_master_key(&MASTER_KEY, PASSPHRASE, SALT, ITERATIONS);
+        assert_eq!(params.encrypted_key, CRYPTED_MASTER);
+        let ciphertext =
+            test_encryption::encrypt_secret(&MASTER_KEY, &SECRET_PLAINTEXT, &IV_SOURCE32);
+        assert_eq!(ciphertext, CRYPTED_SECRET);
+    }
+
     #[test]
     fn short_iv_source_is_rejected() {
         assert!(matches!(
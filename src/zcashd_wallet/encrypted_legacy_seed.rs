@@ -0,0 +1,59 @@
+use secrecy::SecretVec;
+use zewif::{LegacySeed, SeedFingerprint};
+
+use crate::{
+    Error,
+    zcashd_wallet::{DecryptionError, MasterKeyParams, decrypt_master_key, decrypt_secret},
+};
+
+/// A wallet's legacy HD seed as an encrypted wallet stores it, in a `chdseed`
+/// record: the AES-256-CBC ciphertext, under the wallet master key, of the
+/// raw seed bytes a plaintext wallet stores in its `hdseed` record.
+///
+/// zcashd 5.0.0 stopped writing `chdseed` records, but wallets encrypted
+/// before upgrading still carry one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncryptedLegacySeed {
+    /// The seed's ZIP 32 fingerprint, which keys the record.
+    pub fingerprint: SeedFingerprint,
+    /// The ciphertext of the seed.
+    pub ciphertext: Vec<u8>,
+    /// The AES IV: the first 16 bytes of the seed fingerprint.
+    pub iv: [u8; 16],
+}
+
+impl EncryptedLegacySeed {
+    /// Decrypt the seed under the wallet master key.
+    pub fn decrypt(&self, master_key: &[u8; 32]) -> Result<LegacySeed, Error> {
+        let plaintext = decrypt_secret(master_key, &self.ciphertext, &self.iv)?;
+        LegacySeed::from_vec(plaintext.to_vec()).map_err(|_| Error::InvalidLegacySeedLength)
+    }
+
+    /// Decrypt the seed with the wallet passphrase, trying the master key of
+    /// each of the wallet's `mkey` records.
+    ///
+    /// Fails with [`Error::WrongWalletPassphrase`] if no master key decrypts
+    /// with the passphrase and then decrypts the seed.
+    pub fn decrypt_with_passphrase(
+        &self,
+        master_keys: &[MasterKeyParams],
+        passphrase: &SecretVec<u8>,
+    ) -> Result<LegacySeed, Error> {
+        for params in master_keys {
+            match decrypt_master_key(params, passphrase) {
+                Ok(master_key) => {
+                    if let Ok(seed) = self.decrypt(&master_key) {
+                        return Ok(seed);
+                    }
+                }
+                // A method we cannot process is a hard error, not a wrong
+                // passphrase.
+                Err(e @ DecryptionError::UnsupportedDerivationMethod(_)) => {
+                    return Err(Error::Decryption(e));
+                }
+                Err(_) => {}
+            }
+        }
+        Err(Error::WrongWalletPassphrase)
+    }
+}
@@ -0,0 +1,112 @@
+use secrecy::SecretVec;
+
+use crate::{
+    Error, parse,
+    zcashd_wallet::{
+        DecryptionError, MasterKeyParams, MnemonicPhrase, decrypt_master_key, decrypt_secret,
+    },
+};
+
+/// A wallet's BIP-39 mnemonic as an encrypted wallet stores it, in a
+/// `cmnemonicphrase` record: the AES-256-CBC ciphertext, under the wallet
+/// master key, of the serialized `MnemonicSeed` a plaintext wallet stores in
+/// its `mnemonicphrase` record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncryptedMnemonic {
+    /// The ciphertext of the serialized mnemonic.
+    pub ciphertext: Vec<u8>,
+    /// The AES IV: the first 16 bytes of the seed's ZIP 32 fingerprint, which
+    /// keys the record.
+    pub iv: [u8; 16],
+}
+
+impl EncryptedMnemonic {
+    /// Decrypt the mnemonic under the wallet master key.
+    pub fn decrypt(&self, master_key: &[u8; 32]) -> Result<MnemonicPhrase, Error> {
+        let plaintext = decrypt_secret(master_key, &self.ciphertext, &self.iv)?;
+        Ok(parse!(
+            buf = &plaintext.as_slice(),
+            MnemonicPhrase,
+            "mnemonic phrase"
+        )?)
+    }
+
+    /// Decrypt the mnemonic with the wallet passphrase, trying the master key
+    /// of each of the wallet's `mkey` records.
+    ///
+    /// Fails with [`Error::WrongWalletPassphrase`] if no master key decrypts
+    /// with the passphrase and then decrypts the mnemonic.
+    pub fn decrypt_with_passphrase(
+        &self,
+        master_keys: &[MasterKeyParams],
+        passphrase: &SecretVec<u8>,
+    ) -> Result<MnemonicPhrase, Error> {
+        for params in master_keys {
+            match decrypt_master_key(params, passphrase) {
+                Ok(master_key) => {
+                    if let Ok(mnemonic) = self.decrypt(&master_key) {
+                        return Ok(mnemonic);
+                    }
+                }
+                // A method we cannot process is a hard error, not a wrong
+                // passphrase.
+                Err(e @ DecryptionError::UnsupportedDerivationMethod(_)) => {
+                    return Err(Error::Decryption(e));
+                }
+                Err(_) => {}
+            }
+        }
+        Err(Error::WrongWalletPassphrase)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zewif::MnemonicLanguage;
+
+    use super::*;
+    use crate::zcashd_wallet::{
+        test_encryption::{encrypt_master_key, encrypt_secret},
+        write_compact_size,
+    };
+
+    const PASSPHRASE: &[u8] = b"correct horse battery staple";
+    const MASTER_KEY: [u8; 32] = [0x42; 32];
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    /// The `cmnemonicphrase` record for `PHRASE` under `MASTER_KEY`.
+    fn encrypted_mnemonic() -> EncryptedMnemonic {
+        // A serialized `MnemonicSeed`: the language (English), then the phrase.
+        let mut plaintext = 0u32.to_le_bytes().to_vec();
+        write_compact_size(&mut plaintext, PHRASE.len());
+        plaintext.extend_from_slice(PHRASE.as_bytes());
+
+        let fingerprint = [0x5e; 32];
+        let mut iv = [0u8; 16];
+        iv.copy_from_slice(&fingerprint[..16]);
+        EncryptedMnemonic {
+            ciphertext: encrypt_secret(&MASTER_KEY, &plaintext, &fingerprint),
+            iv,
+        }
+    }
+
+    fn master_keys() -> Vec<MasterKeyParams> {
+        vec![encrypt_master_key(&MASTER_KEY, PASSPHRASE, [0x07; 8], 25)]
+    }
+
+    #[test]
+    fn mnemonic_decrypts_with_the_passphrase() {
+        let mnemonic = encrypted_mnemonic()
+            .decrypt_with_passphrase(&master_keys(), &SecretVec::new(PASSPHRASE.to_vec()))
+            .unwrap();
+        assert_eq!(mnemonic.phrase(), PHRASE);
+        assert_eq!(mnemonic.language(), Some(&MnemonicLanguage::English));
+    }
+
+    #[test]
+    fn wrong_passphrase_is_rejected() {
+        let result = encrypted_mnemonic()
+            .decrypt_with_passphrase(&master_keys(), &SecretVec::new(b"wrong".to_vec()));
+        assert!(matches!(result, Err(Error::WrongWalletPassphrase)));
+    }
+}
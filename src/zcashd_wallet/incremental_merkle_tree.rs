@@ -161,6 +161,21 @@ impl IncrementalMerkleTree {
     pub fn push_parent(&mut self, parent: Option<u256>) {
         self.parents.push(parent);
     }
+
+    /// Checks that the tree fits a tree of the given depth, as zcashd's
+    /// `IncrementalMerkleTree::wfcheck` does: the leaves occupy the bottom
+    /// level, so there can be at most `depth - 1` parents above them.
+    pub(crate) fn check_depth(&self, depth: usize) -> Result<()> {
+        if self.parents.len() >= depth {
+            return Err(ParseErrorKind::ExceedsTreeDepth {
+                field: "tree parents",
+                len: self.parents.len(),
+                depth,
+            }
+            .into());
+        }
+        Ok(())
+    }
 }
 
 /// Default implementation creates an empty incremental Merkle tree
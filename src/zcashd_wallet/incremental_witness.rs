@@ -122,12 +122,72 @@ impl<const DEPTH: usize, Hash> IncrementalWitness<DEPTH, Hash> {
     }
 }
 
-/// Implementation of the Parse trait for binary deserialization
+/// Implementation of the Parse trait for binary deserialization.
+///
+/// Rejects a witness whose tree, cursor or filled hashes hold more nodes than
+/// a tree of depth `DEPTH` can, rather than accepting an authentication path
+/// no note in the protocol's tree could have.
 impl<const DEPTH: usize, Hash: Parse> Parse for IncrementalWitness<DEPTH, Hash> {
     fn parse(p: &mut Parser) -> Result<Self> {
-        let tree = parse!(p, "tree")?;
-        let filled = parse!(p, "filled")?;
-        let cursor = parse!(p, "cursor")?;
+        let tree: IncrementalMerkleTree = parse!(p, "tree")?;
+        tree.check_depth(DEPTH).with_frame("tree")?;
+        let filled: Vec<Hash> = parse!(p, "filled")?;
+        if filled.len() > DEPTH {
+            return Err(ParseErrorKind::ExceedsTreeDepth {
+                field: "filled",
+                len: filled.len(),
+                depth: DEPTH,
+            }
+            .into());
+        }
+        let cursor: Option<IncrementalMerkleTree> = parse!(p, "cursor")?;
+        if let Some(cursor) = &cursor {
+            cursor.check_depth(DEPTH).with_frame("cursor")?;
+        }
         Ok(Self::with_fields(tree, filled, cursor))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A serialized tree with a left leaf and `parents` empty parents.
+    fn tree_bytes(parents: u8) -> Vec<u8> {
+        let mut bytes = vec![0x01];
+        bytes.extend_from_slice(&[0x11; 32]);
+        bytes.push(0x00);
+        bytes.push(parents);
+        bytes.extend(std::iter::repeat_n(0x00, parents as usize));
+        bytes
+    }
+
+    /// A serialized witness over `tree_bytes(parents)` with `filled` filled
+    /// hashes and no cursor.
+    fn witness_bytes(parents: u8, filled: u8) -> Vec<u8> {
+        let mut bytes = tree_bytes(parents);
+        bytes.push(filled);
+        bytes.extend(std::iter::repeat_n(0x22, 32 * filled as usize));
+        bytes.push(0x00);
+        bytes
+    }
+
+    #[test]
+    fn witness_within_depth_parses() {
+        let witness =
+            parse!(buf = &witness_bytes(3, 4), IncrementalWitness<4, [u8; 32]>, "witness").unwrap();
+        assert_eq!(witness.tree().parents().len(), 3);
+        assert_eq!(witness.filled().len(), 4);
+    }
+
+    #[test]
+    fn over_long_paths_are_rejected() {
+        for bytes in [witness_bytes(4, 0), witness_bytes(0, 5)] {
+            let err = parse!(buf = &bytes, IncrementalWitness<4, [u8; 32]>, "witness").unwrap_err();
+            assert!(matches!(
+                err.kind(),
+                ParseErrorKind::ExceedsTreeDepth { depth: 4, .. }
+            ));
+        }
+    }
+}
@@ -32,6 +32,21 @@ impl std::fmt::Debug for KeyMetadata {
 }
 
 impl KeyMetadata {
+    /// Metadata for a key known other than from a `keymeta` record, in the
+    /// current record version.
+    pub(crate) fn new(
+        create_time: Option<SecondsSinceEpoch>,
+        hd_keypath: Option<String>,
+        seed_fp: Option<[u8; 32]>,
+    ) -> Self {
+        Self {
+            version: VERSION_WITH_HDDATA,
+            create_time,
+            hd_keypath,
+            seed_fp,
+        }
+    }
+
     pub fn version(&self) -> i32 {
         self.version
     }
@@ -40,6 +55,10 @@ impl KeyMetadata {
         self.create_time
     }
 
+    pub(crate) fn set_create_time(&mut self, create_time: SecondsSinceEpoch) {
+        self.create_time = Some(create_time);
+    }
+
     pub fn hd_keypath(&self) -> Option<&String> {
         self.hd_keypath.as_ref()
     }
@@ -6,6 +6,11 @@ use crate::{
     zcashd_wallet::{SecondsSinceEpoch, encode_seed_fingerprint},
 };
 
+/// zcashd's `CHDChain`: the mnemonic seed's fingerprint and the counters of
+/// the keys derived from it.
+///
+/// zcashd has only ever written version 1 of this record, which serializes
+/// every field; later versions are rejected rather than misread.
 #[derive(Clone, PartialEq)]
 pub struct MnemonicHDChain {
     version: i32,
@@ -46,6 +51,9 @@ impl std::fmt::Debug for MnemonicHDChain {
 }
 
 impl MnemonicHDChain {
+    /// The only record version zcashd writes.
+    pub const VERSION: i32 = 1;
+
     pub fn version(&self) -> i32 {
         self.version
     }
@@ -62,14 +70,19 @@ impl MnemonicHDChain {
         self.account_counter
     }
 
+    /// The number of transparent keys derived on the external chain of the
+    /// legacy account, and so the index the next one will take.
     pub fn legacy_tkey_external_counter(&self) -> u32 {
         self.legacy_tkey_external_counter
     }
 
+    /// The number of transparent keys derived on the internal (change) chain
+    /// of the legacy account.
     pub fn legacy_tkey_internal_counter(&self) -> u32 {
         self.legacy_tkey_internal_counter
     }
 
+    /// The number of Sapling keys derived in the legacy account.
     pub fn legacy_sapling_key_counter(&self) -> u32 {
         self.legacy_sapling_key_counter
     }
@@ -81,8 +94,16 @@ impl MnemonicHDChain {
 
 impl Parse for MnemonicHDChain {
     fn parse(p: &mut Parser) -> Result<Self> {
+        let version = parse!(p, i32, "version")?;
+        if version != Self::VERSION {
+            return Err(ParseErrorKind::UnsupportedVersion {
+                record: "mnemonic HD chain",
+                version,
+            }
+            .into());
+        }
         Ok(Self {
-            version: parse!(p, "version")?,
+            version,
             seed_fp: parse!(p, "seed_fp")?,
             create_time: parse!(p, "create_time")?,
             account_counter: parse!(p, "account_counter")?,
@@ -93,3 +114,44 @@ impl Parse for MnemonicHDChain {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A serialized version-`version` chain with distinct counters.
+    fn chain_bytes(version: i32) -> Vec<u8> {
+        let mut bytes = version.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&[0xab; 32]);
+        bytes.extend_from_slice(&1_700_000_000u64.to_le_bytes());
+        for counter in [2u32, 5, 3, 7] {
+            bytes.extend_from_slice(&counter.to_le_bytes());
+        }
+        bytes.push(1);
+        bytes
+    }
+
+    #[test]
+    fn parses_every_field_of_version_1() {
+        let chain = parse!(buf = &chain_bytes(1), MnemonicHDChain, "chain").unwrap();
+        assert_eq!(chain.version(), 1);
+        assert_eq!(chain.seed_fp(), &[0xab; 32]);
+        assert_eq!(u64::from(chain.create_time()), 1_700_000_000);
+        assert_eq!(chain.account_counter(), 2);
+        assert_eq!(chain.legacy_tkey_external_counter(), 5);
+        assert_eq!(chain.legacy_tkey_internal_counter(), 3);
+        assert_eq!(chain.legacy_sapling_key_counter(), 7);
+        assert!(chain.mnemonic_seed_backup_confirmed());
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        for version in [0, 2] {
+            let err = parse!(buf = &chain_bytes(version), MnemonicHDChain, "chain").unwrap_err();
+            assert!(matches!(
+                err.kind(),
+                ParseErrorKind::UnsupportedVersion { version: v, .. } if *v == version
+            ));
+        }
+    }
+}
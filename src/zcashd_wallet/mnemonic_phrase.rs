@@ -0,0 +1,67 @@
+use zeroize::{Zeroize, ZeroizeOnDrop};
+use zewif::{Bip39Mnemonic, MnemonicLanguage};
+
+use crate::{parse, parser::prelude::*};
+
+/// A wallet's BIP-39 mnemonic, as its `mnemonicphrase` record stores it: a
+/// serialized `MnemonicSeed`, its language then its phrase.
+///
+/// Unlike zewif's [`Bip39Mnemonic`], the phrase is zeroed when dropped.
+#[derive(Clone, PartialEq)]
+pub struct MnemonicPhrase {
+    phrase: String,
+    language: Option<MnemonicLanguage>,
+}
+
+impl MnemonicPhrase {
+    pub fn new(phrase: impl Into<String>, language: Option<MnemonicLanguage>) -> Self {
+        Self {
+            phrase: phrase.into(),
+            language,
+        }
+    }
+
+    pub fn phrase(&self) -> &str {
+        &self.phrase
+    }
+
+    pub fn language(&self) -> Option<&MnemonicLanguage> {
+        self.language.as_ref()
+    }
+
+    /// A copy of the mnemonic for a ZeWIF document, which does not zero it.
+    pub fn to_bip39_mnemonic(&self) -> Bip39Mnemonic {
+        Bip39Mnemonic::new(&self.phrase, self.language.clone())
+    }
+}
+
+impl std::fmt::Debug for MnemonicPhrase {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("MnemonicPhrase")
+            .field("language", &self.language)
+            .field("phrase", &"<elided>")
+            .finish()
+    }
+}
+
+impl Parse for MnemonicPhrase {
+    fn parse(p: &mut Parser) -> Result<Self> {
+        let language = parse!(p, MnemonicLanguage, "language")?;
+        let phrase = parse!(p, String, "mnemonic")?;
+        Ok(Self::new(phrase, Some(language)))
+    }
+}
+
+impl Zeroize for MnemonicPhrase {
+    fn zeroize(&mut self) {
+        self.phrase.zeroize();
+    }
+}
+
+impl Drop for MnemonicPhrase {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for MnemonicPhrase {}
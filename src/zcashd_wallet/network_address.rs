@@ -0,0 +1,136 @@
+use std::fmt;
+
+use zewif::Network;
+
+use super::{
+    RecipientAddress,
+    orchard::OrchardRawAddress,
+    sapling::SaplingZPaymentAddress,
+    transparent::{KeyId, ScriptId},
+};
+use crate::{parse, parser::prelude::*};
+
+/// An address type whose string encoding depends on the network.
+pub trait AddressEncoding {
+    /// The address's string encoding on `network`.
+    fn encode(&self, network: &Network) -> String;
+}
+
+macro_rules! impl_address_encoding {
+    ($($type:ty),*) => {
+        $(
+            impl AddressEncoding for $type {
+                fn encode(&self, network: &Network) -> String {
+                    self.to_string(network)
+                }
+            }
+        )*
+    };
+}
+
+impl_address_encoding!(
+    KeyId,
+    ScriptId,
+    SaplingZPaymentAddress,
+    OrchardRawAddress,
+    RecipientAddress
+);
+
+/// An address parsed together with the network it belongs to, so that it
+/// renders as a string without the network being supplied again.
+///
+/// Parse one with the network as the parameter:
+/// `parse!(p, NetworkAddress<SaplingZPaymentAddress>, param = network, "address")`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkAddress<A> {
+    address: A,
+    network: Network,
+}
+
+impl<A> NetworkAddress<A> {
+    pub fn new(address: A, network: Network) -> Self {
+        Self { address, network }
+    }
+
+    pub fn address(&self) -> &A {
+        &self.address
+    }
+
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+
+    pub fn into_address(self) -> A {
+        self.address
+    }
+}
+
+impl<A: AddressEncoding> fmt::Display for NetworkAddress<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.address.encode(&self.network))
+    }
+}
+
+impl<A: Parse + AddressEncoding> ParseWithParam<Network> for NetworkAddress<A> {
+    fn parse(p: &mut Parser, network: Network) -> Result<Self> {
+        let address = parse!(p, A, "address")?;
+        Ok(Self { address, network })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zcash_address::{ToAddress, ZcashAddress};
+    use zcash_protocol::consensus::NetworkType;
+
+    use super::*;
+
+    /// A serialized Sapling payment address: diversifier, then pk_d.
+    fn sapling_bytes() -> Vec<u8> {
+        let mut bytes = vec![0x01; 11];
+        bytes.extend_from_slice(&[0x02; 32]);
+        bytes
+    }
+
+    #[test]
+    fn sapling_address_renders_for_its_network() {
+        let expected = {
+            let mut raw = [0u8; 43];
+            raw.copy_from_slice(&sapling_bytes());
+            ZcashAddress::from_sapling(NetworkType::Test, raw).encode()
+        };
+        let address = parse!(
+            buf = &sapling_bytes(),
+            NetworkAddress<SaplingZPaymentAddress>,
+            param = Network::Testnet,
+            "address"
+        )
+        .unwrap();
+        assert_eq!(address.network(), &Network::Testnet);
+        assert_eq!(address.to_string(), expected);
+        assert!(address.to_string().starts_with("ztestsapling1"));
+    }
+
+    #[test]
+    fn recipient_address_renders_for_its_network() {
+        // Receiver type P2PKH, then the key ID.
+        let mut bytes = vec![0x00];
+        bytes.extend_from_slice(&[0x03; 20]);
+
+        let mainnet = parse!(
+            buf = &bytes,
+            NetworkAddress<RecipientAddress>,
+            param = Network::Mainnet,
+            "recipient"
+        )
+        .unwrap();
+        assert!(matches!(mainnet.address(), RecipientAddress::KeyId(_)));
+        assert_eq!(
+            mainnet.to_string(),
+            ZcashAddress::from_transparent_p2pkh(NetworkType::Main, [0x03; 20]).encode()
+        );
+
+        let testnet = NetworkAddress::new(mainnet.into_address(), Network::Testnet);
+        assert!(testnet.to_string().starts_with("tm"));
+    }
+}
@@ -1,7 +1,22 @@
+use zcash_protocol::{
+    consensus::{NetworkConstants, NetworkType},
+    constants,
+};
 use zewif::Network;
 
 use crate::{parse, parser::prelude::*};
 
+/// The network type `network`'s addresses are encoded for. Regtest is kept
+/// distinct from testnet, as its shielded and unified addresses have their own
+/// prefixes.
+pub(crate) fn address_network_from_zewif(network: &Network) -> NetworkType {
+    match network {
+        Network::Mainnet => NetworkType::Main,
+        Network::Testnet => NetworkType::Test,
+        Network::Regtest(_) => NetworkType::Regtest,
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct NetworkInfo {
     zcash: String,
@@ -9,6 +24,15 @@ pub struct NetworkInfo {
 }
 
 impl NetworkInfo {
+    /// The network information zcashd records for `network`, for a wallet
+    /// written before zcashd recorded it.
+    pub fn new(network: Network) -> Self {
+        Self {
+            zcash: "Zcash".to_string(),
+            network,
+        }
+    }
+
     pub fn zcash(&self) -> &str {
         &self.zcash
     }
@@ -26,6 +50,47 @@ impl NetworkInfo {
             Network::Regtest(_) => TestNetwork,
         }
     }
+
+    /// The address encoding network type. Unlike
+    /// [`to_address_encoding_network`](Self::to_address_encoding_network),
+    /// this distinguishes regtest, whose shielded and unified addresses have
+    /// their own prefixes.
+    pub fn network_type(&self) -> NetworkType {
+        address_network_from_zewif(&self.network)
+    }
+
+    /// The prefix of a transparent P2PKH address on this network (`t1` on
+    /// mainnet). Transparent addresses are Base58Check encoded, so this is
+    /// the leading characters of the encoding rather than a Bech32 HRP.
+    pub fn transparent_p2pkh_prefix(&self) -> &'static str {
+        match self.network_type() {
+            NetworkType::Main => "t1",
+            NetworkType::Test | NetworkType::Regtest => "tm",
+        }
+    }
+
+    /// The prefix of a transparent P2SH address on this network (`t3` on
+    /// mainnet).
+    pub fn transparent_p2sh_prefix(&self) -> &'static str {
+        match self.network_type() {
+            NetworkType::Main => "t3",
+            NetworkType::Test | NetworkType::Regtest => "t2",
+        }
+    }
+
+    /// The Bech32 HRP of a Sapling payment address on this network.
+    pub fn sapling_hrp(&self) -> &'static str {
+        self.network_type().hrp_sapling_payment_address()
+    }
+
+    /// The Bech32m HRP of a unified address on this network.
+    pub fn unified_hrp(&self) -> &'static str {
+        match self.network_type() {
+            NetworkType::Main => constants::mainnet::HRP_UNIFIED_ADDRESS,
+            NetworkType::Test => constants::testnet::HRP_UNIFIED_ADDRESS,
+            NetworkType::Regtest => constants::regtest::HRP_UNIFIED_ADDRESS,
+        }
+    }
 }
 
 impl Parse for NetworkInfo {
@@ -44,3 +109,56 @@ impl Parse for NetworkInfo {
         Ok(Self { zcash, network })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use zcash_address::{ToAddress, ZcashAddress};
+
+    use super::*;
+
+    fn network_info(identifier: &str) -> NetworkInfo {
+        let mut bytes = vec![];
+        for s in ["Zcash", identifier] {
+            bytes.push(s.len() as u8);
+            bytes.extend_from_slice(s.as_bytes());
+        }
+        NetworkInfo::parse_buf(&bytes, false).unwrap()
+    }
+
+    #[test]
+    fn mainnet_prefixes() {
+        let info = network_info("main");
+        assert_eq!(info.network_type(), NetworkType::Main);
+        assert_eq!(info.transparent_p2pkh_prefix(), "t1");
+        assert_eq!(info.transparent_p2sh_prefix(), "t3");
+        assert_eq!(info.sapling_hrp(), "zs");
+        assert_eq!(info.unified_hrp(), "u");
+    }
+
+    #[test]
+    fn testnet_and_regtest_prefixes() {
+        let info = network_info("test");
+        assert_eq!(info.network_type(), NetworkType::Test);
+        assert_eq!(info.transparent_p2pkh_prefix(), "tm");
+        assert_eq!(info.transparent_p2sh_prefix(), "t2");
+        assert_eq!(info.sapling_hrp(), "ztestsapling");
+        assert_eq!(info.unified_hrp(), "utest");
+
+        let info = network_info("regtest");
+        assert_eq!(info.network_type(), NetworkType::Regtest);
+        assert_eq!(info.transparent_p2pkh_prefix(), "tm");
+        assert_eq!(info.sapling_hrp(), "zregtestsapling");
+        assert_eq!(info.unified_hrp(), "uregtest");
+    }
+
+    #[test]
+    fn transparent_prefixes_match_encoded_addresses() {
+        for identifier in ["main", "test", "regtest"] {
+            let info = network_info(identifier);
+            let p2pkh = ZcashAddress::from_transparent_p2pkh(info.network_type(), [0xff; 20]);
+            let p2sh = ZcashAddress::from_transparent_p2sh(info.network_type(), [0; 20]);
+            assert!(p2pkh.encode().starts_with(info.transparent_p2pkh_prefix()));
+            assert!(p2sh.encode().starts_with(info.transparent_p2sh_prefix()));
+        }
+    }
+}
@@ -5,3 +5,4 @@ pub(crate) mod bridgetree_parsing;
 mod_use!(orchard_note_commitment_tree);
 mod_use!(orchard_raw_address);
 mod_use!(orchard_tx_meta);
+mod_use!(recorded_orchard_ivk);
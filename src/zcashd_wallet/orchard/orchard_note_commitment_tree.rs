@@ -1,14 +1,16 @@
-use bridgetree::BridgeTree;
+use bridgetree::{BridgeTree, BridgeTreeError, Checkpoint, WitnessingError};
 use byteorder::{LittleEndian, ReadBytesExt};
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet, VecDeque},
     io::{self, Read},
 };
 
 use ::orchard::tree::MerkleHashOrchard;
+use incrementalmerkletree::Position;
 use zcash_encoding::{Optional, Vector};
 use zcash_primitives::{merkle_tree::read_position, transaction::TxId};
 use zcash_protocol::consensus::BlockHeight;
+use zewif::IncrementalWitness;
 
 use super::bridgetree_parsing::read_tree;
 use crate::parser::prelude::*;
@@ -39,6 +41,66 @@ impl NotePositions {
     }
 }
 
+/// Failure to prune an [`OrchardNoteCommitmentTree`] to a checkpoint.
+#[derive(Debug, thiserror::Error)]
+pub enum CheckpointPruneError {
+    /// The tree records no checkpoint at or below the requested height.
+    #[error("no Orchard commitment tree checkpoint at or below height {height}")]
+    NoCheckpoint { height: BlockHeight },
+
+    /// A marked note was appended (or marked) after the checkpoint being
+    /// pruned to, so the pruned tree could no longer witness it.
+    #[error(
+        "pruning to the checkpoint at height {checkpoint} would drop the witness for note position {position}"
+    )]
+    WitnessedPositionAfterCheckpoint {
+        checkpoint: BlockHeight,
+        position: u64,
+    },
+
+    /// The pruned tree failed bridgetree's consistency check.
+    #[error("pruned Orchard commitment tree is inconsistent: {0:?}")]
+    Inconsistent(BridgeTreeError),
+}
+
+/// Failure to witness a note in an [`OrchardNoteCommitmentTree`] as of one of
+/// its checkpoints.
+#[derive(Debug, thiserror::Error)]
+pub enum OrchardWitnessError {
+    /// The tree records no checkpoint at or below the requested height.
+    #[error("no Orchard commitment tree checkpoint at or below height {height}")]
+    NoCheckpoint { height: BlockHeight },
+
+    /// The note was appended after the checkpoint, so the tree state there
+    /// holds no commitment to witness.
+    #[error(
+        "note position {position} was appended after the Orchard commitment tree checkpoint at height {checkpoint}"
+    )]
+    NoteAfterCheckpoint {
+        position: u64,
+        checkpoint: BlockHeight,
+    },
+
+    /// The tree does not track the note's position, or did not yet track it
+    /// at the checkpoint.
+    #[error("note position {position} is not marked in the Orchard commitment tree")]
+    NotMarked { position: u64 },
+
+    /// The tree's size at the checkpoint does not fit the 32-bit size of a
+    /// witness's anchor.
+    #[error(
+        "the Orchard commitment tree's size at the checkpoint, {size}, is too large to witness"
+    )]
+    TreeTooLarge { size: u64 },
+
+    /// bridgetree could not build the authentication path.
+    #[error("cannot witness note position {position} in the Orchard commitment tree: {error:?}")]
+    Witnessing {
+        position: u64,
+        error: WitnessingError,
+    },
+}
+
 /// Represents the complete Orchard note commitment tree
 #[derive(Debug, Clone)]
 pub struct OrchardNoteCommitmentTree {
@@ -48,13 +110,46 @@ pub struct OrchardNoteCommitmentTree {
 }
 
 impl OrchardNoteCommitmentTree {
-    const NOTE_STATE_V1: u8 = 1;
+    pub(crate) const NOTE_STATE_V1: u8 = 1;
+
+    /// The number of checkpoints zcashd's Orchard wallet retains.
+    const MAX_CHECKPOINTS: usize = 100;
+
+    /// An empty tree with no checkpoints or tracked notes, standing in for
+    /// the tree of a wallet last written by zcashd 4.x, which predates Orchard
+    /// and so has no `orchard_note_commitment_tree` record.
+    pub fn empty() -> Self {
+        Self {
+            last_checkpoint: None,
+            commitment_tree: BridgeTree::new(Self::MAX_CHECKPOINTS),
+            note_positions: vec![],
+        }
+    }
+
+    /// An empty tree checkpointed at `height`.
+    #[cfg(test)]
+    pub(crate) fn checkpointed_at(height: BlockHeight) -> Self {
+        let mut tree = Self::empty();
+        tree.commitment_tree.checkpoint(height);
+        tree.last_checkpoint = Some(height);
+        tree
+    }
 
     /// The last checkpoint recorded in the commitment tree, if any.
     pub fn last_checkpoint(&self) -> Option<BlockHeight> {
         self.last_checkpoint
     }
 
+    /// The identifiers of the commitment tree's checkpoints, oldest first.
+    ///
+    /// zcashd checkpoints the tree at each block it connects, keeping the last
+    /// [`Self::MAX_CHECKPOINTS`], so these are block heights, the last being
+    /// [`Self::last_checkpoint`]. A tree written before zcashd 5.0.0 recorded
+    /// no identifiers, and its checkpoints are numbered from 1 instead.
+    pub fn checkpoints(&self) -> impl Iterator<Item = BlockHeight> + '_ {
+        self.commitment_tree.checkpoints().iter().map(|c| *c.id())
+    }
+
     /// The Orchard note commitment tree as a [`BridgeTree`].
     pub fn commitment_tree(
         &self,
@@ -67,6 +162,129 @@ impl OrchardNoteCommitmentTree {
         &self.note_positions
     }
 
+    /// A witness for the marked note at `position` as of the tree's latest
+    /// checkpoint at or below `height`: the note's authentication path to that
+    /// checkpoint's root, which is the witness's anchor.
+    ///
+    /// Unlike [`Self::prune_to_checkpoint`], this leaves the tree unchanged, so
+    /// that each note can be witnessed at a stable height below the tip.
+    ///
+    /// Fails if there is no such checkpoint, if the note was appended after it
+    /// (the height predates the note), or if the tree does not track the note.
+    pub fn witness_at(
+        &self,
+        position: Position,
+        height: BlockHeight,
+    ) -> Result<IncrementalWitness<32, zewif::orchard::MerkleHashOrchard>, OrchardWitnessError>
+    {
+        let index = self
+            .commitment_tree
+            .checkpoints()
+            .iter()
+            .rposition(|c| *c.id() <= height)
+            .ok_or(OrchardWitnessError::NoCheckpoint { height })?;
+        witness_at_checkpoint(&self.commitment_tree, position, index)
+    }
+
+    /// Prune the commitment tree to its latest checkpoint at or below `height`.
+    ///
+    /// The tree is rewound to that checkpoint's state, every other checkpoint
+    /// is dropped, and bridges that are not needed to witness a marked position
+    /// at the kept checkpoint are merged away. The note positions are left
+    /// unchanged.
+    ///
+    /// Fails without modifying the tree if there is no such checkpoint, or if
+    /// any marked position was appended or marked after it.
+    pub fn prune_to_checkpoint(&mut self, height: BlockHeight) -> Result<(), CheckpointPruneError> {
+        *self = self.pruned_to_checkpoint(height)?;
+        Ok(())
+    }
+
+    /// A copy of this tree pruned to its latest checkpoint at or below
+    /// `height`, as by [`Self::prune_to_checkpoint`].
+    ///
+    /// Only the bridges the pruned tree keeps are copied, so this is cheaper
+    /// than cloning the tree and pruning the clone.
+    pub fn pruned_to_checkpoint(&self, height: BlockHeight) -> Result<Self, CheckpointPruneError> {
+        let tree = &self.commitment_tree;
+        let checkpoints = tree.checkpoints();
+        let index = checkpoints
+            .iter()
+            .rposition(|c| *c.id() <= height)
+            .ok_or(CheckpointPruneError::NoCheckpoint { height })?;
+        let checkpoint_id = *checkpoints[index].id();
+        let bridges_len = checkpoints[index].bridges_len();
+        let tree_size = match bridges_len.checked_sub(1) {
+            Some(last) => u64::from(tree.prior_bridges()[last].position()) + 1,
+            None => 0,
+        };
+
+        // Marks made after the checkpoint was taken are not part of its state,
+        // whereas marks removed after it still are.
+        let marked_after: BTreeSet<Position> = checkpoints
+            .range(index..)
+            .flat_map(|c| c.marked().iter().copied())
+            .chain(
+                tree.marked_indices()
+                    .keys()
+                    .copied()
+                    .filter(|p| u64::from(*p) >= tree_size),
+            )
+            .collect();
+        let forgotten_after: BTreeSet<Position> = checkpoints
+            .range(index..)
+            .flat_map(|c| c.forgotten().iter().copied())
+            .collect();
+        if let Some(position) = marked_after.difference(&forgotten_after).next() {
+            return Err(CheckpointPruneError::WitnessedPositionAfterCheckpoint {
+                checkpoint: checkpoint_id,
+                position: u64::from(*position),
+            });
+        }
+        let forgotten_before: BTreeSet<Position> = checkpoints
+            .range(..index)
+            .flat_map(|c| c.forgotten().iter().copied())
+            .collect();
+
+        let saved: BTreeMap<Position, usize> = tree
+            .marked_indices()
+            .iter()
+            .filter(|(p, _)| !marked_after.contains(*p) && !forgotten_before.contains(*p))
+            .map(|(p, i)| (*p, *i))
+            .collect();
+        let prior_bridges = tree.prior_bridges()[..bridges_len].to_vec();
+        let current_bridge = prior_bridges
+            .last()
+            .map(|b| b.successor(saved.contains_key(&b.position())));
+
+        // Garbage collection only merges bridges once the tree holds its
+        // maximum number of checkpoints, so collect with room for just the one
+        // kept checkpoint, then restore the original limit.
+        let mut pruned: BridgeTree<_, _, ORCHARD_TREE_DEPTH> = BridgeTree::from_parts(
+            prior_bridges,
+            current_bridge,
+            saved,
+            VecDeque::from([Checkpoint::at_length(bridges_len, checkpoint_id)]),
+            1,
+        )
+        .map_err(CheckpointPruneError::Inconsistent)?;
+        pruned.garbage_collect();
+        let pruned = BridgeTree::from_parts(
+            pruned.prior_bridges().to_vec(),
+            pruned.current_bridge().clone(),
+            pruned.marked_indices().clone(),
+            pruned.checkpoints().clone(),
+            tree.max_checkpoints(),
+        )
+        .map_err(CheckpointPruneError::Inconsistent)?;
+
+        Ok(Self {
+            last_checkpoint: Some(checkpoint_id),
+            commitment_tree: pruned,
+            note_positions: self.note_positions.clone(),
+        })
+    }
+
     fn read<R: Read>(mut reader: R) -> io::Result<Self> {
         match reader.read_u8()? {
             Self::NOTE_STATE_V1 => {
@@ -104,8 +322,376 @@ impl OrchardNoteCommitmentTree {
     }
 }
 
+/// A witness for the marked note at `position` as of the tree's checkpoint at
+/// `index`, oldest first. See [`OrchardNoteCommitmentTree::witness_at`].
+pub(crate) fn witness_at_checkpoint(
+    tree: &BridgeTree<MerkleHashOrchard, BlockHeight, ORCHARD_TREE_DEPTH>,
+    position: Position,
+    index: usize,
+) -> Result<IncrementalWitness<32, zewif::orchard::MerkleHashOrchard>, OrchardWitnessError> {
+    let checkpoints = tree.checkpoints();
+    let checkpoint = &checkpoints[index];
+    let after_checkpoint = || OrchardWitnessError::NoteAfterCheckpoint {
+        position: u64::from(position),
+        checkpoint: *checkpoint.id(),
+    };
+    let not_marked = || OrchardWitnessError::NotMarked {
+        position: u64::from(position),
+    };
+
+    let frontier = match checkpoint.bridges_len().checked_sub(1) {
+        Some(last) => tree.prior_bridges()[last].frontier(),
+        None => return Err(after_checkpoint()),
+    };
+    if position > frontier.position() {
+        return Err(after_checkpoint());
+    }
+    let size = u64::from(frontier.position()) + 1;
+    let anchor_tree_size =
+        u32::try_from(size).map_err(|_| OrchardWitnessError::TreeTooLarge { size })?;
+
+    let note_commitment = tree.get_marked_leaf(position).ok_or_else(not_marked)?;
+    let checkpoint_depth = checkpoints.len() - index;
+    let merkle_path = tree
+        .witness(position, checkpoint_depth)
+        .map_err(|error| match error {
+            WitnessingError::PositionNotMarked(_) => not_marked(),
+            error => OrchardWitnessError::Witnessing {
+                position: u64::from(position),
+                error,
+            },
+        })?;
+    let anchor = tree
+        .root(checkpoint_depth)
+        .expect("the tree has a checkpoint at this depth");
+    let anchor_frontier = std::iter::once(frontier.leaf())
+        .chain(frontier.ommers())
+        .map(orchard_node)
+        .collect();
+
+    Ok(IncrementalWitness::from_parts(
+        orchard_node(note_commitment),
+        // Below the anchor tree size, so it fits.
+        u64::from(position) as u32,
+        merkle_path.iter().map(orchard_node).collect(),
+        orchard_node(&anchor),
+        anchor_tree_size,
+        anchor_frontier,
+    ))
+}
+
+fn orchard_node(hash: &MerkleHashOrchard) -> zewif::orchard::MerkleHashOrchard {
+    zewif::orchard::MerkleHashOrchard::new(hash.to_bytes())
+}
+
 impl Parse for OrchardNoteCommitmentTree {
     fn parse(p: &mut Parser) -> Result<Self> {
         Ok(OrchardNoteCommitmentTree::read(p)?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ::orchard::tree::MerkleHashOrchard;
+    use bridgetree::{BridgeTree, Checkpoint, MerkleBridge};
+    use incrementalmerkletree::{MerklePath, Position};
+    use zcash_primitives::merkle_tree::{
+        HashSer, write_address, write_nonempty_frontier_v1, write_position, write_usize_leu64,
+    };
+    use zcash_protocol::consensus::BlockHeight;
+
+    use super::{
+        CheckpointPruneError, ORCHARD_TREE_DEPTH, OrchardNoteCommitmentTree, OrchardWitnessError,
+    };
+    use crate::parser::Parse;
+
+    type Tree = BridgeTree<MerkleHashOrchard, BlockHeight, ORCHARD_TREE_DEPTH>;
+
+    fn leaf(n: u8) -> MerkleHashOrchard {
+        let mut bytes = [0u8; 32];
+        bytes[0] = n;
+        MerkleHashOrchard::from_bytes(&bytes).unwrap()
+    }
+
+    /// A tree with a checkpoint at each of heights 1..=20, three leaves
+    /// appended per block, and the middle leaf of every fourth block marked.
+    fn many_checkpoints() -> (Tree, Vec<Position>) {
+        let mut tree = Tree::new(100);
+        let mut marked = vec![];
+        for height in 1..=20u32 {
+            for i in 0..3u8 {
+                tree.append(leaf((height as u8) * 3 + i));
+                if i == 1 && height % 4 == 1 {
+                    marked.push(tree.mark().unwrap());
+                }
+            }
+            tree.checkpoint(BlockHeight::from_u32(height));
+        }
+        (tree, marked)
+    }
+
+    fn wrap(commitment_tree: Tree) -> OrchardNoteCommitmentTree {
+        OrchardNoteCommitmentTree {
+            last_checkpoint: commitment_tree.checkpoints().back().map(|c| *c.id()),
+            commitment_tree,
+            note_positions: vec![],
+        }
+    }
+
+    /// The depth of the checkpoint at `height` in a tree checkpointed at
+    /// every height from 1 to 20.
+    fn depth(height: u32) -> usize {
+        (21 - height) as usize
+    }
+
+    #[test]
+    fn pruned_witnesses_match_the_original_checkpoint() {
+        let (original, marked) = many_checkpoints();
+        let mut tree = wrap(original.clone());
+        tree.prune_to_checkpoint(BlockHeight::from_u32(18)).unwrap();
+
+        let pruned = tree.commitment_tree();
+        assert_eq!(tree.last_checkpoint(), Some(BlockHeight::from_u32(18)));
+        assert_eq!(pruned.checkpoints().len(), 1);
+        assert_eq!(pruned.max_checkpoints(), 100);
+        assert!(pruned.prior_bridges().len() < original.prior_bridges().len());
+
+        let root = pruned.root(1).unwrap();
+        assert_eq!(Some(root), original.root(depth(18)));
+        for position in marked {
+            let path = pruned.witness(position, 1).unwrap();
+            assert_eq!(path, original.witness(position, depth(18)).unwrap());
+
+            let leaf = *pruned.get_marked_leaf(position).unwrap();
+            let path = MerklePath::<_, ORCHARD_TREE_DEPTH>::from_parts(path, position).unwrap();
+            assert_eq!(path.root(leaf), root);
+        }
+    }
+
+    #[test]
+    fn witnesses_are_anchored_at_the_requested_checkpoint() {
+        let mut tree = Tree::new(100);
+        tree.append(leaf(1));
+        let first = tree.mark().unwrap();
+        tree.append(leaf(2));
+        tree.checkpoint(BlockHeight::from_u32(10));
+        tree.append(leaf(3));
+        let second = tree.mark().unwrap();
+        tree.checkpoint(BlockHeight::from_u32(20));
+        let tree = wrap(tree);
+        let bridge_tree = tree.commitment_tree();
+
+        let at_10 = tree.witness_at(first, BlockHeight::from_u32(10)).unwrap();
+        let at_20 = tree.witness_at(first, BlockHeight::from_u32(20)).unwrap();
+        assert_ne!(at_10.anchor().as_bytes(), at_20.anchor().as_bytes());
+        assert_eq!(at_10.anchor_tree_size(), 2);
+        assert_eq!(at_20.anchor_tree_size(), 3);
+        for (witness, depth) in [(&at_10, 2), (&at_20, 1)] {
+            let anchor = bridge_tree.root(depth).unwrap();
+            assert_eq!(witness.anchor().as_bytes(), &anchor.to_bytes());
+            assert_eq!(witness.note_position(), 0);
+            assert_eq!(witness.note_commitment().as_bytes(), &leaf(1).to_bytes());
+
+            let path = bridge_tree.witness(first, depth).unwrap();
+            let recorded: Vec<[u8; 32]> = witness
+                .merkle_path()
+                .iter()
+                .map(|node| *node.as_bytes())
+                .collect();
+            let expected: Vec<[u8; 32]> = path.iter().map(|node| node.to_bytes()).collect();
+            assert_eq!(recorded, expected);
+            let path = MerklePath::<_, ORCHARD_TREE_DEPTH>::from_parts(path, first).unwrap();
+            assert_eq!(path.root(leaf(1)), anchor);
+        }
+
+        // A height between checkpoints uses the one below it.
+        let at_15 = tree.witness_at(first, BlockHeight::from_u32(15)).unwrap();
+        assert_eq!(at_15, at_10);
+
+        assert!(matches!(
+            tree.witness_at(second, BlockHeight::from_u32(10)),
+            Err(OrchardWitnessError::NoteAfterCheckpoint { position: 2, checkpoint })
+                if checkpoint == BlockHeight::from_u32(10)
+        ));
+        assert!(matches!(
+            tree.witness_at(first, BlockHeight::from_u32(9)),
+            Err(OrchardWitnessError::NoCheckpoint { .. })
+        ));
+        assert!(matches!(
+            tree.witness_at(Position::from(1), BlockHeight::from_u32(20)),
+            Err(OrchardWitnessError::NotMarked { position: 1 })
+        ));
+        // Witnessing leaves the tree unchanged.
+        assert_eq!(tree.checkpoints().count(), 2);
+    }
+
+    #[test]
+    fn prunes_to_the_latest_checkpoint_at_or_below_the_height() {
+        let mut tree = Tree::new(100);
+        for (i, height) in [10u32, 20, 30].into_iter().enumerate() {
+            tree.append(leaf(i as u8));
+            tree.mark();
+            tree.checkpoint(BlockHeight::from_u32(height));
+        }
+        let mut tree = wrap(tree);
+        tree.prune_to_checkpoint(BlockHeight::from_u32(35)).unwrap();
+        assert_eq!(tree.last_checkpoint(), Some(BlockHeight::from_u32(30)));
+        assert_eq!(
+            tree.commitment_tree().current_position(),
+            Some(Position::from(2))
+        );
+    }
+
+    #[test]
+    fn pruning_below_a_witnessed_position_is_rejected() {
+        let (original, marked) = many_checkpoints();
+        let mut tree = wrap(original.clone());
+
+        // Leaves 0..30 are in the tree at height 10; the next marked note, at
+        // position 37, was appended in block 13.
+        match tree.prune_to_checkpoint(BlockHeight::from_u32(10)) {
+            Err(CheckpointPruneError::WitnessedPositionAfterCheckpoint {
+                checkpoint,
+                position,
+            }) => {
+                assert_eq!(checkpoint, BlockHeight::from_u32(10));
+                assert_eq!(position, 37);
+                assert!(marked.contains(&Position::from(position)));
+            }
+            other => panic!("expected a witnessed-position error, got {other:?}"),
+        }
+        assert_eq!(tree.commitment_tree().checkpoints().len(), 20);
+        assert_eq!(tree.commitment_tree().root(1), original.root(1));
+    }
+
+    #[test]
+    fn pruning_below_the_first_checkpoint_is_rejected() {
+        let (original, _) = many_checkpoints();
+        let mut tree = wrap(original);
+        assert!(matches!(
+            tree.prune_to_checkpoint(BlockHeight::from_u32(0)),
+            Err(CheckpointPruneError::NoCheckpoint { .. })
+        ));
+    }
+
+    #[test]
+    fn forgotten_marks_do_not_block_pruning() {
+        let (mut original, marked) = many_checkpoints();
+        // Unmark the only note received after height 15.
+        let last = *marked.last().unwrap();
+        assert!(original.remove_mark(last));
+        let mut tree = wrap(original);
+        tree.prune_to_checkpoint(BlockHeight::from_u32(15)).unwrap();
+        assert!(tree.commitment_tree().get_marked_leaf(last).is_none());
+    }
+
+    /// A CompactSize count, as zcashd writes the length of a vector; the
+    /// trees here are small enough for it to be one byte.
+    fn write_count(bytes: &mut Vec<u8>, count: usize) {
+        bytes.push(u8::try_from(count).ok().filter(|n| *n < 0xfd).unwrap());
+    }
+
+    /// A bridge as zcashd's `write_bridge` serializes it, in the v2 bridge
+    /// format.
+    fn write_bridge(bytes: &mut Vec<u8>, bridge: &MerkleBridge<MerkleHashOrchard>) {
+        bytes.push(2);
+        match bridge.prior_position() {
+            Some(position) => {
+                bytes.push(1);
+                write_position(&mut *bytes, position).unwrap();
+            }
+            None => bytes.push(0),
+        }
+        write_count(bytes, bridge.tracking().len());
+        for address in bridge.tracking() {
+            write_address(&mut *bytes, *address).unwrap();
+        }
+        write_count(bytes, bridge.ommers().len());
+        for (address, ommer) in bridge.ommers() {
+            write_address(&mut *bytes, *address).unwrap();
+            ommer.write(&mut *bytes).unwrap();
+        }
+        write_nonempty_frontier_v1(&mut *bytes, bridge.frontier()).unwrap();
+    }
+
+    /// A checkpoint as zcashd's `write_checkpoint_v3` serializes it.
+    fn write_checkpoint(bytes: &mut Vec<u8>, checkpoint: &Checkpoint<BlockHeight>) {
+        bytes.extend_from_slice(&u32::from(*checkpoint.id()).to_le_bytes());
+        write_usize_leu64(&mut *bytes, checkpoint.bridges_len()).unwrap();
+        for positions in [checkpoint.marked(), checkpoint.forgotten()] {
+            write_count(bytes, positions.len());
+            for position in positions {
+                write_position(&mut *bytes, *position).unwrap();
+            }
+        }
+    }
+
+    /// An `orchard_note_commitment_tree` record value, without its client
+    /// version prefix, holding `tree` in the v3 tree format and no note
+    /// positions.
+    fn note_state_bytes(tree: &Tree) -> Vec<u8> {
+        let mut bytes = vec![OrchardNoteCommitmentTree::NOTE_STATE_V1];
+        match tree.checkpoints().back() {
+            Some(checkpoint) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&u32::from(*checkpoint.id()).to_le_bytes());
+            }
+            None => bytes.push(0),
+        }
+        bytes.push(3);
+        write_count(&mut bytes, tree.prior_bridges().len());
+        for bridge in tree.prior_bridges() {
+            write_bridge(&mut bytes, bridge);
+        }
+        match tree.current_bridge() {
+            Some(bridge) => {
+                bytes.push(1);
+                write_bridge(&mut bytes, bridge);
+            }
+            None => bytes.push(0),
+        }
+        write_count(&mut bytes, tree.marked_indices().len());
+        for (position, index) in tree.marked_indices() {
+            write_position(&mut bytes, *position).unwrap();
+            write_usize_leu64(&mut bytes, *index).unwrap();
+        }
+        write_count(&mut bytes, tree.checkpoints().len());
+        for checkpoint in tree.checkpoints() {
+            write_checkpoint(&mut bytes, checkpoint);
+        }
+        write_usize_leu64(&mut bytes, tree.max_checkpoints()).unwrap();
+        write_count(&mut bytes, 0);
+        bytes
+    }
+
+    #[test]
+    fn every_serialized_checkpoint_is_restored() {
+        let (original, marked) = many_checkpoints();
+        let bytes = note_state_bytes(&original);
+        let tree = OrchardNoteCommitmentTree::parse_buf(&bytes, false).unwrap();
+
+        let heights: Vec<u32> = tree.checkpoints().map(u32::from).collect();
+        assert_eq!(heights, (1..=20).collect::<Vec<_>>());
+        assert_eq!(tree.last_checkpoint(), Some(BlockHeight::from_u32(20)));
+
+        // The tree can be witnessed as of any of its checkpoints.
+        let restored = tree.commitment_tree();
+        for height in [1, 10, 20] {
+            assert_eq!(restored.root(depth(height)), original.root(depth(height)));
+        }
+        for position in marked.iter().copied().filter(|p| u64::from(*p) < 3 * 10) {
+            assert_eq!(
+                restored.witness(position, depth(10)),
+                original.witness(position, depth(10))
+            );
+        }
+
+        // The historical checkpoints are what pruning to a stable height needs.
+        let mut tree = tree;
+        tree.prune_to_checkpoint(BlockHeight::from_u32(19)).unwrap();
+        assert_eq!(
+            tree.checkpoints().collect::<Vec<_>>(),
+            [BlockHeight::from_u32(19)]
+        );
+    }
+}
@@ -1,9 +1,7 @@
 use zcash_address::{ToAddress, ZcashAddress, unified::Encoding};
 use zewif::Network;
 
-use crate::{parse, parser::prelude::*};
-
-use crate::migrate::primitives::address_network_from_zewif;
+use crate::{parse, parser::prelude::*, zcashd_wallet::address_network_from_zewif};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct OrchardRawAddress {
@@ -1,12 +1,12 @@
-use ::orchard::keys::IncomingViewingKey;
 use std::collections::HashMap;
 
+use super::RecordedOrchardIvk;
 use crate::{parse, parser::prelude::*, zcashd_wallet::ClientVersion};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct OrchardTxMeta {
     version: ClientVersion,
-    receiving_keys: HashMap<u32, IncomingViewingKey>,
+    receiving_keys: HashMap<u32, RecordedOrchardIvk>,
     actions_spending_my_nodes: Vec<u32>,
 }
 
@@ -16,13 +16,14 @@ impl OrchardTxMeta {
         self.version
     }
 
-    /// Returns the IVK that received the output at the given action index, if any.
-    pub fn receiving_key(&self, index: u32) -> Option<&IncomingViewingKey> {
+    /// Returns the IVK that received the output at the given action index, if
+    /// any. A recorded key that does not validate is kept as raw bytes.
+    pub fn receiving_key(&self, index: u32) -> Option<&RecordedOrchardIvk> {
         self.receiving_keys.get(&index)
     }
 
     /// Returns the entire action data map
-    pub fn receiving_keys(&self) -> &HashMap<u32, IncomingViewingKey> {
+    pub fn receiving_keys(&self) -> &HashMap<u32, RecordedOrchardIvk> {
         &self.receiving_keys
     }
 
@@ -44,3 +45,25 @@ impl Parse for OrchardTxMeta {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_receiving_key_does_not_fail_the_record() {
+        let mut bytes = 5_060_150u32.to_le_bytes().to_vec(); // version
+        bytes.push(1); // one receiving key
+        bytes.extend_from_slice(&2u32.to_le_bytes()); // action index
+        bytes.extend_from_slice(&[0xff; 64]); // not a valid IVK
+        bytes.push(1); // one action spending the wallet's notes
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+
+        let meta = parse!(buf = &bytes, OrchardTxMeta, "orchard_tx_meta").unwrap();
+        assert_eq!(
+            meta.receiving_key(2),
+            Some(&RecordedOrchardIvk::Raw([0xff; 64]))
+        );
+        assert_eq!(meta.actions_spending_my_nodes(), &[0]);
+    }
+}
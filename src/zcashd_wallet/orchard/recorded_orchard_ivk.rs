@@ -0,0 +1,77 @@
+use ::orchard::keys::IncomingViewingKey;
+
+use crate::{parse, parser::prelude::*};
+
+/// An Orchard incoming viewing key as zcashd recorded it.
+///
+/// The 64-byte encoding is validated as for [`IncomingViewingKey`], but bytes
+/// that do not form a valid key are kept as [`RecordedOrchardIvk::Raw`] rather
+/// than failing the record they appear in, so that no key material is lost
+/// to validation stricter than the data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordedOrchardIvk {
+    /// A valid incoming viewing key.
+    Valid(IncomingViewingKey),
+    /// Recorded bytes that do not form a valid incoming viewing key.
+    Raw([u8; 64]),
+}
+
+impl RecordedOrchardIvk {
+    /// The incoming viewing key, if the recorded bytes are valid.
+    pub fn key(&self) -> Option<&IncomingViewingKey> {
+        match self {
+            RecordedOrchardIvk::Valid(ivk) => Some(ivk),
+            RecordedOrchardIvk::Raw(_) => None,
+        }
+    }
+
+    /// The recorded 64-byte encoding.
+    pub fn to_bytes(&self) -> [u8; 64] {
+        match self {
+            RecordedOrchardIvk::Valid(ivk) => ivk.to_bytes(),
+            RecordedOrchardIvk::Raw(bytes) => *bytes,
+        }
+    }
+}
+
+impl Parse for RecordedOrchardIvk {
+    fn parse(p: &mut Parser) -> Result<Self> {
+        let bytes: [u8; 64] = parse!(p, "orchard IVK")?;
+        Ok(IncomingViewingKey::from_bytes(&bytes)
+            .into_option()
+            .map_or(RecordedOrchardIvk::Raw(bytes), RecordedOrchardIvk::Valid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::orchard::keys::{FullViewingKey, Scope, SpendingKey};
+
+    use super::*;
+
+    fn valid_ivk() -> IncomingViewingKey {
+        let sk = SpendingKey::from_bytes([0x01; 32]).unwrap();
+        FullViewingKey::from(&sk).to_ivk(Scope::External)
+    }
+
+    #[test]
+    fn valid_ivk_is_parsed() {
+        let ivk = valid_ivk();
+        let parsed = parse!(buf = &ivk.to_bytes(), RecordedOrchardIvk, "ivk").unwrap();
+        assert_eq!(parsed, RecordedOrchardIvk::Valid(ivk.clone()));
+        assert_eq!(parsed.key(), Some(&ivk));
+        assert_eq!(parsed.to_bytes(), ivk.to_bytes());
+    }
+
+    #[test]
+    fn invalid_ivk_keeps_its_bytes() {
+        // Neither half is a canonical Pallas field element.
+        let bytes = [0xff; 64];
+        assert!(parse!(buf = &bytes, IncomingViewingKey, "ivk").is_err());
+
+        let parsed = parse!(buf = &bytes, RecordedOrchardIvk, "ivk").unwrap();
+        assert_eq!(parsed, RecordedOrchardIvk::Raw(bytes));
+        assert_eq!(parsed.key(), None);
+        assert_eq!(parsed.to_bytes(), bytes);
+    }
+}
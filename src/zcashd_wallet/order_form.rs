@@ -0,0 +1,171 @@
+use zewif::Data;
+
+use crate::{parse, parser::prelude::*};
+
+/// An entry of a wallet transaction's order form (`vOrderForm`): a key-value
+/// pair a wallet front end recorded when the transaction was created.
+///
+/// The value is a serialized `std::string`, which zcashd does not require to
+/// be UTF-8: a BIP 70 payment request is stored as its raw protobuf bytes. It
+/// is therefore kept as bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderFormEntry {
+    key: String,
+    value: Data,
+}
+
+/// The order form fields this crate recognizes, from
+/// [`OrderFormEntry::parse_known_fields`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KnownOrderFormData {
+    /// A serialized BIP 70 payment request (`PaymentRequest`).
+    pub payment_request: Option<Vec<u8>>,
+    /// A plaintext memo (`memo`).
+    pub memo: Option<String>,
+    /// A message to or from the payee (`Message`, or `message`).
+    pub message: Option<String>,
+}
+
+impl KnownOrderFormData {
+    /// Whether no known field is present.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Fill each field that is absent here from `other`.
+    fn merge(&mut self, other: KnownOrderFormData) {
+        self.payment_request = self.payment_request.take().or(other.payment_request);
+        self.memo = self.memo.take().or(other.memo);
+        self.message = self.message.take().or(other.message);
+    }
+}
+
+impl OrderFormEntry {
+    pub fn new(key: impl Into<String>, value: impl Into<Vec<u8>>) -> Self {
+        Self {
+            key: key.into(),
+            value: Data::from_vec(value.into()),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn value(&self) -> &Data {
+        &self.value
+    }
+
+    /// The field this entry sets, if its key is a known one. A memo or
+    /// message that is not UTF-8 is ignored.
+    pub fn parse_known_fields(&self) -> KnownOrderFormData {
+        let text = || String::from_utf8(self.value.to_vec()).ok();
+        match self.key.as_str() {
+            "PaymentRequest" => KnownOrderFormData {
+                payment_request: Some(self.value.to_vec()),
+                ..Default::default()
+            },
+            "memo" => KnownOrderFormData {
+                memo: text(),
+                ..Default::default()
+            },
+            "Message" | "message" => KnownOrderFormData {
+                message: text(),
+                ..Default::default()
+            },
+            _ => KnownOrderFormData::default(),
+        }
+    }
+
+    /// The known fields of a whole order form. Where a field is set by more
+    /// than one entry, the first is kept.
+    pub fn parse_known_fields_of(entries: &[OrderFormEntry]) -> KnownOrderFormData {
+        let mut known = KnownOrderFormData::default();
+        for entry in entries {
+            known.merge(entry.parse_known_fields());
+        }
+        known
+    }
+}
+
+impl Parse for OrderFormEntry {
+    fn parse(p: &mut Parser) -> Result<Self> {
+        let key = parse!(p, String, "order form key")?;
+        let value = parse!(p, Data, "order form value")?;
+        Ok(Self { key, value })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry_bytes(key: &str, value: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![key.len() as u8];
+        bytes.extend_from_slice(key.as_bytes());
+        bytes.push(value.len() as u8);
+        bytes.extend_from_slice(value);
+        bytes
+    }
+
+    #[test]
+    fn binary_values_parse() {
+        let request = [0x12, 0x0b, 0xff, 0x00, 0x80];
+        let entry = parse!(
+            buf = &entry_bytes("PaymentRequest", &request),
+            OrderFormEntry,
+            "order form entry"
+        )
+        .unwrap();
+        assert_eq!(entry.key(), "PaymentRequest");
+        assert_eq!(entry.value().as_slice(), &request[..]);
+        assert_eq!(
+            entry.parse_known_fields(),
+            KnownOrderFormData {
+                payment_request: Some(request.to_vec()),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn text_fields_are_recognized() {
+        let memo = OrderFormEntry::new("memo", "rent for May");
+        assert_eq!(
+            memo.parse_known_fields().memo.as_deref(),
+            Some("rent for May")
+        );
+
+        for key in ["Message", "message"] {
+            let message = OrderFormEntry::new(key, "thanks");
+            assert_eq!(
+                message.parse_known_fields().message.as_deref(),
+                Some("thanks")
+            );
+        }
+
+        // Not UTF-8, so not a usable memo.
+        assert!(
+            OrderFormEntry::new("memo", vec![0xff])
+                .parse_known_fields()
+                .is_empty()
+        );
+        assert!(
+            OrderFormEntry::new("unknown", "value")
+                .parse_known_fields()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn first_entry_for_a_field_wins() {
+        let known = OrderFormEntry::parse_known_fields_of(&[
+            OrderFormEntry::new("memo", "first"),
+            OrderFormEntry::new("Message", "hello"),
+            OrderFormEntry::new("memo", "second"),
+        ]);
+        assert_eq!(known.memo.as_deref(), Some("first"));
+        assert_eq!(known.message.as_deref(), Some("hello"));
+        assert_eq!(known.payment_request, None);
+    }
+}
@@ -0,0 +1,114 @@
+use zewif::{Network, TxId};
+
+use super::{Address, SecondsSinceEpoch, UndecodableKey, transparent::PubKey};
+
+/// Something a lenient parse tolerated in a wallet rather than failing on it.
+///
+/// A strict parse fails on most of these instead. The wallet keeps them in
+/// the order they were found; see
+/// [`ZcashdWallet::parse_warnings`](super::ZcashdWallet::parse_warnings).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseWarning {
+    /// The `db_dump` output ended early or was otherwise incomplete, and the
+    /// records read were kept; see
+    /// [`BDBDump::from_dump_output`](crate::BDBDump::from_dump_output).
+    IncompleteDump { problem: String },
+    /// A keyname was recorded with surrounding whitespace.
+    NormalizedKeyname { found: String, keyname: String },
+    /// An address book key was recorded in a non-canonical form, such as
+    /// with whitespace or mixed-case Bech32.
+    NormalizedAddress { found: Address, normalized: Address },
+    /// Key material the upstream crate decoding it rejected, kept as its raw
+    /// record; see
+    /// [`ZcashdWallet::undecodable_keys`](super::ZcashdWallet::undecodable_keys).
+    UndecodableKey(UndecodableKey),
+    /// A transparent key recorded no creation time and was given the
+    /// earliest creation time of the wallet's other keys.
+    KeyTimestampCorrected {
+        pubkey: PubKey,
+        creation_time: SecondsSinceEpoch,
+    },
+    /// A second UFVK record for the same fingerprint, which was dropped in
+    /// favour of the first.
+    DuplicateUfvkRecord {
+        keyname: &'static str,
+        fingerprint: String,
+    },
+    /// The wallet has no `networkinfo` record, so the network was taken from
+    /// its addresses.
+    MissingNetworkInfo { network: Network },
+    /// The wallet's `networkinfo` record could not be read, so the network was
+    /// taken from its addresses.
+    UnreadableNetworkInfo { network: Network, error: String },
+    /// The wallet holds more than one Orchard note commitment tree; the one
+    /// with the latest checkpoint is used.
+    ExtraOrchardTrees {
+        count: usize,
+        checkpoint: Option<u32>,
+    },
+    /// A transaction record could not be parsed and was skipped.
+    UnparsedTransaction { txid: TxId, error: String },
+    /// The wallet was read from a key export, which records no transaction
+    /// history.
+    NoTransactionHistory,
+}
+
+impl std::fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IncompleteDump { problem } => {
+                write!(f, "{problem}; keeping the records read")
+            }
+            Self::NormalizedKeyname { found, keyname } => {
+                write!(
+                    f,
+                    "keyname {found:?} has surrounding whitespace; read as {keyname:?}"
+                )
+            }
+            Self::NormalizedAddress { found, normalized } => {
+                write!(f, "normalized address book key {found:?} to {normalized}")
+            }
+            Self::UndecodableKey(undecodable) => write!(f, "skipping undecodable {undecodable}"),
+            Self::KeyTimestampCorrected {
+                pubkey,
+                creation_time,
+            } => write!(
+                f,
+                "key {} has no creation time; using the earliest key creation time, {creation_time}",
+                hex::encode(pubkey.as_slice())
+            ),
+            Self::DuplicateUfvkRecord {
+                keyname,
+                fingerprint,
+            } => write!(
+                f,
+                "more than one {keyname} record for UFVK fingerprint {fingerprint}; keeping the first"
+            ),
+            Self::MissingNetworkInfo { network } => write!(
+                f,
+                "wallet has no networkinfo record; using {network:?}, the network its addresses are encoded for"
+            ),
+            Self::UnreadableNetworkInfo { network, error } => write!(
+                f,
+                "unreadable networkinfo record ({error}); using {network:?}, the network its addresses are encoded for"
+            ),
+            Self::ExtraOrchardTrees { count, checkpoint } => {
+                write!(
+                    f,
+                    "{count} orchard_note_commitment_tree records; using the one with the latest checkpoint ("
+                )?;
+                match checkpoint {
+                    Some(height) => write!(f, "checkpoint {height})"),
+                    None => write!(f, "no checkpoint)"),
+                }
+            }
+            Self::UnparsedTransaction { txid, error } => {
+                write!(f, "unable to parse transaction {txid}: {error}")
+            }
+            Self::NoTransactionHistory => write!(
+                f,
+                "a key export records no transaction history; the migrated wallet will have none"
+            ),
+        }
+    }
+}
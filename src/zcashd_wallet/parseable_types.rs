@@ -12,7 +12,7 @@ use zewif::{Data, SeedFingerprint, sapling::SaplingIncomingViewingKey};
 use crate::{
     parse,
     parser::prelude::*,
-    zcashd_wallet::{CompactSize, u256},
+    zcashd_wallet::{CompactSize, u256, write_compact_size},
 };
 
 impl Parse for String {
@@ -296,11 +296,24 @@ impl Parse for zewif::Data {
     }
 }
 
+/// Appends `data` to `out` as a `Data` is serialized, prefixed by its length
+/// as a compact size: the inverse of `Data`'s [`Parse`] implementation. A
+/// string is serialized the same way, as its UTF-8 bytes.
+pub fn write_data(out: &mut Vec<u8>, data: &[u8]) {
+    write_compact_size(out, data.len());
+    out.extend_from_slice(data);
+}
+
 impl Parse for zewif::Amount {
+    /// Parses an amount of zatoshis, which must be in the money range
+    /// `0..=MAX_MONEY`.
     fn parse(p: &mut Parser) -> Result<Self> {
         let zat_balance = parse!(p, i64, "Zat balance")?;
+        if zat_balance < 0 {
+            return Err(ParseErrorKind::NegativeAmount(zat_balance).into());
+        }
         Self::try_from(zat_balance)
-            .map_err(|_| ParseErrorKind::InvalidAmount(zat_balance).into())
+            .map_err(|_| ParseErrorKind::AmountExceedsMaxMoney(zat_balance).into())
     }
 }
 
@@ -332,15 +345,6 @@ impl Parse for zewif::MnemonicLanguage {
     }
 }
 
-impl Parse for zewif::Bip39Mnemonic {
-    fn parse(p: &mut Parser) -> Result<Self> {
-        let language = Some(parse!(p, zewif::MnemonicLanguage, "language")?);
-        let mnemonic = parse!(p, String, "mnemonic")?;
-        let bip39_mnemonic = Self::new(mnemonic, language);
-        Ok(bip39_mnemonic)
-    }
-}
-
 impl Parse for zewif::BlockHeight {
     fn parse(p: &mut Parser) -> Result<Self> {
         let height = parse!(p, u32, "BlockHeight")?;
@@ -392,6 +396,22 @@ impl Parse for SaplingIncomingViewingKey {
 
 impl Parse for ::sapling::zip32::ExtendedFullViewingKey {
     fn parse(p: &mut Parser) -> Result<Self> {
+        // `ExtendedFullViewingKey::read` panics on an `ak` that is a Jubjub
+        // subgroup point but not a RedJubjub verification key, so check it
+        // here first. `ak` follows the depth (1), parent FVK tag (4), child
+        // index (4) and chain code (32); a shorter buffer is left for `read`
+        // to reject.
+        if let Ok(ak) = <[u8; 32]>::try_from(p.peek(73).get(41..).unwrap_or_default()) {
+            use group::{Group, GroupEncoding};
+
+            let is_spend_validating_key =
+                Option::<jubjub::SubgroupPoint>::from(jubjub::SubgroupPoint::from_bytes(&ak))
+                    .is_some_and(|point| !bool::from(point.is_identity()))
+                    && redjubjub::VerificationKey::<redjubjub::SpendAuth>::try_from(ak).is_ok();
+            if !is_spend_validating_key {
+                return Err(ParseErrorKind::InvalidSaplingSpendValidatingKey.into());
+            }
+        }
         Ok(::sapling::zip32::ExtendedFullViewingKey::read(p)?)
     }
 }
@@ -420,3 +440,51 @@ impl Parse for ::orchard::keys::IncomingViewingKey {
             .ok_or_else(|| ParseErrorKind::InvalidOrchardIvk.into())
     }
 }
+
+impl Parse for ::orchard::keys::SpendingKey {
+    fn parse(p: &mut Parser) -> Result<Self> {
+        let bytes: [u8; 32] = parse!(p, "orchard spending key")?;
+        ::orchard::keys::SpendingKey::from_bytes(bytes)
+            .into_option()
+            .ok_or_else(|| ParseErrorKind::InvalidOrchardSpendingKey.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zewif::{Amount, MAX_BALANCE};
+
+    use super::*;
+
+    fn parse_amount(zats: i64) -> Result<Amount> {
+        Amount::parse_buf(&zats.to_le_bytes(), false)
+    }
+
+    #[test]
+    fn amounts_in_the_money_range_parse() {
+        assert_eq!(i64::from(parse_amount(0).unwrap()), 0);
+        assert_eq!(i64::from(parse_amount(MAX_BALANCE).unwrap()), MAX_BALANCE);
+    }
+
+    #[test]
+    fn negative_amounts_are_rejected() {
+        for zats in [-1, i64::MIN] {
+            let err = parse_amount(zats).unwrap_err();
+            assert!(
+                matches!(err.kind(), ParseErrorKind::NegativeAmount(z) if *z == zats),
+                "{err}"
+            );
+        }
+    }
+
+    #[test]
+    fn amounts_above_max_money_are_rejected() {
+        for zats in [MAX_BALANCE + 1, i64::MAX] {
+            let err = parse_amount(zats).unwrap_err();
+            assert!(
+                matches!(err.kind(), ParseErrorKind::AmountExceedsMaxMoney(z) if *z == zats),
+                "{err}"
+            );
+        }
+    }
+}
@@ -1,3 +1,7 @@
+use std::collections::HashSet;
+
+use zcash_address::unified::{self, Container, Encoding};
+
 use crate::{parse, parser::prelude::*, zcashd_wallet::CompactSize};
 
 /// ZCash receiver types used in Unified Addresses.
@@ -14,6 +18,30 @@ pub enum ReceiverType {
     Orchard = 0x03,
 }
 
+impl ReceiverType {
+    /// The receiver types present in an encoded unified address: the inverse
+    /// of deriving a unified address with a given set of receivers. Receivers
+    /// of types zcashd does not know are ignored.
+    ///
+    /// Fails with [`ParseErrorKind::UnifiedAddressDecode`] if the string is
+    /// not a valid unified address.
+    pub fn from_unified_address_str(ua_str: &str) -> Result<HashSet<ReceiverType>> {
+        let (_, address) =
+            unified::Address::decode(ua_str).map_err(ParseErrorKind::UnifiedAddressDecode)?;
+        Ok(address
+            .items()
+            .iter()
+            .filter_map(|receiver| match receiver {
+                unified::Receiver::P2pkh(_) => Some(ReceiverType::P2PKH),
+                unified::Receiver::P2sh(_) => Some(ReceiverType::P2SH),
+                unified::Receiver::Sapling(_) => Some(ReceiverType::Sapling),
+                unified::Receiver::Orchard(_) => Some(ReceiverType::Orchard),
+                unified::Receiver::Unknown { .. } => None,
+            })
+            .collect())
+    }
+}
+
 /// Parses a ReceiverType from a binary data stream as encoded in zcashd's wallet.dat format.
 impl Parse for ReceiverType {
     fn parse(p: &mut Parser) -> Result<Self> {
@@ -52,3 +80,62 @@ impl TryFrom<String> for ReceiverType {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use zcash_address::{ToAddress, ZcashAddress};
+    use zcash_keys::keys::{ReceiverRequirement, UnifiedAddressRequest, UnifiedSpendingKey};
+    use zcash_protocol::consensus::{MainNetwork, NetworkType};
+
+    use super::*;
+
+    /// A unified address of a fixed test account with the given receivers.
+    fn unified_address(orchard: bool, sapling: bool, p2pkh: bool) -> String {
+        let requirement = |present: bool| {
+            if present {
+                ReceiverRequirement::Require
+            } else {
+                ReceiverRequirement::Omit
+            }
+        };
+        let request = UnifiedAddressRequest::custom(
+            requirement(orchard),
+            requirement(sapling),
+            requirement(p2pkh),
+        )
+        .unwrap();
+        let ufvk = UnifiedSpendingKey::from_seed(&MainNetwork, &[0x5a; 32], zip32::AccountId::ZERO)
+            .unwrap()
+            .to_unified_full_viewing_key();
+        // Not every diversifier index is valid for Sapling.
+        let (j, _) = ufvk.sapling().unwrap().default_address();
+        ufvk.address(j, request).unwrap().encode(&MainNetwork)
+    }
+
+    #[test]
+    fn receiver_types_are_read_from_a_unified_address() {
+        use ReceiverType::*;
+        let cases = [
+            ((true, false, false), vec![Orchard]),
+            ((false, true, false), vec![Sapling]),
+            ((true, true, false), vec![Orchard, Sapling]),
+            ((false, true, true), vec![Sapling, P2PKH]),
+            ((true, true, true), vec![Orchard, Sapling, P2PKH]),
+        ];
+        for ((orchard, sapling, p2pkh), expected) in cases {
+            let ua = unified_address(orchard, sapling, p2pkh);
+            assert_eq!(
+                ReceiverType::from_unified_address_str(&ua).unwrap(),
+                expected.into_iter().collect::<HashSet<_>>(),
+                "{ua}"
+            );
+        }
+    }
+
+    #[test]
+    fn non_unified_address_is_rejected() {
+        let sapling = ZcashAddress::from_sapling(NetworkType::Main, [0x02; 43]).encode();
+        let err = ReceiverType::from_unified_address_str(&sapling).unwrap_err();
+        assert!(matches!(err.kind(), ParseErrorKind::UnifiedAddressDecode(_)));
+    }
+}
@@ -4,6 +4,7 @@ use super::{
     transparent::{KeyId, ScriptId},
 };
 use crate::{parse, parser::prelude::*};
+use zewif::Network;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum RecipientAddress {
@@ -13,6 +14,19 @@ pub enum RecipientAddress {
     Orchard(OrchardRawAddress),
 }
 
+impl RecipientAddress {
+    /// The canonical address string for the recipient: a t-address, a Sapling
+    /// address, or an Orchard-only unified address.
+    pub fn to_string(&self, network: &Network) -> String {
+        match self {
+            RecipientAddress::KeyId(key_id) => key_id.to_string(network),
+            RecipientAddress::ScriptId(script_id) => script_id.to_string(network),
+            RecipientAddress::Sapling(addr) => addr.to_string(network),
+            RecipientAddress::Orchard(addr) => addr.to_string(network),
+        }
+    }
+}
+
 impl Parse for RecipientAddress {
     fn parse(parser: &mut Parser) -> Result<Self>
     where
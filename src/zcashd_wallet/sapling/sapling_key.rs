@@ -1,13 +1,19 @@
-
+use zeroize::Zeroizing;
 use zewif::sapling::SaplingIncomingViewingKey;
 
 use crate::{parser::error::Result, zcashd_wallet::KeyMetadata};
 
-
-#[derive(Debug, Clone, PartialEq)]
+/// A Sapling extended spending key with its incoming viewing key and
+/// metadata.
+///
+/// `sapling-crypto`'s `ExtendedSpendingKey` cannot be zeroed, so the key is
+/// held as its 169-byte ZIP-32 encoding, which is zeroed when dropped, and
+/// decoded on each call to [`Self::extsk`]. The decoded copies, and the key
+/// passed to [`Self::new`], are not zeroed.
+#[derive(Clone, PartialEq)]
 pub struct SaplingKey {
     ivk: SaplingIncomingViewingKey,
-    extsk: sapling::zip32::ExtendedSpendingKey,
+    extsk: Zeroizing<[u8; 169]>,
     metadata: KeyMetadata,
 }
 
@@ -17,18 +23,58 @@ impl SaplingKey {
         extsk: sapling::zip32::ExtendedSpendingKey,
         metadata: KeyMetadata,
     ) -> Result<Self> {
-        Ok(Self { ivk, extsk, metadata })
+        Ok(Self {
+            ivk,
+            extsk: Zeroizing::new(extsk.to_bytes()),
+            metadata,
+        })
     }
 
     pub fn ivk(&self) -> &SaplingIncomingViewingKey {
         &self.ivk
     }
 
-    pub fn extsk(&self) -> &sapling::zip32::ExtendedSpendingKey {
-        &self.extsk
+    pub fn extsk(&self) -> sapling::zip32::ExtendedSpendingKey {
+        sapling::zip32::ExtendedSpendingKey::from_bytes(&*self.extsk)
+            .expect("encoded from a valid extended spending key")
     }
 
     pub fn metadata(&self) -> &KeyMetadata {
         &self.metadata
     }
 }
+
+impl std::fmt::Debug for SaplingKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SaplingKey")
+            .field("ivk", &self.ivk)
+            .field("extsk", &"<elided>")
+            .field("metadata", &self.metadata)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::ManuallyDrop;
+
+    use super::*;
+
+    #[test]
+    fn key_is_zeroed_when_dropped() {
+        let mut key = ManuallyDrop::new(
+            SaplingKey::new(
+                SaplingIncomingViewingKey::new([0x01; 32]),
+                sapling::zip32::ExtendedSpendingKey::master(&[7; 32]),
+                KeyMetadata::new(None, None, None),
+            )
+            .unwrap(),
+        );
+        let ptr: *const [u8; 169] = &*key.extsk;
+        assert_ne!(unsafe { *ptr }, [0; 169]);
+        // SAFETY: the key is not used again, and the memory its encoding
+        // occupied stays allocated.
+        unsafe { ManuallyDrop::drop(&mut key) };
+        assert_eq!(unsafe { *ptr }, [0; 169]);
+    }
+}
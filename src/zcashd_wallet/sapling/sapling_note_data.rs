@@ -1,10 +1,26 @@
+use std::collections::HashMap;
+
 use zewif::sapling::SaplingIncomingViewingKey;
-use crate::{parse, parser::prelude::*, zcashd_wallet::IncrementalWitness};
+
+use crate::{
+    parse,
+    parser::prelude::*,
+    zcashd_wallet::{CompactSize, IncrementalWitness, transparent::SaplingOutPoint},
+};
 
 pub type SaplingWitness = IncrementalWitness<32, [u8; 32]>;
 
+/// zcashd's `SaplingNoteData`: what the wallet knows about a Sapling note it
+/// received, as stored in a `CWalletTx`'s `mapSaplingNoteData`.
+///
+/// The record begins with the client version that wrote it, then holds the
+/// incoming viewing key, the optional cached nullifier, the cached witnesses
+/// and the height they were last updated at. zcashd has written this layout
+/// unchanged since Sapling support was added in 2.0.0, including across the
+/// 5.x series, so the version is recorded but not needed to parse the rest.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct SaplingNoteData {
+    outpoint: Option<SaplingOutPoint>,
     version: i32,
     incoming_viewing_key: SaplingIncomingViewingKey,
     nullifier: Option<[u8; 32]>,
@@ -13,6 +29,14 @@ pub struct SaplingNoteData {
 }
 
 impl SaplingNoteData {
+    /// The output this note was received in: the key of its entry in
+    /// `mapSaplingNoteData`, which the note data itself does not store. Set
+    /// when parsed by [`parse_sapling_note_data`], and `None` when the note
+    /// data is parsed on its own.
+    pub fn outpoint(&self) -> Option<SaplingOutPoint> {
+        self.outpoint
+    }
+
     pub fn version(&self) -> i32 {
         self.version
     }
@@ -32,16 +56,229 @@ impl SaplingNoteData {
     pub fn witness_height(&self) -> i32 {
         self.witness_height
     }
+
+    /// The block height each cached witness is as of, in the order of
+    /// [`Self::witnesses`].
+    ///
+    /// zcashd records only the height of the most recent witness
+    /// ([`Self::witness_height`]), but caches one witness per block, most
+    /// recent first, so the `i`th is as of block `witness_height - i`. Empty
+    /// if the witness height is unknown (negative).
+    pub fn witness_heights(&self) -> Vec<u32> {
+        let Ok(witness_height) = u32::try_from(self.witness_height) else {
+            return vec![];
+        };
+        (0..self.witnesses.len() as u32)
+            .map_while(|i| witness_height.checked_sub(i))
+            .collect()
+    }
+
+    /// The cached witness as of the latest block at or below `target_height`,
+    /// or `None` if every cached witness is more recent, or the witness height
+    /// is unknown. See [`Self::witness_heights`].
+    pub fn witness_at_height(&self, target_height: u32) -> Option<&SaplingWitness> {
+        let witness_height = u32::try_from(self.witness_height).ok()?;
+        let offset = witness_height.saturating_sub(target_height);
+        self.witnesses.get(offset as usize)
+    }
 }
 
 impl Parse for SaplingNoteData {
     fn parse(p: &mut Parser) -> Result<Self> {
         Ok(Self {
-            version: parse!(p, "version")?,
-            incoming_viewing_key: parse!(p, "incoming_viewing_key")?,
-            nullifier: parse!(p, "nullifier")?,
-            witnesses: parse!(p, "witnesses")?,
-            witness_height: parse!(p, "witness_height")?,
+            outpoint: None,
+            version: parse!(p, "note data client version")?,
+            incoming_viewing_key: parse!(p, "note incoming viewing key")?,
+            nullifier: parse!(p, "cached note nullifier")?,
+            witnesses: parse!(p, "cached note witnesses")?,
+            witness_height: parse!(p, "note witness height")?,
         })
     }
 }
+
+/// Parse a `CWalletTx`'s `mapSaplingNoteData`: the note data of each Sapling
+/// output the wallet received, keyed by the output. Each note data's
+/// [`SaplingNoteData::outpoint`] is its key.
+///
+/// An error names the entry it occurred in, and for the note data, the output
+/// index of the note.
+pub fn parse_sapling_note_data(
+    p: &mut Parser,
+) -> Result<HashMap<SaplingOutPoint, SaplingNoteData>> {
+    let length = *parse!(p, CompactSize, "Sapling note data count")?;
+    let mut notes = HashMap::with_capacity(length);
+    for i in 0..length {
+        let outpoint = parse!(
+            p,
+            SaplingOutPoint,
+            format!("outpoint of Sapling note data item {} of {}", i, length - 1)
+        )?;
+        let mut note_data = parse!(
+            p,
+            SaplingNoteData,
+            format!(
+                "Sapling note data item {} of {}, for output {}",
+                i,
+                length - 1,
+                outpoint.vout()
+            )
+        )?;
+        note_data.outpoint = Some(outpoint);
+        notes.insert(outpoint, note_data);
+    }
+    Ok(notes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A serialized witness over a tree of one leaf, with nothing filled and
+    /// no cursor.
+    fn witness_bytes() -> Vec<u8> {
+        let mut bytes = vec![0x01];
+        bytes.extend_from_slice(&[0x11; 32]);
+        bytes.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+        bytes
+    }
+
+    /// A serialized witness like [`witness_bytes`], over a leaf of `leaf`.
+    fn witness_bytes_of(leaf: u8) -> Vec<u8> {
+        let mut bytes = witness_bytes();
+        bytes[1..33].fill(leaf);
+        bytes
+    }
+
+    /// A serialized `SaplingNoteData` as zcashd writes it.
+    fn note_data_bytes(
+        version: i32,
+        nullifier: Option<[u8; 32]>,
+        witnesses: &[Vec<u8>],
+        witness_height: i32,
+    ) -> Vec<u8> {
+        let mut bytes = version.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&[0x33; 32]);
+        match nullifier {
+            Some(nullifier) => {
+                bytes.push(0x01);
+                bytes.extend_from_slice(&nullifier);
+            }
+            None => bytes.push(0x00),
+        }
+        bytes.push(witnesses.len() as u8);
+        for witness in witnesses {
+            bytes.extend_from_slice(witness);
+        }
+        bytes.extend_from_slice(&witness_height.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn zcashd_5_0_note_data_parses() {
+        let bytes = note_data_bytes(5_000_050, Some([0x44; 32]), &[witness_bytes()], 1_700_000);
+        let note_data = parse!(buf = &bytes, SaplingNoteData, "note data").unwrap();
+        assert_eq!(note_data.version(), 5_000_050);
+        assert_eq!(
+            note_data.incoming_viewing_key(),
+            &SaplingIncomingViewingKey::new([0x33; 32])
+        );
+        assert_eq!(note_data.nullifier(), Some(&[0x44; 32]));
+        assert_eq!(note_data.witnesses().len(), 1);
+        assert_eq!(note_data.witness_height(), 1_700_000);
+    }
+
+    #[test]
+    fn zcashd_5_3_note_data_parses() {
+        let bytes = note_data_bytes(5_030_050, None, &[], -1);
+        let note_data = parse!(buf = &bytes, SaplingNoteData, "note data").unwrap();
+        assert_eq!(note_data.version(), 5_030_050);
+        assert_eq!(note_data.nullifier(), None);
+        assert!(note_data.witnesses().is_empty());
+        assert_eq!(note_data.witness_height(), -1);
+    }
+
+    #[test]
+    fn witnesses_are_found_by_height() {
+        let witnesses = [
+            witness_bytes_of(3),
+            witness_bytes_of(2),
+            witness_bytes_of(1),
+        ];
+        let bytes = note_data_bytes(5_000_050, None, &witnesses, 1_000);
+        let note_data = parse!(buf = &bytes, SaplingNoteData, "note data").unwrap();
+        assert_eq!(note_data.witness_heights(), vec![1_000, 999, 998]);
+
+        let leaf_at = |height| {
+            note_data
+                .witness_at_height(height)
+                .map(|witness| witness.tree().left().unwrap().into_bytes()[0])
+        };
+        assert_eq!(leaf_at(1_200), Some(3));
+        assert_eq!(leaf_at(1_000), Some(3));
+        assert_eq!(leaf_at(999), Some(2));
+        assert_eq!(leaf_at(998), Some(1));
+        // Every cached witness is more recent.
+        assert_eq!(leaf_at(997), None);
+    }
+
+    #[test]
+    fn witnesses_have_no_height_when_it_is_unknown() {
+        let bytes = note_data_bytes(5_030_050, None, &[witness_bytes()], -1);
+        let note_data = parse!(buf = &bytes, SaplingNoteData, "note data").unwrap();
+        assert!(note_data.witness_heights().is_empty());
+        assert!(note_data.witness_at_height(1_000).is_none());
+    }
+
+    /// A serialized outpoint of output `vout` of a transaction.
+    fn outpoint_bytes(vout: u32) -> Vec<u8> {
+        let mut bytes = vec![0x55; 32];
+        bytes.extend_from_slice(&vout.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn note_data_map_entries_know_their_outpoint() {
+        let mut bytes = vec![0x02];
+        for vout in [0, 3] {
+            bytes.extend(outpoint_bytes(vout));
+            bytes.extend(note_data_bytes(5_030_050, None, &[], -1));
+        }
+        let mut p = Parser::new(&bytes);
+        let notes = parse_sapling_note_data(&mut p).unwrap();
+        p.check_finished().unwrap();
+
+        assert_eq!(notes.len(), 2);
+        for (outpoint, note_data) in &notes {
+            assert_eq!(note_data.outpoint(), Some(*outpoint));
+        }
+        let mut vouts: Vec<u32> = notes.keys().map(|outpoint| outpoint.vout()).collect();
+        vouts.sort();
+        assert_eq!(vouts, [0, 3]);
+
+        let standalone = note_data_bytes(5_030_050, None, &[], -1);
+        let note_data = parse!(buf = &standalone, SaplingNoteData, "note data").unwrap();
+        assert_eq!(note_data.outpoint(), None);
+    }
+
+    #[test]
+    fn note_data_map_errors_name_the_entry_and_field() {
+        let mut bytes = vec![0x02];
+        bytes.extend(outpoint_bytes(0));
+        bytes.extend(note_data_bytes(5_030_050, None, &[], -1));
+        bytes.extend(outpoint_bytes(7));
+        // The second note data ends within its incoming viewing key.
+        bytes.extend_from_slice(&5_030_050i32.to_le_bytes());
+        bytes.extend_from_slice(&[0x33; 16]);
+
+        let error = parse_sapling_note_data(&mut Parser::new(&bytes)).unwrap_err();
+        let frames = error.frames();
+        assert!(
+            frames.contains(&"note incoming viewing key".to_string()),
+            "{frames:?}"
+        );
+        assert!(
+            frames.contains(&"Sapling note data item 1 of 1, for output 7".to_string()),
+            "{frames:?}"
+        );
+    }
+}
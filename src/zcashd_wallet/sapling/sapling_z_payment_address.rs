@@ -1,7 +1,7 @@
 use zcash_address::{ToAddress, ZcashAddress};
 use zewif::Network;
 
-use crate::{migrate::primitives::address_network_from_zewif, parse, parser::prelude::*};
+use crate::{parse, parser::prelude::*, zcashd_wallet::address_network_from_zewif};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct SaplingZPaymentAddress {
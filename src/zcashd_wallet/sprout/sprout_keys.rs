@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 
+use zeroize::Zeroize;
+
 use super::{SproutPaymentAddress, SproutSpendingKey};
 
 #[derive(Clone, PartialEq)]
@@ -21,6 +23,13 @@ impl SproutKeys {
     }
 }
 
+/// Zeroes every spending key.
+impl Zeroize for SproutKeys {
+    fn zeroize(&mut self) {
+        self.0.values_mut().for_each(Zeroize::zeroize);
+    }
+}
+
 impl std::fmt::Debug for SproutKeys {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         let mut a = f.debug_list();
@@ -27,6 +27,17 @@ impl SproutNoteData {
     pub fn witness_height(&self) -> i32 {
         self.witness_height
     }
+
+    /// Whether zcashd cached any witness for this note.
+    pub fn has_witness(&self) -> bool {
+        !self.witnesses.is_empty()
+    }
+
+    /// The most recent cached witness. zcashd pushes each updated witness to
+    /// the front of the list, so this is the one for `witness_height`.
+    pub fn best_witness(&self) -> Option<&SproutWitness> {
+        self.witnesses.first()
+    }
 }
 
 impl Parse for SproutNoteData {
@@ -39,3 +50,79 @@ impl Parse for SproutNoteData {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An encoded witness whose tree holds a single left leaf, followed by one
+    /// filled node and no cursor.
+    fn witness_bytes(leaf: u8, filled: u8) -> Vec<u8> {
+        let mut bytes = vec![0x01];
+        bytes.extend_from_slice(&[leaf; 32]);
+        bytes.extend_from_slice(&[0x00, 0x00]); // no right leaf, no parents
+        bytes.push(0x01);
+        bytes.extend_from_slice(&[filled; 32]);
+        bytes.push(0x00); // no cursor
+        bytes
+    }
+
+    fn note_data_bytes(nullifier: Option<u8>, witnesses: &[Vec<u8>], height: i32) -> Vec<u8> {
+        let mut bytes = [0xAAu8; 32].to_vec();
+        bytes.extend_from_slice(&[0xBB; 32]);
+        match nullifier {
+            Some(n) => {
+                bytes.push(0x01);
+                bytes.extend_from_slice(&[n; 32]);
+            }
+            None => bytes.push(0x00),
+        }
+        bytes.push(witnesses.len() as u8);
+        for witness in witnesses {
+            bytes.extend_from_slice(witness);
+        }
+        bytes.extend_from_slice(&height.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn parses_note_data_with_witnesses() {
+        let bytes = note_data_bytes(
+            Some(0x11),
+            &[witness_bytes(0x22, 0x33), witness_bytes(0x44, 0x55)],
+            1_000,
+        );
+        let nd = SproutNoteData::parse_buf(&bytes, false).unwrap();
+
+        assert_eq!(nd.address().a_pk().into_bytes(), [0xAA; 32]);
+        assert_eq!(nd.address().pk_enc().into_bytes(), [0xBB; 32]);
+        assert_eq!(nd.nullifer().unwrap().into_bytes(), [0x11; 32]);
+        assert_eq!(nd.witnesses().len(), 2);
+        assert_eq!(nd.witness_height(), 1_000);
+        assert!(nd.has_witness());
+
+        let best = nd.best_witness().unwrap();
+        assert_eq!(best.tree().left().unwrap().into_bytes(), [0x22; 32]);
+        assert!(best.tree().right().is_none());
+        assert_eq!(best.filled().len(), 1);
+        assert_eq!(best.filled()[0].into_bytes(), [0x33; 32]);
+        assert!(best.cursor().is_none());
+    }
+
+    #[test]
+    fn parses_note_data_without_nullifier_or_witnesses() {
+        let bytes = note_data_bytes(None, &[], -1);
+        let nd = SproutNoteData::parse_buf(&bytes, false).unwrap();
+
+        assert!(nd.nullifer().is_none());
+        assert!(!nd.has_witness());
+        assert!(nd.best_witness().is_none());
+        assert_eq!(nd.witness_height(), -1);
+    }
+
+    #[test]
+    fn rejects_truncated_note_data() {
+        let bytes = note_data_bytes(Some(0x11), &[witness_bytes(0x22, 0x33)], 7);
+        assert!(SproutNoteData::parse_buf(&&bytes[..bytes.len() - 1], false).is_err());
+    }
+}
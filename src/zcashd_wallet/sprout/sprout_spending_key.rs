@@ -1,3 +1,5 @@
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
 use crate::zcashd_wallet::{KeyMetadata, u252};
 
 #[derive(Debug, Clone, PartialEq)]
@@ -21,3 +23,17 @@ impl SproutSpendingKey {
         Self { key, metadata }
     }
 }
+
+impl Zeroize for SproutSpendingKey {
+    fn zeroize(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+impl Drop for SproutSpendingKey {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for SproutSpendingKey {}
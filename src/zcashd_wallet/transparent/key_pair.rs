@@ -1,11 +1,13 @@
 use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
 use zewif::Data;
 
 use crate::{
     parser::error::{ParseErrorKind, Result},
     zcashd_wallet::{
-        KeyMetadata, u256,
+        KeyMetadata, SecondsSinceEpoch,
         transparent::{PrivKey, PubKey},
+        u256,
     },
 };
 
@@ -28,6 +30,17 @@ impl KeyPair {
     pub fn metadata(&self) -> &KeyMetadata {
         &self.metadata
     }
+
+    pub(crate) fn set_create_time(&mut self, create_time: SecondsSinceEpoch) {
+        self.metadata.set_create_time(create_time);
+    }
+}
+
+/// Zeroes the private key.
+impl Zeroize for KeyPair {
+    fn zeroize(&mut self) {
+        self.privkey.zeroize();
+    }
 }
 
 impl KeyPair {
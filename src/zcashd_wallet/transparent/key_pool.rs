@@ -1,4 +1,3 @@
-
 use crate::{
     parse,
     parser::prelude::*,
@@ -7,11 +6,17 @@ use crate::{
 
 use super::PubKey;
 
+/// A pre-generated transparent key held in reserve (`CKeyPool`).
+///
+/// The record is serialized as `nVersion`, `nTime`, and `vchPubKey`. Newer
+/// entries append an `fInternal` byte marking keys reserved for change; as in
+/// the upstream reader, it is read only when present and defaults to external.
 #[derive(Debug, Clone, PartialEq)]
 pub struct KeyPoolEntry {
     version: ClientVersion,
     timestamp: SecondsSinceEpoch,
     key: PubKey,
+    internal: bool,
 }
 
 impl KeyPoolEntry {
@@ -26,14 +31,81 @@ impl KeyPoolEntry {
     pub fn key(&self) -> &PubKey {
         &self.key
     }
+
+    /// Whether this key is reserved for change (`fInternal`) rather than for
+    /// external receiving addresses.
+    pub fn is_internal(&self) -> bool {
+        self.internal
+    }
 }
 
 impl Parse for KeyPoolEntry {
     fn parse(p: &mut Parser) -> Result<Self> {
+        let version = parse!(p, "version")?;
+        let timestamp = parse!(p, "timestamp")?;
+        let key = parse!(p, "key")?;
+        // Entries written before the external/internal split end here.
+        let internal = if p.remaining() > 0 {
+            parse!(p, bool, "internal")?
+        } else {
+            false
+        };
         Ok(Self {
-            version: parse!(p, "version")?,
-            timestamp: parse!(p, "timestamp")?,
-            key: parse!(p, "key")?,
+            version,
+            timestamp,
+            key,
+            internal,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry_bytes(version: u32, time: u64, internal: Option<bool>) -> Vec<u8> {
+        let mut bytes = version.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&time.to_le_bytes());
+        bytes.push(33);
+        bytes.push(0x02);
+        bytes.extend_from_slice(&[0x11; 32]);
+        if let Some(internal) = internal {
+            bytes.push(internal as u8);
+        }
+        bytes
+    }
+
+    #[test]
+    fn parses_entry_without_internal_flag() {
+        let bytes = entry_bytes(2_010_050, 1_600_000_000, None);
+        let entry = KeyPoolEntry::parse_buf(&bytes, false).unwrap();
+        assert_eq!(entry.version().version(), 2_010_050);
+        assert_eq!(entry.timestamp(), SecondsSinceEpoch::from(1_600_000_000u64));
+        assert!(entry.key().is_compressed());
+        assert!(!entry.is_internal());
+    }
+
+    #[test]
+    fn parses_entry_with_internal_flag() {
+        let bytes = entry_bytes(6_000_050, 1_700_000_000, Some(true));
+        let entry = KeyPoolEntry::parse_buf(&bytes, false).unwrap();
+        assert_eq!(entry.version().version(), 6_000_050);
+        assert_eq!(entry.timestamp(), SecondsSinceEpoch::from(1_700_000_000u64));
+        assert_eq!(entry.key().as_slice()[0], 0x02);
+        assert!(entry.is_internal());
+
+        let external = entry_bytes(6_000_050, 1_700_000_000, Some(false));
+        assert!(
+            !KeyPoolEntry::parse_buf(&external, false)
+                .unwrap()
+                .is_internal()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_internal_flag() {
+        let mut bytes = entry_bytes(6_000_050, 1_700_000_000, None);
+        bytes.push(0x02);
+        assert!(KeyPoolEntry::parse_buf(&bytes, false).is_err());
+    }
+}
@@ -1,25 +1,81 @@
 use std::collections::HashMap;
 
+use zeroize::Zeroize;
+
 use super::{KeyPair, PubKey};
+use crate::zcashd_wallet::SecondsSinceEpoch;
 
 #[derive(Clone, PartialEq)]
-pub struct Keys(HashMap<PubKey, KeyPair>);
+pub struct Keys {
+    keys: HashMap<PubKey, KeyPair>,
+    timestamp_corrections: Vec<PubKey>,
+}
 
 impl Keys {
     pub fn new(map: HashMap<PubKey, KeyPair>) -> Self {
-        Self(map)
+        Self {
+            keys: map,
+            timestamp_corrections: Vec::new(),
+        }
     }
 
     pub fn keypairs(&self) -> impl Iterator<Item = &KeyPair> {
-        self.0.values()
+        self.keys.values()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.0.is_empty()
+        self.keys.is_empty()
     }
 
     pub fn keypair_for_pubkey(&self, pubkey: &PubKey) -> Option<&KeyPair> {
-        self.0.get(pubkey)
+        self.keys.get(pubkey)
+    }
+
+    /// Whether any key's creation time was filled in by
+    /// [`Self::correct_creation_times`].
+    pub fn has_corrected_timestamps(&self) -> bool {
+        !self.timestamp_corrections.is_empty()
+    }
+
+    /// The keys whose creation time was filled in by
+    /// [`Self::correct_creation_times`], ordered by public key.
+    pub fn timestamp_corrections(&self) -> &[PubKey] {
+        &self.timestamp_corrections
+    }
+
+    /// Give each key with no recorded creation time the earliest creation
+    /// time recorded for any other key, as an approximation of when the
+    /// wallet was created. Some zcashd versions wrote a creation time of 0,
+    /// which zcashd reads as unknown, for keys generated by earlier versions.
+    ///
+    /// Returns the time used, or `None` if no key was corrected: either none
+    /// lacks a creation time or none has one.
+    pub(crate) fn correct_creation_times(&mut self) -> Option<SecondsSinceEpoch> {
+        let earliest = self
+            .keys
+            .values()
+            .filter_map(|keypair| keypair.metadata().create_time())
+            .min()?;
+        let mut corrected = vec![];
+        for (pubkey, keypair) in &mut self.keys {
+            if keypair.metadata().create_time().is_none() {
+                keypair.set_create_time(earliest);
+                corrected.push(pubkey.clone());
+            }
+        }
+        if corrected.is_empty() {
+            return None;
+        }
+        corrected.sort_by(|a, b| a.as_slice().cmp(b.as_slice()));
+        self.timestamp_corrections = corrected;
+        Some(earliest)
+    }
+}
+
+/// Zeroes every private key.
+impl Zeroize for Keys {
+    fn zeroize(&mut self) {
+        self.keys.values_mut().for_each(Zeroize::zeroize);
     }
 }
 
@@ -32,3 +88,65 @@ impl std::fmt::Debug for Keys {
         a.finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zcashd_wallet::KeyMetadata;
+
+    /// Keys whose creation times are `create_times`, 0 meaning none.
+    fn keys_created_at(create_times: &[u64]) -> Keys {
+        let map = create_times
+            .iter()
+            .enumerate()
+            .map(|(i, &time)| {
+                let pubkey = PubKey::from_slice(&[2 + i as u8; 33]).unwrap();
+                let create_time = (time != 0).then(|| SecondsSinceEpoch::from(time));
+                let metadata = KeyMetadata::new(create_time, None, None);
+                let keypair = KeyPair::from_decrypted_scalar(pubkey.clone(), &[1; 32], metadata);
+                (pubkey, keypair)
+            })
+            .collect();
+        Keys::new(map)
+    }
+
+    #[test]
+    fn zero_creation_times_take_the_earliest_recorded_time() {
+        let mut keys = keys_created_at(&[0, 1_600_000_000, 0, 1_500_000_000]);
+        assert!(!keys.has_corrected_timestamps());
+
+        let earliest = SecondsSinceEpoch::from(1_500_000_000u64);
+        assert_eq!(keys.correct_creation_times(), Some(earliest));
+        assert!(keys.has_corrected_timestamps());
+        assert_eq!(
+            keys.timestamp_corrections(),
+            [
+                PubKey::from_slice(&[2; 33]).unwrap(),
+                PubKey::from_slice(&[4; 33]).unwrap(),
+            ]
+        );
+        for pubkey in keys.timestamp_corrections() {
+            let keypair = keys.keypair_for_pubkey(pubkey).unwrap();
+            assert_eq!(keypair.metadata().create_time(), Some(earliest));
+        }
+        let recorded = PubKey::from_slice(&[3; 33]).unwrap();
+        assert_eq!(
+            keys.keypair_for_pubkey(&recorded)
+                .unwrap()
+                .metadata()
+                .create_time(),
+            Some(SecondsSinceEpoch::from(1_600_000_000u64))
+        );
+    }
+
+    #[test]
+    fn nothing_is_corrected_without_a_recorded_time() {
+        let mut keys = keys_created_at(&[0, 0]);
+        assert_eq!(keys.correct_creation_times(), None);
+        assert!(!keys.has_corrected_timestamps());
+
+        let mut keys = keys_created_at(&[1_600_000_000, 1_500_000_000]);
+        assert_eq!(keys.correct_creation_times(), None);
+        assert!(keys.timestamp_corrections().is_empty());
+    }
+}
@@ -4,7 +4,7 @@ use crate::{parse, parser::prelude::*};
 
 pub type SaplingOutPoint = OutPoint;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct OutPoint {
     txid: TxId,
     vout: u32,
@@ -23,8 +23,8 @@ impl OutPoint {
 impl Parse for OutPoint {
     fn parse(p: &mut Parser) -> Result<Self> {
         Ok(Self {
-            txid: parse!(p, "txid")?,
-            vout: parse!(p, "vout")?,
+            txid: parse!(p, "outpoint txid")?,
+            vout: parse!(p, "outpoint output index")?,
         })
     }
 }
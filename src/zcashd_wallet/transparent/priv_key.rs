@@ -1,4 +1,5 @@
 
+use zeroize::{Zeroize, ZeroizeOnDrop};
 use zewif::Data;
 
 use crate::{
@@ -110,6 +111,21 @@ impl std::fmt::Debug for PrivKey {
     }
 }
 
+/// Zeroes the DER blob, which holds the private scalar; the checksum is kept.
+impl Zeroize for PrivKey {
+    fn zeroize(&mut self) {
+        self.data[..].zeroize();
+    }
+}
+
+impl Drop for PrivKey {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for PrivKey {}
+
 impl AsRef<Data> for PrivKey {
     fn as_ref(&self) -> &Data {
         self.data()
@@ -265,4 +281,19 @@ mod tests {
         };
         assert!(pk.secp256k1_scalar().is_err());
     }
+
+    #[test]
+    fn zeroize_clears_the_blob() {
+        let mut pk = PrivKey {
+            data: make_compressed_blob([0x42; 32]),
+            hash: u256::default(),
+        };
+        pk.zeroize();
+        assert_eq!(pk.as_slice().len(), 214);
+        assert!(pk.as_slice().iter().all(|b| *b == 0));
+
+        // Dropping a key runs the same zeroization.
+        fn zeroized_on_drop<T: ZeroizeOnDrop>() {}
+        zeroized_on_drop::<PrivKey>();
+    }
 }
@@ -17,6 +17,22 @@ impl PubKey {
     pub fn is_compressed(&self) -> bool {
         self.0.as_slice().len() == Self::COMPRESSED_PUBLIC_KEY_SIZE
     }
+
+    /// A serialized compressed or uncompressed public key.
+    pub(crate) fn from_slice(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != Self::COMPRESSED_PUBLIC_KEY_SIZE && bytes.len() != Self::PUBLIC_KEY_SIZE {
+            return Err(ParseErrorKind::InvalidPubKeyLength(bytes.len()).into());
+        }
+        Ok(Self(Data::from_slice(bytes)))
+    }
+}
+
+/// The empty key of zcashd's default-constructed `CPubKey`, which stands in
+/// for the `defaultkey` of a wallet that records none.
+impl Default for PubKey {
+    fn default() -> Self {
+        Self(Data::new())
+    }
 }
 
 impl std::fmt::Debug for PubKey {
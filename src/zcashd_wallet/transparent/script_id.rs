@@ -1,10 +1,12 @@
 use zcash_address::{ToAddress, ZcashAddress};
 
-use crate::{parse, parser::prelude::*, zcashd_wallet::u160};
+use crate::{
+    parse,
+    parser::prelude::*,
+    zcashd_wallet::{address_network_from_zewif, u160},
+};
 use zewif::Network;
 
-use crate::migrate::primitives::address_network_from_zewif;
-
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ScriptId(u160);
 
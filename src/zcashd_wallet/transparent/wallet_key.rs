@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 
+use zeroize::Zeroize;
+
 use crate::zcashd_wallet::SecondsSinceEpoch;
 
 use super::{PrivKey, PubKey};
@@ -17,6 +19,13 @@ impl WalletKeys {
     }
 }
 
+/// Zeroes every private key.
+impl Zeroize for WalletKeys {
+    fn zeroize(&mut self) {
+        self.0.values_mut().for_each(Zeroize::zeroize);
+    }
+}
+
 impl std::fmt::Debug for WalletKeys {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         let mut a = f.debug_list();
@@ -73,3 +82,10 @@ impl WalletKey {
         &self.comment
     }
 }
+
+/// Zeroes the private key.
+impl Zeroize for WalletKey {
+    fn zeroize(&mut self) {
+        self.privkey.zeroize();
+    }
+}
@@ -1,3 +1,5 @@
+use zeroize::Zeroize;
+
 use crate::{parse, parser::prelude::*};
 
 pub const U252_SIZE: usize = 32;
@@ -123,6 +125,15 @@ impl TryFrom<&Vec<u8>> for u252 {
     }
 }
 
+/// A `u252` is `Copy`, so it cannot zero itself when dropped; the owner of a
+/// secret value, such as [`SproutSpendingKey`](super::sprout::SproutSpendingKey),
+/// zeroes it instead.
+impl Zeroize for u252 {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
 impl AsRef<[u8]> for u252 {
     fn as_ref(&self) -> &[u8] {
         &self.0
@@ -0,0 +1,56 @@
+use zewif::Data;
+
+use crate::zcashd_dump::DBKey;
+
+/// Key material from a wallet record that the upstream crate decoding it
+/// rejected, kept by a lenient parse as the record's raw bytes.
+///
+/// zcashd stored some values that later versions of `sapling-crypto` or
+/// `zcash_keys` refuse, such as non-canonical point encodings. A strict parse
+/// fails on them; a lenient one skips the key and keeps its record here, so
+/// that it is not lost and can be recovered by other means.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UndecodableKey {
+    key: DBKey,
+    value: Data,
+    kind: &'static str,
+    error: String,
+}
+
+impl UndecodableKey {
+    pub fn new(key: DBKey, value: Data, kind: &'static str, error: impl Into<String>) -> Self {
+        Self {
+            key,
+            value,
+            kind,
+            error: error.into(),
+        }
+    }
+
+    /// The database key of the record holding the key material.
+    pub fn key(&self) -> &DBKey {
+        &self.key
+    }
+
+    /// The record's raw value.
+    pub fn value(&self) -> &Data {
+        &self.value
+    }
+
+    /// What the key material was decoded as, e.g. "Sapling extended full
+    /// viewing key".
+    pub fn kind(&self) -> &'static str {
+        self.kind
+    }
+
+    /// The error decoding it reported.
+    pub fn error(&self) -> &str {
+        &self.error
+    }
+}
+
+impl std::fmt::Display for UndecodableKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} in record {}: {}", self.kind, self.key, self.error)
+    }
+}
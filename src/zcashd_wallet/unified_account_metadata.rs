@@ -3,7 +3,10 @@ use zewif::SeedFingerprint;
 use crate::{parse, parser::prelude::*};
 
 /// This s a zcashd-specific internal unique identifier.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+///
+/// Fingerprints are ordered by their bytes as stored, which is the reverse of
+/// the display order of [`Self::to_hex`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct UfvkFingerprint([u8; 32]);
 
 impl UfvkFingerprint {
@@ -75,3 +78,44 @@ impl Parse for UnifiedAccountMetadata {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::*;
+
+    /// The order is total, agrees with `Eq`, and is the order of the stored
+    /// bytes rather than of the displayed hex.
+    #[test]
+    fn fingerprint_order_is_byte_order() {
+        // Fingerprints differing in their first, last, and a middle byte.
+        let mut fingerprints = vec![];
+        for (index, byte) in [(0, 1), (0, 0xff), (31, 1), (31, 0xff), (16, 0x80)] {
+            let mut bytes = [0u8; 32];
+            bytes[index] = byte;
+            fingerprints.push(UfvkFingerprint::new(bytes));
+        }
+        fingerprints.push(UfvkFingerprint::new([0; 32]));
+        fingerprints.push(UfvkFingerprint::new([0xff; 32]));
+
+        for a in &fingerprints {
+            for b in &fingerprints {
+                assert_eq!(a.cmp(b) == Ordering::Equal, a == b);
+                assert_eq!(a.cmp(b), b.cmp(a).reverse());
+                assert_eq!(a.cmp(b), a.0.cmp(&b.0));
+            }
+        }
+
+        let mut last_byte = [0u8; 32];
+        last_byte[31] = 1;
+        let mut first_byte = [0u8; 32];
+        first_byte[0] = 1;
+        let (last_byte, first_byte) = (
+            UfvkFingerprint::new(last_byte),
+            UfvkFingerprint::new(first_byte),
+        );
+        assert!(last_byte < first_byte);
+        assert!(last_byte.to_hex() > first_byte.to_hex());
+    }
+}
@@ -1,28 +1,31 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use zcash_keys::keys::UnifiedFullViewingKey;
+use zewif::sapling::SaplingIncomingViewingKey;
 
-use crate::zcashd_wallet::{UfvkFingerprint, UnifiedAccountMetadata, UnifiedAddressMetadata};
+use crate::zcashd_wallet::{
+    ReceiverType, UfvkFingerprint, UnifiedAccountMetadata, UnifiedAddressMetadata,
+};
 
 #[derive(Debug, Clone)]
 pub struct UnifiedAccounts {
     pub address_metadata: Vec<UnifiedAddressMetadata>,
-    pub full_viewing_keys: HashMap<UfvkFingerprint, UnifiedFullViewingKey>,
-    pub account_metadata: HashMap<UfvkFingerprint, UnifiedAccountMetadata>,
+    pub full_viewing_keys: BTreeMap<UfvkFingerprint, UnifiedFullViewingKey>,
+    pub account_metadata: BTreeMap<UfvkFingerprint, UnifiedAccountMetadata>,
 }
 
 impl UnifiedAccounts {
     pub fn none() -> Self {
         Self {
             address_metadata: vec![],
-            full_viewing_keys: HashMap::new(),
-            account_metadata: HashMap::new(),
+            full_viewing_keys: BTreeMap::new(),
+            account_metadata: BTreeMap::new(),
         }
     }
 
     pub fn new(
         address_metadata: Vec<UnifiedAddressMetadata>,
-        full_viewing_keys: HashMap<UfvkFingerprint, UnifiedFullViewingKey>,
-        account_metadata: HashMap<UfvkFingerprint, UnifiedAccountMetadata>,
+        full_viewing_keys: BTreeMap<UfvkFingerprint, UnifiedFullViewingKey>,
+        account_metadata: BTreeMap<UfvkFingerprint, UnifiedAccountMetadata>,
     ) -> Self {
         Self {
             address_metadata,
@@ -30,4 +33,188 @@ impl UnifiedAccounts {
             account_metadata,
         }
     }
+
+    /// The fingerprint of the unified account whose UFVK derives the Sapling
+    /// incoming viewing key `ivk`; see [`sapling_incoming_viewing_keys`].
+    pub fn account_for_sapling_ivk(
+        &self,
+        ivk: &SaplingIncomingViewingKey,
+    ) -> Option<&UfvkFingerprint> {
+        self.full_viewing_keys
+            .iter()
+            .find(|(_, ufvk)| sapling_incoming_viewing_keys(ufvk).contains(ivk))
+            .map(|(fingerprint, _)| fingerprint)
+    }
+
+    /// The unified addresses the wallet derived with a receiver of each of
+    /// the `required` types, such as those that can receive Orchard funds.
+    pub fn addresses_supporting<'a>(
+        &'a self,
+        required: &'a [ReceiverType],
+    ) -> impl Iterator<Item = &'a UnifiedAddressMetadata> {
+        self.address_metadata.iter().filter(move |metadata| {
+            required
+                .iter()
+                .all(|receiver_type| metadata.receiver_types.contains(receiver_type))
+        })
+    }
+
+    /// The fingerprints of the unified accounts whose UFVK can derive an
+    /// address with a receiver of each of the `required` types, whether or
+    /// not the wallet derived one. No UFVK derives P2SH receivers.
+    pub fn accounts_supporting<'a>(
+        &'a self,
+        required: &'a [ReceiverType],
+    ) -> impl Iterator<Item = &'a UfvkFingerprint> {
+        self.full_viewing_keys
+            .iter()
+            .filter(move |(_, ufvk)| {
+                required
+                    .iter()
+                    .all(|receiver_type| ufvk_supports(ufvk, *receiver_type))
+            })
+            .map(|(fingerprint, _)| fingerprint)
+    }
+}
+
+/// Whether `ufvk` has the component that derives receivers of
+/// `receiver_type`.
+fn ufvk_supports(ufvk: &UnifiedFullViewingKey, receiver_type: ReceiverType) -> bool {
+    match receiver_type {
+        ReceiverType::P2PKH => ufvk.transparent().is_some(),
+        ReceiverType::P2SH => false,
+        ReceiverType::Sapling => ufvk.sapling().is_some(),
+        ReceiverType::Orchard => ufvk.orchard().is_some(),
+    }
+}
+
+/// The Sapling incoming viewing keys of a unified full viewing key, external
+/// then internal, derived from its Sapling component; empty if it has none.
+///
+/// zcashd records the incoming viewing key a Sapling note was received with,
+/// so matching it against these finds the unified account that received it.
+pub fn sapling_incoming_viewing_keys(
+    ufvk: &UnifiedFullViewingKey,
+) -> Vec<SaplingIncomingViewingKey> {
+    ufvk.sapling()
+        .map(|dfvk| {
+            [::zip32::Scope::External, ::zip32::Scope::Internal]
+                .map(|scope| SaplingIncomingViewingKey::new(dfvk.to_ivk(scope).to_repr()))
+                .to_vec()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use zcash_keys::keys::UnifiedSpendingKey;
+    use zcash_protocol::consensus::MainNetwork;
+
+    use super::*;
+
+    fn ufvk(seed: u8) -> UnifiedFullViewingKey {
+        UnifiedSpendingKey::from_seed(&MainNetwork, &[seed; 32], zip32::AccountId::ZERO)
+            .unwrap()
+            .to_unified_full_viewing_key()
+    }
+
+    #[test]
+    fn sapling_ivk_is_matched_to_its_ufvk() {
+        let first = UfvkFingerprint::new([0x01; 32]);
+        let second = UfvkFingerprint::new([0x02; 32]);
+        let accounts = UnifiedAccounts::new(
+            vec![],
+            BTreeMap::from([(first, ufvk(0x5a)), (second, ufvk(0x5b))]),
+            BTreeMap::new(),
+        );
+
+        let dfvk = ufvk(0x5b).sapling().unwrap().clone();
+        let external =
+            SaplingIncomingViewingKey::new(dfvk.to_ivk(::zip32::Scope::External).to_repr());
+        let internal =
+            SaplingIncomingViewingKey::new(dfvk.to_ivk(::zip32::Scope::Internal).to_repr());
+        assert_eq!(
+            sapling_incoming_viewing_keys(&ufvk(0x5b)),
+            vec![external, internal]
+        );
+        assert_eq!(accounts.account_for_sapling_ivk(&external), Some(&second));
+        assert_eq!(accounts.account_for_sapling_ivk(&internal), Some(&second));
+        assert_eq!(
+            accounts.account_for_sapling_ivk(&SaplingIncomingViewingKey::new([0x33; 32])),
+            None
+        );
+    }
+
+    fn address(
+        key_id: UfvkFingerprint,
+        index: u8,
+        receivers: &[ReceiverType],
+    ) -> UnifiedAddressMetadata {
+        let mut diversifier_index = [0; 11];
+        diversifier_index[0] = index;
+        UnifiedAddressMetadata {
+            key_id,
+            diversifier_index,
+            receiver_types: receivers.iter().copied().collect(),
+        }
+    }
+
+    #[test]
+    fn addresses_are_filtered_by_their_receivers() {
+        let key_id = UfvkFingerprint::new([0x01; 32]);
+        let accounts = UnifiedAccounts::new(
+            vec![
+                address(key_id, 0, &[ReceiverType::Orchard]),
+                address(key_id, 1, &[ReceiverType::Sapling, ReceiverType::P2PKH]),
+                address(
+                    key_id,
+                    2,
+                    &[
+                        ReceiverType::Orchard,
+                        ReceiverType::Sapling,
+                        ReceiverType::P2PKH,
+                    ],
+                ),
+            ],
+            BTreeMap::new(),
+            BTreeMap::new(),
+        );
+        let indices = |required: &[ReceiverType]| -> Vec<u8> {
+            accounts
+                .addresses_supporting(required)
+                .map(|metadata| metadata.diversifier_index[0])
+                .collect()
+        };
+
+        assert_eq!(indices(&[ReceiverType::Orchard]), vec![0, 2]);
+        assert_eq!(indices(&[ReceiverType::Sapling]), vec![1, 2]);
+        assert_eq!(
+            indices(&[ReceiverType::Orchard, ReceiverType::P2PKH]),
+            vec![2]
+        );
+        assert!(indices(&[ReceiverType::P2SH]).is_empty());
+        assert_eq!(indices(&[]), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn accounts_are_filtered_by_their_ufvk_components() {
+        let first = UfvkFingerprint::new([0x01; 32]);
+        let second = UfvkFingerprint::new([0x02; 32]);
+        let accounts = UnifiedAccounts::new(
+            vec![],
+            BTreeMap::from([(first, ufvk(0x5a)), (second, ufvk(0x5b))]),
+            BTreeMap::new(),
+        );
+
+        let orchard: Vec<_> = accounts
+            .accounts_supporting(&[ReceiverType::Orchard, ReceiverType::Sapling])
+            .collect();
+        assert_eq!(orchard, vec![&first, &second]);
+        assert_eq!(
+            accounts
+                .accounts_supporting(&[ReceiverType::Orchard, ReceiverType::P2SH])
+                .count(),
+            0
+        );
+    }
 }
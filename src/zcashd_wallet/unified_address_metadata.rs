@@ -13,6 +13,35 @@ pub struct UnifiedAddressMetadata {
     pub receiver_types: HashSet<ReceiverType>,
 }
 
+impl UnifiedAddressMetadata {
+    /// The largest ZIP 32 diversifier index: 88 bits, all set.
+    pub const MAX_DIVERSIFIER_INDEX: u128 = (1 << 88) - 1;
+
+    /// The diversifier index as an integer, from its 11 little-endian bytes.
+    pub fn diversifier_index_as_u128(&self) -> u128 {
+        let mut bytes = [0; 16];
+        bytes[..11].copy_from_slice(&self.diversifier_index);
+        u128::from_le_bytes(bytes)
+    }
+
+    /// The 11 little-endian bytes of the diversifier index `value`, or `None`
+    /// if it is above [`Self::MAX_DIVERSIFIER_INDEX`].
+    pub fn diversifier_index_from_u128(value: u128) -> Option<[u8; 11]> {
+        if value > Self::MAX_DIVERSIFIER_INDEX {
+            return None;
+        }
+        let mut index = [0; 11];
+        index.copy_from_slice(&value.to_le_bytes()[..11]);
+        Some(index)
+    }
+
+    /// Whether the address is at diversifier index 0, the index of an
+    /// account's default address.
+    pub fn is_default_diversifier(&self) -> bool {
+        self.diversifier_index == [0; 11]
+    }
+}
+
 impl Parse for UnifiedAddressMetadata {
     fn parse(p: &mut Parser) -> Result<Self> {
         let key_id = parse!(p, "key_id")?;
@@ -25,3 +54,66 @@ impl Parse for UnifiedAddressMetadata {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata_at(diversifier_index: [u8; 11]) -> UnifiedAddressMetadata {
+        UnifiedAddressMetadata {
+            key_id: UfvkFingerprint::new([0x11; 32]),
+            diversifier_index,
+            receiver_types: HashSet::from([ReceiverType::Orchard]),
+        }
+    }
+
+    #[test]
+    fn zero_is_the_default_diversifier() {
+        let metadata = metadata_at([0; 11]);
+        assert_eq!(metadata.diversifier_index_as_u128(), 0);
+        assert!(metadata.is_default_diversifier());
+        assert_eq!(
+            UnifiedAddressMetadata::diversifier_index_from_u128(0),
+            Some([0; 11])
+        );
+    }
+
+    #[test]
+    fn diversifier_index_bytes_are_little_endian() {
+        let mut index = [0; 11];
+        index[0] = 0x02;
+        index[1] = 0x01;
+        let metadata = metadata_at(index);
+        assert_eq!(metadata.diversifier_index_as_u128(), 0x0102);
+        assert!(!metadata.is_default_diversifier());
+        assert_eq!(
+            UnifiedAddressMetadata::diversifier_index_from_u128(0x0102),
+            Some(index)
+        );
+    }
+
+    #[test]
+    fn largest_diversifier_index_uses_all_88_bits() {
+        let metadata = metadata_at([0xff; 11]);
+        assert_eq!(
+            metadata.diversifier_index_as_u128(),
+            UnifiedAddressMetadata::MAX_DIVERSIFIER_INDEX
+        );
+        assert_eq!(
+            UnifiedAddressMetadata::diversifier_index_from_u128(
+                UnifiedAddressMetadata::MAX_DIVERSIFIER_INDEX
+            ),
+            Some([0xff; 11])
+        );
+        assert_eq!(
+            UnifiedAddressMetadata::diversifier_index_from_u128(
+                UnifiedAddressMetadata::MAX_DIVERSIFIER_INDEX + 1
+            ),
+            None
+        );
+        assert_eq!(
+            UnifiedAddressMetadata::diversifier_index_from_u128(u128::MAX),
+            None
+        );
+    }
+}
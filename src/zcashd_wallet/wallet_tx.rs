@@ -1,11 +1,13 @@
 use std::collections::HashMap;
 use zcash_primitives::transaction::Transaction;
+use zcash_protocol::{consensus::BranchId, value::ZatBalance};
+use zcash_transparent::bundle::{Authorized, TxIn, TxOut};
 use zewif::{BlockHash, Data};
 
 use super::{
-    CompactSize,
+    KnownOrderFormData, OrderFormEntry,
     orchard::OrchardTxMeta,
-    sapling::SaplingNoteData,
+    sapling::{SaplingNoteData, parse_sapling_note_data},
     sprout::{JSOutPoint, SproutNoteData},
     transparent::SaplingOutPoint,
     u256,
@@ -25,7 +27,7 @@ pub struct WalletTx {
     // CWalletTx
     map_value: HashMap<String, String>,
     map_sprout_note_data: HashMap<JSOutPoint, SproutNoteData>,
-    order_form: Vec<(String, String)>,
+    order_form: Vec<OrderFormEntry>,
     time_received_is_tx_time: i32,
     time_received: i32,
     is_from_me: bool,
@@ -41,6 +43,46 @@ impl WalletTx {
         &self.transaction
     }
 
+    /// The transaction's transparent inputs; empty if it has no transparent
+    /// bundle.
+    pub fn vin(&self) -> &[TxIn<Authorized>] {
+        self.transaction
+            .transparent_bundle()
+            .map(|b| b.vin.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// The transaction's transparent outputs; empty if it has no transparent
+    /// bundle.
+    pub fn vout(&self) -> &[TxOut] {
+        self.transaction
+            .transparent_bundle()
+            .map(|b| b.vout.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Whether the transaction is a coinbase transaction: its single
+    /// transparent input spends the null outpoint.
+    pub fn is_coinbase(&self) -> bool {
+        self.transaction
+            .transparent_bundle()
+            .is_some_and(|b| b.is_coinbase())
+    }
+
+    /// The transaction's Sapling bundle, if it has Sapling spends or outputs.
+    pub fn sapling_bundle(
+        &self,
+    ) -> Option<&::sapling::Bundle<::sapling::bundle::Authorized, ZatBalance>> {
+        self.transaction.sapling_bundle()
+    }
+
+    /// The transaction's Orchard bundle, if it has Orchard actions.
+    pub fn orchard_bundle(
+        &self,
+    ) -> Option<&::orchard::Bundle<::orchard::bundle::Authorized, ZatBalance>> {
+        self.transaction.orchard_bundle()
+    }
+
     pub fn hash_block(&self) -> BlockHash {
         self.hash_block
     }
@@ -57,14 +99,53 @@ impl WalletTx {
         &self.map_value
     }
 
+    /// The legacy account (`fromaccount`) the transaction was sent from, if
+    /// recorded.
+    pub fn from_account(&self) -> Option<&str> {
+        self.map_value.get("fromaccount").map(String::as_str)
+    }
+
+    /// The transaction's position in the wallet's ordering (`n`), if recorded
+    /// and well-formed.
+    pub fn order_position(&self) -> Option<i64> {
+        self.map_value.get("n")?.parse().ok()
+    }
+
+    /// The wallet's "smart" time for the transaction (`timesmart`): the
+    /// receive time clamped to the times of its block and of the wallet's
+    /// earlier transactions, if recorded and well-formed.
+    pub fn time_smart(&self) -> Option<u32> {
+        self.map_value.get("timesmart")?.parse().ok()
+    }
+
+    /// Whether each output of the transaction was spent, as recorded by old
+    /// wallet versions in the `spent` entry as one `0` or `1` per output.
+    pub fn spent_flags(&self) -> Option<Vec<bool>> {
+        self.map_value
+            .get("spent")?
+            .chars()
+            .map(|c| match c {
+                '0' => Some(false),
+                '1' => Some(true),
+                _ => None,
+            })
+            .collect()
+    }
+
     pub fn map_sprout_note_data(&self) -> &HashMap<JSOutPoint, SproutNoteData> {
         &self.map_sprout_note_data
     }
 
-    pub fn order_form(&self) -> &[(String, String)] {
+    pub fn order_form(&self) -> &[OrderFormEntry] {
         &self.order_form
     }
 
+    /// The known fields of the transaction's order form; see
+    /// [`OrderFormEntry::parse_known_fields`].
+    pub fn known_order_form(&self) -> KnownOrderFormData {
+        OrderFormEntry::parse_known_fields_of(&self.order_form)
+    }
+
     pub fn time_received_is_tx_time(&self) -> i32 {
         self.time_received_is_tx_time
     }
@@ -100,21 +181,46 @@ impl Parse for ParseTransaction {
     where
         Self: Sized,
     {
+        // The consensus branch ID does not direct parsing: transaction serialization for v4 and
+        // below transactions does not encode it and v5 transaction parsing ignores it entirely.
+        // It is only recorded in the parsed transaction, which we parse solely so that we can
+        // reencode it without the remainder of the `CMerkleTx` and `CWalletTx` data. Sprout-era
+        // (v1 and v2) transactions, whose header lacks the overwintered bit, are nonetheless
+        // labelled with the Sprout branch rather than one they could never have been mined in.
+        let header = p.peek(4);
+        let branch_id = if header.len() == 4 && header[3] & 0x80 == 0 {
+            BranchId::Sprout
+        } else {
+            BranchId::Nu5
+        };
         Ok(ParseTransaction(
-            zcash_primitives::transaction::Transaction::read(
-                p,
-                // The consensus branch ID that we use here will be ignored; it does not direct and is
-                // not used by us after parsing; transaction serialization for v4 and below
-                // transactions do not encode it and v5 transaction parsing ignores it entirely,
-                // so it is essentially ephemeral as this parsing is only performed so that we can
-                // reencode the transaction without the remainder of the `CMerkleTx` and `CWalletTx`
-                // data.
-                zcash_protocol::consensus::BranchId::Nu5,
-            )?,
+            zcash_primitives::transaction::Transaction::read(p, branch_id)?,
         ))
     }
 }
 
+/// A `CWalletTx`'s `mapSaplingNoteData`, read by [`parse_sapling_note_data`].
+struct ParseSaplingNoteData(HashMap<SaplingOutPoint, SaplingNoteData>);
+impl Parse for ParseSaplingNoteData {
+    fn parse(p: &mut Parser) -> Result<Self> {
+        Ok(Self(parse_sapling_note_data(p)?))
+    }
+}
+
+/// An entry of the `vtxPrev` vector, in which very old wallet versions stored
+/// a `CMerkleTx` for each input's previous transaction. The entries are read
+/// only to be skipped.
+struct PrevMerkleTx;
+impl Parse for PrevMerkleTx {
+    fn parse(p: &mut Parser) -> Result<Self> {
+        parse!(p, ParseTransaction, "transaction")?;
+        parse!(p, BlockHash, "hash_block")?;
+        parse!(p, Vec<u256>, "merkle_branch")?;
+        parse!(p, i32, "index")?;
+        Ok(Self)
+    }
+}
+
 impl Parse for WalletTx {
     fn parse(p: &mut Parser) -> Result<Self> {
         // CTransaction
@@ -127,11 +233,7 @@ impl Parse for WalletTx {
         let index = parse!(p, "index")?;
 
         // CWalletTx
-        let unused_vt_prev = *parse!(p, CompactSize, "unused_vt_prev")?;
-        assert!(
-            unused_vt_prev == 0,
-            "unused field in CWalletTx is not empty"
-        );
+        parse!(p, Vec<PrevMerkleTx>, "unused_vt_prev")?;
 
         let map_value = parse!(p, "map_value")?;
         let map_sprout_note_data = parse!(p, "map_sprout_note_data")?;
@@ -143,7 +245,7 @@ impl Parse for WalletTx {
 
         let mut sapling_note_data = None;
         if transaction.version().has_sapling() {
-            let value = parse!(p, "sapling_note_data")?;
+            let ParseSaplingNoteData(value) = parse!(p, ParseSaplingNoteData, "sapling_note_data")?;
             sapling_note_data = Some(value);
         }
 
@@ -153,11 +255,10 @@ impl Parse for WalletTx {
             orchard_tx_meta = Some(value);
         }
 
+        // A field this parser does not know about; let the caller decide
+        // whether to skip the record rather than misreading it.
+        p.check_finished().with_frame("CWalletTx")?;
         let unparsed_data = p.rest();
-        assert!(
-            unparsed_data.is_empty(),
-            "unparsed_data in CWalletTx is not empty"
-        );
 
         Ok(Self {
             // CTransaction
@@ -183,3 +284,324 @@ impl Parse for WalletTx {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use zcash_protocol::consensus::BranchId;
+
+    use super::{KnownOrderFormData, WalletTx};
+    use crate::parser::prelude::*;
+
+    /// An empty version 1 transaction: no inputs, no outputs, lock time.
+    const EMPTY_TRANSACTION: [u8; 10] = [1, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+    /// A version 1 transaction spending one transparent input to two outputs.
+    fn transparent_transaction_bytes() -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        // vin: prevout, script_sig, sequence.
+        bytes.push(1);
+        bytes.extend_from_slice(&[0x11; 32]);
+        bytes.extend_from_slice(&3u32.to_le_bytes());
+        bytes.extend_from_slice(&[2, 0x51, 0x51]);
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        // vout: value, script_pubkey.
+        bytes.push(2);
+        for value in [5_000i64, 7_000] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+            bytes.extend_from_slice(&[1, 0x51]);
+        }
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes
+    }
+
+    /// A version 1 coinbase transaction as mined before Overwinter: a null
+    /// prevout whose script pushes the block height, paying the miner and
+    /// the founders' reward.
+    fn sprout_coinbase_transaction_bytes() -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.push(1);
+        bytes.extend_from_slice(&[0; 32]);
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        bytes.extend_from_slice(&[4, 3, 0x80, 0x84, 0x1e]);
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        bytes.push(2);
+        bytes.extend_from_slice(&1_000_000_000i64.to_le_bytes());
+        bytes.extend_from_slice(&[25, 0x76, 0xa9, 20]);
+        bytes.extend_from_slice(&[0x33; 20]);
+        bytes.extend_from_slice(&[0x88, 0xac]);
+        bytes.extend_from_slice(&250_000_000i64.to_le_bytes());
+        bytes.extend_from_slice(&[23, 0xa9, 20]);
+        bytes.extend_from_slice(&[0x44; 20]);
+        bytes.push(0x87);
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes
+    }
+
+    /// A version 2 transaction with a single JoinSplit, carrying a PHGR proof,
+    /// that moves 5,000 zatoshis out of the Sprout pool to one output.
+    fn sprout_joinsplit_transaction_bytes() -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        bytes.push(0);
+        bytes.push(1);
+        bytes.extend_from_slice(&5_000i64.to_le_bytes());
+        bytes.extend_from_slice(&[1, 0x51]);
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        // vJoinSplit: vpub_old, vpub_new, then the anchor, nullifiers,
+        // commitments, ephemeral key, random seed and MACs, the PHGR proof
+        // and the two note ciphertexts.
+        bytes.push(1);
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+        bytes.extend_from_slice(&5_000u64.to_le_bytes());
+        bytes.extend_from_slice(&[0x55; 32 * 9]);
+        bytes.extend_from_slice(&[0x66; 296]);
+        bytes.extend_from_slice(&[0x77; 601 * 2]);
+        // joinSplitPubKey, joinSplitSig.
+        bytes.extend_from_slice(&[0x88; 32]);
+        bytes.extend_from_slice(&[0x99; 64]);
+        bytes
+    }
+
+    /// A `CMerkleTx` holding an empty version 1 transaction.
+    fn merkle_tx_bytes() -> Vec<u8> {
+        merkle_tx_bytes_for(&EMPTY_TRANSACTION)
+    }
+
+    /// A `CMerkleTx` holding the serialized `transaction`.
+    fn merkle_tx_bytes_for(transaction: &[u8]) -> Vec<u8> {
+        let mut bytes = transaction.to_vec();
+        // CMerkleTx: block hash, empty merkle branch, index.
+        bytes.extend_from_slice(&[0x42; 32]);
+        bytes.push(0);
+        bytes.extend_from_slice(&(-1i32).to_le_bytes());
+        bytes
+    }
+
+    /// A `CWalletTx` record holding an empty version 1 transaction.
+    fn wallet_tx_bytes() -> Vec<u8> {
+        wallet_tx_bytes_with_vt_prev(0)
+    }
+
+    /// A `CWalletTx` record whose `vtxPrev` holds `count` merkle transactions.
+    fn wallet_tx_bytes_with_vt_prev(count: u8) -> Vec<u8> {
+        wallet_tx_record(count, &[])
+    }
+
+    /// A `CWalletTx` record whose `vtxPrev` holds `vt_prev` merkle
+    /// transactions and whose `mapValue` holds `map_value`.
+    fn wallet_tx_record(vt_prev: u8, map_value: &[(&str, &str)]) -> Vec<u8> {
+        wallet_tx_record_for(&EMPTY_TRANSACTION, vt_prev, map_value)
+    }
+
+    /// A `CWalletTx` record holding the serialized version 1 `transaction`.
+    fn wallet_tx_record_for(
+        transaction: &[u8],
+        vt_prev: u8,
+        map_value: &[(&str, &str)],
+    ) -> Vec<u8> {
+        wallet_tx_record_with_order_form(transaction, vt_prev, map_value, &[])
+    }
+
+    /// A `CWalletTx` record as [`wallet_tx_record_for`], with the given order
+    /// form entries.
+    fn wallet_tx_record_with_order_form(
+        transaction: &[u8],
+        vt_prev: u8,
+        map_value: &[(&str, &str)],
+        order_form: &[(&str, &[u8])],
+    ) -> Vec<u8> {
+        let mut bytes = merkle_tx_bytes_for(transaction);
+        // CWalletTx: vtxPrev.
+        bytes.push(vt_prev);
+        for _ in 0..vt_prev {
+            bytes.extend(merkle_tx_bytes());
+        }
+        // mapValue.
+        bytes.push(map_value.len() as u8);
+        for (key, value) in map_value {
+            for s in [key, value] {
+                bytes.push(s.len() as u8);
+                bytes.extend_from_slice(s.as_bytes());
+            }
+        }
+        // mapSproutNoteData.
+        bytes.push(0);
+        // vOrderForm.
+        bytes.push(order_form.len() as u8);
+        for (key, value) in order_form {
+            for s in [key.as_bytes(), *value] {
+                bytes.push(s.len() as u8);
+                bytes.extend_from_slice(s);
+            }
+        }
+        // fTimeReceivedIsTxTime, nTimeReceived, fFromMe, fSpent.
+        bytes.extend_from_slice(&0i32.to_le_bytes());
+        bytes.extend_from_slice(&1_600_000_000i32.to_le_bytes());
+        bytes.extend_from_slice(&[1, 0]);
+        bytes
+    }
+
+    #[test]
+    fn parses_a_complete_record() {
+        let tx = WalletTx::parse_buf(&wallet_tx_bytes(), false).unwrap();
+        assert_eq!(tx.time_received(), 1_600_000_000);
+        assert!(tx.is_from_me());
+        assert!(tx.unparsed_data().is_empty());
+    }
+
+    #[test]
+    fn skips_legacy_vt_prev_entries() {
+        let tx = WalletTx::parse_buf(&wallet_tx_bytes_with_vt_prev(2), false).unwrap();
+        assert_eq!(tx.time_received(), 1_600_000_000);
+        assert!(tx.is_from_me());
+        assert!(tx.unparsed_data().is_empty());
+    }
+
+    #[test]
+    fn map_value_fields_are_typed() {
+        let bytes = wallet_tx_record(
+            0,
+            &[
+                ("fromaccount", "savings"),
+                ("n", "17"),
+                ("timesmart", "1600000123"),
+                ("spent", "0110"),
+            ],
+        );
+        let tx = WalletTx::parse_buf(&bytes, false).unwrap();
+        assert_eq!(tx.from_account(), Some("savings"));
+        assert_eq!(tx.order_position(), Some(17));
+        assert_eq!(tx.time_smart(), Some(1_600_000_123));
+        assert_eq!(tx.spent_flags(), Some(vec![false, true, true, false]));
+    }
+
+    #[test]
+    fn absent_or_malformed_map_value_fields_are_none() {
+        let tx = WalletTx::parse_buf(&wallet_tx_bytes(), false).unwrap();
+        assert_eq!(tx.from_account(), None);
+        assert_eq!(tx.order_position(), None);
+        assert_eq!(tx.time_smart(), None);
+        assert_eq!(tx.spent_flags(), None);
+
+        let bytes = wallet_tx_record(0, &[("n", "x"), ("timesmart", "-1"), ("spent", "01?")]);
+        let tx = WalletTx::parse_buf(&bytes, false).unwrap();
+        assert_eq!(tx.order_position(), None);
+        assert_eq!(tx.time_smart(), None);
+        assert_eq!(tx.spent_flags(), None);
+    }
+
+    #[test]
+    fn order_form_fields_are_parsed() {
+        let payment_request: &[u8] = &[0x12, 0x0b, 0xff, 0x00];
+        let bytes = wallet_tx_record_with_order_form(
+            &EMPTY_TRANSACTION,
+            0,
+            &[],
+            &[
+                ("PaymentRequest", payment_request),
+                ("memo", b"rent for May"),
+                ("Message", b"thanks"),
+                ("other", b"ignored"),
+            ],
+        );
+        let tx = WalletTx::parse_buf(&bytes, false).unwrap();
+        assert_eq!(tx.order_form().len(), 4);
+        assert_eq!(tx.order_form()[3].key(), "other");
+        assert_eq!(
+            tx.known_order_form(),
+            KnownOrderFormData {
+                payment_request: Some(payment_request.to_vec()),
+                memo: Some("rent for May".to_string()),
+                message: Some("thanks".to_string()),
+            }
+        );
+
+        let tx = WalletTx::parse_buf(&wallet_tx_bytes(), false).unwrap();
+        assert!(tx.known_order_form().is_empty());
+    }
+
+    #[test]
+    fn transparent_accessors_match_the_bundle() {
+        let bytes = wallet_tx_record_for(&transparent_transaction_bytes(), 0, &[]);
+        let tx = WalletTx::parse_buf(&bytes, false).unwrap();
+        let bundle = tx.transaction().transparent_bundle().unwrap();
+        assert_eq!(tx.vin(), bundle.vin.as_slice());
+        assert_eq!(tx.vout(), bundle.vout.as_slice());
+        assert_eq!(tx.vin().len(), 1);
+        assert_eq!(tx.vout()[1].value().into_u64(), 7_000);
+        assert!(tx.sapling_bundle().is_none());
+        assert!(tx.orchard_bundle().is_none());
+    }
+
+    #[test]
+    fn accessors_of_an_empty_transaction_are_empty() {
+        let tx = WalletTx::parse_buf(&wallet_tx_bytes(), false).unwrap();
+        assert!(tx.transaction().transparent_bundle().is_none());
+        assert!(tx.vin().is_empty());
+        assert!(tx.vout().is_empty());
+        assert!(tx.sapling_bundle().is_none());
+        assert!(tx.orchard_bundle().is_none());
+    }
+
+    #[test]
+    fn trailing_bytes_are_an_error() {
+        let mut bytes = wallet_tx_bytes();
+        bytes.extend_from_slice(&[0xde, 0xad, 0xbe]);
+        let err = WalletTx::parse_buf(&bytes, false).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ParseErrorKind::TrailingData { remaining: 3 }
+        ));
+        assert_eq!(err.frames(), ["CWalletTx"]);
+    }
+
+    #[test]
+    fn sprout_era_transactions_round_trip() {
+        for transaction in [
+            sprout_coinbase_transaction_bytes(),
+            sprout_joinsplit_transaction_bytes(),
+        ] {
+            let bytes = wallet_tx_record_for(&transaction, 0, &[]);
+            let tx = WalletTx::parse_buf(&bytes, false).unwrap();
+            assert_eq!(tx.transaction().consensus_branch_id(), BranchId::Sprout);
+            assert!(tx.unparsed_data().is_empty());
+
+            let mut raw = vec![];
+            tx.transaction().write(&mut raw).unwrap();
+            assert_eq!(raw, transaction);
+        }
+    }
+
+    #[test]
+    fn sprout_era_transactions_keep_their_components() {
+        let bytes = wallet_tx_record_for(&sprout_coinbase_transaction_bytes(), 0, &[]);
+        let tx = WalletTx::parse_buf(&bytes, false).unwrap();
+        assert!(tx.is_coinbase());
+        assert_eq!(tx.vout().len(), 2);
+        assert_eq!(tx.vout()[0].value().into_u64(), 1_000_000_000);
+
+        let bytes = wallet_tx_record_for(&sprout_joinsplit_transaction_bytes(), 0, &[]);
+        let tx = WalletTx::parse_buf(&bytes, false).unwrap();
+        let joinsplits = &tx.transaction().sprout_bundle().unwrap().joinsplits;
+        assert_eq!(joinsplits.len(), 1);
+        assert_eq!(i64::from(joinsplits[0].net_value()), 5_000);
+        assert_eq!(tx.vout()[0].value().into_u64(), 5_000);
+    }
+
+    #[test]
+    fn overwintered_transactions_are_not_labelled_sprout() {
+        let mut transaction = vec![];
+        transaction.extend_from_slice(&(3u32 | 1 << 31).to_le_bytes());
+        transaction.extend_from_slice(&0x03c4_8270u32.to_le_bytes());
+        // No inputs or outputs, lock time, expiry height, no JoinSplits.
+        transaction.extend_from_slice(&[0, 0]);
+        transaction.extend_from_slice(&[0; 8]);
+        transaction.push(0);
+        let bytes = wallet_tx_record_for(&transaction, 0, &[]);
+        let tx = WalletTx::parse_buf(&bytes, false).unwrap();
+        assert_ne!(tx.transaction().consensus_branch_id(), BranchId::Sprout);
+        assert!(tx.sapling_note_data().is_none());
+    }
+}
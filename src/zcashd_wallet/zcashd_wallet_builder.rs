@@ -0,0 +1,539 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::OnceLock,
+};
+
+use zewif::{LegacySeed, Script, TxId, sapling::SaplingIncomingViewingKey};
+
+use super::{
+    Address, BlockLocator, ClientVersion, EncryptedLegacySeed, EncryptedMnemonic, LegacyHdSeed,
+    MasterKeyParams, MnemonicHDChain, MnemonicPhrase, NetworkInfo, ParseWarning, RecipientMapping,
+    UndecodableKey, UnifiedAccounts, WalletTx, ZcashdWallet,
+    orchard::OrchardNoteCommitmentTree,
+    sapling::{SaplingKeys, SaplingZPaymentAddress},
+    sprout::SproutKeys,
+    transparent::{KeyPoolEntry, Keys, PubKey, ScriptId, WalletKeys, WatchScript},
+};
+
+/// Errors arising while building a [`ZcashdWallet`] with a
+/// [`ZcashdWalletBuilder`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum WalletBuildError {
+    /// A record every zcashd wallet holds was not given.
+    #[error("no {keyname} record was given")]
+    MissingRecord { keyname: &'static str },
+
+    /// A transaction is keyed by an ID other than its own.
+    #[error("transaction {txid:?} is keyed by {key:?}")]
+    MismatchedTxId { key: TxId, txid: TxId },
+}
+
+/// Builds a [`ZcashdWallet`] field by field. The parser and the key export
+/// reader build their wallets with it.
+///
+/// Only the `bestblock` and `networkinfo` records, which every zcashd wallet
+/// holds, must be given. Any other field defaults to what the parser reads
+/// from a wallet without the record: an empty collection, `None`, the empty
+/// Orchard note commitment tree, or 0 for `witnesscachesize` and the client
+/// version. The minimum version defaults to the client version.
+#[derive(Debug, Default)]
+pub struct ZcashdWalletBuilder {
+    address_names: BTreeMap<Address, String>,
+    address_purposes: BTreeMap<Address, String>,
+    bestblock_nomerkle: Option<BlockLocator>,
+    bestblock: Option<BlockLocator>,
+    client_version: Option<ClientVersion>,
+    cscripts: HashMap<ScriptId, Script>,
+    default_key: Option<PubKey>,
+    key_pool: HashMap<i64, KeyPoolEntry>,
+    keys: Option<Keys>,
+    min_version: Option<ClientVersion>,
+    legacy_hd_seed: Option<LegacySeed>,
+    encrypted_legacy_hd_seed: Option<EncryptedLegacySeed>,
+    mnemonic_hd_chain: Option<MnemonicHDChain>,
+    bip39_mnemonic: Option<MnemonicPhrase>,
+    encrypted_mnemonic: Option<EncryptedMnemonic>,
+    master_keys: Vec<MasterKeyParams>,
+    network_info: Option<NetworkInfo>,
+    orchard_note_commitment_tree: Option<OrchardNoteCommitmentTree>,
+    orchard_tree_history: Vec<OrchardNoteCommitmentTree>,
+    orchard_placeholder_positions: BTreeMap<TxId, BTreeMap<u32, u64>>,
+    orderposnext: Option<i64>,
+    parse_warnings: Vec<ParseWarning>,
+    sapling_extended_full_viewing_keys:
+        HashMap<SaplingIncomingViewingKey, ::sapling::zip32::ExtendedFullViewingKey>,
+    sapling_keys: Option<SaplingKeys>,
+    sapling_z_addresses: HashMap<SaplingZPaymentAddress, SaplingIncomingViewingKey>,
+    send_recipients: HashMap<TxId, Vec<RecipientMapping>>,
+    sprout_keys: Option<SproutKeys>,
+    wallet_keys: Option<WalletKeys>,
+    transactions: HashMap<TxId, WalletTx>,
+    undecodable_keys: Vec<UndecodableKey>,
+    unified_accounts: Option<UnifiedAccounts>,
+    watch_scripts: Vec<WatchScript>,
+    witnesscachesize: i64,
+}
+
+impl ZcashdWalletBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn address_names(mut self, address_names: BTreeMap<Address, String>) -> Self {
+        self.address_names = address_names;
+        self
+    }
+
+    pub fn address_purposes(mut self, address_purposes: BTreeMap<Address, String>) -> Self {
+        self.address_purposes = address_purposes;
+        self
+    }
+
+    pub fn bestblock_nomerkle(mut self, bestblock_nomerkle: BlockLocator) -> Self {
+        self.bestblock_nomerkle = Some(bestblock_nomerkle);
+        self
+    }
+
+    pub fn bestblock(mut self, bestblock: BlockLocator) -> Self {
+        self.bestblock = Some(bestblock);
+        self
+    }
+
+    pub fn client_version(mut self, client_version: ClientVersion) -> Self {
+        self.client_version = Some(client_version);
+        self
+    }
+
+    pub fn cscripts(mut self, cscripts: HashMap<ScriptId, Script>) -> Self {
+        self.cscripts = cscripts;
+        self
+    }
+
+    pub fn default_key(mut self, default_key: PubKey) -> Self {
+        self.default_key = Some(default_key);
+        self
+    }
+
+    pub fn key_pool(mut self, key_pool: HashMap<i64, KeyPoolEntry>) -> Self {
+        self.key_pool = key_pool;
+        self
+    }
+
+    pub fn keys(mut self, keys: Keys) -> Self {
+        self.keys = Some(keys);
+        self
+    }
+
+    pub fn min_version(mut self, min_version: ClientVersion) -> Self {
+        self.min_version = Some(min_version);
+        self
+    }
+
+    pub fn legacy_hd_seed(mut self, legacy_hd_seed: LegacySeed) -> Self {
+        self.legacy_hd_seed = Some(legacy_hd_seed);
+        self
+    }
+
+    pub fn encrypted_legacy_hd_seed(mut self, seed: EncryptedLegacySeed) -> Self {
+        self.encrypted_legacy_hd_seed = Some(seed);
+        self
+    }
+
+    pub fn mnemonic_hd_chain(mut self, mnemonic_hd_chain: MnemonicHDChain) -> Self {
+        self.mnemonic_hd_chain = Some(mnemonic_hd_chain);
+        self
+    }
+
+    pub fn bip39_mnemonic(mut self, bip39_mnemonic: MnemonicPhrase) -> Self {
+        self.bip39_mnemonic = Some(bip39_mnemonic);
+        self
+    }
+
+    pub fn encrypted_mnemonic(mut self, encrypted_mnemonic: EncryptedMnemonic) -> Self {
+        self.encrypted_mnemonic = Some(encrypted_mnemonic);
+        self
+    }
+
+    pub fn master_keys(mut self, master_keys: Vec<MasterKeyParams>) -> Self {
+        self.master_keys = master_keys;
+        self
+    }
+
+    pub fn network_info(mut self, network_info: NetworkInfo) -> Self {
+        self.network_info = Some(network_info);
+        self
+    }
+
+    pub fn orchard_note_commitment_tree(mut self, tree: OrchardNoteCommitmentTree) -> Self {
+        self.orchard_note_commitment_tree = Some(tree);
+        self
+    }
+
+    pub fn orchard_tree_history(mut self, history: Vec<OrchardNoteCommitmentTree>) -> Self {
+        self.orchard_tree_history = history;
+        self
+    }
+
+    pub fn orchard_placeholder_positions(
+        mut self,
+        positions: BTreeMap<TxId, BTreeMap<u32, u64>>,
+    ) -> Self {
+        self.orchard_placeholder_positions = positions;
+        self
+    }
+
+    pub fn orderposnext(mut self, orderposnext: i64) -> Self {
+        self.orderposnext = Some(orderposnext);
+        self
+    }
+
+    pub fn parse_warnings(mut self, parse_warnings: Vec<ParseWarning>) -> Self {
+        self.parse_warnings = parse_warnings;
+        self
+    }
+
+    pub fn sapling_extended_full_viewing_keys(
+        mut self,
+        keys: HashMap<SaplingIncomingViewingKey, ::sapling::zip32::ExtendedFullViewingKey>,
+    ) -> Self {
+        self.sapling_extended_full_viewing_keys = keys;
+        self
+    }
+
+    pub fn sapling_keys(mut self, sapling_keys: SaplingKeys) -> Self {
+        self.sapling_keys = Some(sapling_keys);
+        self
+    }
+
+    pub fn sapling_z_addresses(
+        mut self,
+        sapling_z_addresses: HashMap<SaplingZPaymentAddress, SaplingIncomingViewingKey>,
+    ) -> Self {
+        self.sapling_z_addresses = sapling_z_addresses;
+        self
+    }
+
+    pub fn send_recipients(
+        mut self,
+        send_recipients: HashMap<TxId, Vec<RecipientMapping>>,
+    ) -> Self {
+        self.send_recipients = send_recipients;
+        self
+    }
+
+    pub fn sprout_keys(mut self, sprout_keys: SproutKeys) -> Self {
+        self.sprout_keys = Some(sprout_keys);
+        self
+    }
+
+    pub fn wallet_keys(mut self, wallet_keys: WalletKeys) -> Self {
+        self.wallet_keys = Some(wallet_keys);
+        self
+    }
+
+    pub fn transactions(mut self, transactions: HashMap<TxId, WalletTx>) -> Self {
+        self.transactions = transactions;
+        self
+    }
+
+    pub fn undecodable_keys(mut self, undecodable_keys: Vec<UndecodableKey>) -> Self {
+        self.undecodable_keys = undecodable_keys;
+        self
+    }
+
+    pub fn unified_accounts(mut self, unified_accounts: UnifiedAccounts) -> Self {
+        self.unified_accounts = Some(unified_accounts);
+        self
+    }
+
+    pub fn watch_scripts(mut self, watch_scripts: Vec<WatchScript>) -> Self {
+        self.watch_scripts = watch_scripts;
+        self
+    }
+
+    pub fn witnesscachesize(mut self, witnesscachesize: i64) -> Self {
+        self.witnesscachesize = witnesscachesize;
+        self
+    }
+
+    /// Build the wallet, checking that the `bestblock` and `networkinfo`
+    /// records were given and that each transaction is keyed by its own ID.
+    pub fn build(self) -> Result<ZcashdWallet, WalletBuildError> {
+        let bestblock = self.bestblock.ok_or(WalletBuildError::MissingRecord {
+            keyname: "bestblock",
+        })?;
+        let network_info = self.network_info.ok_or(WalletBuildError::MissingRecord {
+            keyname: "networkinfo",
+        })?;
+        for (key, wtx) in &self.transactions {
+            let txid = TxId::from_bytes(*wtx.transaction().txid().as_ref());
+            if *key != txid {
+                return Err(WalletBuildError::MismatchedTxId { key: *key, txid });
+            }
+        }
+
+        let client_version = self
+            .client_version
+            .unwrap_or_else(|| ClientVersion::from_integer(0));
+        Ok(ZcashdWallet {
+            address_names: self.address_names,
+            address_purposes: self.address_purposes,
+            bestblock_nomerkle: self.bestblock_nomerkle,
+            bestblock,
+            client_version,
+            cscripts: self.cscripts,
+            default_key: self.default_key.unwrap_or_default(),
+            key_pool: self.key_pool,
+            keys: self.keys.unwrap_or_else(|| Keys::new(HashMap::new())),
+            min_version: self.min_version.unwrap_or(client_version),
+            legacy_hd_seed: self.legacy_hd_seed.map(LegacyHdSeed),
+            encrypted_legacy_hd_seed: self.encrypted_legacy_hd_seed,
+            mnemonic_hd_chain: self.mnemonic_hd_chain,
+            bip39_mnemonic: self.bip39_mnemonic,
+            encrypted_mnemonic: self.encrypted_mnemonic,
+            master_keys: self.master_keys,
+            network_info,
+            orchard_note_commitment_tree: self
+                .orchard_note_commitment_tree
+                .unwrap_or_else(OrchardNoteCommitmentTree::empty),
+            orchard_tree_history: self.orchard_tree_history,
+            orchard_placeholder_positions: self.orchard_placeholder_positions,
+            orderposnext: self.orderposnext,
+            parse_warnings: self.parse_warnings,
+            sapling_extended_full_viewing_keys: self.sapling_extended_full_viewing_keys,
+            sapling_keys: self
+                .sapling_keys
+                .unwrap_or_else(|| SaplingKeys::new(HashMap::new())),
+            sapling_z_addresses: self.sapling_z_addresses,
+            send_recipients: self.send_recipients,
+            sprout_keys: self.sprout_keys,
+            wallet_keys: self.wallet_keys,
+            transactions: self.transactions,
+            undecodable_keys: self.undecodable_keys,
+            unified_accounts: self.unified_accounts.unwrap_or_else(UnifiedAccounts::none),
+            watch_scripts: self.watch_scripts,
+            witnesscachesize: self.witnesscachesize,
+            address_accounts: OnceLock::new(),
+            sapling_addresses_by_ivk: OnceLock::new(),
+            addresses_by_name: OnceLock::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zewif::Network;
+
+    use super::*;
+    use crate::parser::prelude::*;
+
+    fn regtest() -> NetworkInfo {
+        NetworkInfo::new(Network::Regtest(Default::default()))
+    }
+
+    fn locator() -> BlockLocator {
+        BlockLocator::new(ClientVersion::from_integer(0), vec![])
+    }
+
+    /// A wallet transaction holding a version 1 transaction with no inputs
+    /// and one output.
+    fn wallet_tx() -> WalletTx {
+        let mut bytes = 1u32.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&[0, 1]);
+        bytes.extend_from_slice(&1_000i64.to_le_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        // CMerkleTx, then CWalletTx with empty collections.
+        bytes.extend_from_slice(&[0x42; 32]);
+        bytes.push(0);
+        bytes.extend_from_slice(&0i32.to_le_bytes());
+        bytes.extend_from_slice(&[0, 0, 0, 0]);
+        bytes.extend_from_slice(&0i32.to_le_bytes());
+        bytes.extend_from_slice(&1_600_000_000i32.to_le_bytes());
+        bytes.extend_from_slice(&[1, 0]);
+        WalletTx::parse_buf(&bytes, false).unwrap()
+    }
+
+    #[test]
+    fn builds_a_minimal_wallet_with_defaults() {
+        let wallet = ZcashdWalletBuilder::new()
+            .bestblock(locator())
+            .network_info(regtest())
+            .build()
+            .unwrap();
+
+        assert!(matches!(wallet.network(), Network::Regtest(_)));
+        assert_eq!(wallet.bestblock(), &locator());
+        assert_eq!(wallet.client_version(), &ClientVersion::from_integer(0));
+        assert_eq!(wallet.min_version(), &ClientVersion::from_integer(0));
+        assert_eq!(wallet.default_key(), &PubKey::default());
+        assert!(wallet.keys().is_empty());
+        assert!(wallet.transactions().is_empty());
+        assert!(wallet.legacy_hd_seed().is_none());
+        assert!(wallet.bip39_mnemonic().is_none());
+        assert!(wallet.orchard_tree_history().is_empty());
+        assert_eq!(wallet.orderposnext(), None);
+        assert_eq!(wallet.witnesscachesize(), 0);
+        assert!(!wallet.is_encrypted());
+    }
+
+    #[test]
+    fn required_records_must_be_given() {
+        let err = ZcashdWalletBuilder::new()
+            .network_info(regtest())
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            WalletBuildError::MissingRecord {
+                keyname: "bestblock"
+            }
+        );
+
+        let err = ZcashdWalletBuilder::new()
+            .bestblock(locator())
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            WalletBuildError::MissingRecord {
+                keyname: "networkinfo"
+            }
+        );
+    }
+
+    #[test]
+    fn builds_a_wallet_from_every_field() {
+        let client_version = ClientVersion::from_integer(5_030_050);
+        let address = Address::from("tmGys6dBuEGjch5LFnhdo5gpSa7jiNRWse3");
+        let wtx = wallet_tx();
+        let txid = TxId::from_bytes(*wtx.transaction().txid().as_ref());
+
+        let wallet = ZcashdWalletBuilder::new()
+            .address_names(BTreeMap::from([(address.clone(), "savings".to_string())]))
+            .address_purposes(BTreeMap::from([(address.clone(), "receive".to_string())]))
+            .bestblock_nomerkle(locator())
+            .bestblock(BlockLocator::new(client_version, vec![]))
+            .client_version(client_version)
+            .cscripts(HashMap::new())
+            .default_key(PubKey::default())
+            .key_pool(HashMap::new())
+            .keys(Keys::new(HashMap::new()))
+            .min_version(ClientVersion::from_integer(60_000))
+            .legacy_hd_seed(LegacySeed::from_vec(vec![7; 32]).unwrap())
+            .master_keys(vec![])
+            .network_info(regtest())
+            .orchard_note_commitment_tree(OrchardNoteCommitmentTree::empty())
+            .orchard_tree_history(vec![OrchardNoteCommitmentTree::empty()])
+            .orderposnext(12)
+            .sapling_extended_full_viewing_keys(HashMap::new())
+            .sapling_keys(SaplingKeys::new(HashMap::new()))
+            .sapling_z_addresses(HashMap::new())
+            .send_recipients(HashMap::new())
+            .transactions(HashMap::from([(txid, wtx)]))
+            .undecodable_keys(vec![])
+            .unified_accounts(UnifiedAccounts::none())
+            .watch_scripts(vec![])
+            .witnesscachesize(100)
+            .build()
+            .unwrap();
+
+        assert_eq!(wallet.address_names()[&address], "savings");
+        assert_eq!(wallet.address_purposes()[&address], "receive");
+        assert_eq!(wallet.bestblock_nomerkle(), Some(&locator()));
+        assert_eq!(wallet.client_version(), &client_version);
+        assert_eq!(wallet.min_version(), &ClientVersion::from_integer(60_000));
+        assert!(wallet.legacy_hd_seed().is_some());
+        assert_eq!(wallet.orchard_tree_history().len(), 1);
+        assert_eq!(wallet.orderposnext(), Some(12));
+        assert!(wallet.transactions().contains_key(&txid));
+        assert_eq!(wallet.witnesscachesize(), 100);
+    }
+
+    #[test]
+    fn addresses_are_found_by_name() {
+        let savings = Address::from("tmGys6dBuEGjch5LFnhdo5gpSa7jiNRWse3");
+        let also_savings = Address::from("tmHMBeeYRuc2eVicLNfP15YLxbQsooCA6jb");
+        let cafe = Address::from("tmRKtP8pKnhRy5iufgmT79d8VQ1Jc5WnVsC");
+        let wallet = ZcashdWalletBuilder::new()
+            .address_names(BTreeMap::from([
+                (also_savings.clone(), "savings".to_string()),
+                (savings.clone(), "savings".to_string()),
+                (cafe.clone(), "café ☕".to_string()),
+            ]))
+            .bestblock(locator())
+            .network_info(regtest())
+            .build()
+            .unwrap();
+
+        // Names are not unique; addresses sharing one come in address order.
+        assert_eq!(wallet.address_by_name("savings"), [&savings, &also_savings]);
+        assert_eq!(wallet.address_by_name("café ☕"), [&cafe]);
+        // Names match exactly, without Unicode normalization.
+        assert!(wallet.address_by_name("cafe\u{301} ☕").is_empty());
+        assert!(wallet.address_by_name("Savings").is_empty());
+        assert!(wallet.address_by_name("").is_empty());
+    }
+
+    #[test]
+    fn transactions_must_be_keyed_by_their_own_id() {
+        let wtx = wallet_tx();
+        let txid = TxId::from_bytes(*wtx.transaction().txid().as_ref());
+        let key = TxId::from_bytes([0x5a; 32]);
+
+        let err = ZcashdWalletBuilder::new()
+            .bestblock(locator())
+            .network_info(regtest())
+            .transactions(HashMap::from([(key, wtx)]))
+            .build()
+            .unwrap_err();
+        assert_eq!(err, WalletBuildError::MismatchedTxId { key, txid });
+    }
+
+    /// The defaults of a minimal wallet migrate as a wallet without those
+    /// records: no transactions, addresses or key material.
+    #[test]
+    fn a_minimal_wallet_migrates() {
+        let wallet = ZcashdWalletBuilder::new()
+            .bestblock(locator())
+            .network_info(regtest())
+            .build()
+            .unwrap();
+
+        let zewif = crate::migrate_to_zewif(&wallet, zewif::BlockHeight::from_u32(1), None)
+            .expect("migrate");
+        assert_eq!(zewif.wallets_len(), 1);
+        assert!(zewif.transactions().is_empty());
+        let zewif_wallet = &zewif.wallets()[0];
+        assert!(
+            zewif_wallet
+                .accounts()
+                .iter()
+                .all(|account| account.addresses().is_empty())
+        );
+    }
+
+    #[test]
+    fn checks_the_supported_version_range() {
+        let wallet = |client_version, min_version| {
+            ZcashdWalletBuilder::new()
+                .bestblock(locator())
+                .network_info(regtest())
+                .client_version(ClientVersion::from_integer(client_version))
+                .min_version(ClientVersion::from_integer(min_version))
+                .build()
+                .unwrap()
+        };
+
+        wallet(3_000_050, 60_000).check_supported().unwrap();
+        wallet(6_020_050, 0).check_supported().unwrap();
+        assert!(matches!(
+            wallet(2_070_050, 60_000).check_supported(),
+            Err(crate::Error::UnsupportedClientVersion { .. })
+        ));
+        assert!(matches!(
+            wallet(3_000_050, 60_001).check_supported(),
+            Err(crate::Error::UnsupportedWalletFormat { .. })
+        ));
+    }
+}
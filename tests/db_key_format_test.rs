@@ -0,0 +1,104 @@
+//! The binary keys of zcashd's single-record keynames.
+//!
+//! zcashd stores a record such as `version` under its keyname alone, as a
+//! length-prefixed string with no key data after it. Each case checks the key
+//! [`ZcashdDump::key_for_keyname`] builds against the bytes zcashd writes, and
+//! against the key stored in a dump holding those bytes.
+
+use std::collections::HashMap;
+
+use zewif::Data;
+use zewif_zcashd::{BDBDump, DBKey, DumpError, ZcashdDump};
+
+/// Single-record keynames and the hex of the keys zcashd stores them under.
+const SINGLE_RECORD_KEYS: &[(&str, &str)] = &[
+    ("bestblock", "0962657374626c6f636b"),
+    (
+        "bestblock_nomerkle",
+        "1262657374626c6f636b5f6e6f6d65726b6c65",
+    ),
+    ("defaultkey", "0a64656661756c746b6579"),
+    ("minversion", "0a6d696e76657273696f6e"),
+    ("networkinfo", "0b6e6574776f726b696e666f"),
+    (
+        "orchard_note_commitment_tree",
+        "1c6f7263686172645f6e6f74655f636f6d6d69746d656e745f74726565",
+    ),
+    ("orderposnext", "0c6f72646572706f736e657874"),
+    ("version", "0776657273696f6e"),
+    ("witnesscachesize", "107769746e657373636163686573697a65"),
+];
+
+/// A dump holding one record, with an arbitrary value, under each raw key.
+fn dump_with_keys(keys: &[Data]) -> ZcashdDump {
+    let data_records = keys
+        .iter()
+        .map(|key| (key.clone(), Data::from_slice(&[0x00])))
+        .collect();
+    let bdb = BDBDump {
+        header_records: HashMap::new(),
+        data_records,
+        problems: vec![],
+    };
+    ZcashdDump::from_bdb_dump(&bdb, true).expect("from_bdb_dump")
+}
+
+fn raw_key(hex: &str) -> Data {
+    Data::from_hex(hex).expect("valid hex")
+}
+
+#[test]
+fn single_record_keys_have_zcashds_binary_form() {
+    let raw_keys: Vec<Data> = SINGLE_RECORD_KEYS
+        .iter()
+        .map(|(_, hex)| raw_key(hex))
+        .collect();
+    let dump = dump_with_keys(&raw_keys);
+
+    for ((keyname, _), raw) in SINGLE_RECORD_KEYS.iter().zip(&raw_keys) {
+        let built = dump.key_for_keyname(keyname);
+        assert_eq!(built.to_bytes(), raw.as_slice(), "{keyname}");
+
+        let stored = DBKey::parse_data(raw).expect("parse stored key");
+        assert_eq!(stored.keyname, *keyname);
+        assert!(stored.data.is_empty());
+        dump.verify_key_format(keyname, &stored)
+            .unwrap_or_else(|e| panic!("{keyname}: {e}"));
+        assert!(dump.has_value_for_keyname(keyname));
+    }
+}
+
+#[test]
+fn keys_with_data_do_not_match_the_bare_keyname() {
+    // An `hdseed` record is keyed by its seed fingerprint.
+    let mut raw = raw_key("06686473656564").as_slice().to_vec();
+    raw.extend_from_slice(&[0x11; 32]);
+    let raw = Data::from_vec(raw);
+    let dump = dump_with_keys(std::slice::from_ref(&raw));
+    let stored = DBKey::parse_data(&raw).expect("parse stored key");
+
+    match dump.verify_key_format("hdseed", &stored) {
+        Err(DumpError::KeyFormatMismatch {
+            keyname,
+            built,
+            stored,
+        }) => {
+            assert_eq!(keyname, "hdseed");
+            assert_eq!(built, "06686473656564");
+            assert_eq!(stored, hex::encode(&raw));
+        }
+        other => panic!("expected a key format mismatch, got {other:?}"),
+    }
+}
+
+#[test]
+fn keys_absent_from_the_dump_are_not_verified() {
+    let dump = dump_with_keys(&[raw_key(SINGLE_RECORD_KEYS[0].1)]);
+    let version = DBKey::new("version", Data::new());
+    assert_eq!(
+        dump.verify_key_format("version", &version),
+        Err(DumpError::RecordNotFound {
+            key: "version".to_string()
+        })
+    );
+}
@@ -13,12 +13,22 @@
 //! / `z_exportkey`) before encryption, giving an independent (zcashd-side)
 //! oracle for the two spending keys checked below.
 
-use std::path::PathBuf;
+use std::{collections::BTreeSet, path::PathBuf};
 
-use zewif::BlockHeight;
+use zcash_keys::keys::{ReceiverRequirement, UnifiedAddressRequest};
+use zewif::{BlockHeight, Data, Network, SeedMaterial, TxId};
 use zewif_zcashd::{
-    BDBDump, EncryptedKeyPolicy, Error, SecretVec, ZcashdDump, ZcashdParser, ZcashdWallet,
-    migrate_to_zewif,
+    BDBDump, EncryptedKeyPolicy, Error, MigrationOptions, SecretVec, ZcashdDump, ZcashdParser,
+    ZcashdWallet,
+    migrate::{
+        BalanceEstimates, ExportMode, MigrateError, MigrationFilter, MigrationWarning,
+        ORDER_POS_NEXT_EXTENSION_KEY, ORDER_POSITION_EXTENSION_KEY, TransactionClassification,
+        TransactionRule, ZCASHD_EXTENSION_VENDOR, estimate_legacy_balance,
+        explain_transaction_attribution, extract_transaction_memo,
+    },
+    migrate_to_zewif, migrate_to_zewif_filtered, migrate_to_zewif_streaming,
+    migrate_to_zewif_with_mode, migrate_wallets_to_zewif,
+    zcashd_wallet::UfvkFingerprint,
 };
 
 const PASSPHRASE: &str = "test-passphrase-42";
@@ -205,6 +215,37 @@ fn skip_mode_omits_encrypted_keys() {
         .expect("migrates the plaintext remainder");
 }
 
+/// A wallet parsed without its passphrase keeps its encrypted mnemonic, which
+/// then decrypts to the mnemonic of the plaintext wallet.
+///
+/// The encrypted fixture also holds its mnemonic in plaintext, so that record
+/// is dropped to leave only the `cmnemonicphrase`.
+#[test]
+fn skipped_mnemonic_decrypts_later() {
+    require_db_dump!();
+
+    let bdb = fixture_bdb_without("encrypted-regtest-wallet.dat", &["mnemonicphrase"]);
+    let dump = ZcashdDump::from_bdb_dump(&bdb, false).expect("collect records");
+    let (wallet, _) = ZcashdParser::parse_dump_with_policy(&dump, false, EncryptedKeyPolicy::Skip)
+        .expect("skip mode succeeds");
+    assert!(wallet.is_encrypted());
+    assert!(!parse_plaintext().is_encrypted());
+    assert!(wallet.encrypted_mnemonic().is_some());
+    assert!(wallet.bip39_mnemonic().is_none());
+
+    let expected = parse_plaintext().bip39_mnemonic().cloned();
+    let decrypted = wallet
+        .decrypt_mnemonic(&SecretVec::new(PASSPHRASE.as_bytes().to_vec()))
+        .expect("decrypts with the correct passphrase");
+    assert!(decrypted.is_some());
+    assert_eq!(decrypted, expected);
+
+    assert!(matches!(
+        wallet.decrypt_mnemonic(&SecretVec::new(b"wrong".to_vec())),
+        Err(Error::WrongWalletPassphrase)
+    ));
+}
+
 #[test]
 fn migrates_with_a_populated_secret_store() {
     require_db_dump!();
@@ -227,3 +268,1120 @@ fn migrates_with_a_populated_secret_store() {
         "the Sapling spending key is exported"
     );
 }
+
+/// Every Orchard note position tracked by the wallet's commitment tree flows
+/// into the matching received output of the export.
+#[test]
+fn orchard_note_positions_flow_into_received_outputs() {
+    require_db_dump!();
+
+    let wallet = parse_plaintext();
+    let zewif = migrate_to_zewif(&wallet, BlockHeight::from_u32(2_000_000), None)
+        .expect("migrate plaintext");
+    let accounts: Vec<_> = zewif.wallets().iter().flat_map(|w| w.accounts()).collect();
+
+    for (txid, positions) in wallet.orchard_note_commitment_tree().note_positions() {
+        let txid = zewif::TxId::from_bytes(*txid.as_ref());
+        for (action_index, position) in positions.note_positions() {
+            let output = accounts
+                .iter()
+                .filter_map(|a| a.relevant_transactions().get(&txid))
+                .flatten()
+                .find(|o| {
+                    o.output_index() == *action_index
+                        && matches!(o.pool(), zewif::ReceivedOutputPool::Orchard(_))
+                })
+                .expect("a received Orchard output for each tracked action");
+            assert_eq!(
+                output.commitment_tree_position(),
+                Some(u64::from(*position))
+            );
+        }
+    }
+}
+
+/// Each Sapling note the wallet received decrypts with the wallet's own key,
+/// and its memo is carried by the matching received output of the export.
+#[test]
+fn sapling_memos_flow_into_received_outputs() {
+    require_db_dump!();
+
+    let wallet = parse_plaintext();
+    let zewif = migrate_to_zewif(&wallet, BlockHeight::from_u32(2_000_000), None)
+        .expect("migrate plaintext");
+    let accounts: Vec<_> = zewif.wallets().iter().flat_map(|w| w.accounts()).collect();
+
+    for (txid, wtx) in wallet.transactions() {
+        for outpoint in wtx.sapling_note_data().into_iter().flat_map(|m| m.keys()) {
+            let memo = extract_transaction_memo(&wallet, wtx, outpoint.vout() as usize)
+                .expect("the wallet's own Sapling notes decrypt");
+            assert_eq!(memo.len(), 512);
+
+            let output = accounts
+                .iter()
+                .filter_map(|a| a.relevant_transactions().get(txid))
+                .flatten()
+                .find(|o| {
+                    o.output_index() == outpoint.vout()
+                        && matches!(o.pool(), zewif::ReceivedOutputPool::Sapling(_))
+                })
+                .expect("a received Sapling output for each note");
+            assert_eq!(output.memo().map(|m| m.to_vec()), Some(memo));
+        }
+    }
+}
+
+/// The exported wallet carries the parsed `orderposnext`, and each
+/// transaction the order position zcashd recorded for it.
+#[test]
+fn order_positions_are_exported() {
+    require_db_dump!();
+
+    let wallet = parse_plaintext();
+    let zewif = migrate_to_zewif(&wallet, BlockHeight::from_u32(2_000_000), None)
+        .expect("migrate plaintext");
+    let decode = |value: &zewif::ExtensionValue| {
+        minicbor::Decoder::new(value.as_data().as_slice())
+            .i64()
+            .expect("a CBOR integer")
+    };
+
+    let exported = zewif.wallets()[0]
+        .extensions()
+        .get(ZCASHD_EXTENSION_VENDOR, ORDER_POS_NEXT_EXTENSION_KEY)
+        .map(decode);
+    assert_eq!(exported, wallet.orderposnext());
+
+    for (txid, wtx) in wallet.transactions() {
+        let exported = zewif.transactions()[txid]
+            .extensions()
+            .get(ZCASHD_EXTENSION_VENDOR, ORDER_POSITION_EXTENSION_KEY)
+            .map(decode);
+        assert_eq!(exported, wtx.order_position());
+    }
+}
+
+/// Merging two different wallets that share a transaction yields one wallet
+/// per input over a single transaction table, and reports what the wallets
+/// disagree on.
+#[test]
+fn merged_export_reconciles_two_wallets() {
+    require_db_dump!();
+
+    // A hot wallet with the first two transactions, and a cold one, which
+    // also has a unified account, with the second and third. They number the
+    // shared transaction differently.
+    let pubkeys = plaintext_pubkeys();
+    let record = |index: usize, order_position: &str| {
+        transparent_tx_record(
+            100_000_000,
+            &p2pkh_script(&pubkeys[index]),
+            &[("n", order_position)],
+        )
+    };
+    let parse = |bdb: &BDBDump| {
+        let dump = ZcashdDump::from_bdb_dump(bdb, false).expect("collect records");
+        ZcashdParser::parse_dump(&dump, false).expect("parses").0
+    };
+    let mut hot = plaintext_bdb_without(&[]);
+    hot.data_records.extend([record(0, "0"), record(1, "1")]);
+    let hot = parse(&hot);
+    let mut cold = plaintext_bdb_with_unified_accounts(&[0]);
+    cold.data_records.extend([record(1, "0"), record(2, "1")]);
+    let cold = parse(&cold);
+
+    let height = BlockHeight::from_u32(2_000_000);
+    let (merged, warnings) =
+        migrate_wallets_to_zewif(&[&hot, &cold], height, None).expect("merges");
+
+    let all: BTreeSet<_> = hot
+        .transactions()
+        .keys()
+        .chain(cold.transactions().keys())
+        .copied()
+        .collect();
+    assert_eq!(all.len(), 3);
+    let merged_txids: BTreeSet<_> = merged.transactions().keys().copied().collect();
+    assert_eq!(merged_txids, all);
+    let shared = *hot
+        .transactions()
+        .keys()
+        .find(|txid| cold.transactions().contains_key(txid))
+        .expect("a shared transaction");
+    assert_eq!(
+        warnings,
+        [MigrationWarning::ConflictingTransaction {
+            wallet_index: 1,
+            txid: shared,
+            fields: vec!["extension data"],
+        }]
+    );
+
+    // Only the legacy account is in both wallets, so only its name is
+    // prefixed.
+    assert_eq!(merged.wallets().len(), 2);
+    let names = |index: usize| -> Vec<&str> {
+        merged.wallets()[index]
+            .accounts()
+            .iter()
+            .map(|a| a.name())
+            .collect()
+    };
+    assert_eq!(names(0), ["wallet 0: Legacy"]);
+    assert_eq!(names(1), ["Account #0", "wallet 1: Legacy"]);
+}
+
+/// Every legacy account has the same viewing key, so the legacy accounts of
+/// two different merged wallets keep only their own wallet's outputs.
+#[test]
+fn merged_legacy_accounts_keep_their_own_outputs() {
+    require_db_dump!();
+
+    // Each wallet funds one of the keys and spends the funds, which makes the
+    // spend relevant to its legacy account.
+    let pubkeys = plaintext_pubkeys();
+    let parse_with = |index: usize| {
+        let script = p2pkh_script(&pubkeys[index]);
+        let funding = wallet_tx_record(&[], &[(100_000_000, &script)], &[]);
+        let spend = wallet_tx_record(
+            &[(tx_record_txid(&funding), 0)],
+            &[(99_990_000, &script)],
+            &[],
+        );
+        let spend_txid = TxId::from_bytes(tx_record_txid(&spend));
+        let mut bdb = plaintext_bdb_without(&[]);
+        bdb.data_records.extend([funding, spend]);
+        let dump = ZcashdDump::from_bdb_dump(&bdb, false).expect("collect records");
+        let (wallet, _) = ZcashdParser::parse_dump(&dump, false).expect("parses");
+        (wallet, spend_txid)
+    };
+    let (hot, hot_spend) = parse_with(0);
+    let (cold, cold_spend) = parse_with(1);
+
+    let (merged, _) =
+        migrate_wallets_to_zewif(&[&hot, &cold], BlockHeight::from_u32(1), None).expect("merges");
+
+    let legacy_txids = |index: usize| -> Vec<TxId> {
+        let legacy = merged.wallets()[index]
+            .accounts()
+            .iter()
+            .find(|a| a.name() == format!("wallet {index}: Legacy"))
+            .expect("a legacy account");
+        legacy.relevant_transactions().keys().copied().collect()
+    };
+    assert_eq!(legacy_txids(0), [hot_spend]);
+    assert_eq!(legacy_txids(1), [cold_spend]);
+}
+
+/// The transaction attribution names exactly the transactions the migration
+/// assigns, and its per-rule counts exceed that total only by the overlap.
+#[test]
+fn transaction_attribution_reconciles_with_migration() {
+    require_db_dump!();
+
+    let wallet = parse_plaintext();
+    let zewif = migrate_to_zewif(&wallet, BlockHeight::from_u32(1), None).expect("migrates");
+    let attribution = explain_transaction_attribution(&wallet).expect("attributes");
+
+    let assigned: BTreeSet<_> = zewif
+        .wallets()
+        .iter()
+        .flat_map(|w| w.accounts())
+        .flat_map(|a| a.relevant_transactions().keys())
+        .collect();
+    let attributed: BTreeSet<_> = attribution.rules().keys().collect();
+    assert_eq!(attributed, assigned);
+    assert_eq!(attribution.transaction_count(), assigned.len());
+
+    let rule_total: usize = attribution.rule_counts().values().sum();
+    let extra_rules: usize = attribution
+        .rules()
+        .values()
+        .map(|rules| rules.len() - 1)
+        .sum();
+    assert_eq!(rule_total, assigned.len() + extra_rules);
+    assert!(extra_rules >= attribution.overlap_count());
+}
+
+/// A transparent transfer from the legacy account to the account of a
+/// foreign seed is an internal transfer relevant to both, and is counted
+/// apart from sends and receipts; spending the transfer back with a payment
+/// outside the wallet is a send.
+#[test]
+fn internal_transfers_are_relevant_to_both_accounts() {
+    require_db_dump!();
+
+    let pubkeys = plaintext_pubkeys();
+    let (legacy_script, foreign_script) = (p2pkh_script(&pubkeys[0]), p2pkh_script(&pubkeys[1]));
+    let outside_script = [&[0x76, 0xa9, 0x14][..], &[0x99; 20], &[0x88, 0xac]].concat();
+
+    let mut bdb = plaintext_bdb_without(&[]);
+    // Record the second key as derived from a seed the wallet does not hold.
+    let mut keymeta = vec![7];
+    keymeta.extend_from_slice(b"keymeta");
+    keymeta.push(pubkeys[1].len() as u8);
+    keymeta.extend_from_slice(&pubkeys[1]);
+    let keymeta = Data::from_vec(keymeta);
+    let mut metadata = bdb.data_records[&keymeta].as_slice().to_vec();
+    let seed_fp_start = metadata.len() - 32;
+    metadata[seed_fp_start..].copy_from_slice(&[0x77; 32]);
+    bdb.data_records.insert(keymeta, Data::from_vec(metadata));
+
+    let funding = wallet_tx_record(&[], &[(100_000_000, &legacy_script)], &[]);
+    let transfer = wallet_tx_record(
+        &[(tx_record_txid(&funding), 0)],
+        &[(60_000_000, &foreign_script), (39_990_000, &legacy_script)],
+        &[],
+    );
+    let payment = wallet_tx_record(
+        &[(tx_record_txid(&transfer), 0)],
+        &[(50_000_000, &legacy_script), (9_990_000, &outside_script)],
+        &[],
+    );
+    let records = [funding, transfer, payment];
+    let [funding, transfer, payment] =
+        [0, 1, 2].map(|n| zewif::TxId::from_bytes(tx_record_txid(&records[n])));
+    bdb.data_records.extend(records);
+
+    let dump = ZcashdDump::from_bdb_dump(&bdb, false).expect("collect records");
+    let (wallet, _) = ZcashdParser::parse_dump(&dump, false).expect("parses");
+    let zewif = migrate_to_zewif(&wallet, BlockHeight::from_u32(1), None).expect("migrates");
+    let attribution = explain_transaction_attribution(&wallet).expect("attributes");
+    let accounts = zewif.wallets()[0].accounts();
+    let legacy = accounts
+        .iter()
+        .find(|account| account.name() == "Legacy")
+        .expect("a legacy account");
+    let foreign = accounts
+        .iter()
+        .find(|account| account.name() == format!("foreign seed {}", hex::encode([0x77; 32])))
+        .expect("an account for the foreign seed");
+
+    let transfers: Vec<_> = attribution.internal_transfers().collect();
+    let [(txid, from, to)] = transfers.as_slice() else {
+        panic!("one internal transfer: {transfers:?}");
+    };
+    assert_eq!(**txid, transfer);
+    assert_eq!(
+        (from.seed_fingerprint(), from.zip32_account_id()),
+        (wallet_seed_fingerprint(&wallet).as_ref(), 0x7FFF_FFFF)
+    );
+    assert_eq!(
+        (to.seed_fingerprint(), to.zip32_account_id()),
+        (
+            Some(&zewif_zcashd::zcashd_wallet::encode_seed_fingerprint(
+                &[0x77; 32]
+            )),
+            0x7FFF_FFFF
+        )
+    );
+    assert_eq!(
+        attribution.rules()[&transfer],
+        BTreeSet::from([
+            TransactionRule::TransparentSpend,
+            TransactionRule::TransparentPayment
+        ])
+    );
+    assert_eq!(
+        attribution.classifications()[&funding],
+        TransactionClassification::Received
+    );
+    assert_eq!(
+        attribution.classifications()[&payment],
+        TransactionClassification::Sent
+    );
+    for txid in [transfer, payment] {
+        assert!(legacy.relevant_transactions().contains_key(&txid));
+        assert!(foreign.relevant_transactions().contains_key(&txid));
+    }
+    for txid in attribution.rules().keys() {
+        assert!(attribution.classifications().contains_key(txid));
+    }
+
+    // Both accounts hold funds outside the unified accounts.
+    let estimate = estimate_legacy_balance(&wallet).expect("estimates");
+    assert_eq!(
+        (
+            estimate.received_transactions,
+            estimate.sent_transactions,
+            estimate.internal_transfers
+        ),
+        (1, 1, 1)
+    );
+    let estimates = BalanceEstimates::from_wallet(&wallet).expect("estimates");
+    assert_eq!(estimates.legacy, estimate);
+    assert!(estimates.accounts.is_empty());
+}
+
+/// The seed fingerprint of the wallet's mnemonic, as its legacy account
+/// records it.
+fn wallet_seed_fingerprint(wallet: &ZcashdWallet) -> Option<zewif::SeedFingerprint> {
+    let seed_fp = wallet.mnemonic_hd_chain()?.seed_fp();
+    Some(zewif_zcashd::zcashd_wallet::encode_seed_fingerprint(
+        seed_fp,
+    ))
+}
+
+/// The records of the named fixture, less those with any of the given
+/// keynames.
+fn fixture_bdb_without(name: &str, keynames: &[&str]) -> BDBDump {
+    let mut bdb = BDBDump::from_file(&fixture(name)).expect("db_dump");
+    bdb.data_records.retain(|key, _| {
+        let keyname = &key.as_slice()[1..1 + key.as_slice()[0] as usize];
+        !keynames.iter().any(|name| name.as_bytes() == keyname)
+    });
+    bdb
+}
+
+/// The plaintext fixture's records, less those with any of the given
+/// keynames, to simulate a wallet written by an older zcashd.
+fn plaintext_bdb_without(keynames: &[&str]) -> BDBDump {
+    fixture_bdb_without("plaintext-regtest-wallet.dat", keynames)
+}
+
+/// The P2PKH script paying the hash of `pubkey`.
+fn p2pkh_script(pubkey: &[u8]) -> Vec<u8> {
+    use ripemd::Ripemd160;
+    use sha2::{Digest, Sha256};
+
+    let hash = Ripemd160::digest(Sha256::digest(pubkey));
+    [&[0x76, 0xa9, 0x14][..], hash.as_slice(), &[0x88, 0xac]].concat()
+}
+
+/// A `tx` record: a `CWalletTx` holding a version 1 transaction with no
+/// inputs that pays `value` zatoshis to `script_pubkey`, keyed by its txid,
+/// with `map_value` as its `mapValue`.
+fn transparent_tx_record(
+    value: i64,
+    script_pubkey: &[u8],
+    map_value: &[(&str, &str)],
+) -> (Data, Data) {
+    wallet_tx_record(&[], &[(value, script_pubkey)], map_value)
+}
+
+/// A `tx` record: a mined `CWalletTx` holding a version 1 transaction that
+/// spends `inputs` (txid and output index) to `outputs` (value and script),
+/// keyed by its txid, with `map_value` as its `mapValue`.
+fn wallet_tx_record(
+    inputs: &[([u8; 32], u32)],
+    outputs: &[(i64, &[u8])],
+    map_value: &[(&str, &str)],
+) -> (Data, Data) {
+    use sha2::{Digest, Sha256};
+
+    // The transaction: version, inputs with empty scripts, outputs, lock
+    // time.
+    let mut bytes = 1u32.to_le_bytes().to_vec();
+    bytes.push(inputs.len() as u8);
+    for (txid, n) in inputs {
+        bytes.extend_from_slice(txid);
+        bytes.extend_from_slice(&n.to_le_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+    }
+    bytes.push(outputs.len() as u8);
+    for (value, script_pubkey) in outputs {
+        bytes.extend_from_slice(&value.to_le_bytes());
+        bytes.push(script_pubkey.len() as u8);
+        bytes.extend_from_slice(script_pubkey);
+    }
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+
+    let mut key = vec![2];
+    key.extend_from_slice(b"tx");
+    key.extend_from_slice(&Sha256::digest(Sha256::digest(&bytes)));
+
+    // CMerkleTx: block hash, empty merkle branch, index.
+    bytes.extend_from_slice(&[0x42; 32]);
+    bytes.push(0);
+    bytes.extend_from_slice(&(-1i32).to_le_bytes());
+    // CWalletTx: vtxPrev, mapValue, mapSproutNoteData, vOrderForm.
+    bytes.push(0);
+    bytes.push(map_value.len() as u8);
+    for (key, value) in map_value {
+        for string in [key, value] {
+            bytes.push(string.len() as u8);
+            bytes.extend_from_slice(string.as_bytes());
+        }
+    }
+    bytes.extend_from_slice(&[0, 0]);
+    // fTimeReceivedIsTxTime, nTimeReceived, fFromMe, fSpent.
+    bytes.extend_from_slice(&0i32.to_le_bytes());
+    bytes.extend_from_slice(&1_600_000_000i32.to_le_bytes());
+    bytes.extend_from_slice(&[0, 0]);
+    (Data::from_vec(key), Data::from_vec(bytes))
+}
+
+/// The txid of a [`wallet_tx_record`], as its inputs spend it.
+fn tx_record_txid((key, _): &(Data, Data)) -> [u8; 32] {
+    key.as_slice()[3..].try_into().expect("a 32-byte txid")
+}
+
+/// The number of transactions [`plaintext_bdb_with_transactions`] adds.
+const FIXTURE_TRANSACTIONS: usize = 3;
+
+/// The plaintext fixture, which has no transaction history, with
+/// [`FIXTURE_TRANSACTIONS`] transactions paying its first transparent keys.
+fn plaintext_bdb_with_transactions() -> BDBDump {
+    let mut bdb = plaintext_bdb_without(&[]);
+    for pubkey in plaintext_pubkeys().iter().take(FIXTURE_TRANSACTIONS) {
+        let (key, value) = transparent_tx_record(100_000_000, &p2pkh_script(pubkey), &[]);
+        bdb.data_records.insert(key, value);
+    }
+    bdb
+}
+
+/// The plaintext fixture's transparent public keys, in sorted order.
+fn plaintext_pubkeys() -> Vec<Vec<u8>> {
+    let mut pubkeys: Vec<_> = parse_plaintext()
+        .keys()
+        .keypairs()
+        .map(|keypair| keypair.pubkey().as_slice().to_vec())
+        .collect();
+    pubkeys.sort();
+    pubkeys
+}
+
+/// [`plaintext_bdb_with_transactions`], parsed.
+fn parse_plaintext_with_transactions() -> ZcashdWallet {
+    let dump = ZcashdDump::from_bdb_dump(&plaintext_bdb_with_transactions(), false)
+        .expect("collect records");
+    ZcashdParser::parse_dump(&dump, false)
+        .expect("the wallet with transactions parses")
+        .0
+}
+
+/// The fingerprint under which [`plaintext_bdb_with_unified_accounts`]
+/// records ZIP 32 account `account`. The parser takes zcashd's fingerprints
+/// as given, so any value serves; these descend as the account index rises,
+/// so that fingerprint order is not account order.
+fn ufvk_fingerprint(account: u32) -> [u8; 32] {
+    [0xff - account as u8; 32]
+}
+
+/// The plaintext fixture, which has no unified accounts, with the given ZIP 32
+/// accounts of its mnemonic and each account's default Orchard and Sapling
+/// address recorded as `z_getnewaccount` and `z_getaddressforaccount` would.
+fn plaintext_bdb_with_unified_accounts(accounts: &[u32]) -> BDBDump {
+    let wallet = parse_plaintext();
+    let params = wallet.network_info().to_address_encoding_network();
+    let mnemonic = bip0039::Mnemonic::<bip0039::English>::from_phrase(
+        wallet.bip39_mnemonic().expect("a mnemonic").phrase(),
+    )
+    .expect("a valid mnemonic");
+    let seed = mnemonic.to_seed("");
+    let seed_fp = wallet
+        .mnemonic_hd_chain()
+        .expect("a mnemonic HD chain")
+        .seed_fp();
+    // The testnet and regtest coin type.
+    let coin_type = 1u32.to_le_bytes();
+    // Orchard and Sapling receivers.
+    let receiver_types = [2, 3, 2];
+
+    let record = |keyname: &str, key_data: &[&[u8]], value: Vec<u8>| {
+        let mut key = vec![keyname.len() as u8];
+        key.extend_from_slice(keyname.as_bytes());
+        key.extend(key_data.concat());
+        (Data::from_vec(key), Data::from_vec(value))
+    };
+
+    let mut bdb = plaintext_bdb_without(&[]);
+    for &account in accounts {
+        let ufvk = zcash_keys::keys::UnifiedSpendingKey::from_seed(
+            &params,
+            &seed,
+            zip32::AccountId::try_from(account).expect("a valid account index"),
+        )
+        .expect("derives the account")
+        .to_unified_full_viewing_key();
+        // Not every diversifier index is valid for Sapling.
+        let (j, _) = ufvk.sapling().expect("a Sapling key").default_address();
+        let encoded_ufvk = ufvk.encode(&params);
+        // A string longer than 252 bytes has a three-byte CompactSize length.
+        let mut ufvk_value = vec![0xfd];
+        ufvk_value.extend_from_slice(&u16::try_from(encoded_ufvk.len()).unwrap().to_le_bytes());
+        ufvk_value.extend_from_slice(encoded_ufvk.as_bytes());
+
+        let fingerprint = ufvk_fingerprint(account);
+        bdb.data_records.extend([
+            record(
+                "unifiedaccount",
+                &[seed_fp, &coin_type, &account.to_le_bytes(), &fingerprint],
+                vec![0; 4],
+            ),
+            record("unifiedfvk", &[&fingerprint], ufvk_value),
+            record(
+                "unifiedaddrmeta",
+                &[&fingerprint, j.as_bytes(), &receiver_types],
+                vec![0; 4],
+            ),
+        ]);
+    }
+    bdb
+}
+
+/// A pre-mnemonic wallet, simulated by replacing the fixture's mnemonic and
+/// unified account records with a legacy `hdseed`, migrates end to end and
+/// exports the legacy seed alongside the mnemonic zcashd would derive from it.
+#[test]
+fn migrates_a_pre_mnemonic_wallet_from_its_legacy_seed() {
+    require_db_dump!();
+
+    const MNEMONIC_ERA_RECORDS: [&str; 5] = [
+        "mnemonicphrase",
+        "mnemonichdchain",
+        "unifiedaccount",
+        "unifiedfvk",
+        "unifiedaddrmeta",
+    ];
+    let mut bdb = plaintext_bdb_without(&MNEMONIC_ERA_RECORDS);
+    let mut key = vec![6];
+    key.extend_from_slice(b"hdseed");
+    key.extend_from_slice(&[0x33; 32]);
+    let mut value = vec![32];
+    value.extend_from_slice(&[0x44; 32]);
+    bdb.data_records
+        .insert(Data::from_vec(key), Data::from_vec(value));
+
+    let dump = ZcashdDump::from_bdb_dump(&bdb, false).expect("collect records");
+    let (wallet, _) = ZcashdParser::parse_dump(&dump, false).expect("parses");
+    assert!(wallet.bip39_mnemonic().is_none());
+    assert!(wallet.legacy_hd_seed().is_some());
+
+    let zewif = migrate_to_zewif(&wallet, BlockHeight::from_u32(1), None).expect("migrates");
+    let Some(zewif::Secrets::Plain(store)) = zewif.secrets() else {
+        panic!("expected a plaintext secret store");
+    };
+    assert!(
+        store
+            .seeds()
+            .iter()
+            .any(|seed| matches!(seed.material(), SeedMaterial::LegacySeed(_))),
+        "the legacy seed is exported"
+    );
+    assert!(
+        store
+            .seeds()
+            .iter()
+            .any(|seed| matches!(seed.material(), SeedMaterial::Bip39Mnemonic(_))),
+        "the mnemonic derived from the legacy seed is exported"
+    );
+}
+
+/// A wallet whose mnemonic was replaced, simulated by swapping the fixture's
+/// phrase for another while keeping its HD chain and keys, exports the keys
+/// derived from the old seed in an account of their own. The legacy account
+/// and the exported phrase are keyed by the new phrase's fingerprint.
+#[test]
+fn keys_of_a_replaced_mnemonic_get_their_own_account() {
+    require_db_dump!();
+
+    let old_seed_fp = *parse_plaintext()
+        .mnemonic_hd_chain()
+        .expect("a mnemonic HD chain")
+        .seed_fp();
+    let mnemonic =
+        bip0039::Mnemonic::<bip0039::English>::from_entropy(vec![0x11; 32]).expect("valid entropy");
+    let new_seed_fp = zip32::fingerprint::SeedFingerprint::from_seed(&mnemonic.to_seed(""))
+        .expect("a 64-byte seed")
+        .to_bytes();
+
+    let mut bdb = plaintext_bdb_without(&["mnemonicphrase"]);
+    let mut key = vec![14];
+    key.extend_from_slice(b"mnemonicphrase");
+    key.extend_from_slice(&new_seed_fp);
+    // English, then the phrase as a CompactSize-prefixed string.
+    let mut value = 0u32.to_le_bytes().to_vec();
+    value.push(u8::try_from(mnemonic.phrase().len()).expect("a short phrase"));
+    value.extend_from_slice(mnemonic.phrase().as_bytes());
+    bdb.data_records
+        .insert(Data::from_vec(key), Data::from_vec(value));
+
+    let dump = ZcashdDump::from_bdb_dump(&bdb, false).expect("collect records");
+    let (wallet, _) = ZcashdParser::parse_dump(&dump, false).expect("parses");
+    let zewif = migrate_to_zewif(&wallet, BlockHeight::from_u32(1), None).expect("migrates");
+    let accounts = zewif.wallets()[0].accounts();
+
+    let foreign_name = format!("foreign seed {}", hex::encode(old_seed_fp));
+    let foreign = accounts
+        .iter()
+        .find(|account| account.name() == foreign_name)
+        .expect("an account for the old seed");
+    assert!(!foreign.addresses().is_empty());
+
+    let new_fp = zewif_zcashd::zcashd_wallet::encode_seed_fingerprint(&new_seed_fp);
+    let legacy = accounts
+        .iter()
+        .find(|account| account.name() == "Legacy")
+        .expect("a legacy account");
+    let Some(zewif::KeySource::Derived(source)) = legacy.key_source() else {
+        panic!("the legacy account is seed-derived");
+    };
+    assert_eq!(source.seed_fingerprint(), &new_fp);
+
+    let Some(zewif::Secrets::Plain(store)) = zewif.secrets() else {
+        panic!("expected a plaintext secret store");
+    };
+    assert!(
+        store
+            .seeds()
+            .iter()
+            .any(|seed| seed.fingerprint() == &new_fp
+                && matches!(seed.material(), SeedMaterial::Bip39Mnemonic(_))),
+        "the new phrase is exported under its own fingerprint"
+    );
+}
+
+/// A wallet last written by zcashd 4.x, simulated by dropping the fixture's
+/// Orchard note commitment tree, parses with an empty tree and migrates.
+#[test]
+fn migrates_a_wallet_without_an_orchard_note_commitment_tree() {
+    require_db_dump!();
+
+    let bdb = plaintext_bdb_without(&["orchard_note_commitment_tree"]);
+    let dump = ZcashdDump::from_bdb_dump(&bdb, false).expect("collect records");
+    let (wallet, _) = ZcashdParser::parse_dump(&dump, false).expect("parses");
+
+    let tree = wallet.orchard_note_commitment_tree();
+    assert_eq!(tree.last_checkpoint(), None);
+    assert!(tree.note_positions().is_empty());
+
+    migrate_to_zewif(&wallet, BlockHeight::from_u32(2_000_000), None).expect("migrates");
+}
+
+/// With `skip_transactions`, a wallet whose transaction records are all
+/// corrupt still parses strictly and migrates its keys, without history.
+#[test]
+fn skip_transactions_migrates_keys_of_a_wallet_with_corrupt_transactions() {
+    require_db_dump!();
+
+    let mut bdb = plaintext_bdb_with_transactions();
+    let mut corrupted = 0;
+    for (key, value) in bdb.data_records.iter_mut() {
+        if key.as_slice().starts_with(b"\x02tx") {
+            *value = Data::from_slice(&[0xff, 0xff]);
+            corrupted += 1;
+        }
+    }
+    assert_eq!(corrupted, FIXTURE_TRANSACTIONS);
+    let dump = ZcashdDump::from_bdb_dump(&bdb, false).expect("collect records");
+    assert!(ZcashdParser::parse_dump(&dump, true).is_err());
+
+    let options = MigrationOptions::default().skip_transactions(true);
+    let (wallet, _) =
+        ZcashdParser::parse_dump_with_options(&dump, true, EncryptedKeyPolicy::Reject, options)
+            .expect("parses");
+    assert!(wallet.transactions().is_empty());
+
+    let zewif =
+        migrate_to_zewif(&wallet, BlockHeight::from_u32(2_000_000), None).expect("migrates");
+    assert!(zewif.transactions().is_empty());
+    match zewif.secrets() {
+        Some(zewif::Secrets::Plain(store)) => assert!(!store.seeds().is_empty()),
+        other => panic!("expected plaintext secrets, got {other:?}"),
+    }
+}
+
+/// A wallet in the format of zcashd 4.x, simulated by dropping every record
+/// type introduced since, parses with its network inferred from its
+/// addresses and migrates.
+#[test]
+fn migrates_a_wallet_predating_network_info() {
+    require_db_dump!();
+
+    const SINCE_VERSION_5_RECORDS: [&str; 9] = [
+        "networkinfo",
+        "orchard_note_commitment_tree",
+        "unifiedaccount",
+        "unifiedfvk",
+        "unifiedaddrmeta",
+        "mnemonicphrase",
+        "mnemonichdchain",
+        "recipientmapping",
+        "bestblock_nomerkle",
+    ];
+    let bdb = plaintext_bdb_without(&SINCE_VERSION_5_RECORDS);
+    let dump = ZcashdDump::from_bdb_dump(&bdb, false).expect("collect records");
+    let (wallet, _) = ZcashdParser::parse_dump(&dump, false).expect("parses");
+
+    // The fixture is a regtest wallet, whose transparent addresses are
+    // encoded as on testnet.
+    assert!(matches!(
+        wallet.network(),
+        Network::Regtest(_) | Network::Testnet
+    ));
+    assert!(wallet.mnemonic_hd_chain().is_none());
+
+    migrate_to_zewif(&wallet, BlockHeight::from_u32(2_000_000), None).expect("migrates");
+}
+
+/// A wallet missing a record that every supported zcashd version writes is
+/// reported as too old, with the version that last wrote it.
+#[test]
+fn a_missing_required_record_reports_the_wallet_too_old() {
+    require_db_dump!();
+
+    let bdb = plaintext_bdb_without(&["witnesscachesize"]);
+    let dump = ZcashdDump::from_bdb_dump(&bdb, false).expect("collect records");
+    let err = ZcashdParser::parse_dump(&dump, false).unwrap_err();
+    let Error::WalletTooOld { client_version, .. } = err else {
+        panic!("expected WalletTooOld, got {err:?}");
+    };
+    assert!(err.to_string().contains(&client_version.to_string()));
+}
+
+/// Migrating the same wallet twice, each time from a fresh parse, serializes
+/// to the same bytes: the parsed wallet's maps iterate in a different order
+/// each time, but the migration orders its accounts, addresses and
+/// transactions itself.
+#[test]
+fn repeated_migrations_are_byte_identical() {
+    require_db_dump!();
+
+    let height = BlockHeight::from_u32(2_000_000);
+    let export = || {
+        migrate_to_zewif(&parse_plaintext(), height, None)
+            .expect("migrates")
+            .to_bytes()
+            .expect("serializes")
+    };
+    assert_eq!(export(), export());
+}
+
+/// The migration lists a wallet's unified accounts by ascending ZIP 32
+/// account index, whatever the order of their fingerprints, and then the
+/// legacy account.
+#[test]
+fn accounts_are_in_a_stable_order() {
+    require_db_dump!();
+
+    let bdb = plaintext_bdb_with_unified_accounts(&[2, 0, 1]);
+    let dump = ZcashdDump::from_bdb_dump(&bdb, false).expect("collect records");
+    let (wallet, _) = ZcashdParser::parse_dump(&dump, true).expect("parses");
+    let zewif =
+        migrate_to_zewif(&wallet, BlockHeight::from_u32(2_000_000), None).expect("migrates");
+
+    let names: Vec<_> = zewif.wallets()[0]
+        .accounts()
+        .iter()
+        .map(|account| account.name())
+        .collect();
+    assert_eq!(names, ["Account #0", "Account #1", "Account #2", "Legacy"]);
+}
+
+/// An Orchard-only unified address, as `z_getaddressforaccount` creates after
+/// NU5, is derived again from its metadata and migrated into the unified
+/// account whose UFVK it belongs to.
+#[test]
+fn orchard_only_address_is_migrated_into_its_account() {
+    require_db_dump!();
+
+    // Account 1 also records an Orchard-only address at another diversifier
+    // index; every index is valid for Orchard.
+    let j = [0x42; 11];
+    let mut bdb = plaintext_bdb_with_unified_accounts(&[0, 1]);
+    let mut key = vec![15];
+    key.extend_from_slice(b"unifiedaddrmeta");
+    key.extend_from_slice(&ufvk_fingerprint(1));
+    key.extend_from_slice(&j);
+    key.extend_from_slice(&[1, 3]);
+    bdb.data_records
+        .insert(Data::from_vec(key), Data::from_vec(vec![0; 4]));
+    let dump = ZcashdDump::from_bdb_dump(&bdb, false).expect("collect records");
+    let (wallet, _) = ZcashdParser::parse_dump(&dump, true).expect("parses");
+    let zewif =
+        migrate_to_zewif(&wallet, BlockHeight::from_u32(2_000_000), None).expect("migrates");
+
+    let ufvk =
+        &wallet.unified_accounts().full_viewing_keys[&UfvkFingerprint::new(ufvk_fingerprint(1))];
+    let orchard_only = UnifiedAddressRequest::custom(
+        ReceiverRequirement::Require,
+        ReceiverRequirement::Omit,
+        ReceiverRequirement::Omit,
+    )
+    .expect("a valid request");
+    let expected = ufvk
+        .address(zip32::DiversifierIndex::from(j), orchard_only)
+        .expect("derives the address")
+        .encode(&wallet.network_info().to_address_encoding_network());
+
+    let holders: Vec<_> = zewif.wallets()[0]
+        .accounts()
+        .iter()
+        .filter(|account| {
+            account
+                .addresses()
+                .iter()
+                .any(|address| address.as_string() == expected)
+        })
+        .map(|account| account.name())
+        .collect();
+    assert_eq!(holders, ["Account #1"]);
+}
+
+/// Each address the migration exports in a unified account is found to
+/// belong to that account, and every exported address is found the same with
+/// or without the wallet's address index.
+#[test]
+fn addresses_are_looked_up_to_their_unified_account() {
+    require_db_dump!();
+
+    let dump = ZcashdDump::from_bdb_dump(&plaintext_bdb_with_unified_accounts(&[0, 1]), false)
+        .expect("collect records");
+    let (wallet, _) = ZcashdParser::parse_dump(&dump, true).expect("parses");
+    let zewif =
+        migrate_to_zewif(&wallet, BlockHeight::from_u32(2_000_000), None).expect("migrates");
+    let mut unified_addresses = 0;
+    for account in zewif.wallets()[0].accounts() {
+        let zip32_account_id = account
+            .name()
+            .strip_prefix("Account #")
+            .map(|id| id.parse::<u32>().expect("account index"));
+        for address in account.addresses() {
+            let encoded = address.as_string();
+            let found = wallet.account_for_address(&encoded);
+            assert_eq!(found, wallet.find_account_for_address(&encoded));
+            let Some(zip32_account_id) = zip32_account_id else {
+                continue;
+            };
+            let fingerprint = found.expect("a unified account's address has an account");
+            assert_eq!(
+                wallet.unified_accounts().account_metadata[&fingerprint].zip32_account_id(),
+                zip32_account_id,
+                "{encoded}"
+            );
+            unified_addresses += 1;
+        }
+    }
+    assert!(unified_addresses > 0);
+    assert_eq!(wallet.account_for_address("not an address"), None);
+}
+
+/// The Sapling address found for each incoming viewing key is one that the
+/// wallet records for that key.
+#[test]
+fn sapling_addresses_are_looked_up_by_incoming_viewing_key() {
+    require_db_dump!();
+
+    let wallet = parse_plaintext();
+    assert!(!wallet.sapling_z_addresses().is_empty());
+    for ivk in wallet.sapling_z_addresses().values() {
+        let address = wallet
+            .sapling_address_for_ivk(ivk)
+            .expect("a recorded key has an address");
+        assert_eq!(wallet.sapling_z_addresses().get(address), Some(ivk));
+    }
+    for ivk in wallet.sapling_extended_full_viewing_keys().keys() {
+        if let Some(address) = wallet.sapling_address_for_ivk(ivk) {
+            assert_eq!(wallet.sapling_z_addresses().get(address), Some(ivk));
+        }
+    }
+}
+
+/// A streaming migration hands off exactly the transactions a collecting one
+/// records, in txid order, and otherwise produces the same document.
+#[test]
+fn streaming_migration_matches_collecting_migration() {
+    require_db_dump!();
+
+    let wallet = parse_plaintext_with_transactions();
+    let height = BlockHeight::from_u32(2_000_000);
+    let collected = migrate_to_zewif(&wallet, height, None).expect("migrates");
+    assert_eq!(collected.transactions().len(), FIXTURE_TRANSACTIONS);
+
+    let mut streamed = Vec::new();
+    let zewif = migrate_to_zewif_streaming(&wallet, height, None, |txid, tx| {
+        streamed.push((txid, tx));
+        Ok::<_, std::convert::Infallible>(())
+    })
+    .expect("migrates");
+
+    assert!(zewif.transactions().is_empty());
+    let expected: Vec<_> = collected
+        .transactions()
+        .iter()
+        .map(|(txid, tx)| (*txid, tx.clone()))
+        .collect();
+    assert_eq!(streamed, expected);
+    assert_eq!(zewif.wallets(), collected.wallets());
+    assert_eq!(zewif.secrets(), collected.secrets());
+}
+
+/// An error from the sink stops a streaming migration.
+#[test]
+fn streaming_migration_stops_on_a_sink_error() {
+    require_db_dump!();
+
+    let wallet = parse_plaintext_with_transactions();
+    let mut handed_off = 0;
+    let result = migrate_to_zewif_streaming(&wallet, BlockHeight::from_u32(1), None, |_, _| {
+        handed_off += 1;
+        Err("sink is full")
+    });
+
+    assert!(matches!(result, Err(MigrateError::TransactionSink { .. })));
+    assert_eq!(handed_off, 1);
+}
+
+/// The default filter exports exactly what the unfiltered migration does, and
+/// a filter restricted to the legacy account exports only that account.
+#[test]
+fn filtered_migration_selects_accounts() {
+    require_db_dump!();
+
+    let wallet = parse_plaintext();
+    let height = BlockHeight::from_u32(2_000_000);
+    let unfiltered = migrate_to_zewif(&wallet, height, None).expect("migrates");
+    let filtered = migrate_to_zewif_filtered(&wallet, height, None, &MigrationFilter::default())
+        .expect("migrates");
+    assert_eq!(
+        filtered.to_bytes().expect("serializes"),
+        unfiltered.to_bytes().expect("serializes")
+    );
+
+    let legacy_only = MigrationFilter {
+        include_accounts: Some(BTreeSet::from([0x7FFF_FFFF])),
+        exclude_seed_material: true,
+        ..MigrationFilter::default()
+    };
+    let filtered =
+        migrate_to_zewif_filtered(&wallet, height, None, &legacy_only).expect("migrates");
+    let accounts = filtered.wallets()[0].accounts();
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].name(), "Legacy");
+    for txid in filtered.transactions().keys() {
+        assert!(
+            accounts[0].relevant_transactions().contains_key(txid)
+                || accounts[0].sent_outputs().contains_key(txid),
+            "{txid} is not relevant to the exported account"
+        );
+    }
+    if let Some(zewif::Secrets::Plain(store)) = filtered.secrets() {
+        assert!(store.seeds().is_empty());
+    }
+}
+
+/// A view-only export contains none of the secret material a full export
+/// does, nor the fixture's known keys, and keeps the same addresses.
+#[test]
+fn view_only_export_omits_secret_material() {
+    require_db_dump!();
+
+    let wallet = parse_plaintext();
+    let height = BlockHeight::from_u32(2_000_000);
+    let full = migrate_to_zewif(&wallet, height, None).expect("migrates");
+    let view_only =
+        migrate_to_zewif_with_mode(&wallet, height, None, ExportMode::ViewOnly).expect("migrates");
+    assert!(view_only.secrets().is_none());
+
+    let Some(zewif::Secrets::Plain(store)) = full.secrets() else {
+        panic!("expected plaintext secrets in the full export");
+    };
+    let mut secrets = vec![
+        hex::decode(T_SCALAR_HEX).unwrap(),
+        hex::decode(Z_EXTSK_HEX).unwrap(),
+    ];
+    for entry in store.transparent_keys() {
+        secrets.push(entry.spending_key().encoding().as_bytes().to_vec());
+    }
+    for entry in store.sapling_keys() {
+        secrets.push(entry.spending_key().encoding().as_bytes().to_vec());
+    }
+    for entry in store.sprout_keys() {
+        secrets.push(entry.spending_key().encoding().as_bytes().to_vec());
+    }
+    for seed in store.seeds() {
+        secrets.push(match seed.material() {
+            SeedMaterial::Bip39Mnemonic(mnemonic) => mnemonic.mnemonic().as_bytes().to_vec(),
+            SeedMaterial::LegacySeed(seed) => seed.as_slice().to_vec(),
+        });
+    }
+
+    let bytes = view_only.to_bytes().expect("serializes");
+    for secret in &secrets {
+        assert!(
+            !bytes
+                .windows(secret.len())
+                .any(|window| window == secret.as_slice()),
+            "the view-only export contains secret material"
+        );
+    }
+
+    let addresses = |zewif: &zewif::Zewif| -> Vec<String> {
+        zewif.wallets()[0]
+            .accounts()
+            .iter()
+            .flat_map(|account| account.addresses())
+            .map(zewif::Address::as_string)
+            .collect()
+    };
+    assert_eq!(addresses(&view_only), addresses(&full));
+}
+
+/// `transactions_by_height` orders confirmed transactions by known height
+/// before the others, with the unconfirmed ones last, and the confirmed and
+/// unconfirmed iterators partition the wallet's transactions.
+#[test]
+fn transactions_are_ordered_by_height() {
+    require_db_dump!();
+
+    let wallet = parse_plaintext();
+    let ordered = wallet.transactions_by_height();
+    assert_eq!(ordered.len(), wallet.transactions().len());
+    assert_eq!(
+        wallet.confirmed_transactions().count() + wallet.unconfirmed_transactions().count(),
+        wallet.transactions().len()
+    );
+
+    let unconfirmed: BTreeSet<_> = wallet.unconfirmed_transactions().map(|(t, _)| *t).collect();
+    let first_unconfirmed = ordered
+        .iter()
+        .position(|(txid, _)| unconfirmed.contains(txid))
+        .unwrap_or(ordered.len());
+    assert!(
+        ordered[first_unconfirmed..]
+            .iter()
+            .all(|(txid, _)| unconfirmed.contains(txid))
+    );
+
+    let heights: Vec<Option<u32>> = ordered[..first_unconfirmed]
+        .iter()
+        .map(|(txid, _)| wallet.block_height_for_tx(txid))
+        .collect();
+    let known = heights.iter().take_while(|h| h.is_some()).count();
+    assert!(heights[known..].iter().all(Option::is_none));
+    assert!(heights[..known].windows(2).all(|w| w[0] <= w[1]));
+}
+
+/// The unspent outputs and notes the wallet reports are its own, in order,
+/// and not spent by any of its transactions.
+#[test]
+fn unspent_outputs_are_unspent_within_the_wallet() {
+    require_db_dump!();
+
+    let wallet = parse_plaintext();
+    let transparent = wallet.unspent_transparent_outputs();
+    assert!(
+        transparent
+            .windows(2)
+            .all(|w| (w[0].0, w[0].1) < (w[1].0, w[1].1))
+    );
+    for (txid, n, output) in &transparent {
+        assert_eq!(&wallet.transactions()[*txid].vout()[*n], *output);
+        assert!(!wallet.transactions().values().any(|wtx| {
+            wtx.vin().iter().any(|input| {
+                input.prevout().hash() == txid.as_bytes() && input.prevout().n() as usize == *n
+            })
+        }));
+    }
+
+    let sapling = wallet.unspent_sapling_notes();
+    assert_eq!(wallet.has_sapling_funds(), !sapling.is_empty());
+    for (txid, outpoint, note_data) in sapling {
+        assert_eq!(
+            wallet.transactions()[txid].sapling_note_data().unwrap()[outpoint],
+            *note_data
+        );
+    }
+}
@@ -0,0 +1,229 @@
+//! Golden-file regression tests of the end-to-end migration.
+//!
+//! Each case parses a fixture wallet, migrates it, and renders a normalized
+//! text summary of the result: every account with its addresses (and their
+//! address book labels and purposes), received and sent outputs, the address
+//! book, and the transaction table. The summary is compared against the
+//! checked-in `tests/golden/<case>.golden` file, so that any change to account
+//! assignment, note positions, purposes and the like shows up as a diff.
+//!
+//! After an intended change, regenerate the golden files with
+//!
+//! ```text
+//! UPDATE_GOLDEN=1 cargo test --test golden_migration
+//! ```
+//!
+//! and review the diff before committing it. A case whose golden file does
+//! not exist fails until it is generated the same way.
+
+use std::{collections::BTreeMap, fmt::Write, fs, path::PathBuf};
+
+use secrecy::SecretVec;
+use zewif::{BlockHeight, ReceivedOutput, ReceivedOutputPool, Zewif};
+use zewif_zcashd::{BDBDump, EncryptedKeyPolicy, ZcashdDump, ZcashdParser, migrate_to_zewif};
+
+/// A golden case: the golden file's name, the fixture it migrates, and the
+/// policy the fixture is parsed under.
+struct Case {
+    name: &'static str,
+    fixture: &'static str,
+    policy: fn() -> EncryptedKeyPolicy,
+}
+
+const CASES: &[Case] = &[
+    Case {
+        name: "plaintext-regtest",
+        fixture: "plaintext-regtest-wallet.dat",
+        policy: || EncryptedKeyPolicy::Reject,
+    },
+    Case {
+        name: "encrypted-regtest-decrypted",
+        fixture: "encrypted-regtest-wallet.dat",
+        policy: || EncryptedKeyPolicy::Decrypt(SecretVec::new(b"test-passphrase-42".to_vec())),
+    },
+    Case {
+        name: "encrypted-regtest-skipped",
+        fixture: "encrypted-regtest-wallet.dat",
+        policy: || EncryptedKeyPolicy::Skip,
+    },
+];
+
+const EXPORT_HEIGHT: u32 = 2_000_000;
+
+fn manifest_path(relative: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(relative)
+}
+
+/// See `tests/encrypted_wallet.rs`: reading a fixture needs `db_dump`, which
+/// is not vendored on every platform.
+fn db_dump_available() -> bool {
+    BDBDump::from_file(&manifest_path(
+        "tests/fixtures/plaintext-regtest-wallet.dat",
+    ))
+    .is_ok()
+}
+
+fn migrate(case: &Case) -> Zewif {
+    let path = manifest_path("tests/fixtures").join(case.fixture);
+    let bdb = BDBDump::from_file(&path).expect("db_dump the fixture");
+    let dump = ZcashdDump::from_bdb_dump(&bdb, false).expect("collect records");
+    let (wallet, _) = ZcashdParser::parse_dump_with_policy(&dump, false, (case.policy)())
+        .expect("the fixture parses");
+    migrate_to_zewif(&wallet, BlockHeight::from_u32(EXPORT_HEIGHT), None).expect("migrates")
+}
+
+fn pool_name(output: &ReceivedOutput) -> &'static str {
+    match output.pool() {
+        ReceivedOutputPool::Transparent(_) => "transparent",
+        ReceivedOutputPool::Sprout(_) => "sprout",
+        ReceivedOutputPool::Sapling(_) => "sapling",
+        ReceivedOutputPool::Orchard(_) => "orchard",
+        _ => "other",
+    }
+}
+
+/// A deterministic text rendering of the parts of a migrated document that
+/// the golden files pin down. Key material is left out; the secret store is
+/// summarized by its entry counts.
+fn summarize(zewif: &Zewif) -> String {
+    let mut out = String::new();
+    writeln!(out, "export height {}", zewif.export_height()).unwrap();
+
+    for (wallet_index, wallet) in zewif.wallets().iter().enumerate() {
+        writeln!(out, "wallet {wallet_index} {:?}", wallet.network()).unwrap();
+        let book: BTreeMap<&str, _> = wallet
+            .address_book()
+            .iter()
+            .map(|entry| (entry.address(), (entry.label(), entry.purpose())))
+            .collect();
+
+        for (account_index, account) in wallet.accounts().iter().enumerate() {
+            writeln!(
+                out,
+                "  account {account_index} {:?} purpose={:?} provenance={:?} birthday={:?}",
+                account.name(),
+                account.purpose(),
+                account.provenance(),
+                account.birthday_height().map(u32::from),
+            )
+            .unwrap();
+            for address in account.addresses() {
+                let address_string = address.as_string();
+                let (label, purpose) = book
+                    .get(address_string.as_str())
+                    .copied()
+                    .unwrap_or((None, None));
+                writeln!(
+                    out,
+                    "    address {address_string} scope={:?} label={label:?} purpose={purpose:?}",
+                    address.scope(),
+                )
+                .unwrap();
+            }
+            for (txid, outputs) in account.relevant_transactions() {
+                write!(out, "    received {txid}").unwrap();
+                for output in outputs {
+                    write!(
+                        out,
+                        " {}:{} position={:?} memo={}",
+                        pool_name(output),
+                        output.output_index(),
+                        output.commitment_tree_position(),
+                        output.memo().is_some(),
+                    )
+                    .unwrap();
+                }
+                writeln!(out).unwrap();
+            }
+            for (txid, outputs) in account.sent_outputs() {
+                writeln!(out, "    sent {txid} outputs={}", outputs.len()).unwrap();
+            }
+        }
+
+        for (address, (label, purpose)) in &book {
+            writeln!(
+                out,
+                "  address book {address} label={label:?} purpose={purpose:?}"
+            )
+            .unwrap();
+        }
+    }
+
+    for (txid, tx) in zewif.transactions() {
+        writeln!(
+            out,
+            "transaction {txid} mined={:?} expiry={:?} block_position={} created={:?}",
+            tx.mined_height().map(u32::from),
+            tx.expiry_height().map(u32::from),
+            tx.block_position().is_some(),
+            tx.created_time(),
+        )
+        .unwrap();
+    }
+
+    match zewif.secrets() {
+        Some(zewif::Secrets::Plain(store)) => writeln!(
+            out,
+            "secrets seeds={} transparent={} sapling={} sprout={} unified={}",
+            store.seeds().len(),
+            store.transparent_keys().len(),
+            store.sapling_keys().len(),
+            store.sprout_keys().len(),
+            store.unified_keys().len(),
+        )
+        .unwrap(),
+        Some(_) => writeln!(out, "secrets encrypted").unwrap(),
+        None => writeln!(out, "secrets none").unwrap(),
+    }
+    out
+}
+
+/// Compare `actual` against the case's golden file, or write it when
+/// `UPDATE_GOLDEN=1` is set.
+fn check_golden(name: &str, actual: &str) {
+    let path = manifest_path("tests/golden").join(format!("{name}.golden"));
+    if std::env::var("UPDATE_GOLDEN").is_ok_and(|v| v == "1") {
+        fs::create_dir_all(path.parent().unwrap()).expect("create the golden directory");
+        fs::write(&path, actual).expect("write the golden file");
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).unwrap_or_else(|error| {
+        panic!(
+            "cannot read {}: {error}; generate it with UPDATE_GOLDEN=1 and review it",
+            path.display()
+        )
+    });
+    assert!(
+        expected == actual,
+        "migration of {name} differs from {}; if the change is intended, rerun with \
+         UPDATE_GOLDEN=1 and review the diff\n--- expected\n{expected}\n--- actual\n{actual}",
+        path.display()
+    );
+}
+
+#[test]
+fn migrations_match_golden_files() {
+    if !db_dump_available() {
+        eprintln!("skipping: db_dump is unavailable on this platform");
+        return;
+    }
+    for case in CASES {
+        check_golden(case.name, &summarize(&migrate(case)));
+    }
+}
+
+/// Every fixture wallet is covered by at least one golden case.
+#[test]
+fn every_fixture_has_a_golden_case() {
+    for entry in fs::read_dir(manifest_path("tests/fixtures")).expect("list the fixtures") {
+        let file_name = entry.unwrap().file_name();
+        let file_name = file_name.to_string_lossy();
+        if file_name.ends_with(".dat") {
+            assert!(
+                CASES.iter().any(|case| case.fixture == file_name),
+                "no golden case migrates {file_name}"
+            );
+        }
+    }
+}
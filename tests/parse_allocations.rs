@@ -0,0 +1,72 @@
+//! Allocation counts of the `parse!` macro.
+//!
+//! A `parse!` context, such as the `format!` naming each array item, is only
+//! built on the error path (or when recording fields), so a successful parse
+//! allocates no more than the values it returns. This is a benchmark-style
+//! check, run with
+//!
+//! ```text
+//! cargo test --test parse_allocations -- --ignored
+//! ```
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use zewif_zcashd::parser::prelude::*;
+
+/// The system allocator, counting the allocations made through it.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// The number of allocations `f` makes.
+fn allocations<T>(f: impl FnOnce() -> T) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let result = f();
+    let count = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    drop(result);
+    count
+}
+
+#[test]
+#[ignore = "allocation benchmark; run with --ignored"]
+fn successful_parses_do_not_build_their_context() {
+    const ITEMS: usize = 10_000;
+    // A CompactSize length (0xfd, then a u16), then the items.
+    let mut bytes = vec![0xfd];
+    bytes.extend_from_slice(&(ITEMS as u16).to_le_bytes());
+    for i in 0..ITEMS as u32 {
+        bytes.extend_from_slice(&i.to_le_bytes());
+    }
+
+    let count = allocations(|| Vec::<u32>::parse_buf(&bytes, false).unwrap());
+    println!("parsing {ITEMS} array items made {count} allocations");
+    // Each item's context would be one allocation; the vector itself is one.
+    assert!(count < ITEMS / 100, "{count} allocations");
+
+    // A failed parse still names the item it failed on.
+    let error = Vec::<u32>::parse_buf(&&bytes[..bytes.len() - 1], false).unwrap_err();
+    assert_eq!(
+        error.frames(),
+        [
+            "u32".to_string(),
+            format!("array item {0} of {0}", ITEMS - 1)
+        ]
+    );
+}
@@ -0,0 +1,114 @@
+//! Property tests of the encodings the primitive `Parse` implementations
+//! read.
+//!
+//! A compact size must use the shortest encoding of its value, so each of
+//! its multi-byte forms has a minimum value. Canonical encodings of every
+//! value round-trip, and the longer forms of smaller values are rejected as
+//! zcashd rejects them.
+
+use proptest::prelude::*;
+use zewif::Data;
+use zewif_zcashd::parser::prelude::*;
+
+/// Values across each of the compact size encoding's forms, which a uniform
+/// `u64` would almost never leave the largest of.
+fn compact_size_values() -> impl Strategy<Value = u64> {
+    prop_oneof![
+        0..0xfdu64,
+        0xfd..0x1_0000u64,
+        0x1_0000..0x1_0000_0000u64,
+        0x1_0000_0000..=u64::MAX,
+    ]
+}
+
+/// A compact size prefix and a value below the minimum its form allows, in
+/// that form.
+fn non_canonical_compact_sizes() -> impl Strategy<Value = (u8, u64, Vec<u8>)> {
+    prop_oneof![
+        (0..0xfdu16).prop_map(|n| (0xfdu8, u64::from(n), n.to_le_bytes().to_vec())),
+        (0..0x1_0000u32).prop_map(|n| (0xfeu8, u64::from(n), n.to_le_bytes().to_vec())),
+        (0..0x1_0000_0000u64).prop_map(|n| (0xffu8, n, n.to_le_bytes().to_vec())),
+    ]
+    .prop_map(|(prefix, value, rest)| {
+        let mut bytes = vec![prefix];
+        bytes.extend(rest);
+        (prefix, value, bytes)
+    })
+}
+
+proptest! {
+    #[test]
+    fn canonical_compact_sizes_round_trip(value in compact_size_values()) {
+        let mut bytes = vec![];
+        write_compact_size(&mut bytes, value as usize);
+        let expected_len = match value {
+            0..0xfd => 1,
+            0xfd..0x1_0000 => 3,
+            0x1_0000..0x1_0000_0000 => 5,
+            _ => 9,
+        };
+        prop_assert_eq!(bytes.len(), expected_len);
+
+        let size = CompactSize::parse_buf(&bytes, false).unwrap();
+        prop_assert_eq!(*size as u64, value);
+    }
+
+    #[test]
+    fn non_canonical_compact_sizes_are_rejected(
+        (prefix, value, bytes) in non_canonical_compact_sizes()
+    ) {
+        let err = CompactSize::parse_buf(&bytes, false).unwrap_err();
+        prop_assert!(
+            matches!(
+                err.kind(),
+                ParseErrorKind::NonCanonicalCompactSize { prefix: p, value: v }
+                    if *p == prefix && *v == value
+            ),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn data_round_trips(bytes in proptest::collection::vec(any::<u8>(), 0..1024)) {
+        let mut encoded = vec![];
+        write_data(&mut encoded, &bytes);
+        let data = Data::parse_buf(&encoded, false).unwrap();
+        prop_assert_eq!(data.as_slice(), bytes.as_slice());
+    }
+
+    #[test]
+    fn strings_round_trip(s in any::<String>()) {
+        let mut encoded = vec![];
+        write_data(&mut encoded, s.as_bytes());
+        prop_assert_eq!(String::parse_buf(&encoded, false).unwrap(), s);
+    }
+
+    #[test]
+    fn optional_u32s_round_trip(value in any::<Option<u32>>()) {
+        let mut encoded = vec![];
+        match value {
+            None => encoded.push(0x00),
+            Some(n) => {
+                encoded.push(0x01);
+                encoded.extend_from_slice(&n.to_le_bytes());
+            }
+        }
+        let mut p = Parser::new(&encoded);
+        prop_assert_eq!(parse_optional::<u32>(&mut p).unwrap(), value);
+        prop_assert!(p.check_finished().is_ok());
+    }
+
+    /// Any flag other than 0 or 1 is rejected, whatever follows it.
+    #[test]
+    fn optional_flags_other_than_0_or_1_are_rejected(flag in 2u8.., value in any::<u32>()) {
+        let mut encoded = vec![flag];
+        encoded.extend_from_slice(&value.to_le_bytes());
+        let err = Option::<u32>::parse_buf(&encoded, false).unwrap_err();
+        prop_assert!(
+            matches!(err.kind(), ParseErrorKind::InvalidOptionalDiscriminant(d) if *d == flag),
+            "{}",
+            err
+        );
+    }
+}